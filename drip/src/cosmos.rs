@@ -0,0 +1,243 @@
+use std::{collections::HashMap, sync::Arc};
+
+use cosmos_client::{
+    gas::{
+        any::GasFiller,
+        feemarket::{self},
+        fixed, osmosis_eip1559_feemarket,
+    },
+    rpc::{Rpc, RpcT},
+    wallet::{LocalSigner, WalletT},
+    TxClient,
+};
+use prost::{Message, Name};
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument};
+use unionlabs::primitives::H256;
+
+use crate::SendRequest;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CosmosConfig {
+    pub bech32_prefix: String,
+    pub rpc_url: String,
+    pub gas_config: GasFillerConfig,
+    pub signer: H256,
+    pub memo: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type", content = "config")]
+pub enum GasFillerConfig {
+    // fixed gas filler is it's own config
+    Fixed(fixed::GasFiller),
+    Feemarket(FeemarketConfig),
+    OsmosisEip1559Feemarket(OsmosisEip1559FeemarketConfig),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct FeemarketConfig {
+    pub max_gas: u64,
+    pub gas_multiplier: Option<f64>,
+    pub denom: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct OsmosisEip1559FeemarketConfig {
+    pub max_gas: u64,
+    pub gas_multiplier: Option<f64>,
+    pub base_fee_multiplier: Option<f64>,
+    pub denom: Option<String>,
+}
+
+impl GasFillerConfig {
+    async fn into_gas_filler(self, rpc_url: String) -> GasFiller {
+        match self {
+            GasFillerConfig::Fixed(config) => GasFiller::Fixed(config),
+            GasFillerConfig::Feemarket(config) => GasFiller::Feemarket(
+                feemarket::GasFiller::new(feemarket::Config {
+                    rpc_url,
+                    max_gas: config.max_gas,
+                    gas_multiplier: config.gas_multiplier,
+                    denom: config.denom,
+                })
+                .await
+                .expect("failed to build gas filler"),
+            ),
+            GasFillerConfig::OsmosisEip1559Feemarket(config) => GasFiller::OsmosisEip1559Feemarket(
+                osmosis_eip1559_feemarket::GasFiller::new(osmosis_eip1559_feemarket::Config {
+                    rpc_url,
+                    max_gas: config.max_gas,
+                    gas_multiplier: config.gas_multiplier,
+                    base_fee_multiplier: config.base_fee_multiplier,
+                    denom: config.denom,
+                })
+                .await
+                .expect("failed to build gas filler"),
+            ),
+        }
+    }
+}
+
+/// Ensures `address` is a bech32 address with the prefix of the chain.
+pub fn validate_address(config: &CosmosConfig, address: &str) -> Result<(), String> {
+    let (hrp, _bz) = subtle_encoding::bech32::Bech32::lower_case()
+        .decode(address)
+        .map_err(|err| err.to_string())?;
+
+    if hrp != config.bech32_prefix {
+        return Err(format!(
+            "incorrect bech32 prefix, expected `{}` but found `{hrp}`",
+            config.bech32_prefix
+        ));
+    }
+
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct ChainClient {
+    pub chain_id: String,
+    pub config: CosmosConfig,
+    pub cosmos_ctx: Arc<TxClient<LocalSigner, Rpc, GasFiller>>,
+}
+
+impl ChainClient {
+    #[instrument(skip_all, fields(%chain_id))]
+    pub async fn new(chain_id: &str, config: &CosmosConfig) -> Self {
+        let rpc = Rpc::new(config.rpc_url.clone()).await.unwrap();
+
+        let bech32_prefix = rpc
+            .client()
+            .grpc_abci_query::<_, protos::cosmos::auth::v1beta1::Bech32PrefixResponse>(
+                "/cosmos.auth.v1beta1.Query/Bech32Prefix",
+                &protos::cosmos::auth::v1beta1::Bech32PrefixRequest {},
+                None,
+                false,
+            )
+            .await
+            .unwrap()
+            .into_result()
+            .unwrap()
+            .unwrap()
+            .bech32_prefix;
+
+        let gas_filler = config
+            .gas_config
+            .clone()
+            .into_gas_filler(config.rpc_url.clone())
+            .await;
+
+        let ctx = TxClient::new(
+            LocalSigner::new(config.signer, bech32_prefix),
+            rpc,
+            gas_filler,
+        );
+
+        let actual_chain_id = ctx.rpc().chain_id();
+
+        // Check if we are connected to a chain with the correct chain_id
+        assert_eq!(
+            actual_chain_id, chain_id,
+            "rpc_url {} is not for chain {}",
+            config.rpc_url, chain_id
+        );
+
+        Self {
+            chain_id: chain_id.to_owned(),
+            config: config.clone(),
+            cosmos_ctx: Arc::new(ctx),
+        }
+    }
+}
+
+struct AggregatedSendRequest {
+    denom: String,
+    total_amount: u128,
+}
+
+trait SendRequestAggregator {
+    fn aggregate_by_denom(&self) -> Vec<AggregatedSendRequest>;
+}
+
+impl SendRequestAggregator for [SendRequest] {
+    fn aggregate_by_denom(&self) -> Vec<AggregatedSendRequest> {
+        let mut denom_map: HashMap<String, (u128, Vec<(String, u128)>)> = HashMap::new();
+
+        // Iterate over the requests and populate the hashmap
+        for req in self {
+            let entry = denom_map
+                .entry(req.denom.clone())
+                .or_insert((0, Vec::new()));
+            entry.0 += req.amount; // Update the total amount for this denom
+            entry.1.push((req.receiver.clone(), req.amount)); // Add (receiver, amount) to the list
+        }
+
+        denom_map
+            .into_iter()
+            .map(|(denom, (total_amount, _))| AggregatedSendRequest {
+                denom,
+                total_amount,
+            })
+            .collect()
+    }
+}
+
+impl ChainClient {
+    /// `MultiSend` to the specified addresses.
+    #[instrument(
+        skip_all,
+        fields(
+            chain_id = %self.chain_id,
+            requests.len = %requests.len()
+        )
+    )]
+    pub async fn send(&self, requests: &[SendRequest]) -> anyhow::Result<H256> {
+        let agg_reqs = requests.aggregate_by_denom();
+
+        let msg = protos::cosmos::bank::v1beta1::MsgMultiSend {
+            // this is required to be one element
+            inputs: vec![protos::cosmos::bank::v1beta1::Input {
+                address: self.cosmos_ctx.wallet().address().to_string(),
+                coins: agg_reqs
+                    .iter()
+                    .map(|agg_req| protos::cosmos::base::v1beta1::Coin {
+                        denom: agg_req.denom.to_string(),
+                        amount: agg_req.total_amount.to_string(),
+                    })
+                    .collect(),
+            }],
+            outputs: requests
+                .iter()
+                .map(|req| protos::cosmos::bank::v1beta1::Output {
+                    address: req.receiver.clone(),
+                    coins: vec![protos::cosmos::base::v1beta1::Coin {
+                        denom: req.denom.clone(),
+                        amount: req.amount.to_string(),
+                    }],
+                })
+                .collect(),
+        };
+
+        let msg = protos::google::protobuf::Any {
+            type_url: protos::cosmos::bank::v1beta1::MsgMultiSend::type_url(),
+            value: msg.encode_to_vec(),
+        };
+
+        let (tx_hash, res) = self
+            .cosmos_ctx
+            .broadcast_tx_commit([msg], self.config.memo.clone(), true)
+            .await?;
+
+        info!(
+            ?requests,
+            %tx_hash,
+            gas_used = %res.tx_result.gas_used,
+            "submitted multisend"
+        );
+
+        Ok(tx_hash)
+    }
+}
@@ -0,0 +1,886 @@
+use std::fmt;
+
+use async_graphql::{Enum, SimpleObject};
+use async_sqlite::rusqlite::{
+    self,
+    types::{FromSql, FromSqlError, FromSqlResult, ValueRef},
+    Connection, OptionalExtension, TransactionBehavior,
+};
+use serde::{Deserialize, Serialize};
+
+/// Creates the tables, and migrates databases created by older versions of drip.
+pub fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS requests (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            chain_id TEXT NOT NULL,
+            denom TEXT NOT NULL,
+            address TEXT NOT NULL,
+            time TEXT,
+            tx_hash TEXT
+        )",
+        (), // empty list of parameters.
+    )?;
+
+    let columns = [
+        ("ip", "TEXT"),
+        ("requester", "TEXT"),
+        ("status", "TEXT NOT NULL DEFAULT 'pending'"),
+        ("attempts", "INTEGER NOT NULL DEFAULT 0"),
+        ("next_attempt_at", "TEXT"),
+        ("error", "TEXT"),
+        ("updated_at", "TEXT"),
+    ];
+    let mut legacy = false;
+    for (column, definition) in columns {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM pragma_table_info('requests') WHERE name = ?1)",
+            [column],
+            |row| row.get(0),
+        )?;
+        if exists {
+            continue;
+        }
+
+        conn.execute(
+            &format!("ALTER TABLE requests ADD COLUMN {column} {definition}"),
+            (),
+        )?;
+
+        legacy |= column == "status";
+    }
+
+    if legacy {
+        // older versions stored errors in tx_hash
+        conn.execute_batch(
+            "UPDATE requests SET status = 'failed', tx_hash = NULL,
+                error = CASE WHEN tx_hash LIKE 'ERROR: %' THEN substr(tx_hash, 8) ELSE tx_hash END
+             WHERE tx_hash LIKE 'ERROR%';
+             UPDATE requests SET status = 'confirmed' WHERE tx_hash IS NOT NULL;",
+        )?;
+    }
+
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS requests_address ON requests (chain_id, denom, address, time);
+         CREATE INDEX IF NOT EXISTS requests_ip ON requests (chain_id, denom, ip, time);
+         CREATE INDEX IF NOT EXISTS requests_denom ON requests (chain_id, denom, time);
+         CREATE INDEX IF NOT EXISTS requests_status ON requests (chain_id, status, attempts);",
+    )?;
+
+    Ok(())
+}
+
+/// The rate limits of a denom on a chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimits {
+    /// Minimum seconds between two requests for the same address.
+    pub address_seconds: u32,
+    /// Minimum seconds between two requests from the same IP.
+    pub ip_seconds: Option<u32>,
+    /// Maximum number of requests that can be handed out within 24 hours.
+    pub daily_requests: Option<u64>,
+}
+
+/// A request to be inserted into the queue.
+#[derive(Debug, Clone, Copy)]
+pub struct NewRequest<'a> {
+    pub chain_id: &'a str,
+    pub denom: &'a str,
+    pub address: &'a str,
+    pub ip: Option<&'a str>,
+    /// The allowlist entry the request was made under. Requests of an entry count towards the
+    /// daily cap of that entry, all other requests towards the daily cap of the denom.
+    pub requester: Option<&'a str>,
+}
+
+/// Why a request was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimited {
+    Address,
+    Ip,
+    DailyCap,
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RateLimited::Address | RateLimited::Ip => f.write_str("ERROR: ratelimited"),
+            RateLimited::DailyCap => f.write_str("ERROR: daily cap reached"),
+        }
+    }
+}
+
+/// Checks the rate limits and inserts the request, returning its id. Both happen in one
+/// transaction, so concurrent requests can't slip past the limits.
+pub fn insert_request(
+    conn: &mut Connection,
+    request: NewRequest<'_>,
+    limits: RateLimits,
+) -> rusqlite::Result<Result<i64, RateLimited>> {
+    let NewRequest {
+        chain_id,
+        denom,
+        address,
+        ip,
+        requester,
+    } = request;
+
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+    let since = |seconds: u64| format!("-{seconds} seconds");
+
+    let recent_address: bool = tx.query_row(
+        "SELECT EXISTS(SELECT 1 FROM requests
+         WHERE chain_id = ?1 AND denom = ?2 AND address = ?3 AND time > datetime('now', ?4))",
+        (
+            chain_id,
+            denom,
+            address,
+            since(limits.address_seconds.into()),
+        ),
+        |row| row.get(0),
+    )?;
+    if recent_address {
+        return Ok(Err(RateLimited::Address));
+    }
+
+    if let (Some(ip), Some(ip_seconds)) = (ip, limits.ip_seconds) {
+        let recent_ip: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM requests
+             WHERE chain_id = ?1 AND denom = ?2 AND ip = ?3 AND time > datetime('now', ?4))",
+            (chain_id, denom, ip, since(ip_seconds.into())),
+            |row| row.get(0),
+        )?;
+        if recent_ip {
+            return Ok(Err(RateLimited::Ip));
+        }
+    }
+
+    if let Some(daily_requests) = limits.daily_requests {
+        // failed sends don't count towards the cap
+        let handed_out: u64 = tx.query_row(
+            "SELECT COUNT(*) FROM requests
+             WHERE chain_id = ?1 AND denom = ?2 AND requester IS ?3
+             AND time > datetime('now', '-1 day')
+             AND status != 'failed'",
+            (chain_id, denom, requester),
+            |row| row.get(0),
+        )?;
+        if handed_out >= daily_requests {
+            return Ok(Err(RateLimited::DailyCap));
+        }
+    }
+
+    let id = tx.query_row(
+        "INSERT INTO requests (chain_id, denom, address, ip, requester, time)
+         VALUES (?1, ?2, ?3, ?4, ?5, datetime('now')) RETURNING id",
+        (chain_id, denom, address, ip, requester),
+        |row| row.get(0),
+    )?;
+
+    tx.commit()?;
+
+    Ok(Ok(id))
+}
+
+/// The state of a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum RequestState {
+    /// Waiting to be sent, possibly after a failed attempt.
+    Pending,
+    /// Submitted to the chain, waiting for inclusion.
+    Broadcast,
+    Confirmed,
+    /// Failed after all retries.
+    Failed,
+}
+
+impl RequestState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RequestState::Pending => "pending",
+            RequestState::Broadcast => "broadcast",
+            RequestState::Confirmed => "confirmed",
+            RequestState::Failed => "failed",
+        }
+    }
+}
+
+impl FromSql for RequestState {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "pending" => Ok(RequestState::Pending),
+            "broadcast" => Ok(RequestState::Broadcast),
+            "confirmed" => Ok(RequestState::Confirmed),
+            "failed" => Ok(RequestState::Failed),
+            other => Err(FromSqlError::Other(
+                format!("invalid request state {other}").into(),
+            )),
+        }
+    }
+}
+
+/// How failed sends are retried.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Requests are marked as failed after this many attempts, but only once they failed on
+    /// their own. Until then, requests of a failed batch are retried in batches of half the
+    /// size, to isolate the request that made the batch fail.
+    pub max_attempts: u32,
+    pub min_backoff_seconds: u64,
+    pub max_backoff_seconds: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            min_backoff_seconds: 5,
+            max_backoff_seconds: 300,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The delay before the next attempt, after `attempts` failed attempts.
+    pub fn backoff_seconds(&self, attempts: u32) -> u64 {
+        self.min_backoff_seconds
+            .saturating_mul(1 << attempts.saturating_sub(1).min(32))
+            .min(self.max_backoff_seconds)
+    }
+}
+
+/// A request that is ready to be sent.
+#[derive(Debug, Clone)]
+pub struct ReadyRequest {
+    pub id: i64,
+    pub denom: String,
+    pub address: String,
+    pub attempts: u32,
+}
+
+/// The next batch of requests of a chain that are ready to be sent. Requests with the fewest
+/// attempts are sent first, and retried requests in smaller batches.
+pub fn ready_requests(
+    conn: &Connection,
+    chain_id: &str,
+    batch_size: usize,
+) -> rusqlite::Result<Vec<ReadyRequest>> {
+    const READY: &str = "status = 'pending' AND chain_id = ?1
+        AND (next_attempt_at IS NULL OR next_attempt_at <= datetime('now'))";
+
+    let attempts: Option<u32> = conn.query_row(
+        &format!("SELECT MIN(attempts) FROM requests WHERE {READY}"),
+        [chain_id],
+        |row| row.get(0),
+    )?;
+    let Some(attempts) = attempts else {
+        return Ok(vec![]);
+    };
+
+    let limit = (batch_size >> attempts.min(63)).max(1);
+
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT id, denom, address, attempts FROM requests
+         WHERE {READY} AND attempts = ?2 ORDER BY id LIMIT ?3"
+    ))?;
+
+    let rows = stmt.query_map((chain_id, attempts, limit as i64), |row| {
+        Ok(ReadyRequest {
+            id: row.get(0)?,
+            denom: row.get(1)?,
+            address: row.get(2)?,
+            attempts: row.get(3)?,
+        })
+    })?;
+
+    rows.collect()
+}
+
+/// Marks requests as broadcast, with the hash of their transaction if it is known already.
+pub fn mark_broadcast(
+    conn: &Connection,
+    ids: &[i64],
+    tx_hash: Option<&str>,
+) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare_cached(
+        "UPDATE requests SET status = 'broadcast', tx_hash = ?1, updated_at = datetime('now')
+         WHERE id = ?2",
+    )?;
+    for id in ids {
+        stmt.execute((tx_hash, id))?;
+    }
+    Ok(())
+}
+
+/// Records the outcome of an attempt to send a request. Failed requests are retried according to
+/// `retry`, unless they were sent on their own and exceeded `max_attempts`.
+pub fn record_attempt(
+    conn: &Connection,
+    (id, previous_attempts): (i64, u32),
+    outcome: Result<&str, &str>,
+    sent_alone: bool,
+    retry: &RetryPolicy,
+) -> rusqlite::Result<RequestState> {
+    let attempts = previous_attempts + 1;
+
+    let state = match outcome {
+        Ok(tx_hash) => {
+            conn.prepare_cached(
+                "UPDATE requests SET status = 'confirmed', tx_hash = ?1, error = NULL,
+                 attempts = ?2, updated_at = datetime('now')
+                 WHERE id = ?3",
+            )?
+            .execute((tx_hash, attempts, id))?;
+
+            RequestState::Confirmed
+        }
+        Err(error) if sent_alone && attempts >= retry.max_attempts => {
+            conn.prepare_cached("UPDATE requests SET attempts = ?1 WHERE id = ?2")?
+                .execute((attempts, id))?;
+            mark_failed(conn, id, error)?;
+
+            RequestState::Failed
+        }
+        Err(error) => {
+            conn.prepare_cached(
+                "UPDATE requests SET status = 'pending', tx_hash = NULL, error = ?1,
+                 attempts = ?2, next_attempt_at = datetime('now', ?3),
+                 updated_at = datetime('now')
+                 WHERE id = ?4",
+            )?
+            .execute((
+                error,
+                attempts,
+                format!("+{} seconds", retry.backoff_seconds(attempts)),
+                id,
+            ))?;
+
+            RequestState::Pending
+        }
+    };
+
+    Ok(state)
+}
+
+/// Marks a request as failed, it will not be retried.
+pub fn mark_failed(conn: &Connection, id: i64, error: &str) -> rusqlite::Result<()> {
+    conn.prepare_cached(
+        "UPDATE requests SET status = 'failed', error = ?1, updated_at = datetime('now')
+         WHERE id = ?2",
+    )?
+    .execute((error, id))?;
+    Ok(())
+}
+
+/// Moves requests that were broadcast when drip stopped back to pending, so that they are sent
+/// again. Returns the number of requests.
+///
+/// Requests with a known transaction hash are left alone, their transaction may still be
+/// included. They are confirmed by their hash instead, see [`broadcast_requests`].
+pub fn reset_broadcast(conn: &Connection, chain_id: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE requests SET status = 'pending', updated_at = datetime('now')
+         WHERE status = 'broadcast' AND tx_hash IS NULL AND chain_id = ?1",
+        [chain_id],
+    )
+}
+
+/// Requests of a chain that were broadcast with a known transaction hash, but not confirmed yet.
+pub fn broadcast_requests(
+    conn: &Connection,
+    chain_id: &str,
+) -> rusqlite::Result<Vec<(ReadyRequest, String)>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, denom, address, attempts, tx_hash FROM requests
+         WHERE status = 'broadcast' AND tx_hash IS NOT NULL AND chain_id = ?1 ORDER BY id",
+    )?;
+
+    let rows = stmt.query_map([chain_id], |row| {
+        Ok((
+            ReadyRequest {
+                id: row.get(0)?,
+                denom: row.get(1)?,
+                address: row.get(2)?,
+                attempts: row.get(3)?,
+            },
+            row.get(4)?,
+        ))
+    })?;
+
+    rows.collect()
+}
+
+/// The full status of a request.
+#[derive(Debug, Clone, SimpleObject)]
+pub struct RequestStatus {
+    pub id: i64,
+    pub chain_id: String,
+    pub denom: String,
+    pub address: String,
+    pub time: String,
+    pub status: RequestState,
+    pub tx_hash: Option<String>,
+    pub attempts: u32,
+    pub next_attempt_at: Option<String>,
+    pub error: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+pub fn request_status(conn: &Connection, id: i64) -> rusqlite::Result<Option<RequestStatus>> {
+    conn.query_row(
+        "SELECT id, chain_id, denom, address, time, status, tx_hash, attempts, next_attempt_at,
+            error, updated_at
+         FROM requests WHERE id = ?1",
+        [id],
+        |row| {
+            Ok(RequestStatus {
+                id: row.get(0)?,
+                chain_id: row.get(1)?,
+                denom: row.get(2)?,
+                address: row.get(3)?,
+                time: row.get(4)?,
+                status: row.get(5)?,
+                tx_hash: row.get(6)?,
+                attempts: row.get(7)?,
+                next_attempt_at: row.get(8)?,
+                error: row.get(9)?,
+                updated_at: row.get(10)?,
+            })
+        },
+    )
+    .optional()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAIN_ID: &str = "union-devnet-1";
+    const DENOM: &str = "muno";
+
+    fn conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        conn
+    }
+
+    fn request<'a>(address: &'a str, ip: Option<&'a str>) -> NewRequest<'a> {
+        NewRequest {
+            chain_id: CHAIN_ID,
+            denom: DENOM,
+            address,
+            ip,
+            requester: None,
+        }
+    }
+
+    fn limits(ip_seconds: Option<u32>, daily_requests: Option<u64>) -> RateLimits {
+        RateLimits {
+            address_seconds: 60,
+            ip_seconds,
+            daily_requests,
+        }
+    }
+
+    // moves all requests back in time, as if they were made `seconds` ago
+    fn age_requests(conn: &Connection, seconds: u64) {
+        conn.execute(
+            "UPDATE requests SET time = datetime(time, ?1)",
+            [format!("-{seconds} seconds")],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn address_is_rate_limited() {
+        let mut conn = conn();
+
+        let limits = limits(None, None);
+        assert!(insert_request(&mut conn, request("a", None), limits)
+            .unwrap()
+            .is_ok());
+        assert_eq!(
+            insert_request(&mut conn, request("a", None), limits).unwrap(),
+            Err(RateLimited::Address)
+        );
+        assert!(insert_request(&mut conn, request("b", None), limits)
+            .unwrap()
+            .is_ok());
+
+        // other denoms are limited separately
+        let other_denom = NewRequest {
+            denom: "other",
+            ..request("a", None)
+        };
+        assert!(insert_request(&mut conn, other_denom, limits)
+            .unwrap()
+            .is_ok());
+
+        age_requests(&conn, 61);
+        assert!(insert_request(&mut conn, request("a", None), limits)
+            .unwrap()
+            .is_ok());
+    }
+
+    #[test]
+    fn ip_is_rate_limited() {
+        let mut conn = conn();
+
+        let limits = limits(Some(120), None);
+        assert!(
+            insert_request(&mut conn, request("a", Some("1.1.1.1")), limits)
+                .unwrap()
+                .is_ok()
+        );
+        assert_eq!(
+            insert_request(&mut conn, request("b", Some("1.1.1.1")), limits).unwrap(),
+            Err(RateLimited::Ip)
+        );
+        assert!(
+            insert_request(&mut conn, request("c", Some("2.2.2.2")), limits)
+                .unwrap()
+                .is_ok()
+        );
+
+        // past the address limit, but not the ip limit
+        age_requests(&conn, 61);
+        assert_eq!(
+            insert_request(&mut conn, request("b", Some("1.1.1.1")), limits).unwrap(),
+            Err(RateLimited::Ip)
+        );
+
+        age_requests(&conn, 60);
+        assert!(
+            insert_request(&mut conn, request("b", Some("1.1.1.1")), limits)
+                .unwrap()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn ip_is_not_rate_limited_without_ip_limit() {
+        let mut conn = conn();
+
+        let limits = limits(None, None);
+        assert!(
+            insert_request(&mut conn, request("a", Some("1.1.1.1")), limits)
+                .unwrap()
+                .is_ok()
+        );
+        assert!(
+            insert_request(&mut conn, request("b", Some("1.1.1.1")), limits)
+                .unwrap()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn daily_cap_is_enforced() {
+        let mut conn = conn();
+
+        let limits = limits(None, Some(2));
+        let a = insert_request(&mut conn, request("a", None), limits)
+            .unwrap()
+            .unwrap();
+        insert_request(&mut conn, request("b", None), limits)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            insert_request(&mut conn, request("c", None), limits).unwrap(),
+            Err(RateLimited::DailyCap)
+        );
+
+        // failed sends don't count towards the cap
+        mark_failed(&conn, a, "boom").unwrap();
+        assert!(insert_request(&mut conn, request("c", None), limits)
+            .unwrap()
+            .is_ok());
+        assert_eq!(
+            insert_request(&mut conn, request("d", None), limits).unwrap(),
+            Err(RateLimited::DailyCap)
+        );
+
+        // the cap is over the last 24 hours
+        age_requests(&conn, 24 * 60 * 60 + 1);
+        assert!(insert_request(&mut conn, request("d", None), limits)
+            .unwrap()
+            .is_ok());
+    }
+
+    #[test]
+    fn daily_cap_is_counted_per_requester() {
+        let mut conn = conn();
+
+        let limits = limits(None, Some(1));
+        insert_request(&mut conn, request("a", None), limits)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            insert_request(&mut conn, request("b", None), limits).unwrap(),
+            Err(RateLimited::DailyCap)
+        );
+
+        let allowlisted = NewRequest {
+            requester: Some("partner"),
+            ..request("b", None)
+        };
+        assert!(insert_request(&mut conn, allowlisted, limits)
+            .unwrap()
+            .is_ok());
+
+        let allowlisted = NewRequest {
+            requester: Some("partner"),
+            ..request("c", None)
+        };
+        assert_eq!(
+            insert_request(&mut conn, allowlisted, limits).unwrap(),
+            Err(RateLimited::DailyCap)
+        );
+    }
+
+    #[test]
+    fn migrates_legacy_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+
+        conn.execute_batch(
+            "CREATE TABLE requests (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chain_id TEXT NOT NULL,
+                denom TEXT NOT NULL,
+                address TEXT NOT NULL,
+                time TEXT,
+                tx_hash TEXT
+            );
+            INSERT INTO requests (chain_id, denom, address, time, tx_hash) VALUES
+                ('union-devnet-1', 'muno', 'a', datetime('now'), 'ABCDEF'),
+                ('union-devnet-1', 'muno', 'b', datetime('now'), 'ERROR: out of gas'),
+                ('union-devnet-1', 'muno', 'c', datetime('now'), 'ERROR'),
+                ('union-devnet-1', 'muno', 'd', datetime('now'), NULL);",
+        )
+        .unwrap();
+
+        migrate(&conn).unwrap();
+        // migrating again is a noop
+        migrate(&conn).unwrap();
+
+        let status = |id| request_status(&conn, id).unwrap().unwrap();
+
+        let confirmed = status(1);
+        assert_eq!(confirmed.status, RequestState::Confirmed);
+        assert_eq!(confirmed.tx_hash.as_deref(), Some("ABCDEF"));
+        assert_eq!(confirmed.error, None);
+
+        let failed = status(2);
+        assert_eq!(failed.status, RequestState::Failed);
+        assert_eq!(failed.tx_hash, None);
+        assert_eq!(failed.error.as_deref(), Some("out of gas"));
+
+        let failed = status(3);
+        assert_eq!(failed.status, RequestState::Failed);
+        assert_eq!(failed.tx_hash, None);
+        assert_eq!(failed.error.as_deref(), Some("ERROR"));
+
+        let pending = status(4);
+        assert_eq!(pending.status, RequestState::Pending);
+        assert_eq!(pending.attempts, 0);
+    }
+
+    const RETRY: RetryPolicy = RetryPolicy {
+        max_attempts: 3,
+        min_backoff_seconds: 5,
+        max_backoff_seconds: 300,
+    };
+
+    // inserts requests for `n` distinct addresses, returning their ids
+    fn insert_requests(conn: &mut Connection, n: usize) -> Vec<i64> {
+        (0..n)
+            .map(|i| {
+                let address = format!("address-{i}");
+                insert_request(conn, request(&address, None), limits(None, None))
+                    .unwrap()
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    fn ready_ids(conn: &Connection, batch_size: usize) -> Vec<i64> {
+        ready_requests(conn, CHAIN_ID, batch_size)
+            .unwrap()
+            .into_iter()
+            .map(|request| request.id)
+            .collect()
+    }
+
+    // makes requests that are waiting for their backoff ready again
+    fn skip_backoff(conn: &Connection) {
+        conn.execute("UPDATE requests SET next_attempt_at = NULL", ())
+            .unwrap();
+    }
+
+    fn status(conn: &Connection, id: i64) -> RequestStatus {
+        request_status(conn, id).unwrap().unwrap()
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let backoff = (0..=8)
+            .map(|attempts| RETRY.backoff_seconds(attempts))
+            .collect::<Vec<_>>();
+        assert_eq!(backoff, [5, 5, 10, 20, 40, 80, 160, 300, 300]);
+
+        assert_eq!(RETRY.backoff_seconds(u32::MAX), 300);
+    }
+
+    #[test]
+    fn successful_attempt_confirms() {
+        let mut conn = conn();
+        let [id] = insert_requests(&mut conn, 1)[..] else {
+            unreachable!()
+        };
+
+        let state = record_attempt(&conn, (id, 0), Ok("ABCDEF"), false, &RETRY).unwrap();
+        assert_eq!(state, RequestState::Confirmed);
+
+        let status = status(&conn, id);
+        assert_eq!(status.status, RequestState::Confirmed);
+        assert_eq!(status.tx_hash.as_deref(), Some("ABCDEF"));
+        assert_eq!(status.attempts, 1);
+        assert_eq!(status.error, None);
+        assert!(ready_ids(&conn, 10).is_empty());
+    }
+
+    #[test]
+    fn failed_attempt_is_retried_after_backoff() {
+        let mut conn = conn();
+        let [id] = insert_requests(&mut conn, 1)[..] else {
+            unreachable!()
+        };
+
+        let state = record_attempt(&conn, (id, 0), Err("out of gas"), true, &RETRY).unwrap();
+        assert_eq!(state, RequestState::Pending);
+
+        let status = status(&conn, id);
+        assert_eq!(status.status, RequestState::Pending);
+        assert_eq!(status.attempts, 1);
+        assert_eq!(status.error.as_deref(), Some("out of gas"));
+
+        let backoff: i64 = conn
+            .query_row(
+                "SELECT unixepoch(next_attempt_at) - unixepoch('now') FROM requests WHERE id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!((4..=5).contains(&backoff), "{backoff}");
+
+        // not ready until the backoff passed
+        assert!(ready_ids(&conn, 10).is_empty());
+        skip_backoff(&conn);
+        assert_eq!(ready_ids(&conn, 10), [id]);
+    }
+
+    #[test]
+    fn request_fails_after_max_attempts_alone() {
+        let mut conn = conn();
+        let [id] = insert_requests(&mut conn, 1)[..] else {
+            unreachable!()
+        };
+
+        // failing in a batch never fails the request, another request may be at fault
+        let state = record_attempt(&conn, (id, 5), Err("batch failed"), false, &RETRY).unwrap();
+        assert_eq!(state, RequestState::Pending);
+
+        for attempts in 0..2 {
+            let state = record_attempt(&conn, (id, attempts), Err("boom"), true, &RETRY).unwrap();
+            assert_eq!(state, RequestState::Pending);
+        }
+
+        let state = record_attempt(&conn, (id, 2), Err("boom"), true, &RETRY).unwrap();
+        assert_eq!(state, RequestState::Failed);
+
+        let status = status(&conn, id);
+        assert_eq!(status.status, RequestState::Failed);
+        assert_eq!(status.attempts, 3);
+        assert_eq!(status.error.as_deref(), Some("boom"));
+
+        skip_backoff(&conn);
+        assert!(ready_ids(&conn, 10).is_empty());
+    }
+
+    #[test]
+    fn retried_requests_are_sent_in_halved_batches() {
+        let mut conn = conn();
+        let ids = insert_requests(&mut conn, 8);
+
+        assert_eq!(ready_ids(&conn, 8), ids);
+        assert_eq!(ready_ids(&conn, 3), ids[..3]);
+
+        for &id in &ids {
+            record_attempt(&conn, (id, 0), Err("batch failed"), false, &RETRY).unwrap();
+        }
+        skip_backoff(&conn);
+        assert_eq!(ready_ids(&conn, 8), ids[..4]);
+
+        for &id in &ids[..4] {
+            record_attempt(&conn, (id, 1), Err("batch failed"), false, &RETRY).unwrap();
+        }
+        skip_backoff(&conn);
+        // requests with fewer attempts first
+        assert_eq!(ready_ids(&conn, 8), ids[4..]);
+
+        for &id in &ids[4..] {
+            record_attempt(&conn, (id, 1), Err("batch failed"), false, &RETRY).unwrap();
+        }
+        skip_backoff(&conn);
+        assert_eq!(ready_ids(&conn, 8), ids[..2]);
+
+        // batches never shrink below a single request
+        for &id in &ids {
+            record_attempt(&conn, (id, 40), Err("batch failed"), false, &RETRY).unwrap();
+        }
+        skip_backoff(&conn);
+        assert_eq!(ready_ids(&conn, 8), ids[..1]);
+    }
+
+    #[test]
+    fn broadcast_requests_are_not_ready() {
+        let mut conn = conn();
+        let ids = insert_requests(&mut conn, 3);
+
+        mark_broadcast(&conn, &ids[..1], None).unwrap();
+        mark_broadcast(&conn, &ids[1..2], Some("0xabcdef")).unwrap();
+        assert_eq!(ready_ids(&conn, 10), ids[2..]);
+        assert_eq!(status(&conn, ids[0]).status, RequestState::Broadcast);
+    }
+
+    #[test]
+    fn only_broadcast_requests_without_hash_are_reset() {
+        let mut conn = conn();
+        let ids = insert_requests(&mut conn, 3);
+
+        mark_broadcast(&conn, &ids[..1], None).unwrap();
+        mark_broadcast(&conn, &ids[1..2], Some("0xabcdef")).unwrap();
+
+        assert_eq!(reset_broadcast(&conn, "other-chain").unwrap(), 0);
+        assert_eq!(reset_broadcast(&conn, CHAIN_ID).unwrap(), 1);
+
+        // the request with a hash may still be included, so it must be confirmed instead of
+        // being sent again
+        assert_eq!(ready_ids(&conn, 10), [ids[0], ids[2]]);
+
+        let broadcast = broadcast_requests(&conn, CHAIN_ID).unwrap();
+        assert_eq!(broadcast.len(), 1);
+        assert_eq!(broadcast[0].0.id, ids[1]);
+        assert_eq!(broadcast[0].1, "0xabcdef");
+        assert_eq!(status(&conn, ids[1]).status, RequestState::Broadcast);
+
+        // a failed transfer is sent again, an included one never
+        record_attempt(&conn, (ids[1], 0), Err("reverted"), true, &RETRY).unwrap();
+        skip_backoff(&conn);
+        assert_eq!(ready_ids(&conn, 10), [ids[0], ids[2]]);
+        assert_eq!(status(&conn, ids[1]).tx_hash, None);
+        assert!(broadcast_requests(&conn, CHAIN_ID).unwrap().is_empty());
+    }
+}
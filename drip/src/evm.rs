@@ -0,0 +1,200 @@
+use std::time::Duration;
+
+use alloy::{
+    network::{EthereumWallet, TransactionBuilder},
+    primitives::{Address, TxHash, B256, U256},
+    providers::{DynProvider, Provider, ProviderBuilder},
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+};
+use anyhow::{anyhow, bail};
+use serde::{Deserialize, Serialize};
+use tokio::time::{sleep, Instant};
+use tracing::{info, instrument};
+use unionlabs::{primitives::H256, ErrorReporter};
+
+use crate::{Coin, SendRequest};
+
+/// How long to wait for a submitted transfer to be included.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(120);
+/// How often to check whether a submitted transfer was included.
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+alloy::sol! {
+    #[sol(rpc)]
+    interface IERC20 {
+        function transfer(address to, uint256 value) external returns (bool);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvmConfig {
+    pub rpc_url: String,
+    pub signer: H256,
+}
+
+/// Ensures `address` is a hex encoded 20 byte address.
+pub fn validate_address(address: &str) -> Result<(), String> {
+    address
+        .parse::<Address>()
+        .map(|_| ())
+        .map_err(|err| format!("invalid evm address `{address}`: {err}"))
+}
+
+/// The outcome of waiting for a submitted transfer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Confirmation {
+    /// The transfer was included, with the transaction hash that will be displayed to users.
+    Included(String),
+    /// The transfer reverted or was dropped, it can be sent again.
+    Failed(String),
+    /// The transfer may still be included, i.e. it is still pending or the rpc failed. It must
+    /// not be sent again, but confirmed by its hash later.
+    Unknown(String),
+}
+
+#[derive(Clone)]
+pub struct ChainClient {
+    pub chain_id: String,
+    pub coins: Vec<Coin>,
+    pub provider: DynProvider,
+}
+
+impl ChainClient {
+    #[instrument(skip_all, fields(%chain_id))]
+    pub async fn new(chain_id: &str, config: &EvmConfig, coins: &[Coin]) -> Self {
+        let signer = PrivateKeySigner::from_bytes(&B256::from(*config.signer.get()))
+            .expect("invalid signer");
+
+        info!(address = %signer.address(), "loaded signer");
+
+        let provider = DynProvider::new(
+            ProviderBuilder::new()
+                .wallet(EthereumWallet::new(signer))
+                .connect(&config.rpc_url)
+                .await
+                .unwrap(),
+        );
+
+        let actual_chain_id = provider.get_chain_id().await.unwrap();
+
+        // Check if we are connected to a chain with the correct chain_id
+        assert_eq!(
+            actual_chain_id.to_string(),
+            chain_id,
+            "rpc_url {} is not for chain {}",
+            config.rpc_url,
+            chain_id
+        );
+
+        Self {
+            chain_id: chain_id.to_owned(),
+            coins: coins.to_vec(),
+            provider,
+        }
+    }
+
+    /// Waits for a submitted transfer to be included.
+    pub async fn confirm(&self, tx_hash: TxHash) -> Confirmation {
+        let deadline = Instant::now() + CONFIRM_TIMEOUT;
+
+        loop {
+            match self.provider.get_transaction_receipt(tx_hash).await {
+                Ok(Some(receipt)) if receipt.status() => {
+                    info!(%tx_hash, gas_used = receipt.gas_used, "transfer included");
+                    return Confirmation::Included(tx_hash.to_string());
+                }
+                Ok(Some(_)) => {
+                    return Confirmation::Failed(format!("transaction {tx_hash} reverted"))
+                }
+                Ok(None) => {}
+                Err(err) => return Confirmation::Unknown(ErrorReporter(err).to_string()),
+            }
+
+            if Instant::now() >= deadline {
+                break;
+            }
+            sleep(CONFIRM_POLL_INTERVAL).await;
+        }
+
+        // only a transaction that the node doesn't know anymore can't be included later
+        match self.provider.get_transaction_by_hash(tx_hash).await {
+            Ok(None) => Confirmation::Failed(format!("transaction {tx_hash} was dropped")),
+            Ok(Some(_)) => Confirmation::Unknown(format!("transaction {tx_hash} is still pending")),
+            Err(err) => Confirmation::Unknown(ErrorReporter(err).to_string()),
+        }
+    }
+
+    /// Submits a transfer for the request. Coins without an `erc20_address` are sent as the
+    /// native token of the chain.
+    #[instrument(
+        skip_all,
+        fields(
+            chain_id = %self.chain_id,
+            id = request.id
+        )
+    )]
+    pub async fn submit(&self, request: &SendRequest) -> anyhow::Result<TxHash> {
+        let to = request.receiver.parse::<Address>()?;
+        let amount = U256::from(request.amount);
+
+        let coin = self
+            .coins
+            .iter()
+            .find(|coin| coin.denom == request.denom)
+            .ok_or_else(|| anyhow!("unknown denom {}", request.denom))?;
+
+        let pending = match coin.erc20_address {
+            Some(token) => {
+                let token = IERC20::new(Address::from(*token.get()), &self.provider);
+
+                let call = token.transfer(to, amount);
+                if !call.call().await?._0 {
+                    bail!("transfer of {} returned false", request.denom);
+                }
+
+                call.send().await?
+            }
+            None => {
+                self.provider
+                    .send_transaction(TransactionRequest::default().with_to(to).with_value(amount))
+                    .await?
+            }
+        };
+
+        let tx_hash = *pending.tx_hash();
+
+        info!(
+            id = request.id,
+            %tx_hash,
+            denom = %request.denom,
+            "submitted transfer"
+        );
+
+        Ok(tx_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_addresses() {
+        validate_address("0x0000000000000000000000000000000000000000").unwrap();
+        validate_address("0xbe68fc2d8249eb60bfcf0e71d5a0d2f2e292c4ed").unwrap();
+        validate_address("0xBe68fC2d8249eb60bfCf0e71D5A0d2F2e292c4eD").unwrap();
+    }
+
+    #[test]
+    fn invalid_addresses() {
+        // too short, too long
+        validate_address("0xbe68fc2d8249eb60bfcf0e71d5a0d2f2e292c4").unwrap_err();
+        validate_address("0xbe68fc2d8249eb60bfcf0e71d5a0d2f2e292c4edaa").unwrap_err();
+        // not hex
+        validate_address("0xzz68fc2d8249eb60bfcf0e71d5a0d2f2e292c4ed").unwrap_err();
+        // not an evm address at all
+        validate_address("union1jk9psyhvgkrt2cumz8eytll2244m2nnz4yt2g2").unwrap_err();
+        validate_address("").unwrap_err();
+    }
+}
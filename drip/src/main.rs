@@ -1,41 +1,43 @@
 use std::{
-    collections::HashMap, ffi::OsString, fmt, fs::read_to_string, rc::Rc, sync::Arc, time::Duration,
+    ffi::OsString,
+    fmt,
+    fs::read_to_string,
+    net::{IpAddr, SocketAddr},
+    time::Duration,
 };
 
 use async_graphql::{http::GraphiQLSource, *};
-use async_graphql_axum::GraphQL;
-use async_sqlite::{
-    rusqlite::{self, params, OptionalExtension},
-    JournalMode, Pool, PoolBuilder,
-};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use async_sqlite::{rusqlite::params, JournalMode, Pool, PoolBuilder};
 use axum::{
+    extract::{ConnectInfo, State},
+    http::HeaderMap,
     response::{self, IntoResponse},
     routing::get,
     Router,
 };
-use chrono::{NaiveDateTime, Utc};
+use chrono::Utc;
 use clap::Parser;
-use cosmos_client::{
-    gas::{
-        any::GasFiller,
-        feemarket::{self},
-        fixed, osmosis_eip1559_feemarket,
-    },
-    rpc::{Rpc, RpcT},
-    wallet::{LocalSigner, WalletT},
-    TxClient,
-};
-use prost::{Message, Name};
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
 use tokio::net::TcpListener;
 use tracing::{debug, error, info, instrument, warn, Instrument};
 use tracing_subscriber::EnvFilter;
 use unionlabs::{
-    primitives::{encoding::HexUnprefixed, H256},
+    primitives::{encoding::HexUnprefixed, H160},
     ErrorReporter,
 };
 
-mod turnstile;
+use crate::{
+    cosmos::CosmosConfig,
+    db::{NewRequest, RateLimits, RequestState, RequestStatus, RetryPolicy},
+    evm::{Confirmation, EvmConfig},
+    verifier::{AnyVerifier, Requester, Verification, Verifier, VerifierConfig, Verifiers},
+};
+
+mod cosmos;
+mod db;
+mod evm;
+mod verifier;
 
 const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
 
@@ -64,8 +66,6 @@ async fn main() {
         }
     }
 
-    let secret = config.secret.clone().map(CaptchaSecret);
-
     info!("creating database");
     let pool = PoolBuilder::new()
         .path("db.sqlite3")
@@ -74,36 +74,21 @@ async fn main() {
         .await
         .expect("opening db");
 
-    pool.conn(|conn| {
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS requests (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                chain_id TEXT NOT NULL,
-                denom TEXT NOT NULL,
-                address TEXT NOT NULL,
-                time TEXT,
-                tx_hash TEXT
-            )",
-            (), // empty list of parameters.
-        )?;
-        Ok(())
-    })
-    .await
-    .unwrap();
+    pool.conn(db::migrate).await.unwrap();
 
     let schema = Schema::build(
         Query,
         Mutation {
             ratelimit_seconds: config.ratelimit_seconds,
             chains: config.clone().chains,
+            allowlist: config.allowlist.clone(),
         },
         EmptySubscription,
     )
     .data(pool.clone())
     .data(MaxRequestPolls(config.max_request_polls))
-    .data(config.bypass_secret.clone().map(CaptchaBypassSecret))
+    .data(config.verifiers())
     .data(MaxPaginatedResponses(max_paginated_responses))
-    .data(secret)
     .finish();
 
     let config = config.clone();
@@ -111,20 +96,75 @@ async fn main() {
     for chain in config.chains.clone() {
         let pool = pool.clone();
         tokio::spawn(
-            poll_loop(pool, chain, batch_size), // .instrument(chain_polling_span.clone()),
+            poll_loop(pool, chain, batch_size, config.retry), // .instrument(chain_polling_span.clone()),
         );
     }
 
-    let router = Router::new().route("/", get(graphiql).post_service(GraphQL::new(schema)));
+    let router = Router::new()
+        .route("/", get(graphiql).post(graphql))
+        .with_state(AppState {
+            schema,
+            ip_header: config.ip_header,
+        });
 
     info!("starting server");
-    axum::serve(TcpListener::bind("0.0.0.0:8000").await.unwrap(), router)
-        .await
-        .unwrap();
+    axum::serve(
+        TcpListener::bind("0.0.0.0:8000").await.unwrap(),
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
+}
+
+type DripSchema = Schema<Query, Mutation, EmptySubscription>;
+
+#[derive(Clone)]
+struct AppState {
+    schema: DripSchema,
+    ip_header: Option<String>,
+}
+
+/// The IP of the client that sent the request.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+async fn graphql(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    req: GraphQLRequest,
+) -> GraphQLResponse {
+    let ip = client_ip(&headers, state.ip_header.as_deref(), addr.ip());
+
+    let req = req.into_inner().data(ClientIp(ip));
+
+    state.schema.execute(req).await.into()
+}
+
+/// The IP of the client, read from `ip_header` if it is set.
+fn client_ip(headers: &HeaderMap, ip_header: Option<&str>, peer: IpAddr) -> IpAddr {
+    // behind a proxy, the peer address is the proxy itself
+    match ip_header {
+        Some(header) => {
+            let ip = headers
+                .get(header)
+                .and_then(|value| value.to_str().ok())
+                // X-Forwarded-For style headers are appended to by every hop, only the last value
+                // (added by the trusted proxy) can't be set by the client
+                .and_then(|value| value.rsplit(',').next())
+                .and_then(|value| value.trim().parse().ok());
+
+            ip.unwrap_or_else(|| {
+                warn!(%header, %peer, "missing or invalid ip header, using the peer address");
+                peer
+            })
+        }
+        None => peer,
+    }
 }
 
 #[instrument(skip_all, fields(chain_id = %chain.id))]
-async fn poll_loop(pool: Pool, chain: Chain, batch_size: usize) {
+async fn poll_loop(pool: Pool, chain: Chain, batch_size: usize, retry: RetryPolicy) {
     info!("spawning worker for chain");
 
     loop {
@@ -138,115 +178,63 @@ async fn poll_loop(pool: Pool, chain: Chain, batch_size: usize) {
                 // make sure to panic *here* so that the tokio task will catch the panic!
                 info!("creating chain client");
                 let chain_client = ChainClient::new(&chain).await;
+                let chain_id = chain.id.clone();
+                let reset = pool
+                    .conn(move |conn| db::reset_broadcast(conn, &chain_id))
+                    .await
+                    .expect("pool error");
+                if reset > 0 {
+                    warn!(
+                        reset,
+                        "resending requests that were broadcast when the worker stopped"
+                    );
+                }
+
                 info!("entering polling loop");
                 loop {
-                    let chain = chain.clone();
-                    let requests: Vec<SendRequest> = pool
-                        .conn(move |conn| {
-                            let mut stmt = conn
-                                .prepare_cached(
-                                    "SELECT id, denom, address FROM requests 
-                                     WHERE tx_hash IS NULL AND chain_id IS ?1 LIMIT ?2",
-                                )
-                                .expect("SQL statement is valid");
-
-                            let mut rows = stmt
-                                .query((&chain.id, batch_size as i64))
-                                .expect("can't query rows");
-
-                            let mut requests = vec![];
-
-                            while let Some(row) = rows.next().expect("could not read row") {
-                                let id: i64 = row.get(0).expect("could not read id");
-                                let denom: String = row.get(1).expect("could not read denom");
-                                let receiver: String = row.get(2).expect("could not read address");
-
-                                let Some(coin) =
-                                    chain.coins.iter().find(|coin| coin.denom == denom)
-                                else {
-                                    error!(
-                            %denom,
-                            "dropping request for unknown denom");
-                                    break;
-                                };
-
-                                requests.push(SendRequest {
-                                    id,
-                                    receiver,
-                                    denom,
-                                    amount: coin.amount,
-                                });
-                            }
-
-                            Ok(requests)
-                        })
+                    chain_client
+                        .confirm_broadcast(&pool, &chain.id, &retry)
+                        .await;
+
+                    let chain_id = chain.id.clone();
+                    let ready = pool
+                        .conn(move |conn| db::ready_requests(conn, &chain_id, batch_size))
                         .await
                         .expect("pool error");
 
-                    if requests.is_empty() {
+                    if ready.is_empty() {
                         debug!("no requests in queue");
                         tokio::time::sleep(Duration::from_millis(1000)).await;
                         continue;
                     }
-                    let mut i = 0;
-
-                    // try sending batch 5 times
-                    let result = loop {
-                        let send_res = chain_client.send(&requests).await;
-
-                        match send_res {
-                            Err(err) => {
-                                if i >= 5 {
-                                    break format!("ERROR: {}", ErrorReporter(&*err));
-                                }
-                                warn!(
-                                    err = %ErrorReporter(&*err),
-                                    attempt = i,
-                                    "unable to submit transaction"
-                                );
-                                i += 1;
-                            }
-                            // this will be displayed to users, print the hash in the same way that cosmos sdk does
-                            Ok(tx_hash) => {
-                                break tx_hash
-                                    .into_encoding::<HexUnprefixed>()
-                                    .to_string()
-                                    .to_uppercase()
-                            }
+
+                    let mut requests = vec![];
+                    for request in ready {
+                        let Some(coin) =
+                            chain.coins.iter().find(|coin| coin.denom == request.denom)
+                        else {
+                            error!(denom = %request.denom, id = request.id, "unknown denom");
+                            let error = format!("unknown denom {}", request.denom);
+                            pool.conn(move |conn| db::mark_failed(conn, request.id, &error))
+                                .await
+                                .expect("pool error");
+                            continue;
                         };
-                    };
-
-                    pool.conn(move |conn| {
-                        debug!("loading vtab array module required for `IN (1,42,76,...)`");
-                        rusqlite::vtab::array::load_module(conn)
-                            .expect("error loading vtab array module");
-
-                        let mut stmt = conn
-                            .prepare_cached(
-                                "UPDATE requests SET tx_hash = ?1 WHERE id IN rarray(?2)",
-                            )
-                            .expect("???");
-
-                        // https://docs.rs/rusqlite/latest/rusqlite/vtab/array/index.html
-                        let rows_modified = stmt
-                            .execute((
-                                &result,
-                                Rc::new(
-                                    requests
-                                        .iter()
-                                        .map(|req| req.id)
-                                        .map(rusqlite::types::Value::from)
-                                        .collect::<Vec<rusqlite::types::Value>>(),
-                                ),
-                            ))
-                            .expect("can't query rows");
-
-                        info!(rows_modified, "updated requests");
-
-                        Ok(())
-                    })
-                    .await
-                    .expect("pool error");
+
+                        requests.push(SendRequest {
+                            id: request.id,
+                            receiver: request.address,
+                            denom: request.denom,
+                            amount: coin.amount,
+                            attempts: request.attempts,
+                        });
+                    }
+
+                    if requests.is_empty() {
+                        continue;
+                    }
+
+                    chain_client.send(&pool, &requests, &retry).await;
                 }
             }
             .in_current_span(),
@@ -298,252 +286,365 @@ pub struct Config {
     pub chains: Vec<Chain>,
     #[serde(default)]
     pub log_format: LogFormat,
+    /// Verifiers for the token of requests. A request is accepted if any of them accepts it.
+    #[serde(default)]
+    pub verifiers: Vec<VerifierConfig>,
+    /// Shorthand for a turnstile verifier with this secret.
     #[serde(default)]
     pub secret: Option<String>,
+    /// Shorthand for a stub verifier that accepts this token.
     #[serde(default)]
     pub bypass_secret: Option<String>,
+    /// Addresses and API keys with their own rate limits.
+    #[serde(default)]
+    pub allowlist: Vec<AllowlistEntry>,
+    #[serde(default)]
+    pub retry: RetryPolicy,
     pub max_request_polls: u32,
+    /// The default minimum seconds between two requests for the same address, chain and denom.
     #[serde(default)]
     pub ratelimit_seconds: u32,
+    /// Read the client IP from this header (i.e. `cf-connecting-ip` or `x-forwarded-for`)
+    /// instead of the peer address. Set this when running behind a proxy.
+    #[serde(default)]
+    pub ip_header: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Chain {
     pub id: String,
-    pub bech32_prefix: String,
-    pub rpc_url: String,
-    pub gas_config: GasFillerConfig,
-    pub signer: H256,
+    #[serde(flatten)]
+    pub kind: ChainKind,
     pub coins: Vec<Coin>,
-    pub memo: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case", tag = "type", content = "config")]
-pub enum GasFillerConfig {
-    // fixed gas filler is it's own config
-    Fixed(fixed::GasFiller),
-    Feemarket(FeemarketConfig),
-    OsmosisEip1559Feemarket(OsmosisEip1559FeemarketConfig),
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ChainKind {
+    Cosmos(CosmosConfig),
+    Evm(EvmConfig),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub struct FeemarketConfig {
-    pub max_gas: u64,
-    pub gas_multiplier: Option<f64>,
-    pub denom: Option<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub struct OsmosisEip1559FeemarketConfig {
-    pub max_gas: u64,
-    pub gas_multiplier: Option<f64>,
-    pub base_fee_multiplier: Option<f64>,
-    pub denom: Option<String>,
-}
+// chains without a `type` are cosmos chains, so configs from before evm support keep working
+impl<'de> Deserialize<'de> for ChainKind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "snake_case", tag = "type")]
+        enum Tagged {
+            Cosmos(CosmosConfig),
+            Evm(EvmConfig),
+        }
 
-impl GasFillerConfig {
-    async fn into_gas_filler(self, rpc_url: String) -> GasFiller {
-        match self {
-            GasFillerConfig::Fixed(config) => GasFiller::Fixed(config),
-            GasFillerConfig::Feemarket(config) => GasFiller::Feemarket(
-                feemarket::GasFiller::new(feemarket::Config {
-                    rpc_url,
-                    max_gas: config.max_gas,
-                    gas_multiplier: config.gas_multiplier,
-                    denom: config.denom,
-                })
-                .await
-                .expect("failed to build gas filler"),
-            ),
-            GasFillerConfig::OsmosisEip1559Feemarket(config) => GasFiller::OsmosisEip1559Feemarket(
-                osmosis_eip1559_feemarket::GasFiller::new(osmosis_eip1559_feemarket::Config {
-                    rpc_url,
-                    max_gas: config.max_gas,
-                    gas_multiplier: config.gas_multiplier,
-                    base_fee_multiplier: config.base_fee_multiplier,
-                    denom: config.denom,
-                })
-                .await
-                .expect("failed to build gas filler"),
-            ),
+        let mut value = serde_json::Value::deserialize(deserializer)?;
+        if let Some(object) = value.as_object_mut() {
+            object.entry("type").or_insert_with(|| "cosmos".into());
         }
+
+        Ok(
+            match Tagged::deserialize(value).map_err(de::Error::custom)? {
+                Tagged::Cosmos(config) => ChainKind::Cosmos(config),
+                Tagged::Evm(config) => ChainKind::Evm(config),
+            },
+        )
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Coin {
     pub denom: String,
-    pub amount: u64,
+    pub amount: u128,
+    /// The token contract on evm chains. If not set, the native token of the chain is sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub erc20_address: Option<H160>,
+    /// Overrides the global `ratelimit_seconds` for this denom.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_ratelimit_seconds: Option<u32>,
+    /// Minimum seconds between two requests from the same IP for this denom.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_ratelimit_seconds: Option<u32>,
+    /// Maximum amount of this denom that is handed out within 24 hours.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_cap: Option<u128>,
 }
 
-pub struct MaxRequestPolls(pub u32);
-// pub struct Bech32Prefix(pub String);
-pub struct CaptchaBypassSecret(pub String);
-
-#[derive(Clone)]
-struct ChainClient {
-    pub chain: Chain,
-    pub cosmos_ctx: Arc<TxClient<LocalSigner, Rpc, GasFiller>>,
+impl Coin {
+    fn rate_limits(&self, default_ratelimit_seconds: u32) -> RateLimits {
+        RateLimits {
+            address_seconds: self
+                .address_ratelimit_seconds
+                .unwrap_or(default_ratelimit_seconds),
+            ip_seconds: self.ip_ratelimit_seconds,
+            daily_requests: self
+                .daily_cap
+                .map(|cap| (cap / self.amount.max(1)).try_into().unwrap_or(u64::MAX)),
+        }
+    }
 }
 
-impl ChainClient {
-    #[instrument(skip_all, fields(chain_id = %chain.id))]
-    pub async fn new(chain: &Chain) -> Self {
-        let rpc = Rpc::new(chain.rpc_url.clone()).await.unwrap();
-
-        let bech32_prefix = rpc
-            .client()
-            .grpc_abci_query::<_, protos::cosmos::auth::v1beta1::Bech32PrefixResponse>(
-                "/cosmos.auth.v1beta1.Query/Bech32Prefix",
-                &protos::cosmos::auth::v1beta1::Bech32PrefixRequest {},
-                None,
-                false,
-            )
-            .await
-            .unwrap()
-            .into_result()
-            .unwrap()
-            .unwrap()
-            .bech32_prefix;
-
-        let gas_filler = chain
-            .gas_config
-            .clone()
-            .into_gas_filler(chain.rpc_url.clone())
-            .await;
-
-        let ctx = TxClient::new(
-            LocalSigner::new(chain.signer, bech32_prefix),
-            rpc,
-            gas_filler,
-        );
-
-        let chain_id = ctx.rpc().chain_id();
+impl Config {
+    fn verifiers(&self) -> Verifiers {
+        let mut verifiers = self.verifiers.clone();
+
+        if let Some(token) = &self.bypass_secret {
+            verifiers.insert(
+                0,
+                VerifierConfig::Stub {
+                    token: Some(token.clone()),
+                },
+            );
+        }
 
-        // Check if we are connected to a chain with the correct chain_id
-        assert_eq!(
-            chain_id, chain.id,
-            "rpc_url {} is not for chain {}",
-            chain.rpc_url, chain.id
-        );
+        if let Some(secret) = &self.secret {
+            verifiers.push(VerifierConfig::Turnstile {
+                secret: secret.clone(),
+            });
+        }
 
-        Self {
-            chain: chain.clone(),
-            cosmos_ctx: Arc::new(ctx),
+        // without a secret, drip has always accepted every request
+        if self.verifiers.is_empty() && self.secret.is_none() {
+            verifiers.push(VerifierConfig::Stub { token: None });
         }
+
+        Verifiers(verifiers.into_iter().map(AnyVerifier::from).collect())
     }
 }
 
+/// Addresses and API keys that get their own rate limits instead of the ones of the coin.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct SendRequest {
-    pub id: i64,
-    pub receiver: String,
-    pub denom: String,
-    pub amount: u64,
+pub struct AllowlistEntry {
+    pub name: String,
+    #[serde(default)]
+    pub addresses: Vec<String>,
+    /// Ids of keys of an `api_key` verifier.
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+    /// Minimum seconds between two requests for the same address. Required, so an entry never
+    /// lifts the rate limit by accident.
+    pub ratelimit_seconds: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_ratelimit_seconds: Option<u32>,
+    /// Maximum number of requests per chain and denom within 24 hours.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_requests: Option<u64>,
 }
 
-struct AggregatedSendRequest {
-    denom: String,
-    total_amount: u64,
+impl AllowlistEntry {
+    fn matches(&self, requester: &Requester, address: &str) -> bool {
+        let api_key = match requester {
+            Requester::ApiKey(id) => self.api_keys.contains(id),
+            Requester::Anonymous => false,
+        };
+
+        api_key || self.addresses.iter().any(|allowed| allowed == address)
+    }
+
+    fn rate_limits(&self) -> RateLimits {
+        RateLimits {
+            address_seconds: self.ratelimit_seconds,
+            ip_seconds: self.ip_ratelimit_seconds,
+            daily_requests: self.daily_requests,
+        }
+    }
 }
 
-trait SendRequestAggregator {
-    fn aggregate_by_denom(&self) -> Vec<AggregatedSendRequest>;
+pub struct MaxRequestPolls(pub u32);
+
+#[derive(Clone)]
+enum ChainClient {
+    Cosmos(cosmos::ChainClient),
+    Evm(evm::ChainClient),
 }
 
-impl SendRequestAggregator for Vec<SendRequest> {
-    fn aggregate_by_denom(&self) -> Vec<AggregatedSendRequest> {
-        let mut denom_map: HashMap<String, (u64, Vec<(String, u64)>)> = HashMap::new();
-
-        // Iterate over the requests and populate the hashmap
-        for req in self {
-            let entry = denom_map
-                .entry(req.denom.clone())
-                .or_insert((0, Vec::new()));
-            entry.0 += req.amount; // Update the total amount for this denom
-            entry.1.push((req.receiver.clone(), req.amount)); // Add (receiver, amount) to the list
+impl ChainClient {
+    async fn new(chain: &Chain) -> Self {
+        match &chain.kind {
+            ChainKind::Cosmos(config) => {
+                ChainClient::Cosmos(cosmos::ChainClient::new(&chain.id, config).await)
+            }
+            ChainKind::Evm(config) => {
+                ChainClient::Evm(evm::ChainClient::new(&chain.id, config, &chain.coins).await)
+            }
         }
+    }
 
-        denom_map
-            .into_iter()
-            .map(|(denom, (total_amount, _))| AggregatedSendRequest {
-                denom,
-                total_amount,
-            })
-            .collect()
+    /// Sends the requests and records the outcome of each request.
+    async fn send(&self, pool: &Pool, requests: &[SendRequest], retry: &RetryPolicy) {
+        match self {
+            ChainClient::Cosmos(client) => {
+                let ids = requests.iter().map(|req| req.id).collect::<Vec<_>>();
+                pool.conn(move |conn| db::mark_broadcast(conn, &ids, None))
+                    .await
+                    .expect("pool error");
+
+                let outcome = match client.send(requests).await {
+                    // this will be displayed to users, print the hash in the same way that cosmos sdk does
+                    Ok(tx_hash) => Ok(tx_hash
+                        .into_encoding::<HexUnprefixed>()
+                        .to_string()
+                        .to_uppercase()),
+                    Err(err) => {
+                        warn!(
+                            err = %ErrorReporter(&*err),
+                            requests.len = requests.len(),
+                            "unable to submit transaction"
+                        );
+                        Err(ErrorReporter(&*err).to_string())
+                    }
+                };
+
+                record_attempts(
+                    pool,
+                    requests
+                        .iter()
+                        .map(|req| ((req.id, req.attempts), outcome.clone()))
+                        .collect(),
+                    requests.len() == 1,
+                    retry,
+                )
+                .await;
+            }
+            ChainClient::Evm(client) => {
+                let mut pending = vec![];
+
+                for request in requests {
+                    match client.submit(request).await {
+                        Ok(tx_hash) => {
+                            let (id, hash) = (request.id, tx_hash.to_string());
+                            pool.conn(move |conn| db::mark_broadcast(conn, &[id], Some(&hash)))
+                                .await
+                                .expect("pool error");
+                            pending.push((request, tx_hash));
+                        }
+                        Err(err) => {
+                            warn!(
+                                err = %ErrorReporter(&*err),
+                                id = request.id,
+                                "unable to submit transaction"
+                            );
+                            let outcome = Err(ErrorReporter(&*err).to_string());
+                            let attempt = (request.id, request.attempts);
+                            record_attempts(pool, vec![(attempt, outcome)], true, retry).await;
+                        }
+                    }
+                }
+
+                for (request, tx_hash) in pending {
+                    let confirmation = client.confirm(tx_hash).await;
+                    record_confirmation(pool, (request.id, request.attempts), confirmation, retry)
+                        .await;
+                }
+            }
+        }
     }
-}
 
-impl ChainClient {
-    /// `MultiSend` to the specified addresses. Will return `None` if there are no signers available.
-    #[instrument(
-        skip_all,
-        fields(
-            chain_id = %self.chain.id,
-            requests.len = %requests.len()
-        )
-    )]
-    async fn send(&self, requests: &Vec<SendRequest>) -> anyhow::Result<H256> {
-        let agg_reqs = requests.aggregate_by_denom();
-
-        let msg = protos::cosmos::bank::v1beta1::MsgMultiSend {
-            // this is required to be one element
-            inputs: vec![protos::cosmos::bank::v1beta1::Input {
-                address: self.cosmos_ctx.wallet().address().to_string(),
-                coins: agg_reqs
-                    .iter()
-                    .map(|agg_req| protos::cosmos::base::v1beta1::Coin {
-                        denom: agg_req.denom.to_string(),
-                        amount: agg_req.total_amount.to_string(),
-                    })
-                    .collect(),
-            }],
-            outputs: requests
-                .iter()
-                .map(|req| protos::cosmos::bank::v1beta1::Output {
-                    address: req.receiver.clone(),
-                    coins: vec![protos::cosmos::base::v1beta1::Coin {
-                        denom: req.denom.clone(),
-                        amount: req.amount.to_string(),
-                    }],
-                })
-                .collect(),
+    /// Confirms the requests that were left broadcast with a known transaction, i.e. by a
+    /// previous worker or because their confirmation failed. Such requests are never sent again,
+    /// unless their transaction is known to have failed.
+    async fn confirm_broadcast(&self, pool: &Pool, chain_id: &str, retry: &RetryPolicy) {
+        // cosmos requests are marked as broadcast without a transaction hash
+        let ChainClient::Evm(client) = self else {
+            return;
         };
 
-        let msg = protos::google::protobuf::Any {
-            type_url: protos::cosmos::bank::v1beta1::MsgMultiSend::type_url(),
-            value: msg.encode_to_vec(),
-        };
+        let chain_id = chain_id.to_owned();
+        let broadcast = pool
+            .conn(move |conn| db::broadcast_requests(conn, &chain_id))
+            .await
+            .expect("pool error");
+
+        for (request, tx_hash) in broadcast {
+            let confirmation = match tx_hash.parse() {
+                Ok(tx_hash) => client.confirm(tx_hash).await,
+                Err(err) => Confirmation::Failed(format!("invalid tx hash {tx_hash}: {err}")),
+            };
+            record_confirmation(pool, (request.id, request.attempts), confirmation, retry).await;
+        }
+    }
+}
 
-        let (tx_hash, res) = self
-            .cosmos_ctx
-            .broadcast_tx_commit([msg], self.chain.memo.clone(), true)
-            .await?;
+/// Records the outcome of a submitted evm transfer. Transfers that may still be included stay
+/// broadcast, they are confirmed again later instead of being sent again.
+async fn record_confirmation(
+    pool: &Pool,
+    attempt: (i64, u32),
+    confirmation: Confirmation,
+    retry: &RetryPolicy,
+) {
+    let outcome = match confirmation {
+        Confirmation::Included(tx_hash) => Ok(tx_hash),
+        Confirmation::Failed(error) => Err(error),
+        Confirmation::Unknown(error) => {
+            warn!(id = attempt.0, %error, "transfer not confirmed yet, checking again later");
+            return;
+        }
+    };
 
-        info!(
-            ?requests,
-            %tx_hash,
-            gas_used = %res.tx_result.gas_used,
-            "submitted multisend"
-        );
+    record_attempts(pool, vec![(attempt, outcome)], true, retry).await;
+}
 
-        Ok(tx_hash)
-    }
+/// Records the outcome of attempts, given as the id of the request and its previous attempts.
+async fn record_attempts(
+    pool: &Pool,
+    outcomes: Vec<((i64, u32), Result<String, String>)>,
+    sent_alone: bool,
+    retry: &RetryPolicy,
+) {
+    let outcomes = outcomes
+        .into_iter()
+        .map(|((id, attempts), outcome)| (id, attempts, outcome))
+        .collect::<Vec<_>>();
+    let retry = *retry;
+
+    pool.conn(move |conn| {
+        let (mut confirmed, mut pending, mut failed) = (0, 0, 0);
+
+        for (id, attempts, outcome) in &outcomes {
+            let state = db::record_attempt(
+                conn,
+                (*id, *attempts),
+                outcome.as_deref().map_err(String::as_str),
+                sent_alone,
+                &retry,
+            )?;
+
+            match state {
+                RequestState::Confirmed => confirmed += 1,
+                RequestState::Failed => {
+                    failed += 1;
+                    warn!(id, "request failed after {} attempts", attempts + 1);
+                }
+                _ => pending += 1,
+            }
+        }
+
+        info!(confirmed, pending, failed, "updated requests");
+
+        Ok(())
+    })
+    .await
+    .expect("pool error");
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SendRequest {
+    pub id: i64,
+    pub receiver: String,
+    pub denom: String,
+    pub amount: u128,
+    /// The number of previous attempts to send this request.
+    pub attempts: u32,
 }
 
 struct Mutation {
     ratelimit_seconds: u32,
     chains: Vec<Chain>,
+    allowlist: Vec<AllowlistEntry>,
 }
 
-#[derive(Debug)]
-pub struct CaptchaSecret(pub String);
-
 #[Object]
 impl Mutation {
+    /// `captcha_token` is checked by the configured verifiers, and can be a captcha response or a
+    /// token signed with an API key.
     #[instrument(skip_all, fields(%chain_id, %address, %denom))]
     async fn send<'ctx>(
         &self,
@@ -553,9 +654,10 @@ impl Mutation {
         address: String,
         denom: String,
     ) -> Result<String> {
-        let secret = ctx.data::<Option<CaptchaSecret>>().unwrap();
-        let bypass_secret = ctx.data::<Option<CaptchaBypassSecret>>().unwrap();
+        let verifiers = ctx.data::<Verifiers>().unwrap();
         let max_request_polls = ctx.data::<MaxRequestPolls>().unwrap();
+        let client_ip = ctx.data_opt::<ClientIp>().map(|ClientIp(ip)| *ip);
+        let ip = client_ip.map(|ip| ip.to_string());
 
         // Get chain config
         let Some(chain) = self.chains.iter().find(|c| c.id == chain_id) else {
@@ -563,118 +665,113 @@ impl Mutation {
         };
 
         // Ensure denom exists for chain
-        if !chain.coins.iter().any(|coin| coin.denom == denom) {
+        let Some(coin) = chain.coins.iter().find(|coin| coin.denom == denom) else {
             return Err(format!("invalid denom {denom}").into());
         };
 
-        let allow_bypass = bypass_secret
-            .as_ref()
-            .is_some_and(|CaptchaBypassSecret(secret)| secret == &captcha_token);
-
-        if let Some(secret) = secret {
-            if !allow_bypass {
-                let response = turnstile::verify(&captcha_token, &secret.0)
-                    .await
-                    .map_err(|err| format!("failed to verify turnstile: {:?}", err))?;
-
-                if !response.success {
-                    return Err(format!("failed turnstile request: {:?}", response).into());
-                }
-            }
-        }
+        let verification = verifiers
+            .verify(
+                &captcha_token,
+                &verifier::Request {
+                    chain_id: &chain_id,
+                    denom: &denom,
+                    address: &address,
+                    ip: client_ip,
+                },
+            )
+            .await?;
 
-        match subtle_encoding::bech32::Bech32::lower_case().decode(&address) {
-            Ok((hrp, _bz)) => {
-                if hrp != chain.bech32_prefix {
-                    return Err(format!(
-                        "incorrect bech32 prefix, expected `{}` but found `{hrp}`",
-                        chain.bech32_prefix
-                    )
-                    .into());
-                }
+        let requester = match verification {
+            Verification::Accepted(requester) => requester,
+            Verification::Rejected(reason) => {
+                return Err(format!("verification failed: {reason}").into());
             }
-            Err(err) => return Err(err.into()),
         };
 
+        match &chain.kind {
+            ChainKind::Cosmos(config) => cosmos::validate_address(config, &address)?,
+            ChainKind::Evm(_) => evm::validate_address(&address)?,
+        }
+
         let db = ctx.data::<Pool>().unwrap();
 
-        let last_request_ts: Option<String> = db
-            .conn({
+        let allowlisted = self
+            .allowlist
+            .iter()
+            .find(|entry| entry.matches(&requester, &address));
+
+        let limits = match allowlisted {
+            Some(entry) => entry.rate_limits(),
+            None => coin.rate_limits(self.ratelimit_seconds),
+        };
+        let requester = allowlisted.map(|entry| entry.name.clone());
+
+        let id = db
+            .conn_mut({
                 let chain_id = chain_id.clone();
                 let denom = denom.clone();
                 let address = address.clone();
+                let ip = ip.clone();
+                let requester = requester.clone();
 
                 move |conn| {
-                    let mut statement = conn.prepare_cached(
-                        "SELECT time FROM requests WHERE chain_id = ? AND denom = ? AND address = ? ORDER BY id DESC LIMIT 1",
-                    )?;
-                    let time = statement
-                        .query_row([chain_id, denom, address], |row| row.get(0))
-                        .optional()?;
-                    Ok(time)
+                    db::insert_request(
+                        conn,
+                        NewRequest {
+                            chain_id: &chain_id,
+                            denom: &denom,
+                            address: &address,
+                            ip: ip.as_deref(),
+                            requester: requester.as_deref(),
+                        },
+                        limits,
+                    )
                 }
             })
             .await?;
 
-        match last_request_ts {
-            Some(ts) => {
-                let ts = NaiveDateTime::parse_from_str(&ts, DATETIME_FORMAT)
-                    .expect("invalid datetime present in database");
-
-                let now = Utc::now().naive_utc();
-
-                let delta = now - ts;
-                if delta.num_seconds() < 0 {
-                    error!(%now, %ts, %delta, "timestamp in the future?");
-                }
-                if delta.num_seconds() < self.ratelimit_seconds.into() {
-                    info!(
-                        %chain_id,
-                        %denom,
-                        %address,
-                        %delta,
-                        ratelimit_seconds = %self.ratelimit_seconds,
-                        "ratelimited"
-                    );
-
-                    return Ok("ERROR: ratelimited".to_string());
-                }
-            }
-            None => {
-                info!(%address, "new user");
+        let id = match id {
+            Ok(id) => id,
+            Err(limited) => {
+                info!(
+                    %chain_id,
+                    %denom,
+                    %address,
+                    ?ip,
+                    ?requester,
+                    ?limits,
+                    reason = ?limited,
+                    "ratelimited"
+                );
+
+                return Ok(limited.to_string());
             }
-        }
+        };
 
-        let id: i64 = db
-            .conn(move |conn| {
-                let mut statement = conn.prepare_cached(
-                    "INSERT INTO requests (chain_id, denom, address, time) VALUES (?, ?, ?, datetime('now')) RETURNING id",
-                )?;
-                let id = statement.query_row([&chain_id, &denom, &address], |row| row.get(0))?;
-                Ok(id)
-            })
-            .await?;
         let mut counter = 0;
         let tx_hash = loop {
-            let tx_hash: Option<String> = db
+            let (state, tx_hash, error): (RequestState, Option<String>, Option<String>) = db
                 .conn(move |conn| {
                     conn.query_row(
-                        "SELECT tx_hash FROM requests WHERE id=? ORDER BY time DESC LIMIT 1",
+                        "SELECT status, tx_hash, error FROM requests WHERE id = ?",
                         [&id],
-                        |row| row.get(0),
+                        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
                     )
                 })
                 .await?;
 
-            if let Some(tx_hash) = tx_hash {
-                break tx_hash;
-            } else {
-                if counter > max_request_polls.0 {
-                    break "ERROR".to_string();
+            match state {
+                RequestState::Confirmed => break tx_hash.unwrap_or_default(),
+                RequestState::Failed => break format!("ERROR: {}", error.unwrap_or_default()),
+                RequestState::Pending | RequestState::Broadcast => {
+                    if counter > max_request_polls.0 {
+                        // the request can still be followed with `requestStatus`
+                        break format!("ERROR: request {id} is still {}", state.as_str());
+                    }
+                    counter += 1;
+                    debug!(counter, "no response yet, trying again");
+                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
                 }
-                counter += 1;
-                debug!(counter, "no response yet, trying again");
-                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
             }
         };
 
@@ -687,6 +784,7 @@ struct Request {
     id: i64,
     address: String,
     time: String,
+    status: RequestState,
     tx_hash: Option<String>,
 }
 
@@ -695,6 +793,22 @@ struct Query;
 
 #[Object]
 impl Query {
+    /// The status of a request, including the error of the last failed attempt.
+    async fn request_status<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        id: i64,
+    ) -> FieldResult<Option<RequestStatus>> {
+        let db = ctx.data::<Pool>().unwrap();
+
+        let status = db
+            .conn(move |conn| db::request_status(conn, id))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(status)
+    }
+
     async fn handled_transfers<'ctx>(
         &self,
         ctx: &Context<'ctx>,
@@ -709,10 +823,9 @@ impl Query {
         let requests: Vec<Request> = db
             .conn(move |conn| {
                 let mut stmt = conn.prepare(
-                    "SELECT id, address, time, tx_hash 
-                    FROM requests 
-                    WHERE tx_hash IS NOT NULL 
-                    AND tx_hash NOT LIKE 'ERROR%' 
+                    "SELECT id, address, time, status, tx_hash
+                    FROM requests
+                    WHERE status = 'confirmed'
                     AND time < ?1
                     ORDER BY time DESC
                     LIMIT ?2",
@@ -722,7 +835,8 @@ impl Query {
                         id: row.get(0)?,
                         address: row.get(1)?,
                         time: row.get(2)?,
-                        tx_hash: row.get(3)?,
+                        status: row.get(3)?,
+                        tx_hash: row.get(4)?,
                     })
                 })?;
                 let requests: Result<Vec<_>, _> = rows.collect();
@@ -749,9 +863,9 @@ impl Query {
         let requests: Vec<Request> = db
             .conn(move |conn| {
                 let mut stmt = conn.prepare(
-                    "SELECT id, address, time, tx_hash 
-                     FROM requests 
-                     WHERE address = ?1 
+                    "SELECT id, address, time, status, tx_hash
+                     FROM requests
+                     WHERE address = ?1
                      AND time < ?2
                      ORDER BY time DESC
                      LIMIT ?3",
@@ -761,7 +875,8 @@ impl Query {
                         id: row.get(0)?,
                         address: row.get(1)?,
                         time: row.get(2)?,
-                        tx_hash: row.get(3)?,
+                        status: row.get(3)?,
+                        tx_hash: row.get(4)?,
                     })
                 })?;
                 let requests: Result<Vec<_>, _> = rows.collect();
@@ -787,9 +902,9 @@ impl Query {
         let requests: Vec<Request> = db
             .conn(move |conn| {
                 let mut stmt = conn.prepare(
-                    "SELECT id, address, time, tx_hash 
-                    FROM requests 
-                    WHERE tx_hash IS NULL 
+                    "SELECT id, address, time, status, tx_hash
+                    FROM requests
+                    WHERE status IN ('pending', 'broadcast')
                     AND time < ?1
                     ORDER BY time DESC
                     LIMIT ?2",
@@ -799,7 +914,8 @@ impl Query {
                         id: row.get(0)?,
                         address: row.get(1)?,
                         time: row.get(2)?,
-                        tx_hash: row.get(3)?,
+                        status: row.get(3)?,
+                        tx_hash: row.get(4)?,
                     })
                 })?;
                 let requests: Result<Vec<_>, _> = rows.collect();
@@ -815,3 +931,364 @@ impl Query {
 async fn graphiql() -> impl IntoResponse {
     response::Html(GraphiQLSource::build().endpoint("/").finish())
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+    use tokio::task::JoinHandle;
+    use unionlabs::primitives::H256;
+
+    use super::*;
+
+    const PEER: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+
+    fn header_map(header: &'static str, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn client_ip_is_the_peer_without_ip_header() {
+        let headers = header_map("x-forwarded-for", "1.1.1.1");
+        assert_eq!(client_ip(&headers, None, PEER), PEER);
+    }
+
+    #[test]
+    fn client_ip_is_read_from_ip_header() {
+        let headers = header_map("cf-connecting-ip", "1.1.1.1");
+        assert_eq!(
+            client_ip(&headers, Some("cf-connecting-ip"), PEER),
+            "1.1.1.1".parse::<IpAddr>().unwrap()
+        );
+
+        let headers = header_map("cf-connecting-ip", "2001:db8::1");
+        assert_eq!(
+            client_ip(&headers, Some("cf-connecting-ip"), PEER),
+            "2001:db8::1".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn client_ip_is_the_last_forwarded_ip() {
+        // the first value is set by the client, the last one by the proxy
+        let headers = header_map("x-forwarded-for", "6.6.6.6, 2.2.2.2 , 1.1.1.1 ");
+        assert_eq!(
+            client_ip(&headers, Some("x-forwarded-for"), PEER),
+            "1.1.1.1".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn client_ip_falls_back_to_the_peer() {
+        let headers = header_map("x-forwarded-for", "1.1.1.1, not an ip");
+        assert_eq!(client_ip(&headers, Some("x-forwarded-for"), PEER), PEER);
+
+        assert_eq!(client_ip(&headers, Some("cf-connecting-ip"), PEER), PEER);
+    }
+
+    #[test]
+    fn chain_kind_defaults_to_cosmos() {
+        let chain = serde_json::from_value::<Chain>(serde_json::json!({
+            "id": "union-devnet-1",
+            "bech32_prefix": "union",
+            "rpc_url": "http://localhost:26657",
+            "gas_config": {
+                "type": "fixed",
+                "config": {
+                    "gas_price": "1.0",
+                    "gas_denom": "muno",
+                    "gas_multiplier": "1.1",
+                    "max_gas": 10000000
+                }
+            },
+            "signer": "0xaa820fa947beb242032a41b6dc9a8b9c37d8f5fbcda0966b1ec80335b10a7d6f",
+            "memo": "",
+            "coins": []
+        }))
+        .unwrap();
+        let ChainKind::Cosmos(config) = chain.kind else {
+            panic!("expected a cosmos chain");
+        };
+        assert_eq!(config.bech32_prefix, "union");
+    }
+
+    #[test]
+    fn chain_kind_is_read_from_type() {
+        let chain = serde_json::from_value::<Chain>(serde_json::json!({
+            "id": "32382",
+            "type": "evm",
+            "rpc_url": "http://localhost:8545",
+            "signer": "0x4e9444a6efd6d42725a250b650a781da2737ea308c839eaccb0f7f3dbd2fea77",
+            "coins": []
+        }))
+        .unwrap();
+        let ChainKind::Evm(config) = chain.kind else {
+            panic!("expected an evm chain");
+        };
+        assert_eq!(config.rpc_url, "http://localhost:8545");
+
+        // unknown types are rejected instead of being read as cosmos chains
+        serde_json::from_value::<Chain>(serde_json::json!({
+            "id": "32382",
+            "type": "solana",
+            "rpc_url": "http://localhost:8545",
+            "signer": "0x4e9444a6efd6d42725a250b650a781da2737ea308c839eaccb0f7f3dbd2fea77",
+            "coins": []
+        }))
+        .unwrap_err();
+    }
+
+    #[test]
+    fn allowlist_entry_requires_ratelimit() {
+        serde_json::from_value::<AllowlistEntry>(serde_json::json!({
+            "name": "partner",
+            "api_keys": ["partner"]
+        }))
+        .unwrap_err();
+
+        let entry = serde_json::from_value::<AllowlistEntry>(serde_json::json!({
+            "name": "partner",
+            "api_keys": ["partner"],
+            "ratelimit_seconds": 0
+        }))
+        .unwrap();
+        assert_eq!(entry.rate_limits().address_seconds, 0);
+    }
+
+    const CHAIN_ID: &str = "32382";
+    const DENOM: &str = "eth";
+    const ADDRESS: &str = "0xbe68fc2d8249eb60bfcf0e71d5a0d2f2e292c4ed";
+
+    async fn pool() -> Pool {
+        let pool = PoolBuilder::new()
+            .path(":memory:")
+            .num_conns(1)
+            .open()
+            .await
+            .unwrap();
+        pool.conn(db::migrate).await.unwrap();
+        pool
+    }
+
+    fn schema(pool: Pool, verifier: AnyVerifier) -> DripSchema {
+        let chain = Chain {
+            id: CHAIN_ID.to_owned(),
+            kind: ChainKind::Evm(EvmConfig {
+                rpc_url: "http://localhost:8545".to_owned(),
+                signer: H256::new([1; 32]),
+            }),
+            coins: vec![Coin {
+                denom: DENOM.to_owned(),
+                amount: 1,
+                erc20_address: None,
+                address_ratelimit_seconds: None,
+                ip_ratelimit_seconds: None,
+                daily_cap: None,
+            }],
+        };
+
+        Schema::build(
+            Query,
+            Mutation {
+                ratelimit_seconds: 60,
+                chains: vec![chain],
+                allowlist: vec![],
+            },
+            EmptySubscription,
+        )
+        .data(pool)
+        .data(MaxRequestPolls(5))
+        .data(Verifiers(vec![verifier]))
+        .finish()
+    }
+
+    /// Stands in for `poll_loop`, confirming every ready request with a made up hash.
+    fn spawn_worker(pool: Pool) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let requests = pool
+                    .conn(|conn| db::ready_requests(conn, CHAIN_ID, 10))
+                    .await
+                    .unwrap();
+
+                let outcomes = requests
+                    .into_iter()
+                    .map(|req| ((req.id, req.attempts), Ok(format!("0x{:064x}", req.id))))
+                    .collect();
+                record_attempts(&pool, outcomes, false, &RetryPolicy::default()).await;
+
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        })
+    }
+
+    async fn send(schema: &DripSchema, token: &str) -> Result<String, String> {
+        let query = format!(
+            r#"mutation {{
+                send(
+                    captchaToken: "{token}",
+                    chainId: "{CHAIN_ID}",
+                    denom: "{DENOM}",
+                    address: "{ADDRESS}"
+                )
+            }}"#
+        );
+
+        let response = schema
+            .execute(async_graphql::Request::new(query).data(ClientIp(PEER)))
+            .await;
+        match response.errors.first() {
+            Some(error) => Err(error.message.clone()),
+            None => Ok(response.data.into_json().unwrap()["send"]
+                .as_str()
+                .unwrap()
+                .to_owned()),
+        }
+    }
+
+    #[tokio::test]
+    async fn send_is_confirmed() {
+        let pool = pool().await;
+        let schema = schema(pool.clone(), AnyVerifier::Stub(Some("secret".to_owned())));
+        let worker = spawn_worker(pool.clone());
+
+        assert_eq!(
+            send(&schema, "secret").await.unwrap(),
+            format!("0x{:064x}", 1)
+        );
+
+        let status = status(&pool, 1).await;
+        assert_eq!(status.status, RequestState::Confirmed);
+        assert_eq!(status.address, ADDRESS);
+        assert_eq!(status.attempts, 1);
+
+        // the address is ratelimited now
+        assert_eq!(send(&schema, "secret").await.unwrap(), "ERROR: ratelimited");
+
+        worker.abort();
+    }
+
+    #[tokio::test]
+    async fn send_with_invalid_token_is_rejected() {
+        let pool = pool().await;
+        let schema = schema(pool.clone(), AnyVerifier::Stub(Some("secret".to_owned())));
+
+        assert_eq!(
+            send(&schema, "guess").await.unwrap_err(),
+            "verification failed: invalid token"
+        );
+
+        // rejected requests aren't queued, and don't count towards the rate limits
+        let queued: u32 = pool
+            .conn(|conn| conn.query_row("SELECT COUNT(*) FROM requests", [], |row| row.get(0)))
+            .await
+            .unwrap();
+        assert_eq!(queued, 0);
+    }
+
+    // queues a request and marks it as submitted with `tx_hash`, as the evm worker does
+    async fn submitted(pool: &Pool, tx_hash: &'static str) -> i64 {
+        pool.conn_mut(move |conn| {
+            let id = db::insert_request(
+                conn,
+                NewRequest {
+                    chain_id: CHAIN_ID,
+                    denom: DENOM,
+                    address: ADDRESS,
+                    ip: None,
+                    requester: None,
+                },
+                RateLimits {
+                    address_seconds: 0,
+                    ip_seconds: None,
+                    daily_requests: None,
+                },
+            )?
+            .unwrap();
+            db::mark_broadcast(conn, &[id], Some(tx_hash))?;
+            Ok(id)
+        })
+        .await
+        .unwrap()
+    }
+
+    async fn status(pool: &Pool, id: i64) -> RequestStatus {
+        pool.conn(move |conn| db::request_status(conn, id))
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    async fn ready(pool: &Pool) -> Vec<i64> {
+        pool.conn(|conn| db::ready_requests(conn, CHAIN_ID, 10))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|request| request.id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn included_transfer_is_confirmed() {
+        let pool = pool().await;
+        let id = submitted(&pool, "0xabcdef").await;
+
+        let confirmation = Confirmation::Included("0xabcdef".to_owned());
+        record_confirmation(&pool, (id, 0), confirmation, &RetryPolicy::default()).await;
+
+        let status = status(&pool, id).await;
+        assert_eq!(status.status, RequestState::Confirmed);
+        assert_eq!(status.tx_hash.as_deref(), Some("0xabcdef"));
+        assert_eq!(status.attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn failed_transfer_is_sent_again() {
+        let pool = pool().await;
+        let id = submitted(&pool, "0xabcdef").await;
+
+        let confirmation = Confirmation::Failed("transaction 0xabcdef reverted".to_owned());
+        record_confirmation(&pool, (id, 0), confirmation, &RetryPolicy::default()).await;
+
+        let status = status(&pool, id).await;
+        assert_eq!(status.status, RequestState::Pending);
+        assert_eq!(status.tx_hash, None);
+        assert_eq!(status.attempts, 1);
+        assert_eq!(
+            status.error.as_deref(),
+            Some("transaction 0xabcdef reverted")
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_transfer_is_confirmed_instead_of_sent_again() {
+        let pool = pool().await;
+        let id = submitted(&pool, "0xabcdef").await;
+
+        // i.e. the rpc failed, the transfer may still be included
+        let confirmation = Confirmation::Unknown("connection refused".to_owned());
+        record_confirmation(&pool, (id, 0), confirmation, &RetryPolicy::default()).await;
+
+        let status = status(&pool, id).await;
+        assert_eq!(status.status, RequestState::Broadcast);
+        assert_eq!(status.tx_hash.as_deref(), Some("0xabcdef"));
+        assert_eq!(status.attempts, 0);
+
+        // neither sent again by the worker, nor after a restart
+        assert!(ready(&pool).await.is_empty());
+        pool.conn(|conn| db::reset_broadcast(conn, CHAIN_ID))
+            .await
+            .unwrap();
+        assert!(ready(&pool).await.is_empty());
+
+        // but confirmed by its hash
+        let broadcast = pool
+            .conn(|conn| db::broadcast_requests(conn, CHAIN_ID))
+            .await
+            .unwrap();
+        assert_eq!(broadcast.len(), 1);
+        assert_eq!(broadcast[0].0.id, id);
+        assert_eq!(broadcast[0].1, "0xabcdef");
+    }
+}
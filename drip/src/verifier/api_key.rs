@@ -0,0 +1,228 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::verifier::{Request, Requester, Verification, Verifier};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub id: String,
+    pub secret: String,
+}
+
+/// Verifies tokens signed with an API key, for partners that can't solve captchas (i.e. in CI).
+///
+/// Tokens have the form `<id>:<timestamp>:<signature>`, where `timestamp` is in seconds since
+/// the unix epoch and `signature` is the hex encoded HMAC-SHA256 of
+/// `<timestamp>:<chain_id>:<denom>:<address>` with the secret of the key.
+///
+/// Every token is accepted only once. Used tokens are remembered until they expire, which is in
+/// memory, so a token can be replayed once after a restart of drip (within `max_age_seconds`).
+#[derive(Debug, Clone)]
+pub struct ApiKeyVerifier {
+    keys: Vec<ApiKey>,
+    max_age_seconds: u64,
+    /// Signatures of accepted tokens, with their timestamp.
+    used: Arc<Mutex<HashMap<Vec<u8>, u64>>>,
+}
+
+impl ApiKeyVerifier {
+    pub fn new(keys: Vec<ApiKey>, max_age_seconds: u64) -> Self {
+        Self {
+            keys,
+            max_age_seconds,
+            used: Arc::default(),
+        }
+    }
+
+    /// Records the token, returning false if it was used before.
+    fn use_once(&self, signature: Vec<u8>, timestamp: u64, now: u64) -> bool {
+        let mut used = self.used.lock().unwrap_or_else(PoisonError::into_inner);
+        // expired tokens are rejected anyways
+        used.retain(|_, timestamp| now.abs_diff(*timestamp) <= self.max_age_seconds);
+        used.insert(signature, timestamp).is_none()
+    }
+}
+
+impl Verifier for ApiKeyVerifier {
+    async fn verify(&self, token: &str, request: &Request<'_>) -> Result<Verification> {
+        let mut parts = token.splitn(3, ':');
+        let (Some(id), Some(timestamp), Some(signature)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Ok(Verification::Rejected("not an api key token".to_owned()));
+        };
+
+        let Some(key) = self.keys.iter().find(|key| key.id == id) else {
+            return Ok(Verification::Rejected(format!("unknown api key {id}")));
+        };
+
+        let Ok(timestamp) = timestamp.parse::<u64>() else {
+            return Ok(Verification::Rejected("invalid timestamp".to_owned()));
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("time is after the unix epoch")
+            .as_secs();
+        if now.abs_diff(timestamp) > self.max_age_seconds {
+            return Ok(Verification::Rejected("api key token expired".to_owned()));
+        }
+
+        let Ok(signature) = hex::decode(signature) else {
+            return Ok(Verification::Rejected("invalid signature".to_owned()));
+        };
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(key.secret.as_bytes())
+            .expect("hmac accepts keys of any size");
+        mac.update(
+            format!(
+                "{timestamp}:{}:{}:{}",
+                request.chain_id, request.denom, request.address
+            )
+            .as_bytes(),
+        );
+
+        if mac.verify_slice(&signature).is_err() {
+            return Ok(Verification::Rejected("invalid signature".to_owned()));
+        }
+
+        if !self.use_once(signature, timestamp, now) {
+            return Ok(Verification::Rejected(
+                "api key token already used".to_owned(),
+            ));
+        }
+
+        Ok(Verification::Accepted(Requester::ApiKey(key.id.clone())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REQUEST: Request<'static> = Request {
+        chain_id: "union-devnet-1",
+        denom: "muno",
+        address: "union1jk9psyhvgkrt2cumz8eytll2244m2nnz4yt2g2",
+        ip: None,
+    };
+
+    fn verifier() -> ApiKeyVerifier {
+        ApiKeyVerifier::new(
+            vec![ApiKey {
+                id: "ci".to_owned(),
+                secret: "hunter2".to_owned(),
+            }],
+            300,
+        )
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    fn token(id: &str, secret: &str, timestamp: u64, request: &Request<'_>) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(
+            format!(
+                "{timestamp}:{}:{}:{}",
+                request.chain_id, request.denom, request.address
+            )
+            .as_bytes(),
+        );
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        format!("{id}:{timestamp}:{signature}")
+    }
+
+    #[tokio::test]
+    async fn signed_token_is_accepted() {
+        let token = token("ci", "hunter2", now(), &REQUEST);
+
+        assert_eq!(
+            verifier().verify(&token, &REQUEST).await.unwrap(),
+            Verification::Accepted(Requester::ApiKey("ci".to_owned()))
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_tokens_are_rejected() {
+        let verifier = verifier();
+        let rejected = |verification| matches!(verification, Verification::Rejected(_));
+
+        for token in [
+            // wrong secret
+            token("ci", "hunter3", now(), &REQUEST),
+            // unknown key
+            token("other", "hunter2", now(), &REQUEST),
+            // signed for another address
+            token(
+                "ci",
+                "hunter2",
+                now(),
+                &Request {
+                    address: "union1qp0wtsfltjk9rnvyu3fkdv0s0skp4y5y3py96f",
+                    ..REQUEST
+                },
+            ),
+            // expired, or from the future
+            token("ci", "hunter2", now() - 301, &REQUEST),
+            token("ci", "hunter2", now() + 301, &REQUEST),
+            // malformed
+            "ci:not a timestamp:00".to_owned(),
+            format!("ci:{}:not hex", now()),
+            "a captcha response".to_owned(),
+        ] {
+            assert!(
+                rejected(verifier.verify(&token, &REQUEST).await.unwrap()),
+                "{token} was accepted"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn token_is_accepted_only_once() {
+        let verifier = verifier();
+        let token = token("ci", "hunter2", now(), &REQUEST);
+
+        assert!(matches!(
+            verifier.verify(&token, &REQUEST).await.unwrap(),
+            Verification::Accepted(_)
+        ));
+        assert_eq!(
+            verifier.verify(&token, &REQUEST).await.unwrap(),
+            Verification::Rejected("api key token already used".to_owned())
+        );
+
+        // clones share the used tokens
+        assert_eq!(
+            verifier.clone().verify(&token, &REQUEST).await.unwrap(),
+            Verification::Rejected("api key token already used".to_owned())
+        );
+    }
+
+    #[test]
+    fn used_tokens_are_forgotten_once_expired() {
+        let verifier = verifier();
+
+        assert!(verifier.use_once(vec![1], 1000, 1000));
+        assert!(!verifier.use_once(vec![1], 1000, 1300));
+        assert!(verifier.use_once(vec![2], 1300, 1300));
+        assert_eq!(verifier.used.lock().unwrap().len(), 2);
+
+        // the first token expired, and can't be verified anymore
+        assert!(verifier.use_once(vec![3], 1301, 1301));
+        assert_eq!(verifier.used.lock().unwrap().len(), 2);
+    }
+}
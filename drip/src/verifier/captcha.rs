@@ -0,0 +1,92 @@
+use anyhow::Result;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::verifier::{Request, Requester, Verification, Verifier};
+
+pub const TURNSTILE_URL: &str = "https://challenges.cloudflare.com/turnstile/v0/siteverify";
+pub const HCAPTCHA_URL: &str = "https://api.hcaptcha.com/siteverify";
+pub const RECAPTCHA_URL: &str = "https://www.google.com/recaptcha/api/siteverify";
+
+#[derive(Debug, Deserialize)]
+struct TokenValidateResponse {
+    #[serde(rename = "error-codes")]
+    error_codes: Option<Vec<String>>,
+    success: bool,
+    /// Only returned by reCAPTCHA v3 and hCaptcha enterprise.
+    score: Option<f64>,
+    #[allow(dead_code)]
+    action: Option<String>,
+    #[allow(dead_code)]
+    cdata: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TokenValidateRequest<'a> {
+    response: &'a str,
+    secret: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remoteip: Option<String>,
+}
+
+/// Verifies captcha tokens with a `siteverify` endpoint. Turnstile, hCaptcha and reCAPTCHA all
+/// implement the same protocol, they only differ in the url.
+#[derive(Debug, Clone)]
+pub struct CaptchaVerifier {
+    client: Client,
+    url: String,
+    secret: String,
+    min_score: Option<f64>,
+}
+
+impl CaptchaVerifier {
+    pub fn new(url: String, secret: String, min_score: Option<f64>) -> Self {
+        Self {
+            client: Client::new(),
+            url,
+            secret,
+            min_score,
+        }
+    }
+}
+
+impl Verifier for CaptchaVerifier {
+    async fn verify(&self, token: &str, request: &Request<'_>) -> Result<Verification> {
+        let request_body = TokenValidateRequest {
+            response: token,
+            secret: &self.secret,
+            remoteip: request.ip.map(|ip| ip.to_string()),
+        };
+
+        let response = self
+            .client
+            .post(&self.url)
+            .form(&request_body)
+            .send()
+            .await?;
+
+        let data: TokenValidateResponse = response.json().await?;
+
+        if !data.success {
+            warn!(url = %self.url, "error verifying captcha: {:?}", data);
+
+            return Ok(Verification::Rejected(format!(
+                "captcha verification failed: {}",
+                data.error_codes
+                    .map(|codes| codes.join(", "))
+                    .unwrap_or_default()
+            )));
+        }
+
+        if let (Some(min_score), Some(score)) = (self.min_score, data.score) {
+            if score < min_score {
+                return Ok(Verification::Rejected(format!(
+                    "captcha score {score} is below {min_score}"
+                )));
+            }
+        }
+
+        Ok(Verification::Accepted(Requester::Anonymous))
+    }
+}
@@ -0,0 +1,219 @@
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+use tracing::{debug, warn};
+use unionlabs::ErrorReporter;
+
+use crate::verifier::{
+    api_key::{ApiKey, ApiKeyVerifier},
+    captcha::{CaptchaVerifier, HCAPTCHA_URL, RECAPTCHA_URL, TURNSTILE_URL},
+};
+
+pub mod api_key;
+pub mod captcha;
+
+/// A faucet request that needs to be verified.
+#[derive(Debug, Clone, Copy)]
+pub struct Request<'a> {
+    pub chain_id: &'a str,
+    pub denom: &'a str,
+    pub address: &'a str,
+    pub ip: Option<IpAddr>,
+}
+
+/// Who made a verified request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Requester {
+    Anonymous,
+    ApiKey(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    Accepted(Requester),
+    Rejected(String),
+}
+
+/// Checks that a request was made by a human, or by someone we trust.
+pub trait Verifier {
+    async fn verify(&self, token: &str, request: &Request<'_>) -> anyhow::Result<Verification>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum VerifierConfig {
+    Turnstile {
+        secret: String,
+    },
+    Hcaptcha {
+        secret: String,
+        #[serde(default)]
+        url: Option<String>,
+    },
+    Recaptcha {
+        secret: String,
+        #[serde(default)]
+        url: Option<String>,
+        /// The minimum score for reCAPTCHA v3 tokens.
+        #[serde(default)]
+        min_score: Option<f64>,
+    },
+    ApiKey {
+        keys: Vec<ApiKey>,
+        /// How long a signed token is valid for.
+        #[serde(default = "default_max_age_seconds")]
+        max_age_seconds: u64,
+    },
+    /// Accepts every request, or only requests with `token` if set. Useful for testing the send
+    /// flow offline.
+    Stub {
+        #[serde(default)]
+        token: Option<String>,
+    },
+}
+
+fn default_max_age_seconds() -> u64 {
+    300
+}
+
+#[derive(Debug, Clone)]
+pub enum AnyVerifier {
+    Captcha(CaptchaVerifier),
+    ApiKey(ApiKeyVerifier),
+    Stub(Option<String>),
+}
+
+impl From<VerifierConfig> for AnyVerifier {
+    fn from(config: VerifierConfig) -> Self {
+        match config {
+            VerifierConfig::Turnstile { secret } => {
+                AnyVerifier::Captcha(CaptchaVerifier::new(TURNSTILE_URL.to_owned(), secret, None))
+            }
+            VerifierConfig::Hcaptcha { secret, url } => AnyVerifier::Captcha(CaptchaVerifier::new(
+                url.unwrap_or_else(|| HCAPTCHA_URL.to_owned()),
+                secret,
+                None,
+            )),
+            VerifierConfig::Recaptcha {
+                secret,
+                url,
+                min_score,
+            } => AnyVerifier::Captcha(CaptchaVerifier::new(
+                url.unwrap_or_else(|| RECAPTCHA_URL.to_owned()),
+                secret,
+                min_score,
+            )),
+            VerifierConfig::ApiKey {
+                keys,
+                max_age_seconds,
+            } => AnyVerifier::ApiKey(ApiKeyVerifier::new(keys, max_age_seconds)),
+            VerifierConfig::Stub { token } => {
+                if token.is_none() {
+                    warn!("stub verifier without a token configured, all requests are accepted");
+                }
+                AnyVerifier::Stub(token)
+            }
+        }
+    }
+}
+
+impl Verifier for AnyVerifier {
+    async fn verify(&self, token: &str, request: &Request<'_>) -> anyhow::Result<Verification> {
+        match self {
+            AnyVerifier::Captcha(verifier) => verifier.verify(token, request).await,
+            AnyVerifier::ApiKey(verifier) => verifier.verify(token, request).await,
+            AnyVerifier::Stub(None) => Ok(Verification::Accepted(Requester::Anonymous)),
+            // compared in constant time, so that the token can't be guessed byte by byte
+            AnyVerifier::Stub(Some(expected))
+                if bool::from(expected.as_bytes().ct_eq(token.as_bytes())) =>
+            {
+                Ok(Verification::Accepted(Requester::Anonymous))
+            }
+            AnyVerifier::Stub(Some(_)) => Ok(Verification::Rejected("invalid token".to_owned())),
+        }
+    }
+}
+
+/// All configured verifiers. A request is accepted if any of them accepts it.
+#[derive(Debug, Clone)]
+pub struct Verifiers(pub Vec<AnyVerifier>);
+
+impl Verifier for Verifiers {
+    async fn verify(&self, token: &str, request: &Request<'_>) -> anyhow::Result<Verification> {
+        let mut reasons = vec![];
+
+        for verifier in &self.0 {
+            match verifier.verify(token, request).await {
+                Ok(Verification::Accepted(requester)) => {
+                    return Ok(Verification::Accepted(requester))
+                }
+                Ok(Verification::Rejected(reason)) => {
+                    debug!(%reason, "verifier rejected request");
+                    reasons.push(reason);
+                }
+                Err(err) => {
+                    warn!(err = %ErrorReporter(&*err), "verifier failed");
+                    reasons.push(format!("verification failed: {}", ErrorReporter(&*err)));
+                }
+            }
+        }
+
+        Ok(Verification::Rejected(reasons.join("; ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REQUEST: Request<'static> = Request {
+        chain_id: "union-devnet-1",
+        denom: "muno",
+        address: "union1jk9psyhvgkrt2cumz8eytll2244m2nnz4yt2g2",
+        ip: None,
+    };
+
+    #[tokio::test]
+    async fn stub_accepts_only_its_token() {
+        let verifier = AnyVerifier::Stub(Some("secret".to_owned()));
+
+        assert_eq!(
+            verifier.verify("secret", &REQUEST).await.unwrap(),
+            Verification::Accepted(Requester::Anonymous)
+        );
+        for token in ["", "secre", "secret2", "Secret"] {
+            assert_eq!(
+                verifier.verify(token, &REQUEST).await.unwrap(),
+                Verification::Rejected("invalid token".to_owned())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn stub_without_token_accepts_everything() {
+        assert_eq!(
+            AnyVerifier::Stub(None).verify("", &REQUEST).await.unwrap(),
+            Verification::Accepted(Requester::Anonymous)
+        );
+    }
+
+    #[tokio::test]
+    async fn any_verifier_can_accept() {
+        let verifiers = Verifiers(vec![
+            AnyVerifier::Stub(Some("a".to_owned())),
+            AnyVerifier::Stub(Some("b".to_owned())),
+        ]);
+
+        for token in ["a", "b"] {
+            assert_eq!(
+                verifiers.verify(token, &REQUEST).await.unwrap(),
+                Verification::Accepted(Requester::Anonymous)
+            );
+        }
+        assert_eq!(
+            verifiers.verify("c", &REQUEST).await.unwrap(),
+            Verification::Rejected("invalid token; invalid token".to_owned())
+        );
+    }
+}
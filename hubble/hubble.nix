@@ -85,6 +85,8 @@
                   "ethereum"
                   "tendermint"
                   "aptos"
+                  "movement"
+                  "sui"
                 ];
               };
               options.start_height = mkOption {
@@ -138,6 +140,11 @@
                         default = null;
                         description = "sleep time (in seconds) when there is nothing to finalize.";
                       };
+                      tip_poll_interval_milliseconds = mkOption {
+                        type = types.nullOr types.int;
+                        default = null;
+                        description = "how long (in milliseconds) to wait for the next block at the tip, when the chain has no head subscription or its subscription stalls.";
+                      };
                     };
                   }
                 );
@@ -145,6 +152,16 @@
             }
           );
         };
+        migrate = mkOption {
+          type = types.bool;
+          default = false;
+          description = "Create the tables added by hubble on startup.";
+        };
+        packet-lifecycle = mkOption {
+          type = types.bool;
+          default = false;
+          description = "Materialize the lifecycle of ibc-union packets from the indexed events.";
+        };
         log-level = mkOption {
           type = types.str;
           default = "info";
@@ -201,6 +218,8 @@
                     --database-url "$(head -n 1 ${cfg.api-key-file})" \
                     --log-format ${cfg.log-format} \
                     --metrics-addr ${cfg.metrics-addr} \
+                    ${lib.optionalString cfg.migrate "--migrate"} \
+                    ${lib.optionalString cfg.packet-lifecycle "--packet-lifecycle"} \
                     --indexers '${indexersJson}'
                 '';
             };
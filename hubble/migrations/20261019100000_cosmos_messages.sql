@@ -0,0 +1,17 @@
+-- Decoded messages of CosmosSDK transactions, one row per message.
+CREATE TABLE IF NOT EXISTS v2_cosmos.messages (
+    internal_chain_id integer     NOT NULL,
+    block_hash        text        NOT NULL,
+    height            bigint      NOT NULL,
+    transaction_hash  text        NOT NULL,
+    transaction_index integer     NOT NULL,
+    index             integer     NOT NULL,
+    type_url          text        NOT NULL,
+    -- NULL if the type url is not supported or the message could not be decoded
+    data              jsonb,
+    time              timestamptz NOT NULL,
+    PRIMARY KEY (internal_chain_id, transaction_hash, index)
+);
+
+CREATE INDEX IF NOT EXISTS messages_height_idx ON v2_cosmos.messages (internal_chain_id, height);
+CREATE INDEX IF NOT EXISTS messages_type_url_idx ON v2_cosmos.messages (internal_chain_id, type_url);
@@ -0,0 +1,43 @@
+CREATE SCHEMA IF NOT EXISTS v2_sui;
+
+-- One row per checkpoint.
+CREATE TABLE IF NOT EXISTS v2_sui.blocks (
+    internal_chain_id integer     NOT NULL,
+    block_hash        text        NOT NULL,
+    height            bigint      NOT NULL,
+    timestamp         timestamptz NOT NULL,
+    PRIMARY KEY (internal_chain_id, height)
+);
+
+CREATE TABLE IF NOT EXISTS v2_sui.transactions (
+    internal_chain_id integer NOT NULL,
+    height            bigint  NOT NULL,
+    transaction_hash  text    NOT NULL,
+    transaction_index bigint  NOT NULL,
+    PRIMARY KEY (internal_chain_id, transaction_hash)
+);
+
+CREATE INDEX IF NOT EXISTS transactions_height_idx ON v2_sui.transactions (internal_chain_id, height);
+
+CREATE TABLE IF NOT EXISTS v2_sui.events (
+    internal_chain_id       integer NOT NULL,
+    height                  bigint  NOT NULL,
+    transaction_hash        text    NOT NULL,
+    index                   bigint  NOT NULL,
+    transaction_event_index bigint  NOT NULL,
+    package_id              text    NOT NULL,
+    module                  text    NOT NULL,
+    sender                  text    NOT NULL,
+    type                    text    NOT NULL,
+    data                    jsonb   NOT NULL,
+    PRIMARY KEY (internal_chain_id, height, index)
+);
+
+-- The packages that are indexed, and the heights they are indexed at.
+CREATE TABLE IF NOT EXISTS v2_sui.contracts (
+    internal_chain_id integer NOT NULL,
+    address           text    NOT NULL,
+    start_height      bigint  NOT NULL,
+    end_height        bigint  NOT NULL,
+    PRIMARY KEY (internal_chain_id, address)
+);
@@ -0,0 +1,63 @@
+CREATE SCHEMA IF NOT EXISTS v2_packets;
+
+-- The last height of each source table and chain that was materialized.
+CREATE TABLE IF NOT EXISTS v2_packets.cursors (
+    source            text    NOT NULL,
+    internal_chain_id integer NOT NULL,
+    height            bigint  NOT NULL,
+    PRIMARY KEY (source, internal_chain_id)
+);
+
+-- A single stage of a packet, extracted from the event of the chain it happened on.
+CREATE TABLE IF NOT EXISTS v2_packets.stages (
+    packet_hash       text        NOT NULL,
+    stage             text        NOT NULL,
+    batch_hash        text,
+    channel_id        bigint      NOT NULL,
+    relayer           text,
+    internal_chain_id integer     NOT NULL,
+    height            bigint      NOT NULL,
+    transaction_hash  text,
+    time              timestamptz NOT NULL,
+    PRIMARY KEY (packet_hash, stage)
+);
+
+CREATE INDEX IF NOT EXISTS stages_height_idx ON v2_packets.stages (internal_chain_id, height);
+
+-- The stages of a packet joined into a single row, rebuilt whenever one of its stages changes.
+CREATE TABLE IF NOT EXISTS v2_packets.packets (
+    packet_hash                 text PRIMARY KEY,
+    send_batch_hash             text,
+    ack_batch_hash              text,
+    source_channel_id           bigint,
+    destination_channel_id      bigint,
+    send_internal_chain_id      integer,
+    send_height                 bigint,
+    send_transaction_hash       text,
+    send_time                   timestamptz,
+    recv_internal_chain_id      integer,
+    recv_height                 bigint,
+    recv_transaction_hash       text,
+    recv_time                   timestamptz,
+    recv_relayer                text,
+    write_ack_internal_chain_id integer,
+    write_ack_height            bigint,
+    write_ack_transaction_hash  text,
+    write_ack_time              timestamptz,
+    ack_internal_chain_id       integer,
+    ack_height                  bigint,
+    ack_transaction_hash        text,
+    ack_time                    timestamptz,
+    ack_relayer                 text,
+    timeout_internal_chain_id   integer,
+    timeout_height              bigint,
+    timeout_transaction_hash    text,
+    timeout_time                timestamptz,
+    timeout_relayer             text,
+    recv_latency                interval,
+    write_ack_latency           interval,
+    ack_latency                 interval,
+    timeout_latency             interval
+);
+
+CREATE INDEX IF NOT EXISTS packets_send_time_idx ON v2_packets.packets (send_time);
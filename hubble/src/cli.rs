@@ -1,10 +1,17 @@
 use std::{net::SocketAddr, str::FromStr};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use tracing::{info_span, Instrument};
 use url::Url;
 
-use crate::{indexer, logging::LogFormat};
+use crate::{
+    indexer::{
+        self,
+        api::{BlockHeight, BlockRange},
+        auditor::AuditReport,
+    },
+    logging::LogFormat,
+};
 
 /// Hubble is state machine observer.
 #[derive(Parser, Debug)]
@@ -32,6 +39,15 @@ pub struct Args {
     #[arg(short, long, env = "HUBBLE_METRICS_PORT")]
     pub metrics_addr: Option<SocketAddr>,
 
+    /// Create the tables added by hubble (see `migrations`) on startup. The rest of the schema is
+    /// managed externally, and the migrations are tracked in `_sqlx_migrations`.
+    #[arg(long, env = "HUBBLE_MIGRATE", default_value_t = false)]
+    pub migrate: bool,
+
+    /// Materialize the lifecycle of ibc-union packets from the indexed events.
+    #[arg(long, env = "HUBBLE_PACKET_LIFECYCLE", default_value_t = false)]
+    pub packet_lifecycle: bool,
+
     /// The log format for Hubble.
     #[arg(
         global = true,
@@ -41,6 +57,35 @@ pub struct Args {
         default_value = "json"
     )]
     pub log_format: LogFormat,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Compares the stored blocks of an indexer with its rpc, reporting missing and mismatched
+    /// blocks (as json on stdout).
+    Audit(AuditArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct AuditArgs {
+    /// The id of the indexer to audit. Must be one of the configured indexers.
+    #[arg(long)]
+    pub indexer_id: String,
+
+    /// The first height to audit (inclusive).
+    #[arg(long)]
+    pub from: BlockHeight,
+
+    /// The last height to audit (exclusive).
+    #[arg(long)]
+    pub to: BlockHeight,
+
+    /// Schedule missing and mismatched blocks for the fixer.
+    #[arg(long, default_value_t = false)]
+    pub fix: bool,
 }
 
 #[derive(Clone, Debug, serde::Deserialize)]
@@ -68,6 +113,11 @@ pub enum IndexerConfig {
     Tendermint(indexer::tendermint::config::Config),
     #[serde(rename = "aptos")]
     Aptos(indexer::aptos::config::Config),
+    /// Movement exposes the Aptos REST api, so it is indexed with the Aptos indexer.
+    #[serde(rename = "movement")]
+    Movement(indexer::aptos::config::Config),
+    #[serde(rename = "sui")]
+    Sui(indexer::sui::config::Config),
 }
 
 impl IndexerConfig {
@@ -77,6 +127,8 @@ impl IndexerConfig {
             Self::Ethereum(cfg) => &cfg.indexer_id,
             Self::Tendermint(cfg) => &cfg.indexer_id,
             Self::Aptos(cfg) => &cfg.indexer_id,
+            Self::Movement(cfg) => &cfg.indexer_id,
+            Self::Sui(cfg) => &cfg.indexer_id,
         }
     }
 }
@@ -121,10 +173,120 @@ impl IndexerConfig {
                     .instrument(indexer_span)
                     .await
             }
+            Self::Movement(cfg) => {
+                cfg.build(db)
+                    .instrument(initializer_span)
+                    .await?
+                    .index()
+                    .instrument(indexer_span)
+                    .await
+            }
+            Self::Sui(cfg) => {
+                cfg.build(db)
+                    .instrument(initializer_span)
+                    .await?
+                    .index()
+                    .instrument(indexer_span)
+                    .await
+            }
+        }
+    }
+}
+
+impl IndexerConfig {
+    pub async fn audit(
+        self,
+        db: sqlx::PgPool,
+        range: BlockRange,
+        schedule_fix: bool,
+    ) -> Result<AuditReport, color_eyre::eyre::Report> {
+        let label = self.label();
+
+        let initializer_span = info_span!("initializer", label);
+        let auditor_span = info_span!("auditor", label);
+
+        match self {
+            Self::Dummy(cfg) => {
+                cfg.build(db)
+                    .instrument(initializer_span)
+                    .await?
+                    .audit(range, schedule_fix)
+                    .instrument(auditor_span)
+                    .await
+            }
+            Self::Ethereum(cfg) => {
+                cfg.build(db)
+                    .instrument(initializer_span)
+                    .await?
+                    .audit(range, schedule_fix)
+                    .instrument(auditor_span)
+                    .await
+            }
+            Self::Tendermint(cfg) => {
+                cfg.build(db)
+                    .instrument(initializer_span)
+                    .await?
+                    .audit(range, schedule_fix)
+                    .instrument(auditor_span)
+                    .await
+            }
+            Self::Aptos(cfg) => {
+                cfg.build(db)
+                    .instrument(initializer_span)
+                    .await?
+                    .audit(range, schedule_fix)
+                    .instrument(auditor_span)
+                    .await
+            }
+            Self::Movement(cfg) => {
+                cfg.build(db)
+                    .instrument(initializer_span)
+                    .await?
+                    .audit(range, schedule_fix)
+                    .instrument(auditor_span)
+                    .await
+            }
+            Self::Sui(cfg) => {
+                cfg.build(db)
+                    .instrument(initializer_span)
+                    .await?
+                    .audit(range, schedule_fix)
+                    .instrument(auditor_span)
+                    .await
+            }
         }
     }
 }
 
+impl AuditArgs {
+    pub async fn run(
+        self,
+        db: sqlx::PgPool,
+        indexers: Indexers,
+    ) -> Result<AuditReport, color_eyre::eyre::Report> {
+        let indexer = indexers
+            .into_iter()
+            .find(|indexer| indexer.label() == self.indexer_id)
+            .ok_or_else(|| {
+                color_eyre::eyre::eyre!("no indexer configured with id {}", self.indexer_id)
+            })?;
+
+        indexer.audit(db, self.range()?, self.fix).await
+    }
+
+    fn range(&self) -> Result<BlockRange, color_eyre::eyre::Report> {
+        if self.from > self.to {
+            return Err(color_eyre::eyre::eyre!(
+                "invalid range: --from ({}) is above --to ({})",
+                self.from,
+                self.to
+            ));
+        }
+
+        Ok((self.from..self.to).into())
+    }
+}
+
 impl FromStr for Indexers {
     type Err = color_eyre::eyre::Error;
 
@@ -153,3 +315,29 @@ impl FromStr for TokensUrls {
         serde_json::from_str(item).map_err(Into::into)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::AuditArgs;
+
+    fn audit_args(from: u64, to: u64) -> AuditArgs {
+        AuditArgs {
+            indexer_id: "indexer".to_string(),
+            from,
+            to,
+            fix: false,
+        }
+    }
+
+    #[test]
+    fn audit_range_is_from_inclusive_to_exclusive() {
+        let range = audit_args(3, 5).range().unwrap();
+
+        assert_eq!((range.start_inclusive, range.end_exclusive), (3, 5));
+    }
+
+    #[test]
+    fn inverted_audit_range_is_rejected() {
+        assert!(audit_args(5, 3).range().is_err());
+    }
+}
@@ -8,6 +8,8 @@ use time::OffsetDateTime;
 use tokio::task::JoinSet;
 use tracing::error;
 
+use crate::indexer::heads::Heads;
+
 #[derive(Debug, thiserror::Error)]
 pub enum IndexerError {
     #[error("received unexpected height {0}: expecting {1}")]
@@ -49,7 +51,7 @@ pub type BlockHeight = u64;
 pub type BlockHash = String;
 pub type BlockTimestamp = OffsetDateTime;
 
-#[derive(Clone, Debug, serde::Deserialize)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct BlockRange {
     pub start_inclusive: BlockHeight,
     pub end_exclusive: BlockHeight,
@@ -142,6 +144,12 @@ pub trait FetcherClient: Display + Send + Sync + Clone + Sized + 'static {
         selection: BlockSelection,
         mode: FetchMode,
     ) -> Result<Self::BlockHandle, IndexerError>;
+
+    /// New heads pushed by the node. `None` if not configured or supported, in which case the
+    /// fetcher polls for new blocks.
+    fn heads(&self) -> Option<Heads> {
+        None
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -181,4 +189,14 @@ pub trait BlockHandle: Send + Sync + Sized {
     ) -> Result<impl Stream<Item = Result<Self, IndexerError>> + Send, IndexerError>;
     async fn insert(&self, tx: &mut sqlx::Transaction<'_, Postgres>) -> Result<(), IndexerError>;
     async fn update(&self, tx: &mut sqlx::Transaction<'_, Postgres>) -> Result<(), IndexerError>;
+    /// The hash of this block as stored in the database (`None` if it is not stored).
+    async fn stored_hash(
+        &self,
+        tx: &mut sqlx::Transaction<'_, Postgres>,
+    ) -> Result<Option<BlockHash>, IndexerError>;
+    /// Whether this block is expected to be stored. Indexers that only store blocks with
+    /// relevant data override this.
+    async fn expect_stored(&self) -> Result<bool, IndexerError> {
+        Ok(true)
+    }
 }
@@ -14,14 +14,14 @@ use tracing::{debug, trace};
 
 use crate::indexer::{
     api::{
-        BlockHandle, BlockRange, BlockReference, BlockReferenceProvider, BlockSelection, FetchMode,
-        IndexerError,
+        BlockHandle, BlockHash, BlockRange, BlockReference, BlockReferenceProvider, BlockSelection,
+        FetchMode, IndexerError,
     },
     aptos::{
         fetcher_client::AptosFetcherClient,
         postgres::{
-            active_contracts, delete_aptos_block_transactions_events, insert_aptos_block, PgBlock,
-            PgEvent, PgTransaction,
+            active_contracts, delete_aptos_block_transactions_events, get_aptos_block_hash,
+            insert_aptos_block, PgBlock, PgEvent, PgTransaction,
         },
         provider::RpcProviderId,
     },
@@ -203,4 +203,11 @@ impl BlockHandle for AptosBlockHandle {
         debug!("{}: done", reference);
         Ok(())
     }
+
+    async fn stored_hash(
+        &self,
+        tx: &mut sqlx::Transaction<'_, Postgres>,
+    ) -> Result<Option<BlockHash>, IndexerError> {
+        Ok(get_aptos_block_hash(tx, self.internal_chain_id, self.reference.height).await?)
+    }
 }
@@ -17,6 +17,11 @@ pub struct Config {
     pub start_height: BlockHeight,
     pub chunk_size: Option<usize>,
     pub rpc_urls: Vec<Url>,
+    /// Url of the transaction stream service (grpc) to subscribe to new heads. Polls for new
+    /// blocks if not set.
+    pub stream_url: Option<Url>,
+    /// Api key of the transaction stream service, if it requires one.
+    pub stream_auth_token: Option<String>,
     pub tx_search_max_page_size: Option<u16>,
     #[serde(default)]
     pub finalizer: FinalizerConfig,
@@ -32,6 +37,8 @@ impl Config {
             self.finalizer,
             AptosContext {
                 rpc_urls: self.rpc_urls,
+                stream_url: self.stream_url,
+                stream_auth_token: self.stream_auth_token,
                 tx_search_max_page_size: self
                     .tx_search_max_page_size
                     .unwrap_or(DEFAULT_TRANSACTIONS_MAX_PAGE_SIZE),
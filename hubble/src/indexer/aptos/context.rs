@@ -5,6 +5,8 @@ use url::Url;
 #[derive(Clone)]
 pub struct AptosContext {
     pub rpc_urls: Vec<Url>,
+    pub stream_url: Option<Url>,
+    pub stream_auth_token: Option<String>,
     pub tx_search_max_page_size: u16,
 }
 
@@ -12,8 +14,9 @@ impl Display for AptosContext {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "rpcs: {}, tx_search_max_page_size: {}",
+            "rpcs: {}, stream: {}, tx_search_max_page_size: {}",
             to_indexed_url_string(&self.rpc_urls),
+            self.stream_url.as_ref().map_or("-", |url| url.as_str()),
             self.tx_search_max_page_size,
         )
     }
@@ -20,8 +20,10 @@ use crate::{
         aptos::{
             block_handle::{AptosBlockHandle, BlockDetails},
             context::AptosContext,
+            heads::TransactionStream,
             provider::{Provider, RpcProviderId},
         },
+        heads::Heads,
     },
     postgres::{fetch_chain_id_tx, ChainId},
 };
@@ -31,6 +33,7 @@ pub struct AptosFetcherClient {
     pub chain_id: ChainId,
     pub provider: Provider,
     pub tx_search_max_page_size: u16,
+    pub heads: Option<Heads>,
 }
 
 impl Display for AptosFetcherClient {
@@ -239,7 +242,7 @@ impl FetcherClient for AptosFetcherClient {
 
     async fn create(
         pg_pool: sqlx::PgPool,
-        _join_set: &mut JoinSet<Result<(), IndexerError>>,
+        join_set: &mut JoinSet<Result<(), IndexerError>>,
         context: AptosContext,
     ) -> Result<Self, IndexerError> {
         let provider = Provider::new(context.rpc_urls);
@@ -264,10 +267,22 @@ impl FetcherClient for AptosFetcherClient {
 
             tx.commit().await?;
 
+            let heads = context.stream_url.map(|stream_url| {
+                Heads::spawn(
+                    join_set,
+                    TransactionStream {
+                        url: stream_url,
+                        auth_token: context.stream_auth_token,
+                        provider: provider.clone(),
+                    },
+                )
+            });
+
             Ok(AptosFetcherClient {
                 chain_id,
                 provider,
                 tx_search_max_page_size: context.tx_search_max_page_size,
+                heads,
             })
         }
         .instrument(indexing_span)
@@ -281,4 +296,8 @@ impl FetcherClient for AptosFetcherClient {
     ) -> Result<Self::BlockHandle, IndexerError> {
         self.fetch_single_with_provider(selection, mode, None).await
     }
+
+    fn heads(&self) -> Option<Heads> {
+        self.heads.clone()
+    }
 }
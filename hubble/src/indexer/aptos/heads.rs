@@ -0,0 +1,215 @@
+//! New heads from the Aptos transaction stream service (`aptos.indexer.v1.RawData`), which pushes
+//! every transaction (including its block height) as soon as it is committed.
+//!
+//! Only the fields that are needed are decoded, the remaining fields of the messages are skipped.
+
+use std::{fmt::Display, future::Future};
+
+use color_eyre::eyre::{eyre, Report};
+use tonic::{
+    client::Grpc,
+    codec::ProstCodec,
+    metadata::MetadataValue,
+    transport::{ClientTlsConfig, Endpoint},
+    GrpcMethod, IntoRequest,
+};
+use tracing::{debug, info};
+use url::Url;
+
+use crate::indexer::{
+    aptos::provider::Provider,
+    heads::{HeadSender, HeadStream},
+};
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct GetTransactionsRequest {
+    #[prost(uint64, optional, tag = "1")]
+    starting_version: Option<u64>,
+    #[prost(uint64, optional, tag = "2")]
+    transactions_count: Option<u64>,
+    #[prost(uint64, optional, tag = "3")]
+    batch_size: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct TransactionsResponse {
+    #[prost(message, repeated, tag = "1")]
+    transactions: Vec<Transaction>,
+}
+
+/// `aptos.transaction.v1.Transaction`
+#[derive(Clone, PartialEq, prost::Message)]
+struct Transaction {
+    #[prost(uint64, tag = "2")]
+    version: u64,
+    #[prost(uint64, tag = "5")]
+    block_height: u64,
+}
+
+pub struct TransactionStream {
+    pub url: Url,
+    pub auth_token: Option<String>,
+    /// Used to look up the current ledger version to start the stream at.
+    pub provider: Provider,
+}
+
+impl Display for TransactionStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.url)
+    }
+}
+
+impl HeadStream for TransactionStream {
+    fn subscribe(&self, sender: &HeadSender) -> impl Future<Output = Result<(), Report>> + Send {
+        async move {
+            let url = &self.url;
+
+            let starting_version: u64 = self
+                .provider
+                .get_index(None)
+                .await?
+                .response
+                .inner()
+                .ledger_version
+                .into();
+
+            let mut endpoint = Endpoint::from_shared(url.to_string())?;
+            if url.scheme() == "https" {
+                endpoint = endpoint.tls_config(ClientTlsConfig::new())?;
+            }
+
+            let mut client = Grpc::new(endpoint.connect().await?);
+            client.ready().await?;
+
+            let mut request = GetTransactionsRequest {
+                starting_version: Some(starting_version),
+                transactions_count: None,
+                batch_size: None,
+            }
+            .into_request();
+
+            request.extensions_mut().insert(GrpcMethod::new(
+                "aptos.indexer.v1.RawData",
+                "GetTransactions",
+            ));
+
+            if let Some(auth_token) = &self.auth_token {
+                request.metadata_mut().insert(
+                    "authorization",
+                    MetadataValue::try_from(format!("Bearer {auth_token}"))?,
+                );
+            }
+
+            let mut stream = client
+                .server_streaming::<_, TransactionsResponse, _>(
+                    request,
+                    "/aptos.indexer.v1.RawData/GetTransactions".parse().unwrap(),
+                    ProstCodec::default(),
+                )
+                .await?
+                .into_inner();
+
+            info!("{url}: subscribed (from version {starting_version})");
+
+            while let Some(response) = stream.message().await? {
+                if let Some(transaction) = response.transactions.last() {
+                    debug!(
+                        "{url}: new head: {} (version {})",
+                        transaction.block_height, transaction.version
+                    );
+                    sender.send(transaction.block_height);
+                }
+            }
+
+            Err(eyre!("stream ended"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use crate::indexer::aptos::heads::{Transaction, TransactionsResponse};
+
+    /// `aptos.util.timestamp.Timestamp`
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct FullTimestamp {
+        #[prost(int64, tag = "1")]
+        seconds: i64,
+    }
+
+    /// `aptos.transaction.v1.Transaction`, with the fields that are skipped by [`Transaction`].
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct FullTransaction {
+        #[prost(message, optional, tag = "1")]
+        timestamp: Option<FullTimestamp>,
+        #[prost(uint64, tag = "2")]
+        version: u64,
+        #[prost(uint64, tag = "4")]
+        epoch: u64,
+        #[prost(uint64, tag = "5")]
+        block_height: u64,
+        #[prost(int32, tag = "6")]
+        r#type: i32,
+        #[prost(bytes = "vec", tag = "7")]
+        payload: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct FullTransactionsResponse {
+        #[prost(message, repeated, tag = "1")]
+        transactions: Vec<FullTransaction>,
+        #[prost(uint64, optional, tag = "2")]
+        chain_id: Option<u64>,
+    }
+
+    fn full_transaction(version: u64, block_height: u64) -> FullTransaction {
+        FullTransaction {
+            timestamp: Some(FullTimestamp { seconds: 1 }),
+            version,
+            epoch: 3,
+            block_height,
+            r#type: 2,
+            payload: vec![1, 2, 3],
+        }
+    }
+
+    #[test]
+    fn decodes_transaction_heights() {
+        let bytes = FullTransactionsResponse {
+            transactions: vec![full_transaction(100, 10), full_transaction(101, 11)],
+            chain_id: Some(1),
+        }
+        .encode_to_vec();
+
+        let response = TransactionsResponse::decode(bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            response.transactions,
+            vec![
+                Transaction {
+                    version: 100,
+                    block_height: 10,
+                },
+                Transaction {
+                    version: 101,
+                    block_height: 11,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_empty_response() {
+        let bytes = FullTransactionsResponse {
+            transactions: vec![],
+            chain_id: Some(1),
+        }
+        .encode_to_vec();
+
+        let response = TransactionsResponse::decode(bytes.as_slice()).unwrap();
+
+        assert!(response.transactions.is_empty());
+    }
+}
@@ -5,7 +5,11 @@ use sqlx::{Postgres, Transaction};
 use time::OffsetDateTime;
 use tracing::trace;
 
-use crate::{indexer::api::BlockHeight, postgres::schedule_replication_reset};
+use crate::{
+    indexer::api::{BlockHash, BlockHeight},
+    packet_lifecycle::{rewind_cursors, Source},
+    postgres::schedule_replication_reset,
+};
 
 pub struct PgBlock {
     pub internal_chain_id: i32,
@@ -127,6 +131,8 @@ pub async fn insert_aptos_block(
         }
     }
 
+    rewind_cursors(tx, Source::Aptos, [(block.internal_chain_id, block.height)]).await?;
+
     Ok(())
 }
 
@@ -171,6 +177,25 @@ pub async fn delete_aptos_block_transactions_events(
     Ok(())
 }
 
+pub async fn get_aptos_block_hash(
+    tx: &mut Transaction<'_, Postgres>,
+    internal_chain_id: i32,
+    height: BlockHeight,
+) -> sqlx::Result<Option<BlockHash>> {
+    let height: i64 = height.try_into().unwrap();
+    let record = sqlx::query!(
+        "
+        SELECT block_hash FROM v2_aptos.blocks WHERE internal_chain_id = $1 AND height = $2
+        ",
+        internal_chain_id,
+        height,
+    )
+    .fetch_optional(tx.as_mut())
+    .await?;
+
+    Ok(record.map(|r| r.block_hash))
+}
+
 pub async fn active_contracts(
     tx: &mut Transaction<'_, Postgres>,
     internal_chain_id: i32,
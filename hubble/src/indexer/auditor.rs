@@ -0,0 +1,343 @@
+use std::{cell::RefCell, fmt::Display};
+
+use color_eyre::eyre::Report;
+use serde::Serialize;
+use tokio::task::JoinSet;
+use tracing::{debug, info, info_span, warn, Instrument};
+
+use super::{
+    api::{BlockHash, BlockHeight, BlockRange, FetcherClient},
+    postgres::{get_current_height, insert_block_range_to_fix},
+    Indexer,
+};
+use crate::indexer::{
+    api::{BlockHandle, BlockSelection, FetchMode},
+    HappyRangeFetcher,
+};
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditFinding {
+    /// The block is not stored, but should be.
+    Missing {
+        height: BlockHeight,
+        hash: BlockHash,
+    },
+    /// The stored block has a different hash than the block of the rpc.
+    Mismatch {
+        height: BlockHeight,
+        stored: BlockHash,
+        actual: BlockHash,
+    },
+}
+
+impl AuditFinding {
+    pub fn height(&self) -> BlockHeight {
+        match self {
+            AuditFinding::Missing { height, .. } => *height,
+            AuditFinding::Mismatch { height, .. } => *height,
+        }
+    }
+}
+
+impl Display for AuditFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditFinding::Missing { height, hash } => write!(f, "{height}: missing ({hash})"),
+            AuditFinding::Mismatch {
+                height,
+                stored,
+                actual,
+            } => write!(f, "{height}: mismatch (stored: {stored}, actual: {actual})"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AuditReport {
+    pub indexer_id: String,
+    /// The audited range; the requested range limited to indexed and finalized blocks.
+    pub range: Option<BlockRange>,
+    pub findings: Vec<AuditFinding>,
+    /// The ranges that were scheduled for the fixer.
+    pub scheduled: Vec<BlockRange>,
+}
+
+impl<T: FetcherClient> Indexer<T> {
+    /// Compares the stored blocks in the range with the blocks of the rpc. Missing and
+    /// mismatched blocks are optionally scheduled for the fixer.
+    pub async fn audit(
+        &self,
+        range: BlockRange,
+        schedule_fix: bool,
+    ) -> Result<AuditReport, Report> {
+        let mut join_set = JoinSet::new();
+        let fetcher_client =
+            T::create(self.pg_pool.clone(), &mut join_set, self.context.clone()).await?;
+
+        let result = self
+            .audit_with_client(&fetcher_client, range, schedule_fix)
+            .instrument(info_span!("audit"))
+            .await;
+
+        join_set.abort_all();
+
+        result
+    }
+
+    async fn audit_with_client(
+        &self,
+        fetcher_client: &T,
+        range: BlockRange,
+        schedule_fix: bool,
+    ) -> Result<AuditReport, Report> {
+        let last_finalized = fetcher_client
+            .fetch_single(BlockSelection::LastFinalized, FetchMode::Lazy)
+            .await?;
+
+        let Some(range) = self
+            .auditable_range(range.clone(), last_finalized.reference().height)
+            .await?
+        else {
+            warn!("{range}: nothing indexed and finalized => nothing to audit");
+            return Ok(AuditReport {
+                indexer_id: self.indexer_id.clone(),
+                range: None,
+                findings: vec![],
+                scheduled: vec![],
+            });
+        };
+
+        info!("{range}: begin");
+
+        // blocks are audited one by one, so the findings are never borrowed concurrently
+        let findings = RefCell::new(Vec::new());
+
+        for chunk in range.clone().range_chunks(self.chunk_size) {
+            debug!("{chunk}: auditing");
+
+            last_finalized
+                .fetch_range_expect_all(chunk, FetchMode::Lazy, |block| {
+                    let findings = &findings;
+                    async move {
+                        if let Some(finding) = self.audit_block(block).await? {
+                            warn!("{finding}");
+                            findings.borrow_mut().push(finding);
+                        }
+                        Ok(())
+                    }
+                })
+                .await?;
+        }
+
+        let findings = findings.into_inner();
+        let ranges_to_fix = ranges(findings.iter().map(AuditFinding::height));
+
+        if schedule_fix && !ranges_to_fix.is_empty() {
+            let mut tx = self.pg_pool.begin().await?;
+            for range_to_fix in &ranges_to_fix {
+                info!("{range_to_fix}: schedule fix");
+                insert_block_range_to_fix(&mut tx, self.indexer_id.clone(), range_to_fix.clone())
+                    .await?;
+            }
+            tx.commit().await?;
+        }
+
+        info!("{range}: done ({} findings)", findings.len());
+
+        Ok(AuditReport {
+            indexer_id: self.indexer_id.clone(),
+            range: Some(range),
+            findings,
+            scheduled: if schedule_fix { ranges_to_fix } else { vec![] },
+        })
+    }
+
+    // only blocks that are indexed (and finalized, so the rpc is authoritative) can be audited.
+    async fn auditable_range(
+        &self,
+        range: BlockRange,
+        last_finalized: BlockHeight,
+    ) -> Result<Option<BlockRange>, Report> {
+        let mut tx = self.pg_pool.begin().await?;
+        let current_height = get_current_height(&mut tx, self.indexer_id.clone()).await?;
+        tx.commit().await?;
+
+        let Some(current_height) = current_height else {
+            return Ok(None);
+        };
+
+        let start_inclusive = range.start_inclusive.max(self.start_height);
+        let end_exclusive = range
+            .end_exclusive
+            .min(current_height + 1)
+            .min(last_finalized + 1);
+
+        Ok((start_inclusive < end_exclusive).then(|| (start_inclusive..end_exclusive).into()))
+    }
+
+    async fn audit_block(&self, block: T::BlockHandle) -> Result<Option<AuditFinding>, Report> {
+        let mut tx = self.pg_pool.begin().await?;
+        let stored = block.stored_hash(&mut tx).await?;
+        tx.commit().await?;
+
+        compare(&block, stored).await
+    }
+}
+
+/// Compares the block of the rpc with the hash that is stored for its height.
+async fn compare<B: BlockHandle>(
+    block: &B,
+    stored: Option<BlockHash>,
+) -> Result<Option<AuditFinding>, Report> {
+    let reference = block.reference();
+
+    Ok(match stored {
+        Some(stored) if stored == reference.hash => None,
+        Some(stored) => Some(AuditFinding::Mismatch {
+            height: reference.height,
+            stored,
+            actual: reference.hash,
+        }),
+        None if block.expect_stored().await? => Some(AuditFinding::Missing {
+            height: reference.height,
+            hash: reference.hash,
+        }),
+        None => None,
+    })
+}
+
+/// Groups (ascending) heights into consecutive ranges.
+fn ranges(heights: impl IntoIterator<Item = BlockHeight>) -> Vec<BlockRange> {
+    let mut result: Vec<BlockRange> = Vec::new();
+
+    for height in heights {
+        match result.last_mut() {
+            Some(last) if last.end_exclusive == height => last.end_exclusive = height + 1,
+            _ => result.push((height..height + 1).into()),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::async_trait;
+    use futures::Stream;
+    use sqlx::Postgres;
+    use time::OffsetDateTime;
+
+    use super::{compare, ranges, AuditFinding};
+    use crate::indexer::api::{
+        BlockHandle, BlockHash, BlockRange, BlockReference, FetchMode, IndexerError,
+    };
+
+    struct FakeBlock {
+        reference: BlockReference,
+        expect_stored: bool,
+    }
+
+    impl FakeBlock {
+        fn new(hash: &str, expect_stored: bool) -> Self {
+            Self {
+                reference: BlockReference::new(10, hash.to_string(), OffsetDateTime::UNIX_EPOCH),
+                expect_stored,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl BlockHandle for FakeBlock {
+        fn reference(&self) -> BlockReference {
+            self.reference.clone()
+        }
+
+        fn fetch_range(
+            &self,
+            _: BlockRange,
+            _: FetchMode,
+        ) -> Result<impl Stream<Item = Result<Self, IndexerError>>, IndexerError> {
+            Ok(futures::stream::empty())
+        }
+
+        async fn insert(
+            &self,
+            _: &mut sqlx::Transaction<'_, Postgres>,
+        ) -> Result<(), IndexerError> {
+            unreachable!("not audited")
+        }
+
+        async fn update(
+            &self,
+            _: &mut sqlx::Transaction<'_, Postgres>,
+        ) -> Result<(), IndexerError> {
+            unreachable!("not audited")
+        }
+
+        async fn stored_hash(
+            &self,
+            _: &mut sqlx::Transaction<'_, Postgres>,
+        ) -> Result<Option<BlockHash>, IndexerError> {
+            unreachable!("the stored hash is passed to compare")
+        }
+
+        async fn expect_stored(&self) -> Result<bool, IndexerError> {
+            Ok(self.expect_stored)
+        }
+    }
+
+    #[tokio::test]
+    async fn stored_hash_mismatch_is_reported() {
+        let finding = compare(&FakeBlock::new("0xaa", true), Some("0xbb".to_string()))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            finding,
+            Some(AuditFinding::Mismatch { height: 10, stored, actual })
+                if stored == "0xbb" && actual == "0xaa"
+        ));
+    }
+
+    #[tokio::test]
+    async fn stored_hash_match_is_not_reported() {
+        let finding = compare(&FakeBlock::new("0xaa", true), Some("0xaa".to_string()))
+            .await
+            .unwrap();
+
+        assert!(finding.is_none());
+    }
+
+    #[tokio::test]
+    async fn missing_block_is_reported_if_expected() {
+        let finding = compare(&FakeBlock::new("0xaa", true), None).await.unwrap();
+
+        assert!(matches!(
+            finding,
+            Some(AuditFinding::Missing { height: 10, hash }) if hash == "0xaa"
+        ));
+
+        let finding = compare(&FakeBlock::new("0xaa", false), None).await.unwrap();
+
+        assert!(finding.is_none());
+    }
+
+    #[test]
+    fn groups_consecutive_heights() {
+        let actual = ranges([1, 2, 3, 5, 7, 8]);
+
+        assert_eq!(
+            actual
+                .iter()
+                .map(|range| range.to_string())
+                .collect::<Vec<_>>(),
+            vec!["[1,4)", "[5,6)", "[7,9)"]
+        );
+    }
+
+    #[test]
+    fn no_heights_no_ranges() {
+        assert!(ranges([]).is_empty());
+    }
+}
@@ -9,7 +9,8 @@ use tokio::task::JoinSet;
 use tracing::{debug, info};
 
 use crate::indexer::api::{
-    BlockHandle, BlockRange, BlockReference, BlockSelection, FetchMode, FetcherClient, IndexerError,
+    BlockHandle, BlockHash, BlockRange, BlockReference, BlockSelection, FetchMode, FetcherClient,
+    IndexerError,
 };
 
 #[derive(Clone)]
@@ -97,6 +98,14 @@ impl BlockHandle for DummyBlock {
 
         Ok(())
     }
+
+    async fn stored_hash(
+        &self,
+        _tx: &mut sqlx::Transaction<'_, Postgres>,
+    ) -> Result<Option<BlockHash>, IndexerError> {
+        // the dummy indexer does not store blocks
+        Ok(Some(self.reference.hash.clone()))
+    }
 }
 
 #[derive(Clone)]
@@ -9,10 +9,13 @@ use tracing::debug;
 
 use crate::{
     indexer::{
-        api::{BlockHandle, BlockRange, BlockReference, BlockSelection, FetchMode, IndexerError},
+        api::{
+            BlockHandle, BlockHash, BlockRange, BlockReference, BlockSelection, FetchMode,
+            IndexerError,
+        },
         ethereum::{
             fetcher_client::EthFetcherClient,
-            postgres::{delete_eth_log, insert_batch_logs},
+            postgres::{delete_eth_log, get_eth_log_block_hash, insert_batch_logs},
             provider::RpcProviderId,
         },
     },
@@ -144,4 +147,16 @@ impl BlockHandle for EthBlockHandle {
         debug!("{}: done", reference);
         Ok(())
     }
+
+    async fn stored_hash(
+        &self,
+        tx: &mut sqlx::Transaction<'_, Postgres>,
+    ) -> Result<Option<BlockHash>, IndexerError> {
+        Ok(get_eth_log_block_hash(tx, self.eth_client.chain_id.db, self.reference.height).await?)
+    }
+
+    async fn expect_stored(&self) -> Result<bool, IndexerError> {
+        // blocks without transactions of tracked contracts are not stored
+        Ok(self.get_block_insert().await?.is_some())
+    }
 }
@@ -16,6 +16,8 @@ pub struct Config {
     pub start_height: BlockHeight,
     pub chunk_size: Option<usize>,
     pub rpc_urls: Vec<Url>,
+    /// Websocket url to subscribe to new heads (`newHeads`). Polls for new blocks if not set.
+    pub ws_url: Option<Url>,
     #[serde(default)]
     pub finalizer: FinalizerConfig,
 }
@@ -30,6 +32,7 @@ impl Config {
             self.finalizer,
             EthContext {
                 rpc_urls: self.rpc_urls,
+                ws_url: self.ws_url,
             },
         ))
     }
@@ -5,19 +5,21 @@ use url::Url;
 #[derive(Clone)]
 pub struct EthContext {
     pub rpc_urls: Vec<Url>,
+    pub ws_url: Option<Url>,
 }
 
 impl Display for EthContext {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "rpc_urls: {}",
+            "rpc_urls: {}, ws_url: {}",
             self.rpc_urls
                 .iter()
                 .enumerate()
                 .map(|(index, url)| format!("{}: {}", index, url.as_str()))
                 .collect::<Vec<_>>()
-                .join(", ")
+                .join(", "),
+            self.ws_url.as_ref().map_or("-", |url| url.as_str()),
         )
     }
 }
@@ -8,9 +8,11 @@ use alloy::{
 };
 use axum::async_trait;
 use color_eyre::eyre::Report;
+use serde_json::json;
 use time::OffsetDateTime;
 use tokio::task::JoinSet;
 use tracing::{debug, info, info_span, trace, Instrument};
+use url::Url;
 
 use crate::{
     indexer::{
@@ -25,6 +27,7 @@ use crate::{
             postgres::active_contracts,
             provider::{Provider, RpcProviderId},
         },
+        heads::{Heads, WsSubscription},
     },
     postgres::{fetch_chain_id_tx, ChainId},
 };
@@ -61,6 +64,7 @@ pub struct EthFetcherClient {
     pub chain_id: ChainId,
     pub provider: Provider,
     pub transaction_filter: TransactionFilter,
+    pub heads: Option<Heads>,
 }
 
 #[derive(Clone, Debug)]
@@ -259,7 +263,7 @@ impl FetcherClient for EthFetcherClient {
 
     async fn create(
         pg_pool: sqlx::PgPool,
-        _join_set: &mut JoinSet<Result<(), IndexerError>>,
+        join_set: &mut JoinSet<Result<(), IndexerError>>,
         context: EthContext,
     ) -> Result<Self, IndexerError> {
         let provider = Provider::new(context.rpc_urls);
@@ -278,10 +282,15 @@ impl FetcherClient for EthFetcherClient {
 
             let transaction_filter = TransactionFilter { chain_id, pg_pool };
 
+            let heads = context
+                .ws_url
+                .map(|ws_url| Heads::spawn(join_set, new_heads_subscription(ws_url)));
+
             Ok(EthFetcherClient {
                 chain_id,
                 provider,
                 transaction_filter,
+                heads,
             })
         }
         .instrument(indexing_span)
@@ -295,4 +304,62 @@ impl FetcherClient for EthFetcherClient {
     ) -> Result<Self::BlockHandle, IndexerError> {
         self.fetch_single_with_provider(selection, mode, None).await
     }
+
+    fn heads(&self) -> Option<Heads> {
+        self.heads.clone()
+    }
+}
+
+fn new_heads_subscription(url: Url) -> WsSubscription {
+    WsSubscription {
+        url,
+        request: json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_subscribe",
+            "params": ["newHeads"],
+        }),
+        height: |message| {
+            let number = message
+                .pointer("/params/result/number")?
+                .as_str()?
+                .strip_prefix("0x")?;
+            BlockHeight::from_str_radix(number, 16).ok()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::indexer::ethereum::fetcher_client::new_heads_subscription;
+
+    #[test]
+    fn parses_new_heads_height() {
+        let subscription = new_heads_subscription("ws://localhost:8546".parse().unwrap());
+
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "eth_subscription",
+            "params": {
+                "subscription": "0x9ce59a13059e417087c02d3236a0b1cc",
+                "result": { "number": "0x1b4", "hash": "0xabcd" },
+            },
+        });
+
+        assert_eq!((subscription.height)(&notification), Some(436));
+    }
+
+    #[test]
+    fn ignores_other_messages() {
+        let subscription = new_heads_subscription("ws://localhost:8546".parse().unwrap());
+
+        // the response to the subscribe request
+        let response = json!({ "jsonrpc": "2.0", "id": 1, "result": "0x9ce59a13" });
+        assert_eq!((subscription.height)(&response), None);
+
+        let invalid = json!({ "params": { "result": { "number": "1b4" } } });
+        assert_eq!((subscription.height)(&invalid), None);
+    }
 }
@@ -9,6 +9,7 @@ use crate::{
         api::{BlockHash, BlockHeight},
         ethereum::block_handle::{BlockInsert, TransactionInsert},
     },
+    packet_lifecycle::{rewind_cursors, Source},
     postgres::{schedule_replication_reset, ChainId, InsertMode},
 };
 
@@ -74,6 +75,8 @@ pub async fn insert_batch_logs(
                 SELECT unnest($1::int[]), unnest($2::text[]), unnest($3::jsonb[]), unnest($4::bigint[]), unnest($5::timestamptz[])
                 ", &chain_ids, &hashes, &data, &height, &time)
             .execute(tx.as_mut()).await?;
+
+            rewind_cursors(tx, Source::Evm, chain_ids.into_iter().zip(height)).await?;
         }
         InsertMode::Upsert => {
             sqlx::query!("
@@ -124,6 +127,25 @@ pub async fn delete_eth_log(
     Ok(())
 }
 
+pub async fn get_eth_log_block_hash(
+    tx: &mut Transaction<'_, Postgres>,
+    chain_id: i32,
+    height: BlockHeight,
+) -> sqlx::Result<Option<BlockHash>> {
+    let height: i64 = height.try_into().unwrap();
+    let record = sqlx::query!(
+        "
+        SELECT block_hash FROM v2_evm.logs WHERE internal_chain_id = $1 AND height = $2
+        ",
+        chain_id,
+        height,
+    )
+    .fetch_optional(tx.as_mut())
+    .await?;
+
+    Ok(record.map(|r| r.block_hash))
+}
+
 pub async fn active_contracts(
     tx: &mut Transaction<'_, Postgres>,
     internal_chain_id: i32,
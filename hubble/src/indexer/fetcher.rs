@@ -21,7 +21,7 @@ enum RunToFinalizedLoopResult {
 
 enum RunToTipLoopResult {
     RunAgain,
-    TryAgainLater,
+    TryAgainLater(BlockHeight),
 }
 
 impl<T: FetcherClient> Indexer<T> {
@@ -124,9 +124,19 @@ impl<T: FetcherClient> Indexer<T> {
                 Ok(RunToTipLoopResult::RunAgain) => {
                     debug!("run again");
                 }
-                Ok(RunToTipLoopResult::TryAgainLater) => {
-                    debug!("try again later (sleep 1s)");
-                    sleep(Duration::from_secs(1)).await;
+                Ok(RunToTipLoopResult::TryAgainLater(next_height)) => {
+                    let poll_interval = self.finalizer_config.tip_poll_interval;
+
+                    match fetcher_client.heads() {
+                        Some(heads) => {
+                            debug!("try again later (wait for head {next_height})");
+                            heads.wait_for(next_height, poll_interval).await;
+                        }
+                        None => {
+                            debug!("try again later (sleep {}ms)", poll_interval.as_millis());
+                            sleep(poll_interval).await;
+                        }
+                    }
                 }
                 Err(error) => {
                     warn!("error in run to tip loop: {error} => try again later (sleep 1s)");
@@ -193,7 +203,7 @@ impl<T: FetcherClient> Indexer<T> {
             }
             Err(IndexerError::NoBlock(_)) => {
                 debug!("{}: no block yet => sleep", next_height);
-                Ok(RunToTipLoopResult::TryAgainLater)
+                Ok(RunToTipLoopResult::TryAgainLater(next_height))
             }
             Err(err) => {
                 warn!("{}: error reading block => sleep : {:?}", next_height, err);
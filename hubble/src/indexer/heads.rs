@@ -0,0 +1,241 @@
+use std::{fmt::Display, future::Future, time::Duration};
+
+use color_eyre::eyre::{eyre, Report};
+use futures::{SinkExt, StreamExt};
+use serde_json::Value;
+use tokio::{sync::watch, task::JoinSet, time::sleep};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tracing::{debug, info, info_span, warn, Instrument};
+use url::Url;
+
+use crate::indexer::api::{BlockHeight, IndexerError};
+
+/// How long to wait before reconnecting a failed subscription.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Heights of new blocks, pushed by a subscription to the node.
+///
+/// Lets the fetcher retry as soon as the next block is produced instead of on the next poll.
+/// While the subscription is not connected, waiting falls back to polling.
+#[derive(Clone)]
+pub struct Heads {
+    // `None` while not connected
+    receiver: watch::Receiver<Option<BlockHeight>>,
+}
+
+/// A connection to a node that pushes new heads.
+pub trait HeadStream: Display + Send + Sync + 'static {
+    /// Connects and pushes the height of every new head to `sender`, until the connection fails.
+    fn subscribe(&self, sender: &HeadSender) -> impl Future<Output = Result<(), Report>> + Send;
+}
+
+/// The sending half of [`Heads`], see [`HeadStream::subscribe`].
+pub struct HeadSender(watch::Sender<Option<BlockHeight>>);
+
+impl HeadSender {
+    /// Records a new head. Heads lower than the current head are ignored.
+    pub fn send(&self, height: BlockHeight) {
+        self.0.send_if_modified(|head| match head {
+            Some(head) if *head >= height => false,
+            _ => {
+                *head = Some(height);
+                true
+            }
+        });
+    }
+}
+
+impl Heads {
+    /// Subscribes in the background (as part of the join set of the fetcher client, so it is
+    /// aborted when the client is recreated). Reconnects on failure.
+    pub fn spawn(
+        join_set: &mut JoinSet<Result<(), IndexerError>>,
+        stream: impl HeadStream,
+    ) -> Heads {
+        let (sender, receiver) = watch::channel(None);
+        let sender = HeadSender(sender);
+
+        join_set.spawn(
+            async move {
+                loop {
+                    if let Err(error) = stream.subscribe(&sender).await {
+                        warn!(
+                            "{stream}: subscription failed ({error}) => reconnect in {}s",
+                            RECONNECT_DELAY.as_secs()
+                        );
+                    }
+                    sender.0.send_replace(None);
+                    sleep(RECONNECT_DELAY).await;
+                }
+            }
+            .instrument(info_span!("heads")),
+        );
+
+        Heads { receiver }
+    }
+
+    /// Called after `height` could not be fetched. Waits until the head is at least `height`, but
+    /// never longer than `poll_interval`, so a stalled subscription falls back to polling.
+    ///
+    /// If the head is already at `height`, the node that is fetched from lags behind the node of
+    /// the subscription, and this sleeps for `poll_interval`.
+    pub async fn wait_for(&self, height: BlockHeight, poll_interval: Duration) {
+        let mut receiver = self.receiver.clone();
+
+        let head = *receiver.borrow_and_update();
+
+        match head {
+            Some(head) if head < height => {
+                // wakes up on disconnect as well, so the caller falls back to polling
+                let _ = tokio::time::timeout(
+                    poll_interval,
+                    receiver.wait_for(|head| head.is_none_or(|head| head >= height)),
+                )
+                .await;
+            }
+            _ => sleep(poll_interval).await,
+        }
+    }
+}
+
+/// A json-rpc subscription over a websocket: the request to send and how to read the height of a
+/// head from the notifications.
+pub struct WsSubscription {
+    pub url: Url,
+    pub request: Value,
+    pub height: fn(&Value) -> Option<BlockHeight>,
+}
+
+impl Display for WsSubscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.url)
+    }
+}
+
+impl HeadStream for WsSubscription {
+    fn subscribe(&self, sender: &HeadSender) -> impl Future<Output = Result<(), Report>> + Send {
+        async move {
+            let url = &self.url;
+
+            let (mut stream, _) = connect_async(url.as_str()).await?;
+
+            stream.send(Message::text(self.request.to_string())).await?;
+
+            info!("{url}: subscribed");
+
+            while let Some(message) = stream.next().await {
+                let text = match message? {
+                    Message::Text(text) => text,
+                    Message::Close(frame) => return Err(eyre!("closed by server: {frame:?}")),
+                    _ => continue,
+                };
+
+                let Ok(value) = serde_json::from_str::<Value>(&text) else {
+                    debug!("{url}: ignoring non-json message");
+                    continue;
+                };
+
+                if let Some(height) = (self.height)(&value) {
+                    debug!("{url}: new head: {height}");
+                    sender.send(height);
+                }
+            }
+
+            Err(eyre!("stream ended"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use tokio::sync::watch;
+
+    use crate::indexer::heads::{HeadSender, Heads};
+
+    const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+    fn heads(head: Option<u64>) -> (HeadSender, Heads) {
+        let (sender, receiver) = watch::channel(head);
+        (HeadSender(sender), Heads { receiver })
+    }
+
+    #[tokio::test]
+    async fn wait_for_wakes_on_send() {
+        let (sender, heads) = heads(Some(5));
+
+        let wait = tokio::spawn(async move { heads.wait_for(6, Duration::from_secs(60)).await });
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        sender.send(6);
+
+        tokio::time::timeout(Duration::from_secs(5), wait)
+            .await
+            .expect("woken by the new head")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn wait_for_ignores_lower_heads() {
+        let (sender, heads) = heads(Some(5));
+
+        let start = Instant::now();
+        let wait = tokio::spawn(async move { heads.wait_for(7, POLL_INTERVAL).await });
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        sender.send(6);
+        sender.send(4);
+
+        wait.await.unwrap();
+        assert!(start.elapsed() >= POLL_INTERVAL);
+    }
+
+    #[tokio::test]
+    async fn wait_for_times_out() {
+        let (_sender, heads) = heads(Some(5));
+
+        let start = Instant::now();
+        heads.wait_for(6, POLL_INTERVAL).await;
+
+        assert!(start.elapsed() >= POLL_INTERVAL);
+    }
+
+    #[tokio::test]
+    async fn wait_for_wakes_on_disconnect() {
+        let (sender, heads) = heads(Some(5));
+
+        let wait = tokio::spawn(async move { heads.wait_for(6, Duration::from_secs(60)).await });
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        sender.0.send_replace(None);
+
+        tokio::time::timeout(Duration::from_secs(5), wait)
+            .await
+            .expect("woken by the disconnect")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn wait_for_sleeps_if_the_head_is_reached() {
+        // the fetched node lags behind the subscribed one
+        let (_sender, heads) = heads(Some(6));
+
+        let start = Instant::now();
+        heads.wait_for(6, POLL_INTERVAL).await;
+
+        assert!(start.elapsed() >= POLL_INTERVAL);
+    }
+
+    #[test]
+    fn send_keeps_the_highest_head() {
+        let (sender, heads) = heads(None);
+
+        sender.send(5);
+        sender.send(4);
+        assert_eq!(*heads.receiver.borrow(), Some(5));
+
+        sender.send(6);
+        assert_eq!(*heads.receiver.borrow(), Some(6));
+    }
+}
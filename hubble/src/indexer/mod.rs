@@ -1,11 +1,14 @@
 pub mod api;
 pub mod aptos;
+pub mod auditor;
 pub mod dummy;
 pub mod ethereum;
 mod fetcher;
 mod finalizer;
 mod fixer;
+pub mod heads;
 mod postgres;
+pub mod sui;
 pub mod tendermint;
 
 use std::{future::Future, time::Duration};
@@ -62,6 +65,15 @@ pub struct FinalizerConfig {
         deserialize_with = "FinalizerConfig::deserialize_seconds"
     )]
     pub retry_later_sleep: Duration,
+    // how long (in milliseconds) to wait for the next block at the tip, when the chain has no
+    // head subscription or its subscription stalls.
+    // default: 1 second
+    #[serde(
+        rename = "tip_poll_interval_milliseconds",
+        default = "FinalizerConfig::default_tip_poll_interval",
+        deserialize_with = "FinalizerConfig::deserialize_milliseconds"
+    )]
+    pub tip_poll_interval: Duration,
 }
 
 impl FinalizerConfig {
@@ -81,6 +93,10 @@ impl FinalizerConfig {
         Duration::from_secs(5)
     }
 
+    pub fn default_tip_poll_interval() -> Duration {
+        Duration::from_secs(1)
+    }
+
     fn deserialize_seconds<'de, D>(deserializer: D) -> Result<Duration, D::Error>
     where
         D: Deserializer<'de>,
@@ -88,6 +104,14 @@ impl FinalizerConfig {
         let seconds = u64::deserialize(deserializer)?;
         Ok(Duration::from_secs(seconds))
     }
+
+    fn deserialize_milliseconds<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let milliseconds = u64::deserialize(deserializer)?;
+        Ok(Duration::from_millis(milliseconds))
+    }
 }
 
 impl Default for FinalizerConfig {
@@ -98,6 +122,7 @@ impl Default for FinalizerConfig {
             min_duration_between_monitor_checks:
                 FinalizerConfig::default_min_duration_between_monitor_checks(),
             retry_later_sleep: FinalizerConfig::default_retry_later_sleep(),
+            tip_poll_interval: FinalizerConfig::default_tip_poll_interval(),
         }
     }
 }
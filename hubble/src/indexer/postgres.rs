@@ -134,6 +134,28 @@ pub async fn get_block_range_to_fix(
     }))
 }
 
+pub async fn insert_block_range_to_fix(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    indexer_id: IndexerId,
+    range: BlockRange,
+) -> sqlx::Result<()> {
+    let start_inclusive: i64 = range.start_inclusive.try_into().unwrap();
+    let end_exclusive: i64 = range.end_exclusive.try_into().unwrap();
+    sqlx::query!(
+        "
+        INSERT INTO hubble.block_fix (indexer_id, start_height, end_height)
+        VALUES ($1, $2, $3)
+        ",
+        indexer_id,
+        start_inclusive,
+        end_exclusive,
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    Ok(())
+}
+
 pub async fn update_block_range_to_fix(
     tx: &mut sqlx::Transaction<'_, Postgres>,
     indexer_id: IndexerId,
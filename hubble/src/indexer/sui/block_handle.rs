@@ -0,0 +1,183 @@
+use axum::async_trait;
+use color_eyre::eyre::Report;
+use futures::{stream::FuturesOrdered, Stream};
+use itertools::Itertools;
+use sqlx::Postgres;
+use time::OffsetDateTime;
+use tracing::{debug, trace};
+
+use crate::indexer::{
+    api::{
+        BlockHandle, BlockHash, BlockRange, BlockReference, BlockReferenceProvider, BlockSelection,
+        FetchMode, IndexerError,
+    },
+    sui::{
+        fetcher_client::SuiFetcherClient,
+        postgres::{
+            active_contracts, delete_sui_block_transactions_events, get_sui_block_hash,
+            insert_sui_block, PgBlock, PgEvent, PgTransaction,
+        },
+        provider::RpcProviderId,
+        types::{Checkpoint, TransactionBlock},
+    },
+};
+
+impl BlockReferenceProvider for Checkpoint {
+    fn block_reference(&self) -> Result<BlockReference, Report> {
+        let timestamp_ms: i128 = self.timestamp_ms.into();
+        Ok(BlockReference {
+            height: self.sequence_number,
+            hash: self.digest.clone(),
+            timestamp: OffsetDateTime::from_unix_timestamp_nanos(timestamp_ms * 1_000_000)
+                .map_err(Report::from)?,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub enum BlockDetails {
+    Lazy(Checkpoint),
+    Eager(Checkpoint, Vec<TransactionBlock>),
+}
+
+#[derive(Clone)]
+pub struct SuiBlockHandle {
+    pub internal_chain_id: i32,
+    pub reference: BlockReference,
+    pub details: BlockDetails,
+    pub sui_client: SuiFetcherClient,
+    pub provider_id: RpcProviderId,
+}
+
+#[async_trait]
+impl BlockHandle for SuiBlockHandle {
+    fn reference(&self) -> BlockReference {
+        self.reference.clone()
+    }
+
+    fn fetch_range(
+        &self,
+        block_range: BlockRange,
+        fetch_mode: FetchMode,
+    ) -> Result<impl Stream<Item = Result<Self, IndexerError>> + Send, IndexerError> {
+        debug!("{}: fetching", block_range);
+
+        Ok(FuturesOrdered::from_iter(
+            block_range.clone().into_iter().map(|height| async move {
+                self.sui_client
+                    .fetch_single_with_provider(
+                        BlockSelection::Height(height),
+                        fetch_mode,
+                        Some(self.provider_id),
+                    )
+                    .await
+            }),
+        ))
+    }
+
+    async fn insert(&self, tx: &mut sqlx::Transaction<'_, Postgres>) -> Result<(), IndexerError> {
+        let reference = self.reference();
+        debug!("{}: inserting", reference);
+
+        let (checkpoint, transactions) = match &self.details {
+            BlockDetails::Lazy(checkpoint) => (
+                checkpoint,
+                self.sui_client
+                    .fetch_transactions(checkpoint, self.provider_id)
+                    .await?,
+            ),
+            BlockDetails::Eager(checkpoint, transactions) => (checkpoint, transactions.clone()),
+        };
+
+        let active_contracts =
+            active_contracts(tx, self.internal_chain_id, checkpoint.sequence_number).await?;
+        trace!("{reference}: active contracts: {}", active_contracts.len());
+
+        let height: i64 = self.reference.height.try_into().unwrap();
+        let mut event_index_iter = 0..;
+
+        let transactions = transactions
+            .into_iter()
+            .enumerate()
+            .filter_map(|(transaction_index, transaction)| {
+                let events = transaction
+                    .events
+                    .into_iter()
+                    .filter(|event| active_contracts.contains(&event.package_id))
+                    .map(|event| PgEvent {
+                        internal_chain_id: self.internal_chain_id,
+                        height,
+                        transaction_hash: transaction.digest.clone(),
+                        index: event_index_iter.next().unwrap(),
+                        transaction_event_index: event.id.event_seq.try_into().unwrap(),
+                        package_id: event.package_id,
+                        module: event.transaction_module,
+                        sender: event.sender,
+                        typ: event.typ,
+                        data: event.parsed_json,
+                    })
+                    .collect_vec();
+
+                if events.is_empty() {
+                    trace!(
+                        "{reference}: no events for configured contracts in {}",
+                        transaction.digest
+                    );
+                    return None;
+                }
+
+                Some(PgTransaction {
+                    internal_chain_id: self.internal_chain_id,
+                    height,
+                    transaction_hash: transaction.digest,
+                    transaction_index: transaction_index.try_into().unwrap(),
+                    events,
+                })
+            })
+            .collect_vec();
+
+        if !transactions.is_empty() {
+            trace!(
+                "{}: transactions with matching events: {}",
+                reference,
+                transactions.len()
+            );
+
+            insert_sui_block(
+                tx,
+                PgBlock {
+                    internal_chain_id: self.internal_chain_id,
+                    height,
+                    block_hash: self.reference.hash.clone(),
+                    timestamp: self.reference.timestamp,
+                    transactions,
+                },
+            )
+            .await?;
+        } else {
+            trace!("{}: no matching events: ignore", reference);
+        }
+
+        debug!("{}: done", reference);
+        Ok(())
+    }
+
+    async fn update(&self, tx: &mut sqlx::Transaction<'_, Postgres>) -> Result<(), IndexerError> {
+        let reference = self.reference();
+        debug!("{}: updating", reference);
+
+        delete_sui_block_transactions_events(tx, self.internal_chain_id, self.reference.height)
+            .await?;
+        self.insert(tx).await?;
+
+        debug!("{}: done", reference);
+        Ok(())
+    }
+
+    async fn stored_hash(
+        &self,
+        tx: &mut sqlx::Transaction<'_, Postgres>,
+    ) -> Result<Option<BlockHash>, IndexerError> {
+        Ok(get_sui_block_hash(tx, self.internal_chain_id, self.reference.height).await?)
+    }
+}
@@ -0,0 +1,217 @@
+use std::fmt::Display;
+
+use axum::async_trait;
+use color_eyre::Result;
+use jsonrpsee::core::client::Error as ClientError;
+use tokio::task::JoinSet;
+use tracing::{debug, info, info_span, trace, Instrument};
+
+use crate::{
+    indexer::{
+        api::{
+            BlockHeight, BlockReferenceProvider, BlockSelection, FetchMode, FetcherClient,
+            IndexerError,
+        },
+        sui::{
+            block_handle::{BlockDetails, SuiBlockHandle},
+            context::SuiContext,
+            provider::{Provider, RpcProviderId},
+            types::{Checkpoint, TransactionBlock},
+        },
+    },
+    postgres::{fetch_chain_id_tx, ChainId},
+};
+
+#[derive(Clone)]
+pub struct SuiFetcherClient {
+    pub chain_id: ChainId,
+    pub provider: Provider,
+    pub tx_search_max_page_size: u16,
+}
+
+impl Display for SuiFetcherClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "chain_id: {}", self.chain_id)
+    }
+}
+
+impl SuiFetcherClient {
+    pub async fn fetch_single_with_provider(
+        &self,
+        selection: BlockSelection,
+        mode: FetchMode,
+        provider_id: Option<RpcProviderId>,
+    ) -> Result<SuiBlockHandle, IndexerError> {
+        debug!("{}: fetching", selection);
+
+        match selection {
+            BlockSelection::LastFinalized => self.fetch_last_finalized(mode, provider_id).await,
+            BlockSelection::Height(height) => self.fetch_at_height(mode, provider_id, height).await,
+        }
+    }
+
+    // checkpoints are final once they are certified, so the latest checkpoint is the last finalized block.
+    async fn fetch_last_finalized(
+        &self,
+        mode: FetchMode,
+        provider_id: Option<RpcProviderId>,
+    ) -> Result<SuiBlockHandle, IndexerError> {
+        trace!("fetch latest checkpoint");
+
+        let (provider_id, height) = self
+            .provider
+            .latest_checkpoint_sequence_number(provider_id)
+            .await
+            .map(|result| (result.provider_id, result.response.0))?;
+
+        trace!(
+            "current checkpoint: {height} using {:?} to fetch checkpoint",
+            provider_id
+        );
+
+        self.fetch_at_height(mode, Some(provider_id), height).await
+    }
+
+    async fn fetch_at_height(
+        &self,
+        mode: FetchMode,
+        provider_id: Option<RpcProviderId>,
+        height: BlockHeight,
+    ) -> Result<SuiBlockHandle, IndexerError> {
+        trace!("fetching checkpoint {height}");
+
+        let result = self
+            .provider
+            .checkpoint(height, provider_id)
+            .await
+            .map_err(|err| {
+                // map error to NoBlock if checkpoint does not exist (yet), so it's not reported
+                if is_checkpoint_not_found(&err) {
+                    return IndexerError::NoBlock(BlockSelection::Height(height));
+                }
+                err.into()
+            })?;
+
+        let checkpoint = result.response;
+
+        trace!(
+            "fetched checkpoint {height} using {:?}: {} (transactions: {})",
+            provider_id,
+            checkpoint.digest,
+            checkpoint.transactions.len()
+        );
+
+        Ok(SuiBlockHandle {
+            internal_chain_id: self.chain_id.db,
+            reference: checkpoint.block_reference()?,
+            details: match mode {
+                FetchMode::Lazy => BlockDetails::Lazy(checkpoint),
+                FetchMode::Eager => {
+                    let transactions = self
+                        .fetch_transactions(&checkpoint, result.provider_id)
+                        .await?;
+                    BlockDetails::Eager(checkpoint, transactions)
+                }
+            },
+            sui_client: self.clone(),
+            provider_id: result.provider_id,
+        })
+    }
+
+    pub async fn fetch_transactions(
+        &self,
+        checkpoint: &Checkpoint,
+        provider_id: RpcProviderId,
+    ) -> Result<Vec<TransactionBlock>, IndexerError> {
+        trace!(
+            "fetching transactions for checkpoint {} - transactions: {}",
+            checkpoint.sequence_number,
+            checkpoint.transactions.len()
+        );
+
+        let mut result = Vec::with_capacity(checkpoint.transactions.len());
+
+        for digests in checkpoint
+            .transactions
+            .chunks(self.tx_search_max_page_size.into())
+        {
+            trace!(
+                "fetching chunk for checkpoint {} - transactions: {}",
+                checkpoint.sequence_number,
+                digests.len()
+            );
+
+            result.extend(
+                self.provider
+                    .transaction_blocks(digests, Some(provider_id))
+                    .await?
+                    .response,
+            );
+        }
+
+        trace!(
+            "fetched transactions for checkpoint {} - transactions: {}",
+            checkpoint.sequence_number,
+            result.len()
+        );
+
+        Ok(result)
+    }
+}
+
+fn is_checkpoint_not_found(error: &ClientError) -> bool {
+    match error {
+        ClientError::Call(error_object) => error_object
+            .message()
+            .contains("Could not find the referenced checkpoint"),
+        _ => false,
+    }
+}
+
+#[async_trait]
+impl FetcherClient for SuiFetcherClient {
+    type BlockHandle = SuiBlockHandle;
+    type Context = SuiContext;
+
+    async fn create(
+        pg_pool: sqlx::PgPool,
+        _join_set: &mut JoinSet<Result<(), IndexerError>>,
+        context: SuiContext,
+    ) -> Result<Self, IndexerError> {
+        let provider = Provider::new(context.rpc_urls)?;
+
+        info!("fetching chain-id from node");
+        let chain_id = provider
+            .chain_identifier(None)
+            .await
+            .inspect_err(|e| debug!(?e, "error fetching chain-id: {}", e))?
+            .response;
+
+        info!("fetched chain-id from node: {}", chain_id);
+
+        let indexing_span = info_span!("indexer", chain_id = chain_id).or_current();
+        async move {
+            let mut tx = pg_pool.begin().await?;
+
+            let chain_id = fetch_chain_id_tx(&mut tx, chain_id.to_string()).await?;
+
+            tx.commit().await?;
+
+            Ok(SuiFetcherClient {
+                chain_id,
+                provider,
+                tx_search_max_page_size: context.tx_search_max_page_size,
+            })
+        }
+        .instrument(indexing_span)
+        .await
+    }
+
+    async fn fetch_single(
+        &self,
+        selection: BlockSelection,
+        mode: FetchMode,
+    ) -> Result<Self::BlockHandle, IndexerError> {
+        self.fetch_single_with_provider(selection, mode, None).await
+    }
+}
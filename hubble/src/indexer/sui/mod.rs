@@ -0,0 +1,18 @@
+use color_eyre::eyre::Report;
+use jsonrpsee::core::client::Error as ClientError;
+
+use crate::indexer::api::IndexerError;
+
+mod block_handle;
+pub mod config;
+mod context;
+mod fetcher_client;
+mod postgres;
+mod provider;
+mod types;
+
+impl From<ClientError> for IndexerError {
+    fn from(error: ClientError) -> Self {
+        Self::ProviderError(Report::from(error))
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashSet;
+
+use serde_json::Value;
+use sqlx::{Postgres, Transaction};
+use time::OffsetDateTime;
+use tracing::trace;
+
+use crate::{
+    indexer::api::{BlockHash, BlockHeight},
+    postgres::schedule_replication_reset,
+};
+
+/// DTO corresponding to the v2_sui.blocks table (one row per checkpoint).
+pub struct PgBlock {
+    pub internal_chain_id: i32,
+    pub height: i64,
+    pub block_hash: String,
+    pub timestamp: OffsetDateTime,
+    pub transactions: Vec<PgTransaction>,
+}
+
+/// DTO corresponding to the v2_sui.transactions table.
+pub struct PgTransaction {
+    pub internal_chain_id: i32,
+    pub height: i64,
+    pub transaction_hash: String,
+    pub transaction_index: i64,
+    pub events: Vec<PgEvent>,
+}
+
+/// DTO corresponding to the v2_sui.events table.
+pub struct PgEvent {
+    pub internal_chain_id: i32,
+    pub height: i64,
+    pub transaction_hash: String,
+    pub index: i64,
+    pub transaction_event_index: i64,
+    pub package_id: String,
+    pub module: String,
+    pub sender: String,
+    pub typ: String,
+    pub data: Value,
+}
+
+pub async fn insert_sui_block(
+    tx: &mut Transaction<'_, Postgres>,
+    block: PgBlock,
+) -> sqlx::Result<()> {
+    trace!("insert: {}", block.height);
+    sqlx::query!(
+        "
+        INSERT INTO v2_sui.blocks (
+            internal_chain_id,
+            block_hash,
+            height,
+            timestamp
+        ) VALUES ($1, $2, $3, $4);
+        ",
+        block.internal_chain_id,
+        block.block_hash,
+        block.height,
+        block.timestamp,
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    for transaction in block.transactions {
+        trace!("insert: {}/{}", block.height, transaction.transaction_hash);
+        sqlx::query!(
+            "
+            INSERT INTO v2_sui.transactions (
+                internal_chain_id,
+                height,
+                transaction_hash,
+                transaction_index
+            ) VALUES ($1, $2, $3, $4);
+            ",
+            transaction.internal_chain_id,
+            transaction.height,
+            transaction.transaction_hash,
+            transaction.transaction_index,
+        )
+        .execute(tx.as_mut())
+        .await?;
+
+        for event in transaction.events {
+            trace!(
+                "insert: {}/{}/{} ({}) {}",
+                block.height,
+                transaction.transaction_hash,
+                event.transaction_event_index,
+                event.package_id,
+                event.typ,
+            );
+            sqlx::query!(
+                "
+                INSERT INTO v2_sui.events (
+                    internal_chain_id,
+                    height,
+                    transaction_hash,
+                    index,
+                    transaction_event_index,
+                    package_id,
+                    module,
+                    sender,
+                    type,
+                    data
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10);
+                ",
+                event.internal_chain_id,
+                event.height,
+                event.transaction_hash,
+                event.index,
+                event.transaction_event_index,
+                event.package_id,
+                event.module,
+                event.sender,
+                event.typ,
+                event.data
+            )
+            .execute(tx.as_mut())
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn delete_sui_block_transactions_events(
+    tx: &mut Transaction<'_, Postgres>,
+    internal_chain_id: i32,
+    height: BlockHeight,
+) -> sqlx::Result<()> {
+    let height: i64 = height.try_into().unwrap();
+    sqlx::query!(
+        "
+        DELETE FROM v2_sui.events WHERE internal_chain_id = $1 AND height = $2
+        ",
+        internal_chain_id,
+        height,
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    sqlx::query!(
+        "
+        DELETE FROM v2_sui.transactions WHERE internal_chain_id = $1 AND height = $2
+        ",
+        internal_chain_id,
+        height,
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    sqlx::query!(
+        "
+        DELETE FROM v2_sui.blocks WHERE internal_chain_id = $1 AND height = $2
+        ",
+        internal_chain_id,
+        height,
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    schedule_replication_reset(tx, internal_chain_id, height, "block reorg (delete)").await?;
+
+    Ok(())
+}
+
+pub async fn get_sui_block_hash(
+    tx: &mut Transaction<'_, Postgres>,
+    internal_chain_id: i32,
+    height: BlockHeight,
+) -> sqlx::Result<Option<BlockHash>> {
+    let height: i64 = height.try_into().unwrap();
+    let record = sqlx::query!(
+        "
+        SELECT block_hash FROM v2_sui.blocks WHERE internal_chain_id = $1 AND height = $2
+        ",
+        internal_chain_id,
+        height,
+    )
+    .fetch_optional(tx.as_mut())
+    .await?;
+
+    Ok(record.map(|r| r.block_hash))
+}
+
+pub async fn active_contracts(
+    tx: &mut Transaction<'_, Postgres>,
+    internal_chain_id: i32,
+    height: BlockHeight,
+) -> sqlx::Result<HashSet<String>> {
+    let height: i64 = height.try_into().unwrap();
+
+    let result = sqlx::query!(
+        r#"
+        SELECT    address
+        FROM      v2_sui.contracts
+        WHERE     internal_chain_id = $1
+        AND       $2 between start_height and end_height
+        "#,
+        internal_chain_id,
+        height,
+    )
+    .fetch_all(tx.as_mut())
+    .await?
+    .into_iter()
+    .map(|record| record.address)
+    .collect();
+
+    Ok(result)
+}
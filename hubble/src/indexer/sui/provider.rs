@@ -0,0 +1,121 @@
+use std::result::Result;
+
+use jsonrpsee::{
+    core::client::{ClientT, Error as ClientError},
+    http_client::{HttpClient, HttpClientBuilder},
+    rpc_params,
+};
+use serde_json::json;
+use url::Url;
+
+use crate::{
+    indexer::{
+        api::BlockHeight,
+        sui::types::{Checkpoint, CheckpointSequenceNumber, TransactionBlock, TransactionDigest},
+    },
+    race_client::{RaceClient, RaceClientId, RaceClientResponse},
+};
+
+#[derive(Clone, Debug)]
+pub struct Provider {
+    pub rpc_client: RaceClient<HttpClient>,
+}
+
+#[derive(Clone, Debug, Copy)]
+pub struct RpcProviderId {
+    race_client_id: RaceClientId,
+}
+
+impl From<RpcProviderId> for RaceClientId {
+    fn from(value: RpcProviderId) -> Self {
+        value.race_client_id
+    }
+}
+
+#[derive(Debug)]
+pub struct RpcResult<T> {
+    pub provider_id: RpcProviderId,
+    pub response: T,
+}
+
+impl<T> RpcResult<T> {
+    fn new(race_client_id: RaceClientId, result: T) -> Self {
+        Self {
+            provider_id: RpcProviderId { race_client_id },
+            response: result,
+        }
+    }
+}
+
+impl<T> From<RaceClientResponse<T>> for RpcResult<T> {
+    fn from(value: RaceClientResponse<T>) -> Self {
+        RpcResult::new(value.race_client_id, value.response)
+    }
+}
+
+impl Provider {
+    pub fn new(rpc_urls: Vec<Url>) -> Result<Self, ClientError> {
+        Ok(Self {
+            rpc_client: RaceClient::new(
+                rpc_urls
+                    .into_iter()
+                    .map(|rpc_url| HttpClientBuilder::default().build(rpc_url.as_str()))
+                    .collect::<Result<_, _>>()?,
+            ),
+        })
+    }
+
+    // RPC
+    pub async fn chain_identifier(
+        &self,
+        provider_id: Option<RpcProviderId>,
+    ) -> Result<RpcResult<String>, ClientError> {
+        self.rpc_client
+            .race(provider_id.map(Into::into), |c| {
+                c.request("sui_getChainIdentifier", rpc_params![])
+            })
+            .await
+            .map(Into::into)
+    }
+
+    pub async fn latest_checkpoint_sequence_number(
+        &self,
+        provider_id: Option<RpcProviderId>,
+    ) -> Result<RpcResult<CheckpointSequenceNumber>, ClientError> {
+        self.rpc_client
+            .race(provider_id.map(Into::into), |c| {
+                c.request("sui_getLatestCheckpointSequenceNumber", rpc_params![])
+            })
+            .await
+            .map(Into::into)
+    }
+
+    pub async fn checkpoint(
+        &self,
+        height: BlockHeight,
+        provider_id: Option<RpcProviderId>,
+    ) -> Result<RpcResult<Checkpoint>, ClientError> {
+        self.rpc_client
+            .race(provider_id.map(Into::into), |c| {
+                c.request("sui_getCheckpoint", rpc_params![height.to_string()])
+            })
+            .await
+            .map(Into::into)
+    }
+
+    pub async fn transaction_blocks(
+        &self,
+        digests: &[TransactionDigest],
+        provider_id: Option<RpcProviderId>,
+    ) -> Result<RpcResult<Vec<TransactionBlock>>, ClientError> {
+        self.rpc_client
+            .race(provider_id.map(Into::into), |c| {
+                c.request(
+                    "sui_multiGetTransactionBlocks",
+                    rpc_params![digests, json!({ "showEvents": true })],
+                )
+            })
+            .await
+            .map(Into::into)
+    }
+}
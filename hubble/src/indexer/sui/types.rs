@@ -0,0 +1,96 @@
+//! Subset of the sui json-rpc types that are required for indexing.
+//!
+//! See <https://docs.sui.io/sui-api-ref> for the complete definitions. Unknown fields are ignored.
+
+use serde::Deserialize;
+use serde_json::Value;
+
+pub type CheckpointDigest = String;
+pub type TransactionDigest = String;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(transparent)]
+pub struct CheckpointSequenceNumber(#[serde(with = "::serde_utils::string")] pub u64);
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+    #[serde(with = "::serde_utils::string")]
+    pub sequence_number: u64,
+    pub digest: CheckpointDigest,
+    #[serde(with = "::serde_utils::string")]
+    pub timestamp_ms: u64,
+    pub transactions: Vec<TransactionDigest>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionBlock {
+    pub digest: TransactionDigest,
+    #[serde(default)]
+    pub events: Vec<Event>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+    pub id: EventId,
+    pub package_id: String,
+    pub transaction_module: String,
+    pub sender: String,
+    #[serde(rename = "type")]
+    pub typ: String,
+    pub parsed_json: Value,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventId {
+    #[serde(with = "::serde_utils::string")]
+    pub event_seq: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::indexer::sui::types::{Checkpoint, TransactionBlock};
+
+    #[test]
+    fn deserializes_checkpoint() {
+        let checkpoint: Checkpoint = serde_json::from_value(json!({
+            "epoch": "712",
+            "sequenceNumber": "140000000",
+            "digest": "4ZtPzvZdLgFXRpFJ9ATHTyaJ7BR6Yy6eHHNbT2KbsXsK",
+            "networkTotalTransactions": "3546311201",
+            "previousDigest": "9gjGpq3q4z6bXLBfrwrFdw5ZEBJqYCk5nWnCGfrtR6tM",
+            "epochRollingGasCostSummary": {
+                "computationCost": "0",
+                "storageCost": "0",
+                "storageRebate": "0",
+                "nonRefundableStorageFee": "0"
+            },
+            "timestampMs": "1742551398553",
+            "transactions": ["7dyZnFxzAmGsGD3amvBa9uphc3PEuFwUtdaZh7JGQa4K"],
+            "checkpointCommitments": [],
+            "validatorSignature": "rMq5yrqs0pPbLPkf/rT8xnHMZ8xMjkZTKLxGiWl6e7A="
+        }))
+        .unwrap();
+
+        assert_eq!(checkpoint.sequence_number, 140000000);
+        assert_eq!(checkpoint.timestamp_ms, 1742551398553);
+        assert_eq!(checkpoint.transactions.len(), 1);
+    }
+
+    #[test]
+    fn deserializes_transaction_block_without_events() {
+        let transaction: TransactionBlock = serde_json::from_value(json!({
+            "digest": "7dyZnFxzAmGsGD3amvBa9uphc3PEuFwUtdaZh7JGQa4K",
+            "checkpoint": "140000000",
+            "timestampMs": "1742551398553"
+        }))
+        .unwrap();
+
+        assert!(transaction.events.is_empty());
+    }
+}
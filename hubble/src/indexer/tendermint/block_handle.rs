@@ -15,13 +15,15 @@ use tracing::{debug, trace};
 
 use crate::indexer::{
     api::{
-        BlockHandle, BlockRange, BlockReference, BlockReferenceProvider, FetchMode, IndexerError,
+        BlockHandle, BlockHash, BlockRange, BlockReference, BlockReferenceProvider, FetchMode,
+        IndexerError,
     },
     tendermint::{
         fetcher_client::TmFetcherClient,
         postgres::{
-            active_contracts, delete_tm_block_transactions_events, insert_batch_blocks,
-            insert_batch_events, insert_batch_transactions, PgBlock, PgEvent, PgTransaction,
+            active_contracts, delete_tm_block_transactions_events, get_tm_block_hash,
+            insert_batch_blocks, insert_batch_events, insert_batch_messages,
+            insert_batch_transactions, PgBlock, PgEvent, PgTransaction,
         },
         provider::RpcProviderId,
     },
@@ -329,8 +331,14 @@ impl BlockHandle for TmBlockHandle {
                 filtered_events.len(),
             );
 
+            let messages = filtered_transactions
+                .iter()
+                .flat_map(|transaction| transaction.messages.clone())
+                .collect_vec();
+
             insert_batch_blocks(tx, vec![block]).await?;
             insert_batch_transactions(tx, filtered_transactions).await?;
+            insert_batch_messages(tx, messages).await?;
             insert_batch_events(tx, filtered_events).await?;
         } else {
             trace!("{}: ignore (no events for registered contracts)", reference);
@@ -351,6 +359,13 @@ impl BlockHandle for TmBlockHandle {
         debug!("{}: done", reference);
         Ok(())
     }
+
+    async fn stored_hash(
+        &self,
+        tx: &mut sqlx::Transaction<'_, Postgres>,
+    ) -> Result<Option<BlockHash>, IndexerError> {
+        Ok(get_tm_block_hash(tx, self.tm_client.chain_id.db, self.reference.height).await?)
+    }
 }
 
 #[cfg(test)]
@@ -17,6 +17,8 @@ pub struct Config {
     pub start_height: BlockHeight,
     pub chunk_size: Option<usize>,
     pub rpc_urls: Vec<Url>,
+    /// Websocket url to subscribe to new block headers. Polls for new blocks if not set.
+    pub ws_url: Option<Url>,
     pub tx_search_max_page_size: Option<u8>,
     #[serde(default)]
     pub finalizer: FinalizerConfig,
@@ -34,6 +36,7 @@ impl Config {
             self.finalizer,
             TmContext {
                 rpc_urls: self.rpc_urls,
+                ws_url: self.ws_url,
                 tx_search_max_page_size: self
                     .tx_search_max_page_size
                     .unwrap_or(DEFAULT_TRANSACTIONS_MAX_PAGE_SIZE),
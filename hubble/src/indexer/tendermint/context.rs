@@ -5,6 +5,7 @@ use url::Url;
 #[derive(Clone)]
 pub struct TmContext {
     pub rpc_urls: Vec<Url>,
+    pub ws_url: Option<Url>,
     pub tx_search_max_page_size: u8,
     pub testnet: bool,
 }
@@ -13,8 +14,9 @@ impl Display for TmContext {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "rpcs: {}, tx_search_max_page_size: {}",
+            "rpcs: {}, ws: {}, tx_search_max_page_size: {}",
             to_indexed_url_string(&self.rpc_urls),
+            self.ws_url.as_ref().map_or("-", |url| url.as_str()),
             self.tx_search_max_page_size,
         )
     }
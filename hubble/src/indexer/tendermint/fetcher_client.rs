@@ -16,9 +16,11 @@ use futures::{
 };
 use itertools::Itertools;
 use jsonrpsee::types::{error::INTERNAL_ERROR_CODE, ErrorObject};
+use serde_json::json;
 use time::OffsetDateTime;
 use tokio::task::JoinSet;
 use tracing::{debug, info, info_span, trace, warn, Instrument};
+use url::Url;
 
 use crate::{
     indexer::{
@@ -26,10 +28,12 @@ use crate::{
             BlockHeight, BlockRange, BlockReferenceProvider, BlockSelection, FetchMode,
             FetcherClient, IndexerError,
         },
+        heads::{Heads, WsSubscription},
         tendermint::{
             block_handle::{BlockDetails, BlockHeader, TmBlockHandle},
             context::TmContext,
-            postgres::{PgBlock, PgEvent, PgTransaction},
+            messages::decode_messages,
+            postgres::{PgBlock, PgEvent, PgMessage, PgTransaction},
             provider::{Provider, RpcProviderId},
         },
     },
@@ -42,6 +46,7 @@ pub struct TmFetcherClient {
     pub provider: Provider,
     pub tx_search_max_page_size: u8,
     pub testnet: bool,
+    pub heads: Option<Heads>,
 }
 
 impl Display for TmFetcherClient {
@@ -257,6 +262,31 @@ impl TmFetcherClient {
                             event
                         },
                     ));
+                    let transaction_index: i32 = tx.index.try_into().unwrap();
+                    let messages = match decode_messages(&tx.tx) {
+                        Ok(messages) => messages
+                            .into_iter()
+                            .enumerate()
+                            .map(|(i, message)| PgMessage {
+                                chain_id: self.chain_id,
+                                block_hash: block_reference.hash.clone(),
+                                block_height: block_reference.height,
+                                time: block_reference.timestamp,
+                                transaction_hash: transaction_hash.clone(),
+                                transaction_index,
+                                index: i.try_into().unwrap(),
+                                type_url: message.type_url,
+                                data: message.data.map(|data| data.replace_escape_chars()),
+                            })
+                            .collect(),
+                        Err(error) => {
+                            warn!(
+                                "{}: cannot decode transaction {}: {}",
+                                block_reference, transaction_hash, error
+                            );
+                            vec![]
+                        }
+                    };
                     PgTransaction {
                         chain_id: self.chain_id,
                         block_hash: block_reference.hash.clone(),
@@ -264,7 +294,8 @@ impl TmFetcherClient {
                         time: block_reference.timestamp,
                         data,
                         hash: transaction_hash,
-                        index: tx.index.try_into().unwrap(),
+                        index: transaction_index,
+                        messages,
                     }
                 })
                 .collect::<Vec<_>>();
@@ -535,7 +566,7 @@ impl FetcherClient for TmFetcherClient {
 
     async fn create(
         pg_pool: sqlx::PgPool,
-        _join_set: &mut JoinSet<Result<(), IndexerError>>,
+        join_set: &mut JoinSet<Result<(), IndexerError>>,
         context: TmContext,
     ) -> Result<Self, IndexerError> {
         let provider = Provider::new(context.rpc_urls).await?;
@@ -560,11 +591,16 @@ impl FetcherClient for TmFetcherClient {
 
             tx.commit().await?;
 
+            let heads = context
+                .ws_url
+                .map(|ws_url| Heads::spawn(join_set, new_block_header_subscription(ws_url)));
+
             Ok(TmFetcherClient {
                 chain_id,
                 provider,
                 tx_search_max_page_size: context.tx_search_max_page_size,
                 testnet: context.testnet,
+                heads,
             })
         }
         .instrument(indexing_span)
@@ -578,4 +614,65 @@ impl FetcherClient for TmFetcherClient {
     ) -> Result<Self::BlockHandle, IndexerError> {
         self.fetch_single_with_provider(selection, mode, None).await
     }
+
+    fn heads(&self) -> Option<Heads> {
+        self.heads.clone()
+    }
+}
+
+fn new_block_header_subscription(url: Url) -> WsSubscription {
+    WsSubscription {
+        url,
+        request: json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "subscribe",
+            "params": { "query": "tm.event='NewBlockHeader'" },
+        }),
+        height: |message| {
+            message
+                .pointer("/result/data/value/header/height")?
+                .as_str()?
+                .parse()
+                .ok()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::indexer::tendermint::fetcher_client::new_block_header_subscription;
+
+    #[test]
+    fn parses_new_block_header_height() {
+        let subscription = new_block_header_subscription("ws://localhost:26657".parse().unwrap());
+
+        let event = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "query": "tm.event='NewBlockHeader'",
+                "data": {
+                    "type": "tendermint/event/NewBlockHeader",
+                    "value": { "header": { "chain_id": "union-1", "height": "436" } },
+                },
+            },
+        });
+
+        assert_eq!((subscription.height)(&event), Some(436));
+    }
+
+    #[test]
+    fn ignores_other_messages() {
+        let subscription = new_block_header_subscription("ws://localhost:26657".parse().unwrap());
+
+        // the response to the subscribe request
+        let response = json!({ "jsonrpc": "2.0", "id": 1, "result": {} });
+        assert_eq!((subscription.height)(&response), None);
+
+        let invalid = json!({ "result": { "data": { "value": { "header": { "height": 436 } } } } });
+        assert_eq!((subscription.height)(&invalid), None);
+    }
 }
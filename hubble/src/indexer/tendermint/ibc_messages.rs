@@ -0,0 +1,251 @@
+//! Json representations of IBC messages, built from their raw protobuf types.
+//!
+//! Bytes are hex encoded with a `0x` prefix and heights are represented as
+//! `{ revision_number, revision_height }`. The signer is kept, as it is the relayer that
+//! submitted the message.
+
+use protos::{
+    cosmos::base::v1beta1::Coin,
+    google::protobuf::Any,
+    ibc::{
+        applications::transfer::v1::MsgTransfer,
+        core::{
+            channel::v1::{
+                Channel, MsgAcknowledgement, MsgChannelOpenAck, MsgChannelOpenConfirm,
+                MsgChannelOpenInit, MsgChannelOpenTry, MsgRecvPacket, MsgTimeout, Packet,
+            },
+            client::v1::{Height, MsgCreateClient, MsgUpdateClient},
+            connection::v1::{
+                Counterparty, MsgConnectionOpenAck, MsgConnectionOpenConfirm,
+                MsgConnectionOpenInit, MsgConnectionOpenTry, Version,
+            },
+        },
+    },
+};
+use serde_json::{json, Value};
+
+pub fn msg_transfer(msg: MsgTransfer) -> Value {
+    json!({
+        "source_port": msg.source_port,
+        "source_channel": msg.source_channel,
+        "token": msg.token.map(coin),
+        "sender": msg.sender,
+        "receiver": msg.receiver,
+        "timeout_height": msg.timeout_height.map(height),
+        "timeout_timestamp": msg.timeout_timestamp,
+        "memo": msg.memo,
+    })
+}
+
+pub fn msg_create_client(msg: MsgCreateClient) -> Value {
+    json!({
+        "client_state": msg.client_state.map(any),
+        "consensus_state": msg.consensus_state.map(any),
+        "signer": msg.signer,
+    })
+}
+
+pub fn msg_update_client(msg: MsgUpdateClient) -> Value {
+    json!({
+        "client_id": msg.client_id,
+        "client_message": msg.client_message.map(any),
+        "signer": msg.signer,
+    })
+}
+
+pub fn msg_connection_open_init(msg: MsgConnectionOpenInit) -> Value {
+    json!({
+        "client_id": msg.client_id,
+        "counterparty": msg.counterparty.map(connection_counterparty),
+        "version": msg.version.map(version),
+        "delay_period": msg.delay_period,
+        "signer": msg.signer,
+    })
+}
+
+// the deprecated fields are still set by chains running older ibc-go versions
+#[allow(deprecated)]
+pub fn msg_connection_open_try(msg: MsgConnectionOpenTry) -> Value {
+    let counterparty_versions = msg
+        .counterparty_versions
+        .into_iter()
+        .map(version)
+        .collect::<Vec<_>>();
+
+    json!({
+        "client_id": msg.client_id,
+        "client_state": msg.client_state.map(any),
+        "counterparty": msg.counterparty.map(connection_counterparty),
+        "delay_period": msg.delay_period,
+        "counterparty_versions": counterparty_versions,
+        "proof_height": msg.proof_height.map(height),
+        "proof_init": hex(&msg.proof_init),
+        "proof_client": hex(&msg.proof_client),
+        "proof_consensus": hex(&msg.proof_consensus),
+        "consensus_height": msg.consensus_height.map(height),
+        "signer": msg.signer,
+    })
+}
+
+// the deprecated fields are still set by chains running older ibc-go versions
+#[allow(deprecated)]
+pub fn msg_connection_open_ack(msg: MsgConnectionOpenAck) -> Value {
+    json!({
+        "connection_id": msg.connection_id,
+        "counterparty_connection_id": msg.counterparty_connection_id,
+        "version": msg.version.map(version),
+        "client_state": msg.client_state.map(any),
+        "proof_height": msg.proof_height.map(height),
+        "proof_try": hex(&msg.proof_try),
+        "proof_client": hex(&msg.proof_client),
+        "proof_consensus": hex(&msg.proof_consensus),
+        "consensus_height": msg.consensus_height.map(height),
+        "signer": msg.signer,
+    })
+}
+
+pub fn msg_connection_open_confirm(msg: MsgConnectionOpenConfirm) -> Value {
+    json!({
+        "connection_id": msg.connection_id,
+        "proof_ack": hex(&msg.proof_ack),
+        "proof_height": msg.proof_height.map(height),
+        "signer": msg.signer,
+    })
+}
+
+pub fn msg_channel_open_init(msg: MsgChannelOpenInit) -> Value {
+    json!({
+        "port_id": msg.port_id,
+        "channel": msg.channel.map(channel),
+        "signer": msg.signer,
+    })
+}
+
+pub fn msg_channel_open_try(msg: MsgChannelOpenTry) -> Value {
+    json!({
+        "port_id": msg.port_id,
+        "channel": msg.channel.map(channel),
+        "counterparty_version": msg.counterparty_version,
+        "proof_init": hex(&msg.proof_init),
+        "proof_height": msg.proof_height.map(height),
+        "signer": msg.signer,
+    })
+}
+
+pub fn msg_channel_open_ack(msg: MsgChannelOpenAck) -> Value {
+    json!({
+        "port_id": msg.port_id,
+        "channel_id": msg.channel_id,
+        "counterparty_channel_id": msg.counterparty_channel_id,
+        "counterparty_version": msg.counterparty_version,
+        "proof_try": hex(&msg.proof_try),
+        "proof_height": msg.proof_height.map(height),
+        "signer": msg.signer,
+    })
+}
+
+pub fn msg_channel_open_confirm(msg: MsgChannelOpenConfirm) -> Value {
+    json!({
+        "port_id": msg.port_id,
+        "channel_id": msg.channel_id,
+        "proof_ack": hex(&msg.proof_ack),
+        "proof_height": msg.proof_height.map(height),
+        "signer": msg.signer,
+    })
+}
+
+pub fn msg_recv_packet(msg: MsgRecvPacket) -> Value {
+    json!({
+        "packet": msg.packet.map(packet),
+        "proof_commitment": hex(&msg.proof_commitment),
+        "proof_height": msg.proof_height.map(height),
+        "signer": msg.signer,
+    })
+}
+
+pub fn msg_acknowledgement(msg: MsgAcknowledgement) -> Value {
+    json!({
+        "packet": msg.packet.map(packet),
+        "acknowledgement": hex(&msg.acknowledgement),
+        "proof_acked": hex(&msg.proof_acked),
+        "proof_height": msg.proof_height.map(height),
+        "signer": msg.signer,
+    })
+}
+
+pub fn msg_timeout(msg: MsgTimeout) -> Value {
+    json!({
+        "packet": msg.packet.map(packet),
+        "proof_unreceived": hex(&msg.proof_unreceived),
+        "proof_height": msg.proof_height.map(height),
+        "next_sequence_recv": msg.next_sequence_recv,
+        "signer": msg.signer,
+    })
+}
+
+fn packet(packet: Packet) -> Value {
+    json!({
+        "sequence": packet.sequence,
+        "source_port": packet.source_port,
+        "source_channel": packet.source_channel,
+        "destination_port": packet.destination_port,
+        "destination_channel": packet.destination_channel,
+        "data": hex(&packet.data),
+        "timeout_height": packet.timeout_height.map(height),
+        "timeout_timestamp": packet.timeout_timestamp,
+    })
+}
+
+fn channel(channel: Channel) -> Value {
+    json!({
+        "state": channel.state().as_str_name(),
+        "ordering": channel.ordering().as_str_name(),
+        "counterparty": channel.counterparty.map(|counterparty| json!({
+            "port_id": counterparty.port_id,
+            "channel_id": counterparty.channel_id,
+        })),
+        "connection_hops": channel.connection_hops,
+        "version": channel.version,
+    })
+}
+
+fn connection_counterparty(counterparty: Counterparty) -> Value {
+    json!({
+        "client_id": counterparty.client_id,
+        "connection_id": counterparty.connection_id,
+        "prefix": counterparty.prefix.map(|prefix| hex(&prefix.key_prefix)),
+    })
+}
+
+fn version(version: Version) -> Value {
+    json!({
+        "identifier": version.identifier,
+        "features": version.features,
+    })
+}
+
+fn height(height: Height) -> Value {
+    json!({
+        "revision_number": height.revision_number,
+        "revision_height": height.revision_height,
+    })
+}
+
+fn coin(coin: Coin) -> Value {
+    json!({
+        "denom": coin.denom,
+        "amount": coin.amount,
+    })
+}
+
+// the value is kept encoded, as the client types are chain specific
+fn any(any: Any) -> Value {
+    json!({
+        "type_url": any.type_url,
+        "value": hex(&any.value),
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
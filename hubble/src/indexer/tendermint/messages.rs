@@ -0,0 +1,764 @@
+use color_eyre::eyre::{eyre, Report};
+use protos::ibc::{
+    applications::transfer::v1::MsgTransfer,
+    core::{
+        channel::v1::{
+            MsgAcknowledgement, MsgChannelOpenAck, MsgChannelOpenConfirm, MsgChannelOpenInit,
+            MsgChannelOpenTry, MsgRecvPacket, MsgTimeout,
+        },
+        client::v1::{MsgCreateClient, MsgUpdateClient},
+        connection::v1::{
+            MsgConnectionOpenAck, MsgConnectionOpenConfirm, MsgConnectionOpenInit,
+            MsgConnectionOpenTry,
+        },
+    },
+};
+use serde::Serialize;
+use serde_json::Value;
+use tracing::{trace, warn};
+use unionlabs::{
+    cosmos::{
+        bank::msg_send::MsgSend,
+        staking::{
+            msg_begin_redelegate::MsgBeginRedelegate, msg_delegate::MsgDelegate,
+            msg_undelegate::MsgUndelegate,
+        },
+        tx::{tx_body::TxBody, tx_raw::TxRaw},
+    },
+    cosmwasm::wasm::{
+        msg_execute_contract::MsgExecuteContract,
+        msg_instantiate_contract2::MsgInstantiateContract2,
+        msg_migrate_contract::MsgMigrateContract,
+    },
+    encoding::{Decode, DecodeAs, Proto},
+    google::protobuf::any::RawAny,
+    ErrorReporter, TypeUrl,
+};
+
+use crate::indexer::tendermint::ibc_messages;
+
+/// A message of a transaction, decoded to json if the type is known.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedMessage {
+    pub type_url: String,
+    /// `None` if the type url is not supported or the message could not be decoded.
+    pub data: Option<Value>,
+}
+
+/// Decodes the messages of a protobuf encoded `TxRaw`.
+///
+/// Only the body is decoded; signatures and auth info are ignored, so transactions signed with
+/// unsupported key types are still decoded.
+pub fn decode_messages(tx_bytes: &[u8]) -> Result<Vec<DecodedMessage>, Report> {
+    let tx_raw = TxRaw::decode_as::<Proto>(tx_bytes)
+        .map_err(|err| eyre!("invalid tx: {}", ErrorReporter(err)))?;
+
+    let tx_body = <TxBody<RawAny>>::decode_as::<Proto>(&tx_raw.body_bytes)
+        .map_err(|err| eyre!("invalid tx body: {}", ErrorReporter(err)))?;
+
+    Ok(tx_body
+        .messages
+        .into_iter()
+        .map(|message| {
+            let data = decode_message(&message).unwrap_or_else(|err| {
+                // keep the message (without data), so the type is still queryable
+                warn!("cannot decode message {}: {err}", message.type_url);
+                None
+            });
+
+            DecodedMessage {
+                type_url: message.type_url,
+                data,
+            }
+        })
+        .collect())
+}
+
+fn decode_message(message: &RawAny) -> Result<Option<Value>, Report> {
+    Ok(Some(match message.type_url.as_str() {
+        "/cosmos.bank.v1beta1.MsgSend" => to_json(decode::<MsgSend>(message)?)?,
+        "/cosmos.staking.v1beta1.MsgDelegate" => to_json(decode::<MsgDelegate>(message)?)?,
+        "/cosmos.staking.v1beta1.MsgUndelegate" => to_json(decode::<MsgUndelegate>(message)?)?,
+        "/cosmos.staking.v1beta1.MsgBeginRedelegate" => {
+            to_json(decode::<MsgBeginRedelegate>(message)?)?
+        }
+        "/cosmwasm.wasm.v1.MsgExecuteContract" => {
+            let msg = decode::<MsgExecuteContract>(message)?;
+            with_wasm_msg(to_json(&msg)?, &msg.msg)
+        }
+        "/cosmwasm.wasm.v1.MsgInstantiateContract2" => {
+            let msg = decode::<MsgInstantiateContract2>(message)?;
+            with_wasm_msg(to_json(&msg)?, &msg.msg)
+        }
+        "/cosmwasm.wasm.v1.MsgMigrateContract" => {
+            let msg = decode::<MsgMigrateContract>(message)?;
+            with_wasm_msg(to_json(&msg)?, &msg.msg)
+        }
+        "/ibc.applications.transfer.v1.MsgTransfer" => {
+            ibc_messages::msg_transfer(decode_raw::<MsgTransfer>(message)?)
+        }
+        "/ibc.core.client.v1.MsgCreateClient" => {
+            ibc_messages::msg_create_client(decode_raw::<MsgCreateClient>(message)?)
+        }
+        "/ibc.core.client.v1.MsgUpdateClient" => {
+            ibc_messages::msg_update_client(decode_raw::<MsgUpdateClient>(message)?)
+        }
+        "/ibc.core.connection.v1.MsgConnectionOpenInit" => {
+            ibc_messages::msg_connection_open_init(decode_raw::<MsgConnectionOpenInit>(message)?)
+        }
+        "/ibc.core.connection.v1.MsgConnectionOpenTry" => {
+            ibc_messages::msg_connection_open_try(decode_raw::<MsgConnectionOpenTry>(message)?)
+        }
+        "/ibc.core.connection.v1.MsgConnectionOpenAck" => {
+            ibc_messages::msg_connection_open_ack(decode_raw::<MsgConnectionOpenAck>(message)?)
+        }
+        "/ibc.core.connection.v1.MsgConnectionOpenConfirm" => {
+            ibc_messages::msg_connection_open_confirm(decode_raw::<MsgConnectionOpenConfirm>(
+                message,
+            )?)
+        }
+        "/ibc.core.channel.v1.MsgChannelOpenInit" => {
+            ibc_messages::msg_channel_open_init(decode_raw::<MsgChannelOpenInit>(message)?)
+        }
+        "/ibc.core.channel.v1.MsgChannelOpenTry" => {
+            ibc_messages::msg_channel_open_try(decode_raw::<MsgChannelOpenTry>(message)?)
+        }
+        "/ibc.core.channel.v1.MsgChannelOpenAck" => {
+            ibc_messages::msg_channel_open_ack(decode_raw::<MsgChannelOpenAck>(message)?)
+        }
+        "/ibc.core.channel.v1.MsgChannelOpenConfirm" => {
+            ibc_messages::msg_channel_open_confirm(decode_raw::<MsgChannelOpenConfirm>(message)?)
+        }
+        "/ibc.core.channel.v1.MsgRecvPacket" => {
+            ibc_messages::msg_recv_packet(decode_raw::<MsgRecvPacket>(message)?)
+        }
+        "/ibc.core.channel.v1.MsgAcknowledgement" => {
+            ibc_messages::msg_acknowledgement(decode_raw::<MsgAcknowledgement>(message)?)
+        }
+        "/ibc.core.channel.v1.MsgTimeout" => {
+            ibc_messages::msg_timeout(decode_raw::<MsgTimeout>(message)?)
+        }
+        type_url => {
+            trace!("unsupported message type: {type_url}");
+            return Ok(None);
+        }
+    }))
+}
+
+fn decode<T: TypeUrl + Decode<Proto, Error: core::error::Error>>(
+    message: &RawAny,
+) -> Result<T, Report> {
+    message
+        .decode::<T>()
+        .map_err(|err| eyre!("{}", ErrorReporter(err)))
+}
+
+// the unionlabs ibc messages have no proto conversions, so these are decoded from the raw
+// protobuf types instead
+fn decode_raw<T: prost::Message + Default>(message: &RawAny) -> Result<T, Report> {
+    Ok(T::decode(message.value.as_slice())?)
+}
+
+fn to_json(message: impl Serialize) -> Result<Value, Report> {
+    Ok(serde_json::to_value(message)?)
+}
+
+// replaces the base64 encoded contract msg with its json representation, so it can be queried.
+// contract messages that are not valid json are kept as they are.
+fn with_wasm_msg(mut data: Value, msg: &[u8]) -> Value {
+    if let (Value::Object(data), Ok(msg)) = (&mut data, serde_json::from_slice::<Value>(msg)) {
+        data.insert("msg".to_string(), msg);
+    }
+
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use protos::{
+        cosmos::base::v1beta1::Coin as ProtoCoin,
+        google::protobuf::Any as ProtoAny,
+        ibc::{
+            applications::transfer::v1::MsgTransfer,
+            core::{
+                channel::v1::{
+                    Channel, Counterparty as ChannelCounterparty, MsgAcknowledgement,
+                    MsgChannelOpenAck, MsgChannelOpenConfirm, MsgChannelOpenInit,
+                    MsgChannelOpenTry, MsgRecvPacket, MsgTimeout, Order, Packet, State,
+                },
+                client::v1::{Height, MsgCreateClient, MsgUpdateClient},
+                commitment::v1::MerklePrefix,
+                connection::v1::{
+                    Counterparty as ConnectionCounterparty, MsgConnectionOpenAck,
+                    MsgConnectionOpenConfirm, MsgConnectionOpenInit, MsgConnectionOpenTry, Version,
+                },
+            },
+        },
+    };
+    use serde_json::{json, Value};
+    use unionlabs::{
+        cosmos::{
+            bank::msg_send::MsgSend,
+            base::coin::Coin,
+            tx::{tx_body::TxBody, tx_raw::TxRaw},
+        },
+        cosmwasm::wasm::msg_execute_contract::MsgExecuteContract,
+        encoding::{EncodeAs, Proto},
+        google::protobuf::any::{Any, RawAny},
+    };
+
+    use crate::indexer::tendermint::messages::{decode_messages, DecodedMessage};
+
+    const ADDRESS: &str = "union17e93ukhcyesrvu72cgfvamdhyracghrx4f7ww89rqjg944ntdegscxepme";
+
+    #[test]
+    fn decodes_known_messages() {
+        let actual = decode_messages(&tx_bytes(vec![
+            Any(MsgSend {
+                from_address: ADDRESS.parse().unwrap(),
+                to_address: ADDRESS.parse().unwrap(),
+                amount: vec![Coin {
+                    denom: "muno".to_string(),
+                    amount: 10,
+                }],
+            })
+            .into(),
+            Any(MsgExecuteContract {
+                sender: ADDRESS.parse().unwrap(),
+                contract: ADDRESS.parse().unwrap(),
+                msg: br#"{"packet_recv":{"packets":[]}}"#.to_vec().into(),
+                funds: vec![],
+            })
+            .into(),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            actual,
+            vec![
+                DecodedMessage {
+                    type_url: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+                    data: Some(json!({
+                        "from_address": ADDRESS,
+                        "to_address": ADDRESS,
+                        "amount": [{ "denom": "muno", "amount": "10" }],
+                    })),
+                },
+                DecodedMessage {
+                    type_url: "/cosmwasm.wasm.v1.MsgExecuteContract".to_string(),
+                    data: Some(json!({
+                        "sender": ADDRESS,
+                        "contract": ADDRESS,
+                        "msg": { "packet_recv": { "packets": [] } },
+                        "funds": [],
+                    })),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_messages_without_data() {
+        let actual = decode_messages(&tx_bytes(vec![RawAny {
+            type_url: "/unknown.v1.MsgUnknown".to_string(),
+            value: vec![1, 2, 3],
+        }]))
+        .unwrap();
+
+        assert_eq!(
+            actual,
+            vec![DecodedMessage {
+                type_url: "/unknown.v1.MsgUnknown".to_string(),
+                data: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn keeps_undecodable_messages_without_data() {
+        let actual = decode_messages(&tx_bytes(vec![RawAny {
+            type_url: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+            value: vec![0xff, 0xff],
+        }]))
+        .unwrap();
+
+        assert_eq!(
+            actual,
+            vec![DecodedMessage {
+                type_url: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+                data: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn error_when_tx_is_invalid() {
+        assert!(decode_messages(&[0xff, 0xff]).is_err());
+    }
+
+    #[test]
+    fn decodes_msg_transfer() {
+        assert_eq!(
+            decode_ibc(
+                "/ibc.applications.transfer.v1.MsgTransfer",
+                MsgTransfer {
+                    source_port: "transfer".to_string(),
+                    source_channel: "channel-0".to_string(),
+                    token: Some(ProtoCoin {
+                        denom: "muno".to_string(),
+                        amount: "10".to_string(),
+                    }),
+                    sender: ADDRESS.to_string(),
+                    receiver: "0xbeef".to_string(),
+                    timeout_height: Some(proof_height()),
+                    timeout_timestamp: 1000,
+                    memo: "memo".to_string(),
+                },
+            ),
+            json!({
+                "source_port": "transfer",
+                "source_channel": "channel-0",
+                "token": { "denom": "muno", "amount": "10" },
+                "sender": ADDRESS,
+                "receiver": "0xbeef",
+                "timeout_height": { "revision_number": 1, "revision_height": 100 },
+                "timeout_timestamp": 1000,
+                "memo": "memo",
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_msg_create_client() {
+        assert_eq!(
+            decode_ibc(
+                "/ibc.core.client.v1.MsgCreateClient",
+                MsgCreateClient {
+                    client_state: Some(any("/client.State")),
+                    consensus_state: Some(any("/client.ConsensusState")),
+                    signer: ADDRESS.to_string(),
+                },
+            ),
+            json!({
+                "client_state": { "type_url": "/client.State", "value": "0x0102" },
+                "consensus_state": { "type_url": "/client.ConsensusState", "value": "0x0102" },
+                "signer": ADDRESS,
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_msg_update_client() {
+        assert_eq!(
+            decode_ibc(
+                "/ibc.core.client.v1.MsgUpdateClient",
+                MsgUpdateClient {
+                    client_id: "07-tendermint-0".to_string(),
+                    client_message: Some(any("/client.Header")),
+                    signer: ADDRESS.to_string(),
+                },
+            ),
+            json!({
+                "client_id": "07-tendermint-0",
+                "client_message": { "type_url": "/client.Header", "value": "0x0102" },
+                "signer": ADDRESS,
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_msg_connection_open_init() {
+        assert_eq!(
+            decode_ibc(
+                "/ibc.core.connection.v1.MsgConnectionOpenInit",
+                MsgConnectionOpenInit {
+                    client_id: "07-tendermint-0".to_string(),
+                    counterparty: Some(connection_counterparty()),
+                    version: Some(version()),
+                    delay_period: 5,
+                    signer: ADDRESS.to_string(),
+                },
+            ),
+            json!({
+                "client_id": "07-tendermint-0",
+                "counterparty": {
+                    "client_id": "08-wasm-1",
+                    "connection_id": "",
+                    "prefix": "0x696263",
+                },
+                "version": { "identifier": "1", "features": ["ORDER_UNORDERED"] },
+                "delay_period": 5,
+                "signer": ADDRESS,
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_msg_connection_open_try() {
+        #[allow(deprecated)]
+        let msg = MsgConnectionOpenTry {
+            client_id: "07-tendermint-0".to_string(),
+            previous_connection_id: String::new(),
+            client_state: Some(any("/client.State")),
+            counterparty: Some(connection_counterparty()),
+            delay_period: 5,
+            counterparty_versions: vec![version()],
+            proof_height: Some(proof_height()),
+            proof_init: vec![3],
+            proof_client: vec![4],
+            proof_consensus: vec![5],
+            consensus_height: Some(proof_height()),
+            signer: ADDRESS.to_string(),
+            host_consensus_state_proof: vec![],
+        };
+
+        assert_eq!(
+            decode_ibc("/ibc.core.connection.v1.MsgConnectionOpenTry", msg),
+            json!({
+                "client_id": "07-tendermint-0",
+                "client_state": { "type_url": "/client.State", "value": "0x0102" },
+                "counterparty": {
+                    "client_id": "08-wasm-1",
+                    "connection_id": "",
+                    "prefix": "0x696263",
+                },
+                "delay_period": 5,
+                "counterparty_versions": [{ "identifier": "1", "features": ["ORDER_UNORDERED"] }],
+                "proof_height": { "revision_number": 1, "revision_height": 100 },
+                "proof_init": "0x03",
+                "proof_client": "0x04",
+                "proof_consensus": "0x05",
+                "consensus_height": { "revision_number": 1, "revision_height": 100 },
+                "signer": ADDRESS,
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_msg_connection_open_ack() {
+        #[allow(deprecated)]
+        let msg = MsgConnectionOpenAck {
+            connection_id: "connection-0".to_string(),
+            counterparty_connection_id: "connection-1".to_string(),
+            version: Some(version()),
+            client_state: Some(any("/client.State")),
+            proof_height: Some(proof_height()),
+            proof_try: vec![3],
+            proof_client: vec![4],
+            proof_consensus: vec![5],
+            consensus_height: Some(proof_height()),
+            signer: ADDRESS.to_string(),
+            host_consensus_state_proof: vec![],
+        };
+
+        assert_eq!(
+            decode_ibc("/ibc.core.connection.v1.MsgConnectionOpenAck", msg),
+            json!({
+                "connection_id": "connection-0",
+                "counterparty_connection_id": "connection-1",
+                "version": { "identifier": "1", "features": ["ORDER_UNORDERED"] },
+                "client_state": { "type_url": "/client.State", "value": "0x0102" },
+                "proof_height": { "revision_number": 1, "revision_height": 100 },
+                "proof_try": "0x03",
+                "proof_client": "0x04",
+                "proof_consensus": "0x05",
+                "consensus_height": { "revision_number": 1, "revision_height": 100 },
+                "signer": ADDRESS,
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_msg_connection_open_confirm() {
+        assert_eq!(
+            decode_ibc(
+                "/ibc.core.connection.v1.MsgConnectionOpenConfirm",
+                MsgConnectionOpenConfirm {
+                    connection_id: "connection-0".to_string(),
+                    proof_ack: vec![3],
+                    proof_height: Some(proof_height()),
+                    signer: ADDRESS.to_string(),
+                },
+            ),
+            json!({
+                "connection_id": "connection-0",
+                "proof_ack": "0x03",
+                "proof_height": { "revision_number": 1, "revision_height": 100 },
+                "signer": ADDRESS,
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_msg_channel_open_init() {
+        assert_eq!(
+            decode_ibc(
+                "/ibc.core.channel.v1.MsgChannelOpenInit",
+                MsgChannelOpenInit {
+                    port_id: "transfer".to_string(),
+                    channel: Some(channel()),
+                    signer: ADDRESS.to_string(),
+                },
+            ),
+            json!({
+                "port_id": "transfer",
+                "channel": channel_json(),
+                "signer": ADDRESS,
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_msg_channel_open_try() {
+        #[allow(deprecated)]
+        let msg = MsgChannelOpenTry {
+            port_id: "transfer".to_string(),
+            previous_channel_id: String::new(),
+            channel: Some(channel()),
+            counterparty_version: "ics20-1".to_string(),
+            proof_init: vec![3],
+            proof_height: Some(proof_height()),
+            signer: ADDRESS.to_string(),
+        };
+
+        assert_eq!(
+            decode_ibc("/ibc.core.channel.v1.MsgChannelOpenTry", msg),
+            json!({
+                "port_id": "transfer",
+                "channel": channel_json(),
+                "counterparty_version": "ics20-1",
+                "proof_init": "0x03",
+                "proof_height": { "revision_number": 1, "revision_height": 100 },
+                "signer": ADDRESS,
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_msg_channel_open_ack() {
+        assert_eq!(
+            decode_ibc(
+                "/ibc.core.channel.v1.MsgChannelOpenAck",
+                MsgChannelOpenAck {
+                    port_id: "transfer".to_string(),
+                    channel_id: "channel-0".to_string(),
+                    counterparty_channel_id: "channel-1".to_string(),
+                    counterparty_version: "ics20-1".to_string(),
+                    proof_try: vec![3],
+                    proof_height: Some(proof_height()),
+                    signer: ADDRESS.to_string(),
+                },
+            ),
+            json!({
+                "port_id": "transfer",
+                "channel_id": "channel-0",
+                "counterparty_channel_id": "channel-1",
+                "counterparty_version": "ics20-1",
+                "proof_try": "0x03",
+                "proof_height": { "revision_number": 1, "revision_height": 100 },
+                "signer": ADDRESS,
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_msg_channel_open_confirm() {
+        assert_eq!(
+            decode_ibc(
+                "/ibc.core.channel.v1.MsgChannelOpenConfirm",
+                MsgChannelOpenConfirm {
+                    port_id: "transfer".to_string(),
+                    channel_id: "channel-0".to_string(),
+                    proof_ack: vec![3],
+                    proof_height: Some(proof_height()),
+                    signer: ADDRESS.to_string(),
+                },
+            ),
+            json!({
+                "port_id": "transfer",
+                "channel_id": "channel-0",
+                "proof_ack": "0x03",
+                "proof_height": { "revision_number": 1, "revision_height": 100 },
+                "signer": ADDRESS,
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_msg_recv_packet() {
+        assert_eq!(
+            decode_ibc(
+                "/ibc.core.channel.v1.MsgRecvPacket",
+                MsgRecvPacket {
+                    packet: Some(packet()),
+                    proof_commitment: vec![3],
+                    proof_height: Some(proof_height()),
+                    signer: ADDRESS.to_string(),
+                },
+            ),
+            json!({
+                "packet": packet_json(),
+                "proof_commitment": "0x03",
+                "proof_height": { "revision_number": 1, "revision_height": 100 },
+                "signer": ADDRESS,
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_msg_acknowledgement() {
+        assert_eq!(
+            decode_ibc(
+                "/ibc.core.channel.v1.MsgAcknowledgement",
+                MsgAcknowledgement {
+                    packet: Some(packet()),
+                    acknowledgement: vec![1],
+                    proof_acked: vec![3],
+                    proof_height: Some(proof_height()),
+                    signer: ADDRESS.to_string(),
+                },
+            ),
+            json!({
+                "packet": packet_json(),
+                "acknowledgement": "0x01",
+                "proof_acked": "0x03",
+                "proof_height": { "revision_number": 1, "revision_height": 100 },
+                "signer": ADDRESS,
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_msg_timeout() {
+        assert_eq!(
+            decode_ibc(
+                "/ibc.core.channel.v1.MsgTimeout",
+                MsgTimeout {
+                    packet: Some(packet()),
+                    proof_unreceived: vec![3],
+                    proof_height: Some(proof_height()),
+                    next_sequence_recv: 2,
+                    signer: ADDRESS.to_string(),
+                },
+            ),
+            json!({
+                "packet": packet_json(),
+                "proof_unreceived": "0x03",
+                "proof_height": { "revision_number": 1, "revision_height": 100 },
+                "next_sequence_recv": 2,
+                "signer": ADDRESS,
+            })
+        );
+    }
+
+    // decodes a single raw protobuf message through the full tx decoding
+    fn decode_ibc(type_url: &str, message: impl prost::Message) -> Value {
+        let mut decoded = decode_messages(&tx_bytes(vec![RawAny {
+            type_url: type_url.to_string(),
+            value: message.encode_to_vec(),
+        }]))
+        .unwrap();
+
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].type_url, type_url);
+
+        decoded.remove(0).data.expect("message is decoded")
+    }
+
+    fn proof_height() -> Height {
+        Height {
+            revision_number: 1,
+            revision_height: 100,
+        }
+    }
+
+    fn any(type_url: &str) -> ProtoAny {
+        ProtoAny {
+            type_url: type_url.to_string(),
+            value: vec![1, 2],
+        }
+    }
+
+    fn connection_counterparty() -> ConnectionCounterparty {
+        ConnectionCounterparty {
+            client_id: "08-wasm-1".to_string(),
+            connection_id: String::new(),
+            prefix: Some(MerklePrefix {
+                key_prefix: b"ibc".to_vec(),
+            }),
+        }
+    }
+
+    fn version() -> Version {
+        Version {
+            identifier: "1".to_string(),
+            features: vec!["ORDER_UNORDERED".to_string()],
+        }
+    }
+
+    fn channel() -> Channel {
+        Channel {
+            state: State::Init.into(),
+            ordering: Order::Unordered.into(),
+            counterparty: Some(ChannelCounterparty {
+                port_id: "transfer".to_string(),
+                channel_id: String::new(),
+            }),
+            connection_hops: vec!["connection-0".to_string()],
+            version: "ics20-1".to_string(),
+            upgrade_sequence: 0,
+        }
+    }
+
+    fn channel_json() -> Value {
+        json!({
+            "state": "STATE_INIT",
+            "ordering": "ORDER_UNORDERED",
+            "counterparty": { "port_id": "transfer", "channel_id": "" },
+            "connection_hops": ["connection-0"],
+            "version": "ics20-1",
+        })
+    }
+
+    fn packet() -> Packet {
+        Packet {
+            sequence: 1,
+            source_port: "transfer".to_string(),
+            source_channel: "channel-0".to_string(),
+            destination_port: "transfer".to_string(),
+            destination_channel: "channel-1".to_string(),
+            data: vec![0xbe, 0xef],
+            timeout_height: None,
+            timeout_timestamp: 1000,
+        }
+    }
+
+    fn packet_json() -> Value {
+        json!({
+            "sequence": 1,
+            "source_port": "transfer",
+            "source_channel": "channel-0",
+            "destination_port": "transfer",
+            "destination_channel": "channel-1",
+            "data": "0xbeef",
+            "timeout_height": null,
+            "timeout_timestamp": 1000,
+        })
+    }
+
+    fn tx_bytes(messages: Vec<RawAny>) -> Vec<u8> {
+        TxRaw {
+            body_bytes: TxBody {
+                messages,
+                memo: String::new(),
+                timeout_height: 0,
+                extension_options: vec![],
+                non_critical_extension_options: vec![],
+            }
+            .encode_as::<Proto>(),
+            auth_info_bytes: vec![],
+            signatures: vec![],
+        }
+        .encode_as::<Proto>()
+    }
+}
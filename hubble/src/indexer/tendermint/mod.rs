@@ -7,6 +7,8 @@ mod block_handle;
 pub mod config;
 mod context;
 mod fetcher_client;
+mod ibc_messages;
+mod messages;
 mod postgres;
 mod provider;
 
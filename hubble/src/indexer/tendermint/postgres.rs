@@ -7,6 +7,7 @@ use crate::{
         api::{BlockHash, BlockHeight},
         tendermint::block_handle::{ActiveContracts, EventInFlows},
     },
+    packet_lifecycle::{rewind_cursors, Source},
     postgres::{schedule_replication_reset, ChainId},
 };
 
@@ -33,6 +34,21 @@ pub struct PgTransaction {
     pub data: serde_json::Value,
     pub hash: TransactionHash,
     pub index: i32,
+    pub messages: Vec<PgMessage>,
+}
+
+/// DTO corresponding to the v2_cosmos.messages table.
+#[derive(Clone)]
+pub struct PgMessage {
+    pub chain_id: ChainId,
+    pub block_hash: BlockHash,
+    pub block_height: BlockHeight,
+    pub time: OffsetDateTime,
+    pub transaction_hash: TransactionHash,
+    pub transaction_index: i32,
+    pub index: i32,
+    pub type_url: String,
+    pub data: Option<serde_json::Value>,
 }
 
 /// DTO corresponding to the v2_cosmos.events table.
@@ -113,6 +129,60 @@ pub async fn insert_batch_transactions(
     Ok(())
 }
 
+pub async fn insert_batch_messages(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    messages: impl IntoIterator<Item = PgMessage>,
+) -> sqlx::Result<()> {
+    #![allow(clippy::type_complexity)]
+    let (
+        chain_ids,
+        block_hashes,
+        heights,
+        transaction_hashes,
+        transaction_indexes,
+        indexes,
+        type_urls,
+        data,
+        times,
+    ): (
+        Vec<i32>,
+        Vec<String>,
+        Vec<i64>,
+        Vec<String>,
+        Vec<i32>,
+        Vec<i32>,
+        Vec<String>,
+        Vec<Option<serde_json::Value>>,
+        Vec<OffsetDateTime>,
+    ) = messages
+        .into_iter()
+        .map(|m| {
+            let block_height: i64 = m.block_height.try_into().unwrap();
+
+            (
+                m.chain_id.db,
+                m.block_hash,
+                block_height,
+                m.transaction_hash,
+                m.transaction_index,
+                m.index,
+                m.type_url,
+                m.data,
+                m.time,
+            )
+        })
+        .multiunzip();
+
+    sqlx::query!("
+        INSERT INTO v2_cosmos.messages (internal_chain_id, block_hash, height, transaction_hash, transaction_index, index, type_url, data, time)
+        SELECT unnest($1::int[]), unnest($2::text[]), unnest($3::bigint[]), unnest($4::text[]), unnest($5::int[]), unnest($6::int[]), unnest($7::text[]), unnest($8::jsonb[]), unnest($9::timestamptz[])
+        ",
+        &chain_ids, &block_hashes, &heights, &transaction_hashes, &transaction_indexes, &indexes, &type_urls, &data as _, &times)
+    .execute(tx.as_mut()).await?;
+
+    Ok(())
+}
+
 pub async fn insert_batch_events(
     tx: &mut sqlx::Transaction<'_, Postgres>,
     events: impl IntoIterator<Item = EventInFlows>,
@@ -168,6 +238,8 @@ pub async fn insert_batch_events(
         &chain_ids, &block_hashes, &heights, &transaction_hashes as _, &indexes, &transaction_indexes as _, &data, &times, &flows)
     .execute(tx.as_mut()).await?;
 
+    rewind_cursors(tx, Source::Cosmos, chain_ids.into_iter().zip(heights)).await?;
+
     Ok(())
 }
 
@@ -188,6 +260,16 @@ pub async fn delete_tm_block_transactions_events(
     .execute(tx.as_mut())
     .await?;
 
+    sqlx::query!(
+        "
+        DELETE FROM v2_cosmos.messages WHERE internal_chain_id = $1 AND height = $2
+        ",
+        chain_id,
+        height,
+    )
+    .execute(tx.as_mut())
+    .await?;
+
     sqlx::query!(
         "
         DELETE FROM v2_cosmos.transactions WHERE internal_chain_id = $1 AND height = $2
@@ -213,6 +295,25 @@ pub async fn delete_tm_block_transactions_events(
     Ok(())
 }
 
+pub async fn get_tm_block_hash(
+    tx: &mut Transaction<'_, Postgres>,
+    chain_id: i32,
+    height: BlockHeight,
+) -> sqlx::Result<Option<BlockHash>> {
+    let height: i64 = height.try_into().unwrap();
+    let record = sqlx::query!(
+        "
+        SELECT hash FROM v2_cosmos.blocks WHERE internal_chain_id = $1 AND height = $2
+        ",
+        chain_id,
+        height,
+    )
+    .fetch_optional(tx.as_mut())
+    .await?;
+
+    Ok(record.map(|r| r.hash))
+}
+
 pub async fn active_contracts(
     tx: &mut Transaction<'_, Postgres>,
     internal_chain_id: i32,
@@ -17,6 +17,7 @@ mod healthz;
 mod indexer;
 mod logging;
 mod metrics;
+mod packet_lifecycle;
 mod postgres;
 mod race_client;
 mod token_fetcher;
@@ -40,6 +41,18 @@ async fn main() -> color_eyre::eyre::Result<()> {
         .connect(&args.database_url.unwrap())
         .await?;
 
+    if args.migrate {
+        // creates the tables introduced by hubble itself, the rest of the schema is managed
+        // externally
+        sqlx::migrate!().run(&db).await?;
+    }
+
+    if let Some(cli::Command::Audit(audit)) = args.command {
+        let report = audit.run(db, args.indexers).await?;
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
     let mut set = JoinSet::new();
 
     if let Some(addr) = args.metrics_addr {
@@ -113,6 +126,26 @@ async fn main() -> color_eyre::eyre::Result<()> {
 
     set.spawn(abi_fetcher);
 
+    if args.packet_lifecycle {
+        packet_lifecycle::enable();
+
+        let packet_lifecycle_db = db.clone();
+        let packet_lifecycle = async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(10));
+            interval.tick().await;
+            loop {
+                info!("materializing packets");
+                match packet_lifecycle::materialize_packets(&packet_lifecycle_db).await {
+                    Ok(()) => info!("materialized packets"),
+                    Err(err) => error!("failed to materialize packets: {:?}", err),
+                };
+                interval.tick().await;
+            }
+        };
+
+        set.spawn(packet_lifecycle);
+    }
+
     while let Some(res) = set.join_next().await {
         match res {
             Ok(Err(err)) => {
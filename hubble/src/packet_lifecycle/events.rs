@@ -0,0 +1,391 @@
+use alloy::primitives::{keccak256, B256};
+use color_eyre::eyre::{eyre, Report};
+use ibc_union_spec::{ChannelId, Packet, Timestamp};
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use serde_json::Value;
+use unionlabs::primitives::Bytes;
+
+use crate::packet_lifecycle::{Stage, StageEvent};
+
+lazy_static! {
+    static ref EVM_PACKET_SEND: B256 =
+        keccak256("PacketSend(uint32,bytes32,(uint32,uint32,bytes,uint64,uint64))");
+    static ref EVM_PACKET_RECV: B256 = keccak256("PacketRecv(uint32,bytes32,address,bytes)");
+    static ref EVM_INTENT_PACKET_RECV: B256 =
+        keccak256("IntentPacketRecv(uint32,bytes32,address,bytes)");
+    static ref EVM_WRITE_ACK: B256 = keccak256("WriteAck(uint32,bytes32,bytes)");
+    static ref EVM_PACKET_ACK: B256 = keccak256("PacketAck(uint32,bytes32,bytes,address)");
+    static ref EVM_PACKET_TIMEOUT: B256 = keccak256("PacketTimeout(uint32,bytes32,address)");
+    static ref EVM_BATCHED_PREVIOUSLY_SENT: B256 =
+        keccak256("BatchedPreviouslySent(uint32,bytes32,bytes32)");
+    static ref EVM_BATCHED_PREVIOUSLY_ACKED: B256 =
+        keccak256("BatchedPreviouslyAcked(uint32,bytes32,bytes32)");
+}
+
+/// Cosmos event types (as stored in `v2_cosmos.events`) that describe a packet stage.
+pub const COSMOS_EVENT_TYPES: [&str; 8] = [
+    "wasm-packet_send",
+    "wasm-packet_recv",
+    "wasm-intent_packet_recv",
+    "wasm-write_ack",
+    "wasm-packet_ack",
+    "wasm-packet_timeout",
+    "wasm-batch_send",
+    "wasm-batch_acks",
+];
+
+#[derive(Deserialize)]
+struct CosmosEvent {
+    #[serde(rename = "type")]
+    ty: String,
+    attributes: Vec<CosmosEventAttribute>,
+}
+
+#[derive(Deserialize)]
+struct CosmosEventAttribute {
+    key: String,
+    value: String,
+}
+
+/// Extracts the packet stage of an ibc-union core contract event.
+pub fn from_cosmos_event(data: &Value) -> Result<Option<StageEvent>, Report> {
+    let event = CosmosEvent::deserialize(data)?;
+
+    let stage = match event.ty.as_str() {
+        "wasm-packet_send" => Stage::Send,
+        "wasm-packet_recv" | "wasm-intent_packet_recv" => Stage::Recv,
+        "wasm-write_ack" => Stage::WriteAck,
+        "wasm-packet_ack" => Stage::Ack,
+        "wasm-packet_timeout" => Stage::Timeout,
+        "wasm-batch_send" => Stage::BatchSend,
+        "wasm-batch_acks" => Stage::BatchAck,
+        _ => return Ok(None),
+    };
+
+    let attribute = |key: &str| {
+        event
+            .attributes
+            .iter()
+            .find(|attribute| attribute.key == key)
+            .map(|attribute| attribute.value.clone())
+    };
+
+    let packet_hash = attribute("packet_hash").ok_or(eyre!("missing packet_hash"))?;
+    let channel_id = attribute("channel_id")
+        .ok_or(eyre!("missing channel_id"))?
+        .parse::<u32>()?;
+    let batch_hash = match stage {
+        Stage::BatchSend | Stage::BatchAck => {
+            Some(attribute("batch_hash").ok_or(eyre!("missing batch_hash"))?)
+        }
+        _ => None,
+    };
+    let relayer = match stage {
+        Stage::Recv | Stage::Ack | Stage::Timeout => attribute("maker"),
+        _ => None,
+    };
+
+    Ok(Some(StageEvent {
+        stage,
+        packet_hash: packet_hash.to_lowercase(),
+        batch_hash: batch_hash.map(|hash| hash.to_lowercase()),
+        channel_id: channel_id.into(),
+        relayer,
+    }))
+}
+
+#[derive(Deserialize)]
+struct EvmLogData {
+    transactions: Vec<EvmTransaction>,
+}
+
+#[derive(Deserialize)]
+struct EvmTransaction {
+    hash: String,
+    events: Vec<EvmEvent>,
+}
+
+#[derive(Deserialize)]
+struct EvmEvent {
+    data: EvmLog,
+}
+
+#[derive(Deserialize)]
+struct EvmLog {
+    topics: Vec<B256>,
+}
+
+/// Extracts the packet stages of the ibc-union handler logs of a block (as stored in
+/// `v2_evm.logs`), together with the hash of the transaction that emitted them.
+pub fn from_evm_logs(data: &Value) -> Result<Vec<(String, StageEvent)>, Report> {
+    let data = EvmLogData::deserialize(data)?;
+
+    Ok(data
+        .transactions
+        .into_iter()
+        .flat_map(|transaction| {
+            let hash = transaction.hash;
+            transaction.events.into_iter().filter_map(move |event| {
+                from_evm_topics(&event.data.topics).map(|stage| (hash.clone(), stage))
+            })
+        })
+        .collect())
+}
+
+// every packet event indexes the channel id and the packet (or batch) hash, which is all we need.
+fn from_evm_topics(topics: &[B256]) -> Option<StageEvent> {
+    let [signature, channel_id, hash, rest @ ..] = topics else {
+        return None;
+    };
+
+    let stage = match signature {
+        s if s == &*EVM_PACKET_SEND => Stage::Send,
+        s if s == &*EVM_PACKET_RECV || s == &*EVM_INTENT_PACKET_RECV => Stage::Recv,
+        s if s == &*EVM_WRITE_ACK => Stage::WriteAck,
+        s if s == &*EVM_PACKET_ACK => Stage::Ack,
+        s if s == &*EVM_PACKET_TIMEOUT => Stage::Timeout,
+        s if s == &*EVM_BATCHED_PREVIOUSLY_SENT => Stage::BatchSend,
+        s if s == &*EVM_BATCHED_PREVIOUSLY_ACKED => Stage::BatchAck,
+        _ => return None,
+    };
+
+    let channel_id = u32::from_be_bytes(channel_id[28..].try_into().expect("4 bytes"));
+
+    let (packet_hash, batch_hash, relayer) = match (stage, rest) {
+        (Stage::BatchSend | Stage::BatchAck, [packet_hash, ..]) => {
+            (packet_hash.to_string(), Some(hash.to_string()), None)
+        }
+        (Stage::BatchSend | Stage::BatchAck, []) => return None,
+        (Stage::Recv | Stage::Ack | Stage::Timeout, [maker, ..]) => (
+            hash.to_string(),
+            None,
+            Some(format!("0x{}", hex::encode(&maker[12..]))),
+        ),
+        _ => (hash.to_string(), None, None),
+    };
+
+    Some(StageEvent {
+        stage,
+        packet_hash,
+        batch_hash,
+        channel_id: channel_id.into(),
+        relayer,
+    })
+}
+
+#[derive(Deserialize)]
+struct AptosPacket {
+    source_channel_id: u32,
+    destination_channel_id: u32,
+    data: Bytes,
+    #[serde(with = "serde_utils::string")]
+    timeout_height: u64,
+    #[serde(with = "serde_utils::string")]
+    timeout_timestamp: u64,
+}
+
+impl AptosPacket {
+    // aptos events contain the packet instead of its hash.
+    fn hash(self) -> Result<String, Report> {
+        Ok(Packet {
+            source_channel_id: ChannelId::from_raw(self.source_channel_id)
+                .ok_or(eyre!("invalid source channel id"))?,
+            destination_channel_id: ChannelId::from_raw(self.destination_channel_id)
+                .ok_or(eyre!("invalid destination channel id"))?,
+            data: self.data,
+            timeout_height: self.timeout_height,
+            timeout_timestamp: Timestamp::from_nanos(self.timeout_timestamp),
+        }
+        .hash()
+        .to_string())
+    }
+}
+
+#[derive(Deserialize)]
+struct AptosPacketEvent {
+    packet: AptosPacket,
+    maker: Option<String>,
+}
+
+/// Extracts the packet stage of an ibc module event (as stored in `v2_aptos.events`).
+pub fn from_aptos_event(typ: &str, data: &Value) -> Result<Option<StageEvent>, Report> {
+    let Some((_, name)) = typ.rsplit_once("::ibc::") else {
+        return Ok(None);
+    };
+
+    let stage = match name {
+        "PacketSend" => Stage::Send,
+        "PacketRecv" | "RecvIntentPacket" => Stage::Recv,
+        "WriteAck" => Stage::WriteAck,
+        "PacketAck" => Stage::Ack,
+        "TimeoutPacket" => Stage::Timeout,
+        _ => return Ok(None),
+    };
+
+    let (packet, relayer) = match stage {
+        // the send event contains the packet fields instead of the packet
+        Stage::Send => (AptosPacket::deserialize(data)?, None),
+        _ => {
+            let event = AptosPacketEvent::deserialize(data)?;
+            (event.packet, event.maker)
+        }
+    };
+
+    let channel_id = match stage {
+        Stage::Send | Stage::Ack | Stage::Timeout => packet.source_channel_id,
+        _ => packet.destination_channel_id,
+    };
+
+    Ok(Some(StageEvent {
+        stage,
+        packet_hash: packet.hash()?,
+        batch_hash: None,
+        channel_id: channel_id.into(),
+        relayer,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{keccak256, B256};
+    use serde_json::json;
+
+    use crate::packet_lifecycle::{
+        events::{from_aptos_event, from_cosmos_event, from_evm_logs},
+        Stage, StageEvent,
+    };
+
+    const PACKET_HASH: &str = "0x8f4e1a3c2b7d6e5f4a3b2c1d0e9f8a7b6c5d4e3f2a1b0c9d8e7f6a5b4c3d2e1f";
+    const BATCH_HASH: &str = "0x1111111111111111111111111111111111111111111111111111111111111111";
+
+    #[test]
+    fn extracts_cosmos_packet_recv() {
+        let actual = from_cosmos_event(&json!({
+            "type": "wasm-packet_recv",
+            "attributes": [
+                { "key": "_contract_address", "value": "union1core", "index": true },
+                { "key": "channel_id", "value": "5", "index": true },
+                { "key": "packet_hash", "value": PACKET_HASH, "index": true },
+                { "key": "maker", "value": "union1relayer", "index": true },
+                { "key": "maker_msg", "value": "0x", "index": true },
+            ],
+        }))
+        .unwrap();
+
+        assert_eq!(
+            actual,
+            Some(StageEvent {
+                stage: Stage::Recv,
+                packet_hash: PACKET_HASH.to_string(),
+                batch_hash: None,
+                channel_id: 5,
+                relayer: Some("union1relayer".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn extracts_cosmos_batch_send() {
+        let actual = from_cosmos_event(&json!({
+            "type": "wasm-batch_send",
+            "attributes": [
+                { "key": "channel_id", "value": "1", "index": true },
+                { "key": "packet_hash", "value": PACKET_HASH, "index": true },
+                { "key": "batch_hash", "value": BATCH_HASH, "index": true },
+            ],
+        }))
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(actual.stage, Stage::BatchSend);
+        assert_eq!(actual.batch_hash, Some(BATCH_HASH.to_string()));
+    }
+
+    #[test]
+    fn ignores_other_cosmos_events() {
+        let actual = from_cosmos_event(&json!({
+            "type": "wasm-channel_open_init",
+            "attributes": [],
+        }))
+        .unwrap();
+
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn extracts_evm_packet_ack() {
+        let channel_id = B256::left_padding_from(&7u32.to_be_bytes());
+        let maker = B256::left_padding_from(&[0xab; 20]);
+
+        let actual = from_evm_logs(&json!({
+            "header": {},
+            "transactions": [{
+                "hash": "0xabc",
+                "index": 0,
+                "events": [
+                    {
+                        "data": { "topics": [keccak256("Unrelated()")] },
+                        "log_index": 0,
+                        "transaction_log_index": 0,
+                    },
+                    {
+                        "data": {
+                            "topics": [
+                                keccak256("PacketAck(uint32,bytes32,bytes,address)"),
+                                channel_id,
+                                PACKET_HASH,
+                                maker,
+                            ],
+                        },
+                        "log_index": 1,
+                        "transaction_log_index": 1,
+                    },
+                ],
+            }],
+        }))
+        .unwrap();
+
+        assert_eq!(
+            actual,
+            vec![(
+                "0xabc".to_string(),
+                StageEvent {
+                    stage: Stage::Ack,
+                    packet_hash: PACKET_HASH.to_string(),
+                    batch_hash: None,
+                    channel_id: 7,
+                    relayer: Some(format!("0x{}", "ab".repeat(20))),
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn extracts_aptos_packet_send_and_recv_with_same_hash() {
+        let packet = json!({
+            "source_channel_id": 1,
+            "destination_channel_id": 2,
+            "data": "0x1234",
+            "timeout_height": "0",
+            "timeout_timestamp": "1700000000000000000",
+        });
+
+        let send = from_aptos_event("0x1::ibc::PacketSend", &packet)
+            .unwrap()
+            .unwrap();
+        let recv = from_aptos_event(
+            "0x1::ibc::PacketRecv",
+            &json!({ "packet": packet, "maker": "0x2", "maker_msg": "0x" }),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(send.stage, Stage::Send);
+        assert_eq!(send.channel_id, 1);
+        assert_eq!(recv.stage, Stage::Recv);
+        assert_eq!(recv.channel_id, 2);
+        assert_eq!(recv.relayer, Some("0x2".to_string()));
+        assert_eq!(send.packet_hash, recv.packet_hash);
+    }
+}
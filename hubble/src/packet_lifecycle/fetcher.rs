@@ -0,0 +1,213 @@
+use itertools::Itertools;
+use sqlx::Postgres;
+use tracing::{debug, warn};
+
+use crate::packet_lifecycle::{
+    events::{from_aptos_event, from_cosmos_event, from_evm_logs, COSMOS_EVENT_TYPES},
+    postgres::{
+        get_aptos_events, get_chain_ids, get_cosmos_events, get_evm_logs, get_max_height,
+        insert_batch_stages, lock_cursor, rebuild_packets, update_cursor, PgStage,
+    },
+    Source,
+};
+
+/// Maximum number of heights of a single chain that are materialized in one transaction.
+const HEIGHT_WINDOW: i64 = 1000;
+
+pub async fn materialize_packets(db: &sqlx::PgPool) -> color_eyre::Result<()> {
+    let chain_ids = get_chain_ids(&mut db.begin().await?).await?;
+
+    for source in Source::ALL {
+        for &internal_chain_id in &chain_ids {
+            while let Some(height) = materialize_window(db, source, internal_chain_id).await? {
+                debug!("{source}/{internal_chain_id}: materialized up to {height}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Materializes the next window of heights of the chain. Returns the new cursor, or `None` when
+/// the chain has no (new) events.
+async fn materialize_window(
+    db: &sqlx::PgPool,
+    source: Source,
+    internal_chain_id: i32,
+) -> color_eyre::Result<Option<i64>> {
+    let mut tx = db.begin().await?;
+
+    let cursor = lock_cursor(&mut tx, source, internal_chain_id).await?;
+
+    let max_height = get_max_height(&mut tx, source, internal_chain_id).await?;
+
+    let Some(to_height) = next_window(cursor, max_height) else {
+        return Ok(None);
+    };
+
+    let stages = get_stages(&mut tx, source, internal_chain_id, cursor, to_height).await?;
+
+    if !stages.is_empty() {
+        debug!(
+            "{source}/{internal_chain_id}: {} stages in ({cursor}, {to_height}]",
+            stages.len()
+        );
+
+        let packet_hashes = insert_batch_stages(&mut tx, stages).await?;
+        rebuild_packets(&mut tx, &packet_hashes).await?;
+    }
+
+    update_cursor(&mut tx, source, internal_chain_id, to_height).await?;
+
+    tx.commit().await?;
+
+    Ok(Some(to_height))
+}
+
+/// The (inclusive) end of the window after `cursor`, or `None` if there are no heights after it.
+///
+/// Heights that are indexed late (below the cursor) are not skipped, as every insert rewinds the
+/// cursor (see [`rewind_cursors`](crate::packet_lifecycle::rewind_cursors)).
+fn next_window(cursor: i64, max_height: Option<i64>) -> Option<i64> {
+    max_height
+        .filter(|&max_height| max_height > cursor)
+        .map(|max_height| max_height.min(cursor + HEIGHT_WINDOW))
+}
+
+async fn get_stages(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    source: Source,
+    internal_chain_id: i32,
+    from_height_exclusive: i64,
+    to_height_inclusive: i64,
+) -> color_eyre::Result<Vec<PgStage>> {
+    let stages = match source {
+        Source::Cosmos => get_cosmos_events(
+            tx,
+            internal_chain_id,
+            from_height_exclusive,
+            to_height_inclusive,
+            &COSMOS_EVENT_TYPES,
+        )
+        .await?
+        .into_iter()
+        .filter_map(|event| match from_cosmos_event(&event.data) {
+            Ok(stage) => stage.map(|stage| PgStage {
+                internal_chain_id,
+                height: event.height,
+                transaction_hash: event.transaction_hash,
+                time: event.time,
+                event: stage,
+            }),
+            Err(err) => {
+                warn!(
+                    "{source}/{internal_chain_id}: cannot extract stage at {}: {err}",
+                    event.height
+                );
+                None
+            }
+        })
+        .collect_vec(),
+        Source::Evm => get_evm_logs(
+            tx,
+            internal_chain_id,
+            from_height_exclusive,
+            to_height_inclusive,
+        )
+        .await?
+        .into_iter()
+        .flat_map(|log| match from_evm_logs(&log.data) {
+            Ok(stages) => stages
+                .into_iter()
+                .map(|(transaction_hash, stage)| PgStage {
+                    internal_chain_id,
+                    height: log.height,
+                    transaction_hash: Some(transaction_hash),
+                    time: log.time,
+                    event: stage,
+                })
+                .collect_vec(),
+            Err(err) => {
+                warn!(
+                    "{source}/{internal_chain_id}: cannot extract stages at {}: {err}",
+                    log.height
+                );
+                vec![]
+            }
+        })
+        .collect_vec(),
+        Source::Aptos => get_aptos_events(
+            tx,
+            internal_chain_id,
+            from_height_exclusive,
+            to_height_inclusive,
+        )
+        .await?
+        .into_iter()
+        .filter_map(|event| match from_aptos_event(&event.typ, &event.data) {
+            Ok(stage) => stage.map(|stage| PgStage {
+                internal_chain_id,
+                height: event.height,
+                transaction_hash: Some(event.transaction_hash),
+                time: event.time,
+                event: stage,
+            }),
+            Err(err) => {
+                warn!(
+                    "{source}/{internal_chain_id}: cannot extract stage at {}: {err}",
+                    event.height
+                );
+                None
+            }
+        })
+        .collect_vec(),
+    };
+
+    // a packet can be part of multiple batches; only the latest occurrence of a stage is kept,
+    // as a batch insert cannot update the same row twice.
+    Ok(stages
+        .into_iter()
+        .sorted_by_key(|stage| stage.height)
+        .rev()
+        .unique_by(|stage| (stage.event.packet_hash.clone(), stage.event.stage))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::packet_lifecycle::{
+        fetcher::{next_window, HEIGHT_WINDOW},
+        postgres::rewound_cursor,
+    };
+
+    #[test]
+    fn windows_end_at_max_height() {
+        assert_eq!(next_window(0, None), None);
+        assert_eq!(next_window(10, Some(10)), None);
+        assert_eq!(next_window(10, Some(5)), None);
+        assert_eq!(next_window(10, Some(20)), Some(20));
+        assert_eq!(next_window(10, Some(5000)), Some(10 + HEIGHT_WINDOW));
+    }
+
+    #[test]
+    fn late_inserted_heights_are_materialized() {
+        // heights up to 110 are materialized, 105 was not indexed yet
+        let cursor = next_window(0, Some(110)).unwrap();
+        assert_eq!(cursor, 110);
+
+        // 105 is indexed late, which moves the cursor back to before it
+        let cursor = rewound_cursor(cursor, 105);
+        assert_eq!(cursor, 104);
+
+        // so the next window includes it again
+        let to_height = next_window(cursor, Some(110)).unwrap();
+        assert!((cursor + 1..=to_height).contains(&105));
+    }
+
+    #[test]
+    fn inserts_above_the_cursor_do_not_rewind() {
+        assert_eq!(rewound_cursor(110, 111), 110);
+        assert_eq!(rewound_cursor(110, 200), 110);
+        assert_eq!(rewound_cursor(110, 110), 109);
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use sqlx::Postgres;
+
+mod events;
+mod fetcher;
+mod postgres;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Makes the indexers maintain the materialized packets, set when hubble runs with
+/// `--packet-lifecycle`. Otherwise the `v2_packets` tables are left alone, as they might not exist.
+pub fn enable() {
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// A stage in the lifecycle of an ibc-union packet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// The packet was sent on the source chain.
+    Send,
+    /// The packet was received on the destination chain (including intent receives).
+    Recv,
+    /// The acknowledgement was written on the destination chain.
+    WriteAck,
+    /// The acknowledgement was delivered to the source chain.
+    Ack,
+    /// The packet timed out on the source chain.
+    Timeout,
+    /// The packet was (re)committed as part of a batch on the source chain.
+    BatchSend,
+    /// The acknowledgement was (re)committed as part of a batch on the destination chain.
+    BatchAck,
+}
+
+impl Stage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Send => "send",
+            Stage::Recv => "recv",
+            Stage::WriteAck => "write_ack",
+            Stage::Ack => "ack",
+            Stage::Timeout => "timeout",
+            Stage::BatchSend => "batch_send",
+            Stage::BatchAck => "batch_ack",
+        }
+    }
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The table a stage event was extracted from.
+///
+/// Sui (`v2_sui.events`) is not a source yet, as the events of its ibc-union module are not decoded
+/// into stages. Packets sent to or from Sui chains only have the stages of the counterparty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    Cosmos,
+    Evm,
+    Aptos,
+}
+
+impl Source {
+    pub const ALL: [Source; 3] = [Source::Cosmos, Source::Evm, Source::Aptos];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Source::Cosmos => "cosmos",
+            Source::Evm => "evm",
+            Source::Aptos => "aptos",
+        }
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A packet stage, as extracted from a single chain event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StageEvent {
+    pub stage: Stage,
+    /// 0x-prefixed, lowercase hex.
+    pub packet_hash: String,
+    /// Only set for [`Stage::BatchSend`] and [`Stage::BatchAck`].
+    pub batch_hash: Option<String>,
+    /// The channel on the chain that emitted the event.
+    pub channel_id: i64,
+    /// The maker that delivered this stage, if the chain reports it.
+    pub relayer: Option<String>,
+}
+
+impl Display for StageEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{} {} (channel: {})",
+            self.stage, self.packet_hash, self.channel_id
+        ))
+    }
+}
+
+pub async fn materialize_packets(db: &sqlx::PgPool) -> color_eyre::Result<()> {
+    crate::packet_lifecycle::fetcher::materialize_packets(db).await
+}
+
+/// Undoes the materialization of all stages at or above `height` of a chain. Called on every
+/// reorg.
+pub async fn reset_chain(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    internal_chain_id: i32,
+    height: i64,
+) -> sqlx::Result<()> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    crate::packet_lifecycle::postgres::reset_chain(tx, internal_chain_id, height).await
+}
+
+/// Moves the cursor of each chain back to before the lowest inserted height, so heights that are
+/// indexed after the materialization passed them (by the fixer, or out of order) are still
+/// materialized. Called on every insert into a source table, in the same transaction.
+pub async fn rewind_cursors(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    source: Source,
+    inserted: impl IntoIterator<Item = (i32, i64)>,
+) -> sqlx::Result<()> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    // ordered, so concurrent inserts lock the cursors in the same order
+    let mut min_heights = BTreeMap::<i32, i64>::new();
+    for (internal_chain_id, height) in inserted {
+        min_heights
+            .entry(internal_chain_id)
+            .and_modify(|min_height| *min_height = height.min(*min_height))
+            .or_insert(height);
+    }
+
+    for (internal_chain_id, height) in min_heights {
+        crate::packet_lifecycle::postgres::rewind_cursor(tx, source, internal_chain_id, height)
+            .await?;
+    }
+
+    Ok(())
+}
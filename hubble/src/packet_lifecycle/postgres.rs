@@ -0,0 +1,427 @@
+use itertools::Itertools;
+use serde_json::Value;
+use sqlx::Postgres;
+use time::OffsetDateTime;
+
+use crate::packet_lifecycle::{Source, StageEvent};
+
+/// A stage event, located on the chain that emitted it.
+pub struct PgStage {
+    pub internal_chain_id: i32,
+    pub height: i64,
+    pub transaction_hash: Option<String>,
+    pub time: OffsetDateTime,
+    pub event: StageEvent,
+}
+
+pub struct PgCosmosEvent {
+    pub height: i64,
+    pub transaction_hash: Option<String>,
+    pub time: OffsetDateTime,
+    pub data: Value,
+}
+
+pub struct PgEvmLog {
+    pub height: i64,
+    pub time: OffsetDateTime,
+    pub data: Value,
+}
+
+pub struct PgAptosEvent {
+    pub height: i64,
+    pub transaction_hash: String,
+    pub time: OffsetDateTime,
+    pub typ: String,
+    pub data: Value,
+}
+
+pub async fn get_chain_ids(tx: &mut sqlx::Transaction<'_, Postgres>) -> sqlx::Result<Vec<i32>> {
+    Ok(sqlx::query!("SELECT id FROM config.chains ORDER BY id")
+        .fetch_all(tx.as_mut())
+        .await?
+        .into_iter()
+        .map(|record| record.id)
+        .collect())
+}
+
+/// Returns the last height that was materialized for the chain, locking the cursor until the
+/// transaction ends, so a concurrent reorg reset waits for (and then undoes) this batch.
+pub async fn lock_cursor(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    source: Source,
+    internal_chain_id: i32,
+) -> sqlx::Result<i64> {
+    sqlx::query!(
+        "
+        INSERT INTO v2_packets.cursors (source, internal_chain_id, height)
+        VALUES ($1, $2, 0)
+        ON CONFLICT (source, internal_chain_id) DO NOTHING
+        ",
+        source.as_str(),
+        internal_chain_id,
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    let record = sqlx::query!(
+        "
+        SELECT height
+        FROM v2_packets.cursors
+        WHERE source = $1 AND internal_chain_id = $2
+        FOR UPDATE
+        ",
+        source.as_str(),
+        internal_chain_id,
+    )
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    Ok(record.height)
+}
+
+pub async fn update_cursor(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    source: Source,
+    internal_chain_id: i32,
+    height: i64,
+) -> sqlx::Result<()> {
+    sqlx::query!(
+        "
+        UPDATE v2_packets.cursors
+        SET height = $3
+        WHERE source = $1 AND internal_chain_id = $2
+        ",
+        source.as_str(),
+        internal_chain_id,
+        height,
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    Ok(())
+}
+
+/// Moves the cursor back to before `height`. The cursor is locked even if it is not moved, so a
+/// materialization that is running concurrently either sees the inserted height, or is undone once
+/// it commits.
+pub async fn rewind_cursor(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    source: Source,
+    internal_chain_id: i32,
+    height: i64,
+) -> sqlx::Result<()> {
+    let cursor = lock_cursor(tx, source, internal_chain_id).await?;
+
+    let rewound = rewound_cursor(cursor, height);
+    if rewound != cursor {
+        update_cursor(tx, source, internal_chain_id, rewound).await?;
+    }
+
+    Ok(())
+}
+
+/// The cursor after `height` was inserted; heights above the cursor are materialized anyway.
+pub fn rewound_cursor(cursor: i64, height: i64) -> i64 {
+    cursor.min(height - 1)
+}
+
+pub async fn get_max_height(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    source: Source,
+    internal_chain_id: i32,
+) -> sqlx::Result<Option<i64>> {
+    Ok(match source {
+        Source::Cosmos => {
+            sqlx::query!(
+                "SELECT MAX(height) height FROM v2_cosmos.events WHERE internal_chain_id = $1",
+                internal_chain_id,
+            )
+            .fetch_one(tx.as_mut())
+            .await?
+            .height
+        }
+        Source::Evm => {
+            sqlx::query!(
+                "SELECT MAX(height) height FROM v2_evm.logs WHERE internal_chain_id = $1",
+                internal_chain_id,
+            )
+            .fetch_one(tx.as_mut())
+            .await?
+            .height
+        }
+        Source::Aptos => {
+            sqlx::query!(
+                "SELECT MAX(height) height FROM v2_aptos.events WHERE internal_chain_id = $1",
+                internal_chain_id,
+            )
+            .fetch_one(tx.as_mut())
+            .await?
+            .height
+        }
+    })
+}
+
+pub async fn get_cosmos_events(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    internal_chain_id: i32,
+    from_height_exclusive: i64,
+    to_height_inclusive: i64,
+    types: &[&str],
+) -> sqlx::Result<Vec<PgCosmosEvent>> {
+    let types = types.iter().map(|ty| ty.to_string()).collect_vec();
+
+    Ok(sqlx::query!(
+        r#"
+        SELECT height, transaction_hash, time, data
+        FROM v2_cosmos.events
+        WHERE internal_chain_id = $1
+        AND height > $2 AND height <= $3
+        AND data->>'type' = ANY($4)
+        "#,
+        internal_chain_id,
+        from_height_exclusive,
+        to_height_inclusive,
+        &types,
+    )
+    .fetch_all(tx.as_mut())
+    .await?
+    .into_iter()
+    .map(|record| PgCosmosEvent {
+        height: record.height,
+        transaction_hash: record.transaction_hash,
+        time: record.time,
+        data: record.data,
+    })
+    .collect())
+}
+
+pub async fn get_evm_logs(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    internal_chain_id: i32,
+    from_height_exclusive: i64,
+    to_height_inclusive: i64,
+) -> sqlx::Result<Vec<PgEvmLog>> {
+    Ok(sqlx::query!(
+        "
+        SELECT height, time, data
+        FROM v2_evm.logs
+        WHERE internal_chain_id = $1
+        AND height > $2 AND height <= $3
+        ",
+        internal_chain_id,
+        from_height_exclusive,
+        to_height_inclusive,
+    )
+    .fetch_all(tx.as_mut())
+    .await?
+    .into_iter()
+    .map(|record| PgEvmLog {
+        height: record.height,
+        time: record.time,
+        data: record.data,
+    })
+    .collect())
+}
+
+pub async fn get_aptos_events(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    internal_chain_id: i32,
+    from_height_exclusive: i64,
+    to_height_inclusive: i64,
+) -> sqlx::Result<Vec<PgAptosEvent>> {
+    Ok(sqlx::query!(
+        "
+        SELECT e.height, t.transaction_hash, b.timestamp, e.type, e.data
+        FROM v2_aptos.events e
+        JOIN v2_aptos.transactions t ON t.internal_chain_id = e.internal_chain_id AND t.version = e.version
+        JOIN v2_aptos.blocks b ON b.internal_chain_id = e.internal_chain_id AND b.height = e.height
+        WHERE e.internal_chain_id = $1
+        AND e.height > $2 AND e.height <= $3
+        AND e.type LIKE '%::ibc::%'
+        ",
+        internal_chain_id,
+        from_height_exclusive,
+        to_height_inclusive,
+    )
+    .fetch_all(tx.as_mut())
+    .await?
+    .into_iter()
+    .map(|record| PgAptosEvent {
+        height: record.height,
+        transaction_hash: record.transaction_hash,
+        time: record.timestamp,
+        typ: record.r#type,
+        data: record.data,
+    })
+    .collect())
+}
+
+pub async fn insert_batch_stages(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    stages: impl IntoIterator<Item = PgStage>,
+) -> sqlx::Result<Vec<String>> {
+    let (
+        packet_hashes,
+        stages,
+        batch_hashes,
+        channel_ids,
+        relayers,
+        chain_ids,
+        heights,
+        transaction_hashes,
+        times,
+    ): (
+        Vec<String>,
+        Vec<String>,
+        Vec<Option<String>>,
+        Vec<i64>,
+        Vec<Option<String>>,
+        Vec<i32>,
+        Vec<i64>,
+        Vec<Option<String>>,
+        Vec<OffsetDateTime>,
+    ) = stages
+        .into_iter()
+        .map(|s| {
+            (
+                s.event.packet_hash,
+                s.event.stage.as_str().to_string(),
+                s.event.batch_hash,
+                s.event.channel_id,
+                s.event.relayer,
+                s.internal_chain_id,
+                s.height,
+                s.transaction_hash,
+                s.time,
+            )
+        })
+        .multiunzip();
+
+    sqlx::query!("
+        INSERT INTO v2_packets.stages (packet_hash, stage, batch_hash, channel_id, relayer, internal_chain_id, height, transaction_hash, time)
+        SELECT unnest($1::text[]), unnest($2::text[]), unnest($3::text[]), unnest($4::bigint[]), unnest($5::text[]), unnest($6::int[]), unnest($7::bigint[]), unnest($8::text[]), unnest($9::timestamptz[])
+        ON CONFLICT (packet_hash, stage) DO
+        UPDATE SET
+            batch_hash = excluded.batch_hash,
+            channel_id = excluded.channel_id,
+            relayer = excluded.relayer,
+            internal_chain_id = excluded.internal_chain_id,
+            height = excluded.height,
+            transaction_hash = excluded.transaction_hash,
+            time = excluded.time
+        ",
+        &packet_hashes, &stages, &batch_hashes as _, &channel_ids, &relayers as _, &chain_ids, &heights, &transaction_hashes as _, &times)
+    .execute(tx.as_mut()).await?;
+
+    Ok(packet_hashes.into_iter().unique().collect())
+}
+
+/// Rebuilds the `packets` rows of the given packet hashes from their stages.
+pub async fn rebuild_packets(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    packet_hashes: &[String],
+) -> sqlx::Result<()> {
+    sqlx::query!(
+        "DELETE FROM v2_packets.packets WHERE packet_hash = ANY($1)",
+        packet_hashes,
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    sqlx::query!("
+        INSERT INTO v2_packets.packets (
+            packet_hash, send_batch_hash, ack_batch_hash,
+            source_channel_id, destination_channel_id,
+            send_internal_chain_id, send_height, send_transaction_hash, send_time,
+            recv_internal_chain_id, recv_height, recv_transaction_hash, recv_time, recv_relayer,
+            write_ack_internal_chain_id, write_ack_height, write_ack_transaction_hash, write_ack_time,
+            ack_internal_chain_id, ack_height, ack_transaction_hash, ack_time, ack_relayer,
+            timeout_internal_chain_id, timeout_height, timeout_transaction_hash, timeout_time, timeout_relayer,
+            recv_latency, write_ack_latency, ack_latency, timeout_latency
+        )
+        SELECT
+            p.*,
+            p.recv_time - p.send_time,
+            p.write_ack_time - p.recv_time,
+            p.ack_time - p.send_time,
+            p.timeout_time - p.send_time
+        FROM (
+            SELECT
+                packet_hash,
+                MAX(batch_hash) FILTER (WHERE stage = 'batch_send') send_batch_hash,
+                MAX(batch_hash) FILTER (WHERE stage = 'batch_ack') ack_batch_hash,
+                MAX(channel_id) FILTER (WHERE stage = 'send') source_channel_id,
+                MAX(channel_id) FILTER (WHERE stage = 'recv') destination_channel_id,
+                MAX(internal_chain_id) FILTER (WHERE stage = 'send') send_internal_chain_id,
+                MAX(height) FILTER (WHERE stage = 'send') send_height,
+                MAX(transaction_hash) FILTER (WHERE stage = 'send') send_transaction_hash,
+                MAX(time) FILTER (WHERE stage = 'send') send_time,
+                MAX(internal_chain_id) FILTER (WHERE stage = 'recv') recv_internal_chain_id,
+                MAX(height) FILTER (WHERE stage = 'recv') recv_height,
+                MAX(transaction_hash) FILTER (WHERE stage = 'recv') recv_transaction_hash,
+                MAX(time) FILTER (WHERE stage = 'recv') recv_time,
+                MAX(relayer) FILTER (WHERE stage = 'recv') recv_relayer,
+                MAX(internal_chain_id) FILTER (WHERE stage = 'write_ack') write_ack_internal_chain_id,
+                MAX(height) FILTER (WHERE stage = 'write_ack') write_ack_height,
+                MAX(transaction_hash) FILTER (WHERE stage = 'write_ack') write_ack_transaction_hash,
+                MAX(time) FILTER (WHERE stage = 'write_ack') write_ack_time,
+                MAX(internal_chain_id) FILTER (WHERE stage = 'ack') ack_internal_chain_id,
+                MAX(height) FILTER (WHERE stage = 'ack') ack_height,
+                MAX(transaction_hash) FILTER (WHERE stage = 'ack') ack_transaction_hash,
+                MAX(time) FILTER (WHERE stage = 'ack') ack_time,
+                MAX(relayer) FILTER (WHERE stage = 'ack') ack_relayer,
+                MAX(internal_chain_id) FILTER (WHERE stage = 'timeout') timeout_internal_chain_id,
+                MAX(height) FILTER (WHERE stage = 'timeout') timeout_height,
+                MAX(transaction_hash) FILTER (WHERE stage = 'timeout') timeout_transaction_hash,
+                MAX(time) FILTER (WHERE stage = 'timeout') timeout_time,
+                MAX(relayer) FILTER (WHERE stage = 'timeout') timeout_relayer
+            FROM v2_packets.stages
+            WHERE packet_hash = ANY($1)
+            GROUP BY packet_hash
+        ) p
+        ",
+        packet_hashes,
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    Ok(())
+}
+
+/// Undoes the materialization of all stages at or above `height` of a chain, which are
+/// rematerialized once the chain is reindexed.
+pub async fn reset_chain(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    internal_chain_id: i32,
+    height: i64,
+) -> sqlx::Result<()> {
+    sqlx::query!(
+        "
+        UPDATE v2_packets.cursors
+        SET height = LEAST(height, $2 - 1)
+        WHERE internal_chain_id = $1
+        ",
+        internal_chain_id,
+        height,
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    let packet_hashes = sqlx::query!(
+        "
+        DELETE FROM v2_packets.stages
+        WHERE internal_chain_id = $1 AND height >= $2
+        RETURNING packet_hash
+        ",
+        internal_chain_id,
+        height,
+    )
+    .fetch_all(tx.as_mut())
+    .await?
+    .into_iter()
+    .map(|record| record.packet_hash)
+    .unique()
+    .collect_vec();
+
+    rebuild_packets(tx, &packet_hashes).await
+}
@@ -76,5 +76,7 @@ pub async fn schedule_replication_reset(
     .execute(tx.as_mut())
     .await?;
 
+    crate::packet_lifecycle::reset_chain(tx, chain_id, height).await?;
+
     Ok(())
 }
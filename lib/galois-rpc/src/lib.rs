@@ -9,6 +9,8 @@ use unionlabs::ErrorReporter;
 use crate::{poll_request::PollRequest, poll_response::PollResponse};
 
 pub mod canonical_vote;
+#[cfg(feature = "mock")]
+pub mod mock;
 pub mod poll_request;
 pub mod poll_response;
 pub mod prove_request;
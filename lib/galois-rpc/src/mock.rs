@@ -0,0 +1,254 @@
+//! An in-process galois server that answers prove requests with deterministic fake proofs, for
+//! testing consumers of the [`Client`](crate::Client) without a prover.
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use cometbft_types::types::{
+    canonical_block_id::CanonicalBlockId, canonical_part_set_header::CanonicalPartSetHeader,
+    header::Header, signed_msg_type::SignedMsgType,
+};
+use prost::Message;
+use sha2::{Digest, Sha256};
+use tonic::{
+    codec::ProstCodec,
+    codegen::{empty_body, http, Body, BoxFuture, Context, Poll, Service, StdError},
+    server::{Grpc, NamedService, UnaryService},
+    transport::Server,
+};
+use unionlabs::{bounded::BoundedI64, primitives::H256};
+
+use crate::{
+    canonical_vote::CanonicalVote,
+    poll_response::{PollResponse, ProveRequestDone},
+    prove_request::ProveRequest,
+    prove_response::ProveResponse,
+    validator_set_commit::ValidatorSetCommit,
+    zero_knowledge_proof::ZeroKnowledgeProof,
+};
+
+const POLL_PATH: &str = "/union.galois.api.v3.UnionProverAPI/Poll";
+
+#[derive(Debug, Clone, Default)]
+pub struct MockProver {
+    /// How many times a request is reported as pending before its proof is returned.
+    pending_polls: usize,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Number of polls received for every distinct request.
+    polls: HashMap<H256, usize>,
+}
+
+impl MockProver {
+    pub fn new(pending_polls: usize) -> Self {
+        Self {
+            pending_polls,
+            state: Arc::default(),
+        }
+    }
+
+    /// The proof returned for a request, derived from the hash of the request.
+    pub fn proof(request: &protos::union::galois::api::v3::PollRequest) -> ProveResponse {
+        let hash = Sha256::digest(request.encode_to_vec());
+
+        ProveResponse {
+            proof: ZeroKnowledgeProof {
+                content: hash.to_vec(),
+                compressed_content: hash.to_vec(),
+                evm_proof: hash.to_vec(),
+                public_inputs: hash.to_vec(),
+            },
+            trusted_validator_set_root: H256::new(hash.into()),
+        }
+    }
+
+    /// Total number of polls received.
+    pub fn polls(&self) -> usize {
+        self.state.lock().unwrap().polls.values().sum()
+    }
+
+    /// Number of distinct requests received.
+    pub fn requests(&self) -> usize {
+        self.state.lock().unwrap().polls.len()
+    }
+
+    fn poll(&self, request: protos::union::galois::api::v3::PollRequest) -> PollResponse {
+        let key = H256::new(Sha256::digest(request.encode_to_vec()).into());
+
+        let mut state = self.state.lock().unwrap();
+        let polls = state.polls.entry(key).or_default();
+        *polls += 1;
+
+        if *polls > self.pending_polls {
+            PollResponse::Done(ProveRequestDone {
+                response: Self::proof(&request),
+            })
+        } else {
+            PollResponse::Pending
+        }
+    }
+
+    /// Serves the mock on a random local port, returning the endpoint to connect to.
+    pub async fn spawn(self) -> std::io::Result<String> {
+        let listener = tokio::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await?;
+        let addr = listener.local_addr()?;
+
+        let incoming = futures::stream::unfold(listener, |listener| async move {
+            Some((listener.accept().await.map(|(stream, _)| stream), listener))
+        });
+
+        tokio::spawn(
+            Server::builder()
+                .add_service(self)
+                .serve_with_incoming(incoming),
+        );
+
+        Ok(format!("http://{addr}"))
+    }
+}
+
+/// A minimal prove request for the untrusted `height`, to exercise the mock.
+pub fn prove_request(height: i64) -> ProveRequest {
+    ProveRequest {
+        vote: CanonicalVote {
+            ty: SignedMsgType::Precommit,
+            height: BoundedI64::new(height).unwrap(),
+            round: BoundedI64::new(0).unwrap(),
+            block_id: CanonicalBlockId {
+                hash: Default::default(),
+                part_set_header: CanonicalPartSetHeader {
+                    total: 1,
+                    hash: Default::default(),
+                },
+            },
+            chain_id: "union-devnet-1".to_owned(),
+        },
+        untrusted_header: Header {
+            version: Default::default(),
+            chain_id: "union-devnet-1".to_owned(),
+            height: BoundedI64::new(height).unwrap(),
+            time: Default::default(),
+            last_block_id: Default::default(),
+            last_commit_hash: Default::default(),
+            data_hash: Default::default(),
+            validators_hash: Default::default(),
+            next_validators_hash: Default::default(),
+            consensus_hash: Default::default(),
+            app_hash: Default::default(),
+            last_results_hash: Default::default(),
+            evidence_hash: Default::default(),
+            proposer_address: Default::default(),
+        },
+        trusted_commit: ValidatorSetCommit {
+            validators: vec![],
+            signatures: vec![],
+            bitmap: vec![],
+        },
+        untrusted_commit: ValidatorSetCommit {
+            validators: vec![],
+            signatures: vec![],
+            bitmap: vec![],
+        },
+    }
+}
+
+struct PollService(MockProver);
+
+impl UnaryService<protos::union::galois::api::v3::PollRequest> for PollService {
+    type Response = protos::union::galois::api::v3::PollResponse;
+    type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+
+    fn call(
+        &mut self,
+        request: tonic::Request<protos::union::galois::api::v3::PollRequest>,
+    ) -> Self::Future {
+        let response = self.0.poll(request.into_inner());
+
+        Box::pin(async move { Ok(tonic::Response::new(response.into())) })
+    }
+}
+
+impl<B> Service<http::Request<B>> for MockProver
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<tonic::body::BoxBody>;
+    type Error = std::convert::Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if req.uri().path() == POLL_PATH {
+            let prover = self.clone();
+
+            Box::pin(async move {
+                Ok(Grpc::new(ProstCodec::default())
+                    .unary(PollService(prover), req)
+                    .await)
+            })
+        } else {
+            Box::pin(async move {
+                Ok(http::Response::builder()
+                    .status(200)
+                    .header("grpc-status", "12")
+                    .header("content-type", "application/grpc")
+                    .body(empty_body())
+                    .unwrap())
+            })
+        }
+    }
+}
+
+impl NamedService for MockProver {
+    const NAME: &'static str = "union.galois.api.v3.UnionProverAPI";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{poll_request::PollRequest, Client};
+
+    fn request(height: i64) -> PollRequest {
+        PollRequest {
+            request: prove_request(height),
+        }
+    }
+
+    #[tokio::test]
+    async fn pending_then_deterministic_proof() {
+        let prover = MockProver::new(1);
+        let endpoint = prover.clone().spawn().await.unwrap();
+        let mut client = Client::connect(endpoint).await.unwrap();
+
+        assert_eq!(
+            client.poll(request(10)).await.unwrap(),
+            PollResponse::Pending
+        );
+
+        let PollResponse::Done(first) = client.poll(request(10)).await.unwrap() else {
+            panic!("expected a proof");
+        };
+        let PollResponse::Done(second) = client.poll(request(10)).await.unwrap() else {
+            panic!("expected a proof");
+        };
+        assert_eq!(first, second);
+        assert_eq!(first.response, MockProver::proof(&request(10).into()));
+
+        let PollResponse::Pending = client.poll(request(11)).await.unwrap() else {
+            panic!("expected a pending proof");
+        };
+
+        assert_eq!(prover.polls(), 4);
+        assert_eq!(prover.requests(), 2);
+    }
+}
@@ -1,7 +1,15 @@
 use core::f64;
 use std::{
-    borrow::Borrow, cmp::Eq, collections::HashMap, fmt::Write, future::Future, hash::Hash,
-    marker::PhantomData, time::Duration,
+    borrow::Borrow,
+    cmp::Eq,
+    collections::HashMap,
+    fmt::Write,
+    future::Future,
+    hash::Hash,
+    marker::PhantomData,
+    path::PathBuf,
+    sync::{Arc, Weak},
+    time::Duration,
 };
 
 use futures_util::TryStreamExt;
@@ -9,22 +17,31 @@ use itertools::Itertools;
 use schemars::JsonSchema;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use sqlx::{
-    postgres::PgPoolOptions, prelude::FromRow, types::Json, Either, Executor, PgPool, Postgres,
-    Transaction,
+    postgres::{PgListener, PgPoolOptions},
+    prelude::FromRow,
+    types::Json,
+    Either, Executor, PgPool, Postgres, Transaction,
 };
+use tokio::sync::watch;
 use tracing::{debug, debug_span, error, info, info_span, instrument, trace, warn, Instrument};
 use voyager_vm::{
     filter::{FilterResult, Interest, InterestFilter},
     pass::{Pass, PassResult},
-    BoxDynError, Captures, EnqueueResult, ItemId, Op, QueueError, QueueMessage,
+    BoxDynError, Captures, EnqueueResult, ItemId, Op, Priority, QueueError, QueueMessage,
 };
 
 use crate::metrics::{ITEM_PROCESSING_DURATION, OPTIMIZE_ITEM_COUNT, OPTIMIZE_PROCESSING_DURATION};
 
 pub mod metrics;
+mod retention;
+
+pub use crate::retention::PruneDoneSummary;
 
 /// A fifo queue backed by a postgres table. Not suitable for high-throughput, but enough for ~1k items/sec.
 ///
+/// Inserts into the queue table send a notification on the `queue` channel, which idle workers
+/// listen for instead of polling the table.
+///
 /// The queue assumes the following database schema:
 ///
 /// ```ignore
@@ -39,6 +56,10 @@ pub struct PgQueue<T> {
     optimize_batch_limit: Option<i64>,
     retryable_error_expo_backoff_max: f64,
     retryable_error_expo_backoff_multiplier: f64,
+    done_retention: Option<DoneRetentionConfig>,
+    poll_interval: Duration,
+    /// Updated whenever a notification is received on the `queue` channel.
+    wakeups: Arc<watch::Sender<()>>,
     __marker: PhantomData<fn() -> T>,
 }
 
@@ -60,6 +81,35 @@ pub struct PgQueueConfig {
     pub retryable_error_expo_backoff_max: f64,
     #[serde(default = "default_retryable_error_expo_backoff_multiplier")]
     pub retryable_error_expo_backoff_multiplier: f64,
+    /// The maximum amount of time an idle worker waits before checking the queue again, in case a
+    /// notification was missed.
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval: Duration,
+    /// Prune old rows from the `done` table. If not set, the `done` table grows forever.
+    #[serde(default)]
+    pub done_retention: Option<DoneRetentionConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DoneRetentionConfig {
+    /// Rows in `done` created more than this many days ago are pruned.
+    pub retain_days: u32,
+    /// Partition `done` by day, such that pruning drops whole partitions instead of deleting
+    /// rows. An existing unpartitioned `done` table is renamed to `done_legacy`, which is pruned
+    /// by deleting rows and dropped once it is empty.
+    #[serde(default)]
+    pub partition: bool,
+    /// Write pruned rows to gzip compressed JSONL files in this directory before deleting them.
+    #[serde(default)]
+    pub archive_dir: Option<PathBuf>,
+    /// How often `done` is pruned.
+    #[serde(default = "default_prune_interval_milliseconds")]
+    pub prune_interval_milliseconds: u64,
+    /// The maximum amount of rows deleted (and archived) per transaction when pruning without
+    /// partitions.
+    #[serde(default = "default_prune_batch_size")]
+    pub prune_batch_size: i64,
 }
 
 pub const fn default_max_connections() -> u32 {
@@ -78,6 +128,18 @@ pub const fn default_retryable_error_expo_backoff_multiplier() -> f64 {
     2.0
 }
 
+pub const fn default_poll_interval() -> Duration {
+    Duration::from_secs(1)
+}
+
+pub const fn default_prune_interval_milliseconds() -> u64 {
+    60 * 60 * 1000
+}
+
+pub const fn default_prune_batch_size() -> i64 {
+    10_000
+}
+
 impl PgQueueConfig {
     pub async fn into_pg_pool(self) -> sqlx::Result<PgPool> {
         PgPoolOptions::new()
@@ -95,6 +157,11 @@ struct Id {
     id: i64,
 }
 
+#[derive(FromRow)]
+struct HandleAt {
+    handle_at: Option<time::OffsetDateTime>,
+}
+
 #[derive(Debug, FromRow)]
 struct QueueRecord {
     id: i64,
@@ -102,6 +169,7 @@ struct QueueRecord {
     item: String,
     created_at: time::OffsetDateTime,
     attempt: i64,
+    priority: i16,
 }
 
 #[derive(Debug, FromRow)]
@@ -112,6 +180,7 @@ struct OptimizeRecord {
     item: String,
     #[allow(dead_code)]
     created_at: time::OffsetDateTime,
+    priority: i16,
 }
 
 #[derive(Debug, FromRow, Serialize)]
@@ -124,6 +193,78 @@ pub struct FailedRecord<T: QueueMessage> {
     // pub created_at: sqlx::types::time::OffsetDateTime,
 }
 
+/// A table of the queue that stores ops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemTable {
+    Queue,
+    Optimize,
+    Done,
+    Failed,
+}
+
+impl ItemTable {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            ItemTable::Queue => "queue",
+            ItemTable::Optimize => "optimize",
+            ItemTable::Done => "done",
+            ItemTable::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, FromRow, Serialize)]
+#[serde(bound(serialize = ""))]
+pub struct ItemRecord<T: QueueMessage> {
+    pub id: i64,
+    pub item: Json<Op<T>>,
+}
+
+/// The `limit` most recently inserted items of `table`.
+///
+/// This only reads from `pool`, in a read-only transaction, and as such doesn't require the queue
+/// to be set up first (see [`PgQueue::new`]).
+pub async fn recent_items<T: QueueMessage>(
+    pool: &PgPool,
+    table: ItemTable,
+    limit: i64,
+) -> Result<Vec<ItemRecord<T>>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("SET TRANSACTION READ ONLY")
+        .execute(tx.as_mut())
+        .await?;
+
+    let items = sqlx::query(
+        format!(
+            r#"
+            SELECT
+                id,
+                item
+            FROM
+                {}
+            ORDER BY
+                id DESC
+            LIMIT
+                $1
+            "#,
+            table.as_str()
+        )
+        .as_str(),
+    )
+    .bind(limit)
+    .map(|row| ItemRecord::<T>::from_row(&row))
+    .fetch_all(tx.as_mut())
+    .await?
+    .into_iter()
+    .collect::<Result<Vec<_>, _>>()?;
+
+    tx.commit().await?;
+
+    Ok(items)
+}
+
 impl<T: QueueMessage> PgQueue<T> {
     pub async fn query_failed(
         &self,
@@ -197,6 +338,45 @@ impl<T: QueueMessage> PgQueue<T> {
     }
 }
 
+impl<T: QueueMessage> PgQueue<T> {
+    /// Wait until either an item is inserted into the queue, the earliest `handle_at` of the items
+    /// that are not yet ready is reached, or the poll interval elapses.
+    async fn wait_for_ready(&self, mut wakeups: watch::Receiver<()>) -> Result<(), sqlx::Error> {
+        let next_handle_at = sqlx::query(
+            r#"
+            SELECT
+              min(handle_at) AS handle_at
+            FROM
+              queue
+            WHERE
+              handle_at > now()
+            "#,
+        )
+        .try_map(|x| HandleAt::from_row(&x))
+        .fetch_one(&self.client)
+        .await?
+        .handle_at;
+
+        let timeout = next_handle_at.map_or(self.poll_interval, |handle_at| {
+            (handle_at - time::OffsetDateTime::now_utc())
+                .try_into()
+                .unwrap_or(Duration::ZERO)
+                .min(self.poll_interval)
+        });
+
+        trace!(
+            ?next_handle_at,
+            ?timeout,
+            "queue is empty, waiting for wakeup"
+        );
+
+        // the sender is owned by self, so this can't return an error
+        let _ = tokio::time::timeout(timeout, wakeups.changed()).await;
+
+        Ok(())
+    }
+}
+
 impl<T: QueueMessage> voyager_vm::Queue<T> for PgQueue<T> {
     type Config = PgQueueConfig;
     // type Error = tokio_postgres::Error;
@@ -218,6 +398,8 @@ impl<T: QueueMessage> voyager_vm::Queue<T> for PgQueue<T> {
         let retryable_error_expo_backoff_multiplier =
             config.retryable_error_expo_backoff_multiplier;
         let retryable_error_expo_backoff_max = config.retryable_error_expo_backoff_max;
+        let done_retention = config.done_retention.clone();
+        let poll_interval = config.poll_interval;
 
         let pool = config.into_pg_pool().await?;
 
@@ -243,15 +425,6 @@ impl<T: QueueMessage> voyager_vm::Queue<T> for PgQueue<T> {
                 created_at timestamptz NOT NULL DEFAULT now()
               );
 
-            CREATE TABLE IF NOT EXISTS
-              done (
-                id BIGINT,
-                item JSONB NOT NULL,
-                parents BIGINT[] DEFAULT '{}',
-                created_at timestamptz NOT NULL DEFAULT now(),
-                PRIMARY KEY (id, created_at)
-              );
-
             CREATE TABLE IF NOT EXISTS
               failed (
                 id BIGINT PRIMARY KEY,
@@ -266,6 +439,25 @@ impl<T: QueueMessage> voyager_vm::Queue<T> for PgQueue<T> {
             CREATE INDEX IF NOT EXISTS index_queue_created_at ON queue (created_at ASC) INCLUDE (id);
 
             CREATE INDEX IF NOT EXISTS index_queue_handle_at ON queue(handle_at DESC) INCLUDE (id);
+
+            ALTER TABLE queue ADD COLUMN IF NOT EXISTS priority SMALLINT NOT NULL DEFAULT 0;
+
+            ALTER TABLE optimize ADD COLUMN IF NOT EXISTS priority SMALLINT NOT NULL DEFAULT 0;
+
+            CREATE INDEX IF NOT EXISTS index_queue_priority_handle_at
+              ON queue(priority DESC, handle_at ASC) INCLUDE (id);
+
+            CREATE OR REPLACE FUNCTION notify_queue() RETURNS trigger AS $$
+            BEGIN
+              PERFORM pg_notify('queue', '');
+              RETURN NULL;
+            END;
+            $$ LANGUAGE plpgsql;
+
+            CREATE OR REPLACE TRIGGER notify_queue
+              AFTER INSERT ON queue
+              FOR EACH STATEMENT
+              EXECUTE FUNCTION notify_queue();
             "#,
         )
         .try_for_each(|result| async move {
@@ -275,13 +467,32 @@ impl<T: QueueMessage> voyager_vm::Queue<T> for PgQueue<T> {
         .instrument(info_span!("init"))
         .await?;
 
-        Ok(Self {
+        let mut listener = PgListener::connect_with(&pool).await?;
+        listener.listen("queue").await?;
+
+        let wakeups = Arc::new(watch::Sender::new(()));
+
+        tokio::spawn(
+            listen_for_wakeups(listener, Arc::downgrade(&wakeups)).instrument(info_span!("listen")),
+        );
+
+        let queue = Self {
             client: pool,
             optimize_batch_limit,
             retryable_error_expo_backoff_max,
             retryable_error_expo_backoff_multiplier,
+            done_retention,
+            poll_interval,
+            wakeups,
             __marker: PhantomData,
-        })
+        };
+
+        queue
+            .init_done_table()
+            .instrument(info_span!("init_done"))
+            .await?;
+
+        Ok(queue)
     }
 
     async fn enqueue<'a>(
@@ -291,24 +502,34 @@ impl<T: QueueMessage> voyager_vm::Queue<T> for PgQueue<T> {
     ) -> Result<EnqueueResult, Self::Error> {
         trace!("enqueue");
 
-        let (optimize, ready): (Vec<_>, Vec<_>) =
-            op.normalize()
-                .into_iter()
-                .partition_map(|op| match filter.check_interest(&op) {
-                    FilterResult::Interest(interest) => Either::Left((op, interest)),
-                    FilterResult::NoInterest => Either::Right(op),
-                });
+        let (optimize, ready): (Vec<_>, Vec<_>) = op
+            .normalize()
+            .into_iter()
+            .map(|op| {
+                let (priority, op) = op.take_priority();
+                (op, priority.unwrap_or_default())
+            })
+            .partition_map(|(op, priority)| match filter.check_interest(&op) {
+                FilterResult::Interest(interest) => Either::Left((op, interest, priority)),
+                FilterResult::NoInterest => Either::Right((op, priority)),
+            });
 
         let mut tx = self.client.begin().await?;
 
+        let (ready, ready_priorities): (Vec<_>, Vec<_>) = ready
+            .into_iter()
+            .map(|(op, priority)| (Json(op), priority.as_i16()))
+            .unzip();
+
         let ready_ids = sqlx::query(
             "
-            INSERT INTO queue (item)
-            SELECT * FROM UNNEST($1::JSONB[])
+            INSERT INTO queue (item, priority)
+            SELECT * FROM UNNEST($1::JSONB[], $2::SMALLINT[])
             RETURNING id
             ",
         )
-        .bind(ready.into_iter().map(Json).collect::<Vec<_>>())
+        .bind(ready)
+        .bind(ready_priorities)
         .try_map(|x| Id::from_row(&x))
         .fetch_all(tx.as_mut())
         .await?;
@@ -319,21 +540,27 @@ impl<T: QueueMessage> voyager_vm::Queue<T> for PgQueue<T> {
 
         let optimize_further_ids = sqlx::query(
             "
-            INSERT INTO optimize (item, tag)
-            SELECT * FROM UNNEST($1::JSONB[], $2::TEXT[])
+            INSERT INTO optimize (item, tag, priority)
+            SELECT * FROM UNNEST($1::JSONB[], $2::TEXT[], $3::SMALLINT[])
             RETURNING id
             ",
         )
         .bind(
             optimize
                 .iter()
-                .map(|x| Json(x.0.clone()))
+                .flat_map(|(op, interest, _)| interest.tags.iter().map(|_| Json(op.clone())))
+                .collect::<Vec<_>>(),
+        )
+        .bind(
+            optimize
+                .iter()
+                .flat_map(|(_, interest, _)| interest.tags.clone())
                 .collect::<Vec<_>>(),
         )
         .bind(
             optimize
                 .iter()
-                .flat_map(|x| x.1.tags.clone())
+                .flat_map(|(_, interest, priority)| interest.tags.iter().map(|_| priority.as_i16()))
                 .collect::<Vec<_>>(),
         )
         .try_map(|x| Id::from_row(&x))
@@ -371,6 +598,10 @@ impl<T: QueueMessage> voyager_vm::Queue<T> for PgQueue<T> {
     {
         trace!("process");
 
+        // subscribe before checking the queue so that no notifications sent after the check are
+        // missed
+        let wakeups = self.wakeups.subscribe();
+
         let mut tx = self.client.begin().await?;
 
         let row = sqlx::query(
@@ -386,6 +617,7 @@ impl<T: QueueMessage> voyager_vm::Queue<T> for PgQueue<T> {
                 WHERE
                   handle_at < now()
                 ORDER BY
+                  priority DESC,
                   handle_at ASC
                 FOR UPDATE
                   SKIP LOCKED
@@ -395,28 +627,32 @@ impl<T: QueueMessage> voyager_vm::Queue<T> for PgQueue<T> {
               parents,
               item::text,
               attempt,
-              created_at
+              created_at,
+              priority
             "#,
         )
         .try_map(|x| QueueRecord::from_row(&x))
         .fetch_optional(tx.as_mut())
         .await?;
 
-        let res = match row {
-            Some(record) => {
-                process_item(
-                    &mut tx,
-                    record,
-                    f,
-                    filter,
-                    self.retryable_error_expo_backoff_max,
-                    self.retryable_error_expo_backoff_multiplier,
-                )
-                .await?
-            }
-            None => None,
+        let Some(record) = row else {
+            tx.commit().await?;
+
+            self.wait_for_ready(wakeups).await?;
+
+            return Ok(None);
         };
 
+        let res = process_item(
+            &mut tx,
+            record,
+            f,
+            filter,
+            self.retryable_error_expo_backoff_max,
+            self.retryable_error_expo_backoff_multiplier,
+        )
+        .await?;
+
         tx.commit().await?;
 
         Ok(res)
@@ -454,7 +690,8 @@ impl<T: QueueMessage> voyager_vm::Queue<T> for PgQueue<T> {
               id,
               parents,
               item::text,
-              created_at
+              created_at,
+              priority
             "#,
         )
         .bind(tag)
@@ -470,6 +707,11 @@ impl<T: QueueMessage> voyager_vm::Queue<T> for PgQueue<T> {
             return Ok(());
         }
 
+        let priorities = msgs
+            .iter()
+            .map(|r| Priority::from_i16(r.priority))
+            .collect::<Vec<_>>();
+
         let (ids, msgs) = msgs
             .into_iter()
             .map(|r| {
@@ -514,21 +756,34 @@ impl<T: QueueMessage> voyager_vm::Queue<T> for PgQueue<T> {
                 .collect::<Vec<_>>()
         };
 
+        // new messages inherit the highest priority of their parents
+        let get_parents_priority = |parent_idxs: &[usize]| {
+            parent_idxs
+                .iter()
+                .filter_map(|&idx| priorities.get(idx).copied())
+                .max()
+                .unwrap_or_default()
+        };
+
         for (parent_idxs, new_msg, tag) in optimize_further {
             let parents = get_parent_ids(&parent_idxs);
             trace!(parent_idxs = ?&parent_idxs, parents = ?&parents);
 
+            let (priority, new_msg) = new_msg.take_priority();
+            let priority = priority.unwrap_or_else(|| get_parents_priority(&parent_idxs));
+
             let new_row = sqlx::query(
                 "
-                INSERT INTO optimize (item, parents, tag)
+                INSERT INTO optimize (item, parents, tag, priority)
                 VALUES
-                    ($1::JSONB, $2, $3)
+                    ($1::JSONB, $2, $3, $4)
                 RETURNING id
                 ",
             )
             .bind(Json(new_msg))
             .bind(&parents)
             .bind(tag)
+            .bind(priority.as_i16())
             .try_map(|row| Id::from_row(&row))
             .fetch_one(tx.as_mut())
             .await
@@ -546,20 +801,24 @@ impl<T: QueueMessage> voyager_vm::Queue<T> for PgQueue<T> {
             trace!(parent_idxs = ?&parent_idxs, parents = ?&parents);
 
             'block: for op in normalized_ops {
+                let (priority, op) = op.take_priority();
+                let priority = priority.unwrap_or_else(|| get_parents_priority(&parent_idxs));
+
                 match filter.check_interest(&op) {
                     FilterResult::Interest(Interest { tags, remove }) => {
                         for tag in tags {
                             let new_row = sqlx::query(
                                 "
-                                INSERT INTO optimize (item, parents, tag)
+                                INSERT INTO optimize (item, parents, tag, priority)
                                 VALUES
-                                    ($1::JSONB, $2, $3)
+                                    ($1::JSONB, $2, $3, $4)
                                 RETURNING id
                                 ",
                             )
                             .bind(Json(op.clone()))
                             .bind(&parents)
                             .bind(tag)
+                            .bind(priority.as_i16())
                             .try_map(|row| Id::from_row(&row))
                             .fetch_one(tx.as_mut())
                             .await
@@ -575,21 +834,22 @@ impl<T: QueueMessage> voyager_vm::Queue<T> for PgQueue<T> {
                     FilterResult::NoInterest => {}
                 }
 
-                ready_insert_into_queue.push((parents.clone(), op));
+                ready_insert_into_queue.push((parents.clone(), op, priority));
             }
         }
 
-        for (parents, op) in ready_insert_into_queue {
+        for (parents, op, priority) in ready_insert_into_queue {
             let ready_ids = sqlx::query(
                 "
-                INSERT INTO queue (item, parents)
+                INSERT INTO queue (item, parents, priority)
                 VALUES
-                    ($2::JSONB, $1)
+                    ($2::JSONB, $1, $3)
                 RETURNING id
                 ",
             )
             .bind(parents)
             .bind(Json(op))
+            .bind(priority.as_i16())
             .try_map(|x| Id::from_row(&x))
             .fetch_all(tx.as_mut())
             .await
@@ -606,6 +866,34 @@ impl<T: QueueMessage> voyager_vm::Queue<T> for PgQueue<T> {
     }
 }
 
+/// Forward notifications on the `queue` channel to all workers, until the queue is dropped.
+async fn listen_for_wakeups(mut listener: PgListener, wakeups: Weak<watch::Sender<()>>) {
+    loop {
+        let res = listener.try_recv().await;
+
+        let Some(wakeups) = wakeups.upgrade() else {
+            debug!("queue dropped, no longer listening for notifications");
+            return;
+        };
+
+        match res {
+            Ok(Some(_)) => {
+                trace!("received notification");
+            }
+            Ok(None) => {
+                // notifications may have been missed while the connection was lost
+                warn!("notification listener connection lost, reconnecting");
+            }
+            Err(error) => {
+                error!(%error, "error receiving notification");
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+
+        wakeups.send_replace(());
+    }
+}
+
 #[instrument(
     skip_all,
     fields(
@@ -651,8 +939,8 @@ where
             sqlx::query(
                 "
                 INSERT INTO
-                queue  (id, item,      parents, attempt, handle_at, created_at)
-                VALUES ($1, $2::JSONB, $3,      $4,      $5,        $6        )
+                queue  (id, item,      parents, attempt, handle_at, created_at, priority)
+                VALUES ($1, $2::JSONB, $3,      $4,      $5,        $6,         $7      )
                 ",
             )
             .bind(record.id)
@@ -672,6 +960,7 @@ where
                 ),
             )
             .bind(record.created_at)
+            .bind(record.priority)
             .execute(tx.as_mut())
             .await?;
 
@@ -698,36 +987,49 @@ where
                     break 'block;
                 }
 
+                // new messages inherit the priority of the message that produced them
+                let parent_priority = Priority::from_i16(record.priority);
+
                 let (optimize, ready): (Vec<_>, Vec<_>) = ops
                     .into_iter()
                     .flat_map(Op::normalize)
-                    .partition_map(|op| match filter.check_interest(&op) {
-                        FilterResult::Interest(tag) => Either::Left((op, tag)),
-                        FilterResult::NoInterest => Either::Right(op),
+                    .map(|op| {
+                        let (priority, op) = op.take_priority();
+                        (op, priority.unwrap_or(parent_priority))
+                    })
+                    .partition_map(|(op, priority)| match filter.check_interest(&op) {
+                        FilterResult::Interest(tag) => Either::Left((op, tag, priority)),
+                        FilterResult::NoInterest => Either::Right((op, priority)),
                     });
 
+                let (ready, ready_priorities): (Vec<_>, Vec<_>) = ready
+                    .into_iter()
+                    .map(|(op, priority)| (Json(op), priority.as_i16()))
+                    .unzip();
+
                 sqlx::query(
                     "
-                    INSERT INTO queue (item, parents)
-                    SELECT *, $1 as parents FROM UNNEST($2::JSONB[])
+                    INSERT INTO queue (item, priority, parents)
+                    SELECT *, $1 as parents FROM UNNEST($2::JSONB[], $3::SMALLINT[])
                     ",
                 )
                 .bind(vec![record.id])
-                .bind(ready.into_iter().map(Json).collect::<Vec<_>>())
+                .bind(ready)
+                .bind(ready_priorities)
                 .execute(tx.as_mut())
                 .await?;
 
                 sqlx::query(
                     "
-                    INSERT INTO optimize (item, tag, parents)
-                    SELECT *, $1 as parents FROM UNNEST($2::JSONB[], $3::TEXT[])
+                    INSERT INTO optimize (item, tag, priority, parents)
+                    SELECT *, $1 as parents FROM UNNEST($2::JSONB[], $3::TEXT[], $4::SMALLINT[])
                     ",
                 )
                 .bind(vec![record.id])
                 .bind(
                     optimize
                         .iter()
-                        .flat_map(|(op, interest)| {
+                        .flat_map(|(op, interest, _)| {
                             interest.tags.iter().map(|_| Json(op.clone())).clone()
                         })
                         .collect::<Vec<_>>(),
@@ -735,10 +1037,18 @@ where
                 .bind(
                     optimize
                         .iter()
-                        .flat_map(|(_, interest)| &interest.tags)
+                        .flat_map(|(_, interest, _)| &interest.tags)
                         .copied()
                         .collect::<Vec<_>>(),
                 )
+                .bind(
+                    optimize
+                        .iter()
+                        .flat_map(|(_, interest, priority)| {
+                            interest.tags.iter().map(|_| priority.as_i16())
+                        })
+                        .collect::<Vec<_>>(),
+                )
                 .execute(tx.as_mut())
                 .await?;
             }
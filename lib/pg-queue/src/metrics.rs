@@ -1,6 +1,6 @@
 use std::sync::LazyLock;
 
-use prometheus::{register_histogram, Histogram};
+use prometheus::{register_histogram, register_int_counter, Histogram, IntCounter};
 
 pub static ITEM_PROCESSING_DURATION: LazyLock<Histogram> = LazyLock::new(|| {
     register_histogram!(
@@ -28,3 +28,11 @@ pub static OPTIMIZE_ITEM_COUNT: LazyLock<Histogram> = LazyLock::new(|| {
     )
     .unwrap()
 });
+
+pub static DONE_PRUNED_ROW_COUNT: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "pg_queue_done_pruned_row_count",
+        "The amount of rows pruned from the done table.",
+    )
+    .unwrap()
+});
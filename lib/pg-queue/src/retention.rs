@@ -0,0 +1,701 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use flate2::{write::GzEncoder, Compression};
+use futures_util::TryStreamExt;
+use serde::Serialize;
+use sqlx::{prelude::FromRow, types::Json, Executor, PgConnection};
+use time::{format_description::well_known::Rfc3339, Date, Month, OffsetDateTime};
+use tracing::{debug, info, trace, warn};
+use voyager_vm::QueueMessage;
+
+use crate::{metrics::DONE_PRUNED_ROW_COUNT, DoneRetentionConfig, PgQueue};
+
+/// How many days of partitions are created ahead of the current day.
+const DONE_PARTITIONS_AHEAD: u8 = 3;
+
+const DONE_TABLE_DEFINITION: &str = r#"
+    (
+      id BIGINT,
+      item JSONB NOT NULL,
+      parents BIGINT[] DEFAULT '{}',
+      created_at timestamptz NOT NULL DEFAULT now(),
+      PRIMARY KEY (id, created_at)
+    )
+"#;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct PruneDoneSummary {
+    /// The daily partitions that were dropped.
+    pub dropped_partitions: Vec<String>,
+    /// The amount of rows that were deleted, not including rows in dropped partitions.
+    pub deleted_rows: u64,
+    /// The amount of rows that were written to archive files, including rows in dropped
+    /// partitions.
+    pub archived_rows: u64,
+    pub archive_files: Vec<PathBuf>,
+}
+
+#[derive(Debug, FromRow)]
+struct DoneRecord {
+    id: i64,
+    parents: Vec<i64>,
+    item: Json<serde_json::Value>,
+    created_at: OffsetDateTime,
+}
+
+#[derive(FromRow)]
+struct RelKind {
+    relkind: String,
+}
+
+#[derive(FromRow)]
+struct RelName {
+    relname: String,
+}
+
+impl<T: QueueMessage> PgQueue<T> {
+    pub fn done_retention(&self) -> Option<&DoneRetentionConfig> {
+        self.done_retention.as_ref()
+    }
+
+    /// Create the `done` table, partitioning it (and migrating an existing unpartitioned table) if
+    /// configured to do so.
+    pub(crate) async fn init_done_table(&self) -> Result<(), sqlx::Error> {
+        if !self.done_retention.as_ref().is_some_and(|r| r.partition) {
+            self.client
+                .execute(
+                    format!("CREATE TABLE IF NOT EXISTS done {DONE_TABLE_DEFINITION}").as_str(),
+                )
+                .await?;
+
+            return Ok(());
+        }
+
+        match self.done_relkind("done").await?.as_deref() {
+            // already partitioned
+            Some("p") => {}
+            Some(_) => {
+                info!("migrating unpartitioned done table to done_legacy");
+
+                let mut tx = self.client.begin().await?;
+
+                tx.as_mut()
+                    .execute_many(
+                        format!(
+                            r#"
+                        ALTER TABLE done RENAME TO done_legacy;
+                        ALTER INDEX done_pkey RENAME TO done_legacy_pkey;
+                        CREATE TABLE done {DONE_TABLE_DEFINITION}
+                          PARTITION BY RANGE (created_at);
+                        CREATE TABLE done_default PARTITION OF done DEFAULT;
+                        "#
+                        )
+                        .as_str(),
+                    )
+                    .try_for_each(|_| async { Ok(()) })
+                    .await?;
+
+                tx.commit().await?;
+            }
+            None => {
+                self.client
+                    .execute_many(
+                        format!(
+                            r#"
+                            CREATE TABLE done {DONE_TABLE_DEFINITION}
+                              PARTITION BY RANGE (created_at);
+                            CREATE TABLE done_default PARTITION OF done DEFAULT;
+                            "#
+                        )
+                        .as_str(),
+                    )
+                    .try_for_each(|_| async { Ok(()) })
+                    .await?;
+            }
+        }
+
+        self.create_done_partitions().await
+    }
+
+    /// Prune all rows in `done` that are older than the configured retention, archiving them first
+    /// if an archive directory is configured. This is a noop if no retention is configured.
+    pub async fn prune_done(&self) -> Result<PruneDoneSummary, sqlx::Error> {
+        let mut summary = PruneDoneSummary::default();
+
+        let Some(retention) = &self.done_retention else {
+            return Ok(summary);
+        };
+
+        let now = OffsetDateTime::now_utc();
+        let cutoff = now
+            .date()
+            .saturating_sub(time::Duration::days(retention.retain_days.into()));
+
+        debug!(%cutoff, "pruning done");
+
+        let tables = if self.done_relkind("done").await?.as_deref() == Some("p") {
+            // keep the partitions ahead of the current day in place, in case voyager is not
+            // running when the day rolls over
+            self.create_done_partitions().await?;
+
+            for (name, date) in self.done_partitions().await? {
+                // the partition is dropped once all of the rows it can contain are past the cutoff
+                if date >= cutoff {
+                    continue;
+                }
+
+                // the partition is only dropped once its rows are written to the archive
+                let mut tx = self.client.begin().await?;
+
+                let rows = match &retention.archive_dir {
+                    Some(archive_dir) => {
+                        match archive_table(
+                            tx.as_mut(),
+                            &name,
+                            archive_dir,
+                            retention.prune_batch_size,
+                        )
+                        .await?
+                        {
+                            Some((path, rows)) => {
+                                summary.archived_rows += rows;
+                                summary.archive_files.push(path);
+
+                                rows
+                            }
+                            None => 0,
+                        }
+                    }
+                    None => sqlx::query_scalar::<_, i64>(&format!("SELECT count(*) FROM {name}"))
+                        .fetch_one(tx.as_mut())
+                        .await?
+                        .try_into()
+                        .expect("count is non-negative; qed;"),
+                };
+
+                tx.as_mut()
+                    .execute(format!("DROP TABLE IF EXISTS {name}").as_str())
+                    .await?;
+
+                tx.commit().await?;
+
+                DONE_PRUNED_ROW_COUNT.inc_by(rows);
+
+                info!(%name, rows, "dropped done partition");
+
+                summary.dropped_partitions.push(name);
+            }
+
+            let mut tables = vec!["done_default"];
+
+            if self.done_relkind("done_legacy").await?.is_some() {
+                tables.push("done_legacy");
+            }
+
+            tables
+        } else {
+            vec!["done"]
+        };
+
+        let cutoff = cutoff.midnight().assume_utc();
+
+        for table in tables {
+            self.delete_done_rows(table, cutoff, now, retention, &mut summary)
+                .await?;
+        }
+
+        // the legacy table only contains rows from before the migration, so once it is empty it
+        // will never be written to again
+        if self.done_relkind("done_legacy").await?.is_some()
+            && sqlx::query("SELECT 1 FROM done_legacy LIMIT 1")
+                .fetch_optional(&self.client)
+                .await?
+                .is_none()
+        {
+            self.client.execute("DROP TABLE done_legacy").await?;
+
+            info!("dropped empty done_legacy table");
+        }
+
+        Ok(summary)
+    }
+
+    async fn delete_done_rows(
+        &self,
+        table: &str,
+        cutoff: OffsetDateTime,
+        now: OffsetDateTime,
+        retention: &DoneRetentionConfig,
+        summary: &mut PruneDoneSummary,
+    ) -> Result<(), sqlx::Error> {
+        let mut archive = None;
+        let mut total_deleted = 0;
+
+        loop {
+            let mut tx = self.client.begin().await?;
+
+            let records = sqlx::query(
+                format!(
+                    r#"
+                    DELETE FROM
+                      {table}
+                    WHERE
+                      (id, created_at) IN (
+                        SELECT
+                          id,
+                          created_at
+                        FROM
+                          {table}
+                        WHERE
+                          created_at < $1
+                        LIMIT $2)
+                    RETURNING
+                      id,
+                      parents,
+                      item,
+                      created_at
+                    "#
+                )
+                .as_str(),
+            )
+            .bind(cutoff)
+            .bind(retention.prune_batch_size)
+            .try_map(|row| DoneRecord::from_row(&row))
+            .fetch_all(tx.as_mut())
+            .await?;
+
+            let deleted = records.len() as u64;
+
+            if deleted == 0 {
+                break;
+            }
+
+            // only commit the deletion once the rows are written to the archive
+            if let Some(archive_dir) = &retention.archive_dir {
+                let archive = match &mut archive {
+                    Some(archive) => archive,
+                    None => archive.insert(
+                        Archive::create(archive_dir, &format!("{table}-{}", now.unix_timestamp()))
+                            .await?,
+                    ),
+                };
+
+                archive.write(records).await?;
+                archive.sync().await?;
+            }
+
+            tx.commit().await?;
+
+            trace!(%table, deleted, "deleted done rows");
+
+            DONE_PRUNED_ROW_COUNT.inc_by(deleted);
+            total_deleted += deleted;
+
+            if deleted < retention.prune_batch_size as u64 {
+                break;
+            }
+        }
+
+        if let Some(archive) = archive {
+            let (path, rows) = archive.finish().await?;
+
+            summary.archived_rows += rows;
+            summary.archive_files.push(path);
+        }
+
+        info!(%table, deleted = total_deleted, "pruned done rows");
+
+        summary.deleted_rows += total_deleted;
+
+        Ok(())
+    }
+
+    /// Create the daily partitions of `done` from the current day up to
+    /// [`DONE_PARTITIONS_AHEAD`] days ahead.
+    async fn create_done_partitions(&self) -> Result<(), sqlx::Error> {
+        let mut date = OffsetDateTime::now_utc().date();
+
+        for _ in 0..=DONE_PARTITIONS_AHEAD {
+            let Some(next) = date.next_day() else {
+                break;
+            };
+
+            let name = partition_name(date);
+
+            let res = self
+                .client
+                .execute(
+                    format!(
+                        "CREATE TABLE IF NOT EXISTS {name} PARTITION OF done \
+                        FOR VALUES FROM ('{date} 00:00:00+00') TO ('{next} 00:00:00+00')"
+                    )
+                    .as_str(),
+                )
+                .await;
+
+            // this can only fail if rows for this day were already inserted into the default
+            // partition, in which case they will be pruned from there instead
+            if let Err(error) = res {
+                warn!(%name, %error, "unable to create done partition");
+            }
+
+            date = next;
+        }
+
+        Ok(())
+    }
+
+    /// All daily partitions of `done`, along with the day they contain.
+    async fn done_partitions(&self) -> Result<Vec<(String, Date)>, sqlx::Error> {
+        Ok(sqlx::query(
+            r#"
+            SELECT
+              c.relname::text AS relname
+            FROM
+              pg_inherits i
+              JOIN pg_class c ON c.oid = i.inhrelid
+            WHERE
+              i.inhparent = to_regclass('done')
+            "#,
+        )
+        .try_map(|row| RelName::from_row(&row))
+        .fetch_all(&self.client)
+        .await?
+        .into_iter()
+        .filter_map(|RelName { relname }| {
+            let date = parse_partition_name(&relname)?;
+            Some((relname, date))
+        })
+        .collect())
+    }
+
+    async fn done_relkind(&self, table: &str) -> Result<Option<String>, sqlx::Error> {
+        Ok(sqlx::query(
+            r#"
+            SELECT
+              relkind::text AS relkind
+            FROM
+              pg_class
+            WHERE
+              oid = to_regclass($1)
+            "#,
+        )
+        .bind(table)
+        .try_map(|row| RelKind::from_row(&row))
+        .fetch_optional(&self.client)
+        .await?
+        .map(|r| r.relkind))
+    }
+}
+
+/// Write all rows of `table` to a new archive file. Returns `None` if the table is empty.
+async fn archive_table(
+    conn: &mut PgConnection,
+    table: &str,
+    archive_dir: &Path,
+    batch_size: i64,
+) -> Result<Option<(PathBuf, u64)>, sqlx::Error> {
+    let query = format!("SELECT id, parents, item, created_at FROM {table}");
+
+    let mut rows = sqlx::query(&query)
+        .try_map(|row| DoneRecord::from_row(&row))
+        .fetch(conn);
+
+    let mut archive = None;
+    let mut batch = vec![];
+
+    while let Some(record) = rows.try_next().await? {
+        batch.push(record);
+
+        if batch.len() as i64 >= batch_size {
+            let archive = match &mut archive {
+                Some(archive) => archive,
+                None => archive.insert(Archive::create(archive_dir, table).await?),
+            };
+
+            archive.write(std::mem::take(&mut batch)).await?;
+        }
+    }
+
+    if !batch.is_empty() {
+        let archive = match &mut archive {
+            Some(archive) => archive,
+            None => archive.insert(Archive::create(archive_dir, table).await?),
+        };
+
+        archive.write(batch).await?;
+    }
+
+    match archive {
+        Some(archive) => Ok(Some(archive.finish().await?)),
+        None => Ok(None),
+    }
+}
+
+fn partition_name(date: Date) -> String {
+    format!(
+        "done_p{:04}{:02}{:02}",
+        date.year(),
+        u8::from(date.month()),
+        date.day()
+    )
+}
+
+fn parse_partition_name(name: &str) -> Option<Date> {
+    let date = name.strip_prefix("done_p")?;
+
+    if date.len() != 8 || !date.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    Date::from_calendar_date(
+        date[0..4].parse().ok()?,
+        Month::try_from(date[4..6].parse::<u8>().ok()?).ok()?,
+        date[6..8].parse().ok()?,
+    )
+    .ok()
+}
+
+/// A gzip compressed JSONL file of pruned `done` rows. All file io is done on the blocking thread
+/// pool.
+///
+/// An archive that was synced but not finished (i.e. because voyager stopped while pruning) is
+/// missing the end of the gzip stream, but all rows up to the last sync can still be read from it
+/// (`zcat` prints them before reporting the unexpected end of the file).
+struct Archive {
+    path: PathBuf,
+    encoder: Option<GzEncoder<BufWriter<File>>>,
+    rows: u64,
+}
+
+impl Archive {
+    /// Creates `<name>.jsonl.gz` in `archive_dir`. Existing archives are never overwritten, a
+    /// numbered name (`<name>.<n>.jsonl.gz`) is used instead if the file already exists, i.e.
+    /// because a previous prune failed after writing the archive.
+    async fn create(archive_dir: &Path, name: &str) -> Result<Self, sqlx::Error> {
+        let archive_dir = archive_dir.to_owned();
+        let name = name.to_owned();
+
+        let (path, encoder) = spawn_blocking(move || {
+            std::fs::create_dir_all(&archive_dir)?;
+
+            let mut n = 0_u32;
+
+            loop {
+                let path = match n {
+                    0 => archive_dir.join(format!("{name}.jsonl.gz")),
+                    n => archive_dir.join(format!("{name}.{n}.jsonl.gz")),
+                };
+
+                match File::create_new(&path) {
+                    Ok(file) => {
+                        return Ok((
+                            path,
+                            GzEncoder::new(BufWriter::new(file), Compression::default()),
+                        ))
+                    }
+                    Err(error) if error.kind() == io::ErrorKind::AlreadyExists => n += 1,
+                    Err(error) => return Err(error),
+                }
+            }
+        })
+        .await?;
+
+        debug!(path = %path.display(), "created archive");
+
+        Ok(Self {
+            path,
+            encoder: Some(encoder),
+            rows: 0,
+        })
+    }
+
+    async fn write(&mut self, records: Vec<DoneRecord>) -> Result<(), sqlx::Error> {
+        let mut encoder = self.encoder.take().expect("archive is not finished; qed;");
+        let len = records.len() as u64;
+
+        let encoder = spawn_blocking(move || {
+            for record in records {
+                serde_json::to_writer(
+                    &mut encoder,
+                    &serde_json::json!({
+                        "id": record.id,
+                        "parents": record.parents,
+                        "item": record.item.0,
+                        "created_at": record.created_at.format(&Rfc3339).map_err(io::Error::other)?,
+                    }),
+                )?;
+                encoder.write_all(b"\n")?;
+            }
+
+            Ok(encoder)
+        })
+        .await?;
+
+        self.encoder = Some(encoder);
+        self.rows += len;
+
+        Ok(())
+    }
+
+    /// Flushes the rows written so far to disk, such that they are not lost if the archive is
+    /// never finished.
+    async fn sync(&mut self) -> Result<(), sqlx::Error> {
+        let mut encoder = self.encoder.take().expect("archive is not finished; qed;");
+
+        let encoder = spawn_blocking(move || {
+            encoder.flush()?;
+            encoder.get_ref().get_ref().sync_data()?;
+
+            Ok(encoder)
+        })
+        .await?;
+
+        self.encoder = Some(encoder);
+
+        Ok(())
+    }
+
+    async fn finish(mut self) -> Result<(PathBuf, u64), sqlx::Error> {
+        let encoder = self.encoder.take().expect("archive is not finished; qed;");
+
+        spawn_blocking(move || {
+            let file = encoder.finish()?.into_inner().map_err(|e| e.into_error())?;
+            file.sync_all()?;
+
+            Ok(())
+        })
+        .await?;
+
+        info!(path = %self.path.display(), rows = self.rows, "wrote archive");
+
+        Ok((self.path, self.rows))
+    }
+}
+
+async fn spawn_blocking<R: Send + 'static>(
+    f: impl FnOnce() -> io::Result<R> + Send + 'static,
+) -> Result<R, sqlx::Error> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(io::Error::other)?
+        .map_err(sqlx::Error::Io)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+
+    #[test]
+    fn partition_name_roundtrip() {
+        let date = Date::from_calendar_date(2025, Month::March, 7).unwrap();
+
+        assert_eq!(partition_name(date), "done_p20250307");
+        assert_eq!(parse_partition_name("done_p20250307"), Some(date));
+    }
+
+    #[test]
+    fn parse_partition_name_invalid() {
+        for name in [
+            "done",
+            "done_default",
+            "done_legacy",
+            "done_p2025037",
+            "done_p202503071",
+            "done_p2025-3-7",
+            "done_p20251307",
+            "done_p20250230",
+            "done_q20250307",
+        ] {
+            assert_eq!(parse_partition_name(name), None, "{name}");
+        }
+    }
+
+    #[tokio::test]
+    async fn archive_does_not_overwrite() {
+        let archive_dir = std::env::temp_dir().join(format!(
+            "pg-queue-archive-{}-{}",
+            std::process::id(),
+            OffsetDateTime::now_utc().unix_timestamp_nanos()
+        ));
+
+        let first = Archive::create(&archive_dir, "done_p20250307")
+            .await
+            .unwrap();
+        let second = Archive::create(&archive_dir, "done_p20250307")
+            .await
+            .unwrap();
+
+        assert_eq!(first.path, archive_dir.join("done_p20250307.jsonl.gz"));
+        assert_eq!(second.path, archive_dir.join("done_p20250307.1.jsonl.gz"));
+
+        first.finish().await.unwrap();
+        second.finish().await.unwrap();
+
+        std::fs::remove_dir_all(&archive_dir).unwrap();
+    }
+
+    fn record(id: i64) -> DoneRecord {
+        DoneRecord {
+            id,
+            parents: vec![],
+            item: Json(serde_json::json!({ "id": id })),
+            created_at: OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+
+    /// The ids of the rows in the archive, reading as much of it as possible.
+    fn archived_ids(path: &Path) -> Vec<i64> {
+        let mut content = vec![];
+        // unfinished archives are missing the end of the stream
+        let _ = GzDecoder::new(File::open(path).unwrap()).read_to_end(&mut content);
+
+        content
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                serde_json::from_slice::<serde_json::Value>(line).unwrap()["id"]
+                    .as_i64()
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn synced_archive_is_readable_before_finish() {
+        let archive_dir = std::env::temp_dir().join(format!(
+            "pg-queue-archive-{}-{}",
+            std::process::id(),
+            OffsetDateTime::now_utc().unix_timestamp_nanos()
+        ));
+
+        let mut archive = Archive::create(&archive_dir, "done").await.unwrap();
+        let path = archive.path.clone();
+
+        archive.write(vec![record(1), record(2)]).await.unwrap();
+        archive.sync().await.unwrap();
+        // the rows of a committed batch must be on disk, even if pruning stops afterwards
+        assert_eq!(archived_ids(&path), [1, 2]);
+
+        archive.write(vec![record(3)]).await.unwrap();
+        archive.sync().await.unwrap();
+        assert_eq!(archived_ids(&path), [1, 2, 3]);
+
+        archive.finish().await.unwrap();
+        let mut content = vec![];
+        GzDecoder::new(File::open(&path).unwrap())
+            .read_to_end(&mut content)
+            .unwrap();
+        assert_eq!(content.iter().filter(|b| **b == b'\n').count(), 3);
+
+        std::fs::remove_dir_all(&archive_dir).unwrap();
+    }
+}
@@ -2,5 +2,8 @@ pub mod bond_status;
 pub mod commission;
 pub mod commission_rates;
 pub mod description;
+pub mod msg_begin_redelegate;
+pub mod msg_delegate;
+pub mod msg_undelegate;
 pub mod query_validators_response;
 pub mod validator;
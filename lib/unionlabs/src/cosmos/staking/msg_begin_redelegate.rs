@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use unionlabs_primitives::Bytes;
+
+use crate::{bech32::Bech32, cosmos::base::coin::Coin};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MsgBeginRedelegate {
+    pub delegator_address: Bech32<Bytes>,
+    pub validator_src_address: Bech32<Bytes>,
+    pub validator_dst_address: Bech32<Bytes>,
+    pub amount: Coin,
+}
+
+#[cfg(feature = "proto")]
+pub mod proto {
+    use core::str::FromStr;
+
+    use unionlabs_primitives::Bytes;
+
+    use super::MsgBeginRedelegate;
+    use crate::{
+        bech32::Bech32, cosmos::base::coin, errors::MissingField, impl_proto_via_try_from_into,
+        required,
+    };
+
+    impl_proto_via_try_from_into!(MsgBeginRedelegate => protos::cosmos::staking::v1beta1::MsgBeginRedelegate);
+
+    impl From<MsgBeginRedelegate> for protos::cosmos::staking::v1beta1::MsgBeginRedelegate {
+        fn from(value: MsgBeginRedelegate) -> Self {
+            Self {
+                delegator_address: value.delegator_address.to_string(),
+                validator_src_address: value.validator_src_address.to_string(),
+                validator_dst_address: value.validator_dst_address.to_string(),
+                amount: Some(value.amount.into()),
+            }
+        }
+    }
+
+    impl TryFrom<protos::cosmos::staking::v1beta1::MsgBeginRedelegate> for MsgBeginRedelegate {
+        type Error = Error;
+
+        fn try_from(
+            value: protos::cosmos::staking::v1beta1::MsgBeginRedelegate,
+        ) -> Result<Self, Self::Error> {
+            Ok(Self {
+                delegator_address: value
+                    .delegator_address
+                    .parse()
+                    .map_err(Error::DelegatorAddress)?,
+                validator_src_address: value
+                    .validator_src_address
+                    .parse()
+                    .map_err(Error::ValidatorSrcAddress)?,
+                validator_dst_address: value
+                    .validator_dst_address
+                    .parse()
+                    .map_err(Error::ValidatorDstAddress)?,
+                amount: required!(value.amount)?.try_into()?,
+            })
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, thiserror::Error)]
+    pub enum Error {
+        #[error(transparent)]
+        MissingField(#[from] MissingField),
+        #[error("invalid delegator address")]
+        DelegatorAddress(#[source] <Bech32<Bytes> as FromStr>::Err),
+        #[error("invalid validator source address")]
+        ValidatorSrcAddress(#[source] <Bech32<Bytes> as FromStr>::Err),
+        #[error("invalid validator destination address")]
+        ValidatorDstAddress(#[source] <Bech32<Bytes> as FromStr>::Err),
+        #[error("invalid amount")]
+        Amount(#[from] coin::proto::Error),
+    }
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use unionlabs_primitives::Bytes;
+
+use crate::{bech32::Bech32, cosmos::base::coin::Coin};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MsgDelegate {
+    pub delegator_address: Bech32<Bytes>,
+    pub validator_address: Bech32<Bytes>,
+    pub amount: Coin,
+}
+
+#[cfg(feature = "proto")]
+pub mod proto {
+    use core::str::FromStr;
+
+    use unionlabs_primitives::Bytes;
+
+    use super::MsgDelegate;
+    use crate::{
+        bech32::Bech32, cosmos::base::coin, errors::MissingField, impl_proto_via_try_from_into,
+        required,
+    };
+
+    impl_proto_via_try_from_into!(MsgDelegate => protos::cosmos::staking::v1beta1::MsgDelegate);
+
+    impl From<MsgDelegate> for protos::cosmos::staking::v1beta1::MsgDelegate {
+        fn from(value: MsgDelegate) -> Self {
+            Self {
+                delegator_address: value.delegator_address.to_string(),
+                validator_address: value.validator_address.to_string(),
+                amount: Some(value.amount.into()),
+            }
+        }
+    }
+
+    impl TryFrom<protos::cosmos::staking::v1beta1::MsgDelegate> for MsgDelegate {
+        type Error = Error;
+
+        fn try_from(
+            value: protos::cosmos::staking::v1beta1::MsgDelegate,
+        ) -> Result<Self, Self::Error> {
+            Ok(Self {
+                delegator_address: value
+                    .delegator_address
+                    .parse()
+                    .map_err(Error::DelegatorAddress)?,
+                validator_address: value
+                    .validator_address
+                    .parse()
+                    .map_err(Error::ValidatorAddress)?,
+                amount: required!(value.amount)?.try_into()?,
+            })
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, thiserror::Error)]
+    pub enum Error {
+        #[error(transparent)]
+        MissingField(#[from] MissingField),
+        #[error("invalid delegator address")]
+        DelegatorAddress(#[source] <Bech32<Bytes> as FromStr>::Err),
+        #[error("invalid validator address")]
+        ValidatorAddress(#[source] <Bech32<Bytes> as FromStr>::Err),
+        #[error("invalid amount")]
+        Amount(#[from] coin::proto::Error),
+    }
+}
@@ -48,6 +48,22 @@ impl Call {
             this => Err(this),
         }
     }
+
+    /// The chain that is queried while processing this call, if any.
+    fn chain_id(&self) -> Option<&ChainId> {
+        match self {
+            Call::WaitForHeight(WaitForHeight { chain_id, .. })
+            | Call::WaitForTimestamp(WaitForTimestamp { chain_id, .. })
+            | Call::WaitForHeightRelative(WaitForHeightRelative { chain_id, .. })
+            | Call::WaitForTrustedHeight(WaitForTrustedHeight { chain_id, .. })
+            | Call::WaitForTrustedTimestamp(WaitForTrustedTimestamp { chain_id, .. })
+            | Call::WaitForClientUpdate(WaitForClientUpdate { chain_id, .. }) => Some(chain_id),
+            Call::FetchBlocks(_)
+            | Call::FetchUpdateHeaders(_)
+            | Call::SubmitTx(_)
+            | Call::Plugin(_) => None,
+        }
+    }
 }
 
 #[model]
@@ -185,6 +201,22 @@ impl CallT<VoyagerMessage> for Call {
         self,
         ctx: voyager_vm::Context<&Context>,
     ) -> Result<Op<VoyagerMessage>, QueueError> {
+        let limiter = match self.chain_id() {
+            Some(chain_id) => ctx
+                .rpc_server
+                .modules()
+                .map_err(error_object_to_queue_error)?
+                .chain_call_limiter(chain_id),
+            None => None,
+        };
+
+        // held until the call is processed. the requests made to the modules of the chain while
+        // processing it are only rate limited, since the modules can call back into voyager.
+        let _permit = match &limiter {
+            Some(limiter) => limiter.acquire().await,
+            None => None,
+        };
+
         match self {
             Call::FetchBlocks(FetchBlocks {
                 start_height,
@@ -1,6 +1,7 @@
 use std::{
     borrow::Cow,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    fmt,
     path::{Path, PathBuf},
     process::Stdio,
     sync::Arc,
@@ -8,22 +9,29 @@ use std::{
 };
 
 use anyhow::{anyhow, Context as _};
-use futures::{
-    future,
-    stream::{self, FuturesUnordered},
-    Future, FutureExt, StreamExt, TryStreamExt,
-};
+use futures::{stream::FuturesUnordered, Future, FutureExt, StreamExt};
 use indexmap::IndexMap;
-use itertools::Itertools;
 use jsonrpsee::{
-    core::{client::ClientT, RpcResult},
+    core::{
+        async_trait,
+        client::{BatchResponse, ClientT},
+        params::BatchRequestBuilder,
+        traits::ToRpcParams,
+        RpcResult,
+    },
+    rpc_params,
     server::middleware::rpc::RpcServiceT,
     types::{ErrorObject, ErrorObjectOwned},
+    ws_client::{CustomCertStore, HeaderMap, HeaderValue, PingConfig, WsClientBuilder},
 };
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{Map, Value};
-use tokio::time::sleep;
+use tokio::{
+    sync::{watch, Mutex},
+    task::JoinHandle,
+    time::sleep,
+};
 use tokio_util::sync::CancellationToken;
 use tracing::{
     debug, debug_span, error, info, info_span, instrument, instrument::Instrumented, trace, warn,
@@ -37,13 +45,15 @@ use voyager_vm::{ItemId, QueueError};
 
 use crate::{
     context::{equivalent_chain_ids::EquivalentChainIds, ibc_spec_handler::IbcSpecHandlers},
+    filter::JaqInterestFilter,
+    limits::{acquire_all, Limiter, Limits, LimitsConfig},
     module::{
         ClientBootstrapModuleInfo, ClientModuleInfo, ConsensusModuleInfo, PluginInfo,
         ProofModuleInfo, StateModuleInfo,
     },
     primitives::{ChainId, ClientType, IbcInterface},
     rpc::{server::Server, VoyagerRpcServer},
-    IdThreadClient, ParamsWithItemId, UNPROCESSABLE_JSONRPC_ERROR_CODE,
+    IdThreadClient, ParamsWithItemId, PLUGIN_INFO_METHOD, UNPROCESSABLE_JSONRPC_ERROR_CODE,
 };
 
 pub const INVALID_CONFIG_EXIT_CODE: u8 = 13;
@@ -56,7 +66,16 @@ pub mod ibc_spec_handler;
 pub struct Context {
     pub rpc_server: Server,
 
-    interest_filters: IndexMap<String, String>,
+    interest_filter: JaqInterestFilter,
+
+    /// Held for the whole duration of a reload, so that reloads don't run concurrently.
+    running: Mutex<Running>,
+
+    register_ibc_spec_handlers: fn(&mut IbcSpecHandlers),
+
+    ipc_client_request_timeout: Duration,
+
+    reloads: watch::Sender<()>,
 
     pub cancellation_token: CancellationToken,
 }
@@ -79,6 +98,9 @@ pub struct Modules {
 
     plugins: HashMap<String, ModuleRpcClient>,
 
+    /// map of chain id to the concurrency limiter of the calls dispatched for it.
+    chain_call_limiters: HashMap<ChainId, Arc<Limiter>>,
+
     equivalent_chain_ids: EquivalentChainIds,
 
     // ibc version id => handler
@@ -88,12 +110,15 @@ pub struct Modules {
 
 impl voyager_vm::ContextT for Context {}
 
+/// A client for a plugin or module. All requests made through this client are subject to the
+/// configured limits of the plugin or module and its chain.
 #[derive(macros::Debug, Clone)]
 pub struct ModuleRpcClient {
     #[debug(skip)]
     client: reconnecting_jsonrpc_ws_client::Client,
     #[allow(dead_code)]
     name: String,
+    limiters: Vec<Arc<Limiter>>,
 }
 
 impl ModuleRpcClient {
@@ -120,9 +145,59 @@ impl ModuleRpcClient {
         Self {
             client,
             name: name.to_owned(),
+            limiters: vec![],
         }
     }
 
+    fn remote(
+        name: &str,
+        remote: &RemoteConfig,
+        request_timeout: Duration,
+    ) -> anyhow::Result<Self> {
+        let mut headers = HeaderMap::new();
+        if let Some(auth_token) = &remote.auth_token {
+            headers.insert(
+                "authorization",
+                HeaderValue::from_str(&format!("Bearer {auth_token}"))
+                    .context("invalid auth token")?,
+            );
+        }
+
+        let cert_store = remote.tls.as_ref().map(TlsConfig::load).transpose()?;
+
+        let client = reconnecting_jsonrpc_ws_client::Client::new({
+            let url = remote.url.clone();
+            let name = name.to_owned();
+            move || {
+                let mut builder = WsClientBuilder::default()
+                    .request_timeout(request_timeout)
+                    .set_headers(headers.clone())
+                    .enable_ws_ping(PingConfig::new());
+
+                if let Some(cert_store) = &cert_store {
+                    builder = builder.with_custom_cert_store(cert_store.clone());
+                }
+
+                let url = url.clone();
+                async move {
+                    trace!("connecting to remote at {url}");
+                    builder.build(url).await
+                }
+                .instrument(debug_span!("module_ws_client", %name))
+            }
+        });
+
+        Ok(Self {
+            client,
+            name: name.to_owned(),
+            limiters: vec![],
+        })
+    }
+
+    fn with_limiters(self, limiters: Vec<Arc<Limiter>>) -> Self {
+        Self { limiters, ..self }
+    }
+
     fn make_socket_path(name: &str) -> String {
         let pid = std::process::id();
 
@@ -139,11 +214,90 @@ impl ModuleRpcClient {
         )
     }
 
+    /// The underlying client, which is not subject to any limits.
     pub fn client(&self) -> &reconnecting_jsonrpc_ws_client::Client {
         &self.client
     }
 }
 
+#[async_trait]
+impl ClientT for ModuleRpcClient {
+    async fn notification<Params>(
+        &self,
+        method: &str,
+        params: Params,
+    ) -> Result<(), jsonrpsee::core::client::Error>
+    where
+        Params: ToRpcParams + Send,
+    {
+        let _permits = acquire_all(&self.limiters).await;
+
+        self.client.notification(method, params).await
+    }
+
+    async fn request<R, Params>(
+        &self,
+        method: &str,
+        params: Params,
+    ) -> Result<R, jsonrpsee::core::client::Error>
+    where
+        R: DeserializeOwned,
+        Params: ToRpcParams + Send,
+    {
+        let _permits = acquire_all(&self.limiters).await;
+
+        self.client.request(method, params).await
+    }
+
+    async fn batch_request<'a, R>(
+        &self,
+        batch: BatchRequestBuilder<'a>,
+    ) -> Result<BatchResponse<'a, R>, jsonrpsee::core::client::Error>
+    where
+        R: DeserializeOwned + fmt::Debug + 'a,
+    {
+        let _permits = acquire_all(&self.limiters).await;
+
+        self.client.batch_request(batch).await
+    }
+}
+
+#[async_trait]
+impl ClientT for &ModuleRpcClient {
+    async fn notification<Params>(
+        &self,
+        method: &str,
+        params: Params,
+    ) -> Result<(), jsonrpsee::core::client::Error>
+    where
+        Params: ToRpcParams + Send,
+    {
+        <ModuleRpcClient as ClientT>::notification(*self, method, params).await
+    }
+
+    async fn request<R, Params>(
+        &self,
+        method: &str,
+        params: Params,
+    ) -> Result<R, jsonrpsee::core::client::Error>
+    where
+        R: DeserializeOwned,
+        Params: ToRpcParams + Send,
+    {
+        <ModuleRpcClient as ClientT>::request(*self, method, params).await
+    }
+
+    async fn batch_request<'a, R>(
+        &self,
+        batch: BatchRequestBuilder<'a>,
+    ) -> Result<BatchResponse<'a, R>, jsonrpsee::core::client::Error>
+    where
+        R: DeserializeOwned + fmt::Debug + 'a,
+    {
+        <ModuleRpcClient as ClientT>::batch_request(*self, batch).await
+    }
+}
+
 pub(crate) trait WithId: Sized + ClientT + Send + Sync
 where
     for<'a> &'a Self: ClientT,
@@ -182,6 +336,12 @@ pub struct ExtractItemId<S> {
     service: S,
 }
 
+impl<S> ExtractItemId<S> {
+    pub fn new(service: S) -> Self {
+        Self { service }
+    }
+}
+
 impl<'a, S: RpcServiceT<'a>> RpcServiceT<'a> for ExtractItemId<S> {
     type Future = futures::future::Either<Instrumented<S::Future>, S::Future>;
 
@@ -231,12 +391,25 @@ fn make_module_rpc_server_socket_path(name: &str) -> String {
 #[derive(Debug, Clone, PartialEq, Hash, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct PluginConfig {
-    pub path: PathBuf,
+    /// Path to the plugin binary, spawned as a child process of voyager. Exactly one of `path` and
+    /// `remote` must be set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// A plugin served by another host, see the `serve` command of the plugin binary. `config` is
+    /// then only used to identify the plugin, the remote is started with its own config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<RemoteConfig>,
     pub config: Value,
     #[serde(default = "default_enabled")]
     pub enabled: bool,
 }
 
+impl PluginConfig {
+    pub fn transport(&self) -> anyhow::Result<Transport<'_>> {
+        Transport::new(self.path.as_deref(), self.remote.as_ref())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct ModulesConfig {
@@ -250,7 +423,13 @@ pub struct ModulesConfig {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct ModuleConfig<T> {
-    pub path: PathBuf,
+    /// Path to the module binary, spawned as a child process of voyager. Exactly one of `path` and
+    /// `remote` must be set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// A module served by another host, see the `serve` command of the module binary.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<RemoteConfig>,
     pub info: T,
     #[serde(default = "default_config")]
     pub config: Value,
@@ -258,6 +437,104 @@ pub struct ModuleConfig<T> {
     pub enabled: bool,
 }
 
+impl<T> ModuleConfig<T> {
+    pub fn transport(&self) -> anyhow::Result<Transport<'_>> {
+        Transport::new(self.path.as_deref(), self.remote.as_ref())
+    }
+}
+
+/// Connection to a plugin or module running outside of voyager, over a websocket.
+///
+/// Remote plugins and modules call back into voyager through the voyager rpc server (see
+/// `voyager.rpc_laddr`). A remote calls back into the single voyager instance passed as its
+/// `--voyager-url`, so it must only be configured in that instance. Serve one remote per voyager
+/// instance.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteConfig {
+    /// The `ws://` or `wss://` url of the remote.
+    pub url: String,
+    /// Sent as a bearer token in the `Authorization` header, must match the `--auth-token` of the
+    /// remote.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_token: Option<String>,
+    /// Custom TLS settings for `wss://` urls. The platform certificate roots are used if unset.
+    ///
+    /// The `serve` command only speaks plain `ws://`, so `wss://` requires a proxy in front of the
+    /// remote that terminates TLS (and checks the client certificate for mutual TLS).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM encoded certificates of the authorities trusted to sign the certificate of the remote.
+    pub ca_cert: PathBuf,
+    /// PEM encoded certificate chain presented to the remote, for mutual TLS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<PathBuf>,
+    /// PEM encoded private key of `client_cert`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<PathBuf>,
+}
+
+impl TlsConfig {
+    fn load(&self) -> anyhow::Result<CustomCertStore> {
+        let read = |path: &Path| {
+            std::fs::read(path).with_context(|| format!("reading {}", path.display()))
+        };
+
+        let mut roots = rustls::RootCertStore::empty();
+        for cert in rustls_pemfile::certs(&mut &*read(&self.ca_cert)?) {
+            roots.add(cert?)?;
+        }
+
+        let builder = rustls::ClientConfig::builder().with_root_certificates(roots);
+
+        Ok(match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => builder.with_client_auth_cert(
+                rustls_pemfile::certs(&mut &*read(cert)?).collect::<Result<_, _>>()?,
+                rustls_pemfile::private_key(&mut &*read(key)?)?
+                    .ok_or_else(|| anyhow!("no private key found in {}", key.display()))?,
+            )?,
+            (None, None) => builder.with_no_client_auth(),
+            _ => {
+                return Err(anyhow!(
+                    "both client_cert and client_key must be set for mutual TLS"
+                ))
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Transport<'a> {
+    /// A binary spawned and supervised by voyager, connected to over IPC.
+    Process(&'a Path),
+    Remote(&'a RemoteConfig),
+}
+
+impl<'a> Transport<'a> {
+    fn new(path: Option<&'a Path>, remote: Option<&'a RemoteConfig>) -> anyhow::Result<Self> {
+        match (path, remote) {
+            (Some(path), None) => Ok(Self::Process(path)),
+            (None, Some(remote)) => Ok(Self::Remote(remote)),
+            (Some(_), Some(_)) => Err(anyhow!("only one of `path` and `remote` can be set")),
+            (None, None) => Err(anyhow!("one of `path` and `remote` must be set")),
+        }
+    }
+}
+
+impl fmt::Display for Transport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Process(path) => write!(f, "{}", path.display()),
+            Self::Remote(remote) => write!(f, "{}", remote.url),
+        }
+    }
+}
+
 fn default_config() -> Value {
     Value::Object(Map::new())
 }
@@ -275,14 +552,229 @@ impl Context {
         register_ibc_spec_handlers: fn(&mut IbcSpecHandlers),
         ipc_client_request_timeout: Duration,
         cache_config: crate::rpc::server::cache::Config,
+        limits_config: LimitsConfig,
     ) -> anyhow::Result<Self> {
-        let cancellation_token = CancellationToken::new();
+        let ctx = Self {
+            rpc_server: Server::new(cache_config),
+            interest_filter: JaqInterestFilter::new(vec![])?,
+            running: Default::default(),
+            register_ibc_spec_handlers,
+            ipc_client_request_timeout,
+            reloads: watch::channel(()).0,
+            cancellation_token: CancellationToken::new(),
+        };
+
+        ctx.reload(
+            plugin_configs,
+            module_configs,
+            equivalent_chain_ids,
+            limits_config,
+        )
+        .await?;
 
+        info!("started");
+
+        Ok(ctx)
+    }
+
+    /// Loads the given configuration, restarting only the plugins and modules whose config
+    /// changed, starting the new ones and stopping the ones that are no longer configured. The
+    /// limiters whose limits are unchanged are kept.
+    ///
+    /// If the configuration is invalid, nothing is changed. The interest filter and the modules
+    /// used by the rpc server are swapped atomically once the new plugins and modules are
+    /// running, requests that are already being processed keep using the previous ones.
+    #[instrument(name = "context_reload", skip_all)]
+    pub async fn reload(
+        &self,
+        plugin_configs: Vec<PluginConfig>,
+        module_configs: ModulesConfig,
+        equivalent_chain_ids: EquivalentChainIds,
+        limits_config: LimitsConfig,
+    ) -> anyhow::Result<ReloadSummary> {
+        let mut running = self.running.lock().await;
+
+        let mut plan = ReloadPlan::default();
+
+        let limits = Limits::new(limits_config, &running.limits);
+
+        let (modules, interest_filters, interest_filter) = match self
+            .plan(
+                &running,
+                &mut plan,
+                &limits,
+                plugin_configs,
+                module_configs,
+                equivalent_chain_ids,
+            )
+            .await
+        {
+            Ok(planned) => planned,
+            Err(err) => {
+                plan.abort();
+                return Err(err);
+            }
+        };
+
+        // remote plugins and modules call back into voyager through the voyager rpc server
+        let ipc_servers = plan
+            .start
+            .iter()
+            .filter(|(name, pending)| {
+                pending.process.is_some() && !running.ipc_servers.contains(*name)
+            })
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+
+        for name in ipc_servers {
+            debug!("starting rpc server for {name}");
+
+            match module_rpc_server(&name, self.rpc_server.clone()).await {
+                Ok(server) => {
+                    tokio::spawn(server);
+                    running.ipc_servers.insert(name);
+                }
+                Err(err) => {
+                    plan.abort();
+                    return Err(err);
+                }
+            }
+        }
+
+        // the new configuration is valid, start applying it
+
+        let mut previous = std::mem::take(&mut running.modules);
+
+        let mut summary = ReloadSummary::default();
+
+        for name in plan.unchanged {
+            let module = previous
+                .remove(&name)
+                .expect("unchanged plugins and modules are running; qed;");
+
+            running.modules.insert(name.clone(), module);
+            summary.unchanged.push(name);
+        }
+
+        // restarted processes bind the same sockets, so the previous ones must exit first
+        for (name, pending) in &plan.start {
+            match previous.remove(name) {
+                Some(module) => {
+                    info!("restarting {name}");
+
+                    module.stop(!pending.reuses_client).await;
+                    summary.restarted.push(name.clone());
+                }
+                None => {
+                    info!("starting {name}");
+
+                    summary.started.push(name.clone());
+                }
+            }
+        }
+
+        let mut started = vec![];
+
+        for (name, pending) in plan.start {
+            let cancellation_token = self.cancellation_token.child_token();
+
+            let process = pending.process.map(|process| {
+                tokio::spawn(process.spawn(
+                    name.clone(),
+                    cancellation_token.clone(),
+                    self.cancellation_token.clone(),
+                ))
+            });
+
+            started.push((name.clone(), pending.client.clone()));
+
+            running.modules.insert(
+                name,
+                RunningModule {
+                    config: pending.config,
+                    client: pending.client,
+                    cancellation_token,
+                    process,
+                },
+            );
+        }
+
+        info!("checking for plugin and module health...");
+
+        let futures = started
+            .iter()
+            .map(|(name, client)| async move {
+                match client
+                    .client
+                    .wait_until_connected(Duration::from_secs(10))
+                    .instrument(debug_span!("health check", %name))
+                    .await
+                {
+                    Ok(()) => {
+                        info!("{name} connected")
+                    }
+                    Err(_) => {
+                        warn!("{name} failed to connect after 10 seconds")
+                    }
+                }
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        match self
+            .cancellation_token
+            .run_until_cancelled(futures.collect::<Vec<_>>())
+            .await
+        {
+            Some(_) => {}
+            None => return Err(anyhow!("startup error")),
+        }
+
+        self.rpc_server.set_modules(Arc::new(modules));
+        self.interest_filter.replace(interest_filter);
+        running.interest_filters = interest_filters;
+        running.limits = limits;
+
+        for (name, module) in previous {
+            info!("stopping {name}");
+
+            module.stop(true).await;
+            summary.stopped.push(name);
+        }
+
+        self.reloads.send_replace(());
+
+        summary.started.sort_unstable();
+        summary.restarted.sort_unstable();
+        summary.stopped.sort_unstable();
+        summary.unchanged.sort_unstable();
+
+        info!(
+            started = ?summary.started,
+            restarted = ?summary.restarted,
+            stopped = ?summary.stopped,
+            unchanged = summary.unchanged.len(),
+            "reloaded"
+        );
+
+        Ok(summary)
+    }
+
+    /// Builds the modules and interest filters for the given configuration, without starting
+    /// anything.
+    async fn plan(
+        &self,
+        running: &Running,
+        plan: &mut ReloadPlan,
+        limits: &Limits,
+        plugin_configs: Vec<PluginConfig>,
+        module_configs: ModulesConfig,
+        equivalent_chain_ids: EquivalentChainIds,
+    ) -> anyhow::Result<(Modules, IndexMap<String, String>, JaqInterestFilter)> {
         let mut ibc_spec_handlers = IbcSpecHandlers {
             handlers: Default::default(),
         };
 
-        register_ibc_spec_handlers(&mut ibc_spec_handlers);
+        (self.register_ibc_spec_handlers)(&mut ibc_spec_handlers);
 
         let mut modules = Modules {
             state_modules: Default::default(),
@@ -293,81 +785,66 @@ impl Context {
             chain_consensus_types: Default::default(),
             client_consensus_types: Default::default(),
             plugins: Default::default(),
+            chain_call_limiters: limits.chain_call_limiters(),
             equivalent_chain_ids,
             ibc_spec_handlers,
         };
 
-        let mut interest_filters = HashMap::new();
-
-        let main_rpc_server = Server::new(cache_config);
+        let mut interest_filters = IndexMap::new();
 
-        info!("spawning {} plugins", plugin_configs.len());
+        info!("loading {} plugins", plugin_configs.len());
 
-        stream::iter(plugin_configs.into_iter().enumerate())
-            .filter(|(_, plugin_config)| {
-                future::ready(if !plugin_config.enabled {
-                    info!(
-                        plugin_path = %plugin_config.path.to_string_lossy(),
-                        "plugin is not enabled, skipping"
-                    );
-                    false
-                } else {
-                    true
-                })
-            })
-            .zip(stream::repeat(main_rpc_server.clone()))
-            .then(async |((idx, plugin_config), server)| {
-                let plugin_info = info_span!("get_plugin_info", %idx)
-                    .in_scope(|| get_plugin_info(&plugin_config))?;
-
-                debug!("starting rpc server for plugin {}", plugin_info.name);
-                tokio::spawn(module_rpc_server(&plugin_info.name, server).await?);
+        for (idx, plugin_config) in plugin_configs.into_iter().enumerate() {
+            if !plugin_config.enabled {
+                info!(
+                    plugin = %plugin_config
+                        .transport()
+                        .map(|transport| transport.to_string())
+                        .unwrap_or_default(),
+                    "plugin is not enabled, skipping"
+                );
+                continue;
+            }
 
-                Ok((idx, plugin_config, plugin_info))
-            })
-            .try_for_each_concurrent(
-                None,
-                |(
-                    idx,
-                    plugin_config,
-                    PluginInfo {
-                        name,
-                        interest_filter,
+            let PluginInfo {
+                name,
+                interest_filter,
+            } = get_plugin_info(&plugin_config)
+                .instrument(info_span!("get_plugin_info", %idx))
+                .await?;
+
+            debug!("registering plugin {}", name);
+
+            let rpc_client = plan
+                .register(
+                    running,
+                    &name,
+                    serde_json::to_value(&plugin_config)
+                        .expect("serialization is infallible; qed;"),
+                    plugin_config.transport()?,
+                    |path| ChildProcess::Plugin {
+                        path,
+                        config: plugin_config.config.clone(),
                     },
-                )| {
-                    debug!("registering plugin {}", name);
-
-                    tokio::spawn(plugin_child_process(
-                        name.clone(),
-                        plugin_config.clone(),
-                        cancellation_token.clone(),
-                    ));
+                    self.ipc_client_request_timeout,
+                )?
+                .with_limiters(limits.limiters(&name, None));
 
-                    let rpc_client = ModuleRpcClient::new(&name, ipc_client_request_timeout);
+            modules.plugins.insert(name.clone(), rpc_client);
 
-                    let prev = modules.plugins.insert(name.clone(), rpc_client.clone());
+            info!("registered plugin {name}");
 
-                    if prev.is_some() {
-                        return future::ready(Err(anyhow!(
-                            "multiple plugins configured with name `{name}`"
-                        )));
-                    }
-
-                    info!("registered plugin {name}");
-
-                    interest_filters.insert((idx, name), interest_filter);
-
-                    future::ready(Ok(()))
-                },
-            )
-            .await?;
+            interest_filters.insert(name, interest_filter);
+        }
 
-        module_startup(
+        plan_modules(
             module_configs.state,
-            cancellation_token.clone(),
-            main_rpc_server.clone(),
-            ipc_client_request_timeout,
+            running,
+            plan,
+            self.ipc_client_request_timeout,
+            limits,
             |info| info.id(),
+            |info| Some(&info.chain_id),
             |StateModuleInfo {
                  chain_id,
                  ibc_spec_id,
@@ -393,15 +870,16 @@ impl Context {
 
                 Ok(())
             },
-        )
-        .await?;
+        )?;
 
-        module_startup(
+        plan_modules(
             module_configs.proof,
-            cancellation_token.clone(),
-            main_rpc_server.clone(),
-            ipc_client_request_timeout,
+            running,
+            plan,
+            self.ipc_client_request_timeout,
+            limits,
             |info| info.id(),
+            |info| Some(&info.chain_id),
             |ProofModuleInfo {
                  chain_id,
                  ibc_spec_id,
@@ -427,15 +905,16 @@ impl Context {
 
                 Ok(())
             },
-        )
-        .await?;
+        )?;
 
-        module_startup(
+        plan_modules(
             module_configs.consensus,
-            cancellation_token.clone(),
-            main_rpc_server.clone(),
-            ipc_client_request_timeout,
+            running,
+            plan,
+            self.ipc_client_request_timeout,
+            limits,
             |info| info.id(),
+            |info| Some(&info.chain_id),
             |ConsensusModuleInfo {
                  chain_id,
                  consensus_type,
@@ -467,15 +946,16 @@ impl Context {
 
                 Ok(())
             },
-        )
-        .await?;
+        )?;
 
-        module_startup(
+        plan_modules(
             module_configs.client,
-            cancellation_token.clone(),
-            main_rpc_server.clone(),
-            ipc_client_request_timeout,
+            running,
+            plan,
+            self.ipc_client_request_timeout,
+            limits,
             |info| info.id(),
+            |_| None,
             |ClientModuleInfo {
                  client_type,
                  consensus_type,
@@ -522,15 +1002,16 @@ impl Context {
 
                 Ok(())
             },
-        )
-        .await?;
+        )?;
 
-        module_startup(
+        plan_modules(
             module_configs.client_bootstrap,
-            cancellation_token.clone(),
-            main_rpc_server.clone(),
-            ipc_client_request_timeout,
+            running,
+            plan,
+            self.ipc_client_request_timeout,
+            limits,
             |info| info.id(),
+            |info| Some(&info.chain_id),
             |ClientBootstrapModuleInfo {
                  client_type,
                  chain_id,
@@ -572,86 +1053,227 @@ impl Context {
 
                 Ok(())
             },
-        )
-        .await?;
-
-        info!("checking for plugin health...");
+        )?;
 
-        let futures = modules
-            .plugins
-            .iter()
-            .map(|(name, client)| async move {
-                match client
-                    .client
-                    .wait_until_connected(Duration::from_secs(10))
-                    .instrument(debug_span!("health check", %name))
-                    .await
-                {
-                    Ok(()) => {
-                        info!("plugin {name} connected")
-                    }
-                    Err(_) => {
-                        warn!("plugin {name} failed to connect after 10 seconds")
-                    }
-                }
-            })
-            .collect::<FuturesUnordered<_>>();
-
-        match cancellation_token
-            .run_until_cancelled(futures.collect::<Vec<_>>())
-            .await
-        {
-            Some(_) => {}
-            None => return Err(anyhow!("startup error")),
-        }
-
-        main_rpc_server.start(Arc::new(modules));
-
-        info!("started");
-
-        Ok(Self {
-            rpc_server: main_rpc_server,
-            interest_filters: interest_filters
-                .into_iter()
-                .sorted_unstable_by(|((a, _), _), ((b, _), _)| a.cmp(b))
-                .map(|((_, k), v)| (k, v))
+        let interest_filter = JaqInterestFilter::new(
+            interest_filters
+                .iter()
+                .map(|(name, interest_filter)| PluginInfo {
+                    name: name.clone(),
+                    interest_filter: interest_filter.clone(),
+                })
                 .collect(),
-            cancellation_token,
-        })
+        )?;
+
+        Ok((modules, interest_filters, interest_filter))
     }
 
     pub async fn shutdown(self) {
         self.cancellation_token.cancel();
     }
 
-    pub fn plugin(
-        &self,
-        name: impl AsRef<str>,
-    ) -> RpcResult<&reconnecting_jsonrpc_ws_client::Client> {
-        Ok(self
-            .rpc_server
-            .modules()?
-            .plugins
-            .get(name.as_ref())
-            .ok_or_else(|| PluginNotFound {
-                name: name.as_ref().into(),
-            })?
-            .client())
+    pub fn plugin(&self, name: impl AsRef<str>) -> RpcResult<ModuleRpcClient> {
+        self.plugin_client_raw(name)
     }
 
-    pub fn plugin_client_raw(&self, name: impl AsRef<str>) -> RpcResult<&ModuleRpcClient> {
+    pub fn plugin_client_raw(&self, name: impl AsRef<str>) -> RpcResult<ModuleRpcClient> {
         self.rpc_server
             .modules()?
             .plugins
             .get(name.as_ref())
+            .cloned()
             .ok_or_else(|| PluginNotFound {
                 name: name.as_ref().into(),
             })
             .map_err(Into::into)
     }
 
-    pub fn interest_filters(&self) -> &IndexMap<String, String> {
-        &self.interest_filters
+    /// The interest filter of all the loaded plugins, updated on every reload.
+    pub fn interest_filter(&self) -> &JaqInterestFilter {
+        &self.interest_filter
+    }
+
+    /// The loaded plugins and their interest filters, in the order they are configured in, along
+    /// with a token that is cancelled once the plugin is stopped or restarted.
+    pub async fn plugins(&self) -> Vec<(String, String, CancellationToken)> {
+        let running = self.running.lock().await;
+
+        running
+            .interest_filters
+            .iter()
+            .filter_map(|(name, interest_filter)| {
+                Some((
+                    name.clone(),
+                    interest_filter.clone(),
+                    running.modules.get(name)?.cancellation_token.clone(),
+                ))
+            })
+            .collect()
+    }
+
+    /// Notified every time the configuration is reloaded.
+    pub fn subscribe_reloads(&self) -> watch::Receiver<()> {
+        self.reloads.subscribe()
+    }
+}
+
+/// The plugins and modules that are started, restarted, stopped and kept by a reload.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ReloadSummary {
+    pub started: Vec<String>,
+    pub restarted: Vec<String>,
+    pub stopped: Vec<String>,
+    pub unchanged: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct Running {
+    /// Plugins by name and modules by id.
+    modules: HashMap<String, RunningModule>,
+    interest_filters: IndexMap<String, String>,
+    limits: Limits,
+    /// Names for which an rpc server has been started for the child processes to call back into
+    /// voyager. These are never stopped, since they only serve the voyager rpc server.
+    ipc_servers: HashSet<String>,
+}
+
+#[derive(Debug)]
+struct RunningModule {
+    /// The config the plugin or module was started with, to detect changes on reload.
+    config: Value,
+    client: ModuleRpcClient,
+    cancellation_token: CancellationToken,
+    /// The supervisor of the child process, if the plugin or module is not remote.
+    process: Option<JoinHandle<()>>,
+}
+
+impl RunningModule {
+    async fn stop(self, shutdown_client: bool) {
+        self.cancellation_token.cancel();
+
+        if let Some(process) = self.process {
+            if let Err(err) = process.await {
+                error!(err = %ErrorReporter(err), "child process supervisor panicked");
+            }
+        }
+
+        if shutdown_client {
+            self.client.client.shutdown();
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ReloadPlan {
+    /// Plugins and modules whose config is unchanged, which are kept running.
+    unchanged: HashSet<String>,
+    /// Plugins and modules to start, replacing the running ones with the same name.
+    start: HashMap<String, PendingModule>,
+}
+
+#[derive(Debug)]
+struct PendingModule {
+    config: Value,
+    client: ModuleRpcClient,
+    /// Whether the client of the running child process with the same name is reused. It
+    /// reconnects to the new process, since the socket only depends on the name.
+    reuses_client: bool,
+    process: Option<ChildProcess>,
+}
+
+impl ReloadPlan {
+    /// Returns the client for the plugin or module `name`, reusing the running one if `config`
+    /// is unchanged.
+    fn register(
+        &mut self,
+        running: &Running,
+        name: &str,
+        config: Value,
+        transport: Transport<'_>,
+        child_process: impl FnOnce(PathBuf) -> ChildProcess,
+        request_timeout: Duration,
+    ) -> anyhow::Result<ModuleRpcClient> {
+        if self.unchanged.contains(name) || self.start.contains_key(name) {
+            return Err(anyhow!(
+                "multiple plugins or modules configured with name `{name}`"
+            ));
+        }
+
+        let current = running.modules.get(name);
+
+        if let Some(current) = current.filter(|current| current.config == config) {
+            self.unchanged.insert(name.to_owned());
+            return Ok(current.client.clone());
+        }
+
+        let (client, reuses_client, process) = match transport {
+            Transport::Process(path) => {
+                let process = Some(child_process(path.to_owned()));
+
+                match current.filter(|current| current.process.is_some()) {
+                    Some(current) => (current.client.clone(), true, process),
+                    None => (ModuleRpcClient::new(name, request_timeout), false, process),
+                }
+            }
+            Transport::Remote(remote) => (
+                ModuleRpcClient::remote(name, remote, request_timeout)?,
+                false,
+                None,
+            ),
+        };
+
+        self.start.insert(
+            name.to_owned(),
+            PendingModule {
+                config,
+                client: client.clone(),
+                reuses_client,
+                process,
+            },
+        );
+
+        Ok(client)
+    }
+
+    /// Shuts down the clients created for this plan, if it is not applied.
+    fn abort(self) {
+        for pending in self.start.into_values() {
+            if !pending.reuses_client {
+                pending.client.client.shutdown();
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum ChildProcess {
+    Plugin {
+        path: PathBuf,
+        config: Value,
+    },
+    Module {
+        path: PathBuf,
+        config: Value,
+        info: Value,
+    },
+}
+
+impl ChildProcess {
+    async fn spawn(
+        self,
+        name: String,
+        cancellation_token: CancellationToken,
+        shutdown_token: CancellationToken,
+    ) {
+        match self {
+            ChildProcess::Plugin { path, config } => {
+                plugin_child_process(name, path, config, cancellation_token, shutdown_token).await
+            }
+            ChildProcess::Module { path, config, info } => {
+                module_child_process(name, path, config, info, cancellation_token, shutdown_token)
+                    .await
+            }
+        }
     }
 }
 
@@ -718,17 +1340,16 @@ impl Modules {
         }
     }
 
-    pub fn plugin<'a>(
-        &'a self,
-        name: &str,
-    ) -> Result<&'a reconnecting_jsonrpc_ws_client::Client, PluginNotFound> {
-        Ok(self
-            .plugins
-            .get(name)
-            .ok_or_else(|| PluginNotFound {
-                name: name.to_owned(),
-            })?
-            .client())
+    pub fn plugin<'a>(&'a self, name: &str) -> Result<&'a ModuleRpcClient, PluginNotFound> {
+        self.plugins.get(name).ok_or_else(|| PluginNotFound {
+            name: name.to_owned(),
+        })
+    }
+
+    /// The concurrency limiter of the calls dispatched for `chain_id`, if it is configured. This
+    /// is not applied to the requests made to the modules of the chain while handling the call.
+    pub(crate) fn chain_call_limiter(&self, chain_id: &ChainId) -> Option<Arc<Limiter>> {
+        self.chain_call_limiters.get(chain_id).cloned()
     }
 
     pub fn equivalent_chain_ids(&self) -> &EquivalentChainIds {
@@ -760,15 +1381,13 @@ impl Modules {
         chain_id: &ChainId,
         ibc_spec_id: &IbcSpecId,
         // ) -> Result<&'a (impl RawStateModuleClient + 'a), StateModuleNotFound> {
-    ) -> Result<&'a reconnecting_jsonrpc_ws_client::Client, StateModuleNotFound> {
-        Ok(self
-            .state_modules
+    ) -> Result<&'a ModuleRpcClient, StateModuleNotFound> {
+        self.state_modules
             .get(&(chain_id.clone(), ibc_spec_id.clone()))
             .ok_or_else(|| StateModuleNotFound {
                 chain_id: chain_id.clone(),
                 ibc_spec_id: ibc_spec_id.clone(),
-            })?
-            .client())
+            })
     }
 
     pub fn proof_module<'a, 'b, 'c: 'a>(
@@ -776,27 +1395,23 @@ impl Modules {
         chain_id: &ChainId,
         ibc_spec_id: &IbcSpecId,
         // ) -> Result<&'a (impl RawProofModuleClient + 'a), ProofModuleNotFound> {
-    ) -> Result<&'a reconnecting_jsonrpc_ws_client::Client, ProofModuleNotFound> {
-        Ok(self
-            .proof_modules
+    ) -> Result<&'a ModuleRpcClient, ProofModuleNotFound> {
+        self.proof_modules
             .get(&(chain_id.clone(), ibc_spec_id.clone()))
             .ok_or_else(|| ProofModuleNotFound {
                 chain_id: chain_id.clone(),
                 ibc_spec_id: ibc_spec_id.clone(),
-            })?
-            .client())
+            })
     }
 
     pub fn consensus_module<'a, 'b, 'c: 'a>(
         &'a self,
         chain_id: &ChainId,
         // ) -> Result<&'a (impl jsonrpsee::core::client::ClientT + 'a), ConsensusModuleNotFound> {
-    ) -> Result<&'a reconnecting_jsonrpc_ws_client::Client, ConsensusModuleNotFound> {
-        Ok(self
-            .consensus_modules
+    ) -> Result<&'a ModuleRpcClient, ConsensusModuleNotFound> {
+        self.consensus_modules
             .get(chain_id)
-            .ok_or_else(|| ConsensusModuleNotFound(chain_id.clone()))?
-            .client())
+            .ok_or_else(|| ConsensusModuleNotFound(chain_id.clone()))
     }
 
     pub fn client_module<'a, 'b, 'c: 'a>(
@@ -805,13 +1420,13 @@ impl Modules {
         ibc_interface: &IbcInterface,
         ibc_spec_id: &IbcSpecId,
         // ) -> Result<&'a (impl ClientModuleClient + 'a), ClientModuleNotFound> {
-    ) -> Result<&'a reconnecting_jsonrpc_ws_client::Client, ClientModuleNotFound> {
+    ) -> Result<&'a ModuleRpcClient, ClientModuleNotFound> {
         match self.client_modules.get(&(
             client_type.clone(),
             ibc_interface.clone(),
             ibc_spec_id.clone(),
         )) {
-            Some(client_module) => Ok(client_module.client()),
+            Some(client_module) => Ok(client_module),
             None => Err(ClientModuleNotFound::NotFound {
                 client_type: client_type.clone(),
                 ibc_interface: ibc_interface.clone(),
@@ -825,15 +1440,13 @@ impl Modules {
         chain_id: &ChainId,
         client_type: &ClientType,
         // ) -> Result<&'a (impl jsonrpsee::core::client::ClientT + 'a), ConsensusModuleNotFound> {
-    ) -> Result<&'a reconnecting_jsonrpc_ws_client::Client, ClientBootstrapModuleNotFound> {
-        Ok(self
-            .client_bootstrap_modules
+    ) -> Result<&'a ModuleRpcClient, ClientBootstrapModuleNotFound> {
+        self.client_bootstrap_modules
             .get(&(chain_id.clone(), client_type.clone()))
             .ok_or_else(|| ClientBootstrapModuleNotFound {
                 chain_id: chain_id.clone(),
                 client_type: client_type.clone(),
-            })?
-            .client())
+            })
     }
 }
 
@@ -850,38 +1463,33 @@ pub struct LoadedModulesInfo {
 #[instrument(skip_all, fields(%plugin_name))]
 async fn plugin_child_process(
     plugin_name: String,
-    module_config: PluginConfig,
+    path: PathBuf,
+    config: Value,
     cancellation_token: CancellationToken,
+    shutdown_token: CancellationToken,
 ) {
     let client_socket = ModuleRpcClient::make_socket_path(&plugin_name);
     let server_socket = make_module_rpc_server_socket_path(&plugin_name);
 
     debug!(%client_socket, %server_socket, "starting plugin {plugin_name}");
 
-    let mut cmd = tokio::process::Command::new(&module_config.path);
-    cmd.arg("run");
-    cmd.arg(&client_socket);
-    cmd.arg(&server_socket);
-    cmd.arg(module_config.config.to_string());
-
     lazarus_pit(
-        &module_config.path,
-        &[
-            "run",
-            &client_socket,
-            &server_socket,
-            &module_config.config.to_string(),
-        ],
+        &path,
+        &["run", &client_socket, &server_socket, &config.to_string()],
         cancellation_token,
+        shutdown_token,
     )
     .await
 }
 
 #[instrument(skip_all, fields(%module_name))]
-async fn module_child_process<Info: Serialize>(
+async fn module_child_process(
     module_name: String,
-    module_config: ModuleConfig<Info>,
+    path: PathBuf,
+    config: Value,
+    info: Value,
     cancellation_token: CancellationToken,
+    shutdown_token: CancellationToken,
 ) {
     let client_socket = ModuleRpcClient::make_socket_path(&module_name);
     let server_socket = make_module_rpc_server_socket_path(&module_name);
@@ -889,20 +1497,28 @@ async fn module_child_process<Info: Serialize>(
     debug!(%client_socket, %server_socket, "starting module {module_name}");
 
     lazarus_pit(
-        &module_config.path,
+        &path,
         &[
             "run",
             &client_socket,
             &server_socket,
-            &module_config.config.to_string(),
-            &serde_json::to_string(&module_config.info).unwrap(),
+            &config.to_string(),
+            &info.to_string(),
         ],
         cancellation_token,
+        shutdown_token,
     )
     .await
 }
 
-async fn lazarus_pit(cmd: &Path, args: &[&str], cancellation_token: CancellationToken) {
+/// Keeps the child process running until `cancellation_token` is cancelled. An invalid config is
+/// fatal to voyager as a whole, which is shut down through `shutdown_token`.
+async fn lazarus_pit(
+    cmd: &Path,
+    args: &[&str],
+    cancellation_token: CancellationToken,
+    shutdown_token: CancellationToken,
+) {
     let mut attempt = 0;
 
     loop {
@@ -965,7 +1581,7 @@ async fn lazarus_pit(cmd: &Path, args: &[&str], cancellation_token: Cancellation
                             .is_some_and(|c| c == INVALID_CONFIG_EXIT_CODE as i32)
                         {
                             error!(%id, "invalid config for plugin or module");
-                            cancellation_token.cancel();
+                            shutdown_token.cancel();
                         }
                     }
                     Err(err) => {
@@ -1067,21 +1683,45 @@ pub struct PluginNotFound {
 
 module_error!(PluginNotFound);
 
-pub fn get_plugin_info(plugin_config: &PluginConfig) -> anyhow::Result<PluginInfo> {
+/// Queries the info of a plugin, either by running its binary or by asking the remote.
+pub async fn get_plugin_info(plugin_config: &PluginConfig) -> anyhow::Result<PluginInfo> {
+    match plugin_config.transport()? {
+        Transport::Process(path) => get_process_plugin_info(path, &plugin_config.config),
+        Transport::Remote(remote) => {
+            debug!("querying module info from remote plugin at {}", remote.url);
+
+            let rpc_client = ModuleRpcClient::remote(&remote.url, remote, Duration::from_secs(10))?;
+
+            rpc_client
+                .client()
+                .wait_until_connected(Duration::from_secs(10))
+                .await
+                .with_context(|| format!("connecting to remote plugin at {}", remote.url))?;
+
+            rpc_client
+                .client()
+                .request(PLUGIN_INFO_METHOD, rpc_params![])
+                .await
+                .with_context(|| format!("querying info of remote plugin at {}", remote.url))
+        }
+    }
+}
+
+fn get_process_plugin_info(path: &Path, config: &Value) -> anyhow::Result<PluginInfo> {
     debug!(
         "querying module info from plugin at {}",
-        &plugin_config.path.to_string_lossy(),
+        &path.to_string_lossy(),
     );
 
-    let mut cmd = std::process::Command::new(&plugin_config.path);
+    let mut cmd = std::process::Command::new(path);
     cmd.arg("info");
-    cmd.arg(plugin_config.config.to_string());
+    cmd.arg(config.to_string());
 
     let output = cmd
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
-        .with_context(|| format!("spawning plugin at {}", plugin_config.path.display()))?
+        .with_context(|| format!("spawning plugin at {}", path.display()))?
         .wait_with_output()
         .unwrap();
 
@@ -1090,14 +1730,14 @@ pub fn get_plugin_info(plugin_config: &PluginConfig) -> anyhow::Result<PluginInf
             Some(code) if code == INVALID_CONFIG_EXIT_CODE as i32 => {
                 return Err(anyhow!(
                     "unable to query info for module at path {}: stdout:\n{}",
-                    &plugin_config.path.to_string_lossy(),
+                    &path.to_string_lossy(),
                     String::from_utf8_lossy(&output.stdout)
                 ));
             }
             Some(_) | None => {
                 return Err(anyhow!(
                     "unable to query info for module at path {}: stdout:\n{}",
-                    &plugin_config.path.to_string_lossy(),
+                    &path.to_string_lossy(),
                     String::from_utf8_lossy(&output.stdout)
                 ));
             }
@@ -1109,65 +1749,268 @@ pub fn get_plugin_info(plugin_config: &PluginConfig) -> anyhow::Result<PluginInf
     Ok(serde_json::from_slice(&output.stdout).unwrap())
 }
 
-async fn module_startup<Info: Serialize + Clone + Unpin + Send + 'static>(
+/// Registers the enabled modules of `configs` with `push_f`, reusing the running modules whose
+/// config is unchanged.
+#[allow(clippy::too_many_arguments)]
+fn plan_modules<Info: Serialize>(
     configs: Vec<ModuleConfig<Info>>,
-    cancellation_token: CancellationToken,
-    main_rpc_server: Server,
+    running: &Running,
+    plan: &mut ReloadPlan,
     ipc_client_request_timeout: Duration,
+    limits: &Limits,
     id_f: fn(&Info) -> String,
+    chain_id_f: fn(&Info) -> Option<&ChainId>,
     mut push_f: impl FnMut(&Info, ModuleRpcClient) -> anyhow::Result<()>,
 ) -> anyhow::Result<()> {
-    stream::iter(configs)
-        .filter(|module_config| {
-            future::ready(if !module_config.enabled {
-                info!(
-                    module_path = %module_config.path.to_string_lossy(),
-                    "module is not enabled, skipping"
-                );
-                false
-            } else {
-                true
-            })
-        })
-        .zip(stream::repeat(main_rpc_server.clone()))
-        .map::<anyhow::Result<_>, _>(anyhow::Result::Ok)
-        .try_filter_map(|(module_config, server)| async move {
-            if !module_config.enabled {
-                info!(
-                    module_path = %module_config.path.to_string_lossy(),
-                    "module is not enabled, skipping"
-                );
-                anyhow::Result::Ok(None)
-            } else {
-                debug!(
-                    "starting rpc server for module {}",
-                    id_f(&module_config.info)
-                );
-                tokio::spawn(module_rpc_server(&id_f(&module_config.info), server).await?);
+    for module_config in configs {
+        if !module_config.enabled {
+            info!(
+                module = %module_config
+                    .transport()
+                    .map(|transport| transport.to_string())
+                    .unwrap_or_default(),
+                "module is not enabled, skipping"
+            );
+            continue;
+        }
 
-                anyhow::Result::Ok(Some(module_config))
-            }
-        })
-        .try_collect::<FuturesUnordered<_>>()
-        .await?
-        .into_iter()
-        .try_for_each(|module_config| {
-            let id = id_f(&module_config.info);
-
-            debug!("registering module {}", id);
-
-            tokio::spawn(module_child_process(
-                id.clone(),
-                module_config.clone(),
-                cancellation_token.clone(),
-            ));
+        let id = id_f(&module_config.info);
+
+        debug!("registering module {}", id);
+
+        let rpc_client = plan
+            .register(
+                running,
+                &id,
+                serde_json::to_value(&module_config).expect("serialization is infallible; qed;"),
+                module_config.transport()?,
+                |path| ChildProcess::Module {
+                    path,
+                    config: module_config.config.clone(),
+                    info: serde_json::to_value(&module_config.info)
+                        .expect("serialization is infallible; qed;"),
+                },
+                ipc_client_request_timeout,
+            )?
+            .with_limiters(limits.limiters(&id, chain_id_f(&module_config.info)));
 
-            let rpc_client = ModuleRpcClient::new(&id, ipc_client_request_timeout);
+        push_f(&module_config.info, rpc_client)?;
 
-            push_f(&module_config.info, rpc_client)?;
+        info!("registered module {id}");
+    }
 
-            info!("registered module {id}");
+    Ok(())
+}
 
-            Ok(())
-        })
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // remote modules connect lazily, so no plugin or module needs to be running
+    fn state_module(chain_id: &str, url: &str) -> ModuleConfig<StateModuleInfo> {
+        ModuleConfig {
+            path: None,
+            remote: Some(RemoteConfig {
+                url: url.to_owned(),
+                auth_token: None,
+                tls: None,
+            }),
+            info: StateModuleInfo {
+                chain_id: ChainId::new(chain_id.to_owned()),
+                ibc_spec_id: IbcSpecId::new_static(IbcSpecId::UNION),
+            },
+            config: default_config(),
+            enabled: true,
+        }
+    }
+
+    fn modules(state: Vec<ModuleConfig<StateModuleInfo>>) -> ModulesConfig {
+        ModulesConfig {
+            state,
+            ..Default::default()
+        }
+    }
+
+    async fn context(state: Vec<ModuleConfig<StateModuleInfo>>) -> Context {
+        Context::new(
+            vec![],
+            modules(state),
+            EquivalentChainIds::default(),
+            |_| {},
+            Duration::from_secs(1),
+            Default::default(),
+            Default::default(),
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn running(ctx: &Context) -> Vec<String> {
+        let mut names = ctx
+            .running
+            .lock()
+            .await
+            .modules
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+    }
+
+    fn reload_summary(
+        started: &[&str],
+        restarted: &[&str],
+        stopped: &[&str],
+        unchanged: &[&str],
+    ) -> ReloadSummary {
+        let names = |names: &[&str]| names.iter().map(|name| (*name).to_owned()).collect();
+
+        ReloadSummary {
+            started: names(started),
+            restarted: names(restarted),
+            stopped: names(stopped),
+            unchanged: names(unchanged),
+        }
+    }
+
+    #[tokio::test]
+    async fn reload() {
+        let ctx = context(vec![
+            state_module("a", "ws://127.0.0.1:1"),
+            state_module("b", "ws://127.0.0.1:1"),
+            state_module("c", "ws://127.0.0.1:1"),
+        ])
+        .await;
+
+        let summary = ctx
+            .reload(
+                vec![],
+                modules(vec![
+                    state_module("a", "ws://127.0.0.1:1"),
+                    state_module("b", "ws://127.0.0.1:2"),
+                    state_module("d", "ws://127.0.0.1:1"),
+                ]),
+                EquivalentChainIds::default(),
+                LimitsConfig::default(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            summary,
+            reload_summary(
+                &["state/ibc-union/d"],
+                &["state/ibc-union/b"],
+                &["state/ibc-union/c"],
+                &["state/ibc-union/a"],
+            )
+        );
+
+        assert_eq!(
+            running(&ctx).await,
+            [
+                "state/ibc-union/a",
+                "state/ibc-union/b",
+                "state/ibc-union/d"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn reload_unchanged() {
+        let config = || {
+            modules(vec![
+                state_module("a", "ws://127.0.0.1:1"),
+                state_module("b", "ws://127.0.0.1:1"),
+            ])
+        };
+
+        let ctx = context(config().state).await;
+
+        assert_eq!(
+            ctx.reload(
+                vec![],
+                config(),
+                EquivalentChainIds::default(),
+                LimitsConfig::default(),
+            )
+            .await
+            .unwrap(),
+            reload_summary(&[], &[], &[], &["state/ibc-union/a", "state/ibc-union/b"])
+        );
+    }
+
+    #[tokio::test]
+    async fn reload_invalid_config() {
+        let ctx = context(vec![
+            state_module("a", "ws://127.0.0.1:1"),
+            state_module("b", "ws://127.0.0.1:1"),
+        ])
+        .await;
+
+        // b is changed and c is added before the duplicate is found
+        ctx.reload(
+            vec![],
+            modules(vec![
+                state_module("b", "ws://127.0.0.1:2"),
+                state_module("c", "ws://127.0.0.1:1"),
+                state_module("c", "ws://127.0.0.1:1"),
+            ]),
+            EquivalentChainIds::default(),
+            LimitsConfig::default(),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(
+            running(&ctx).await,
+            ["state/ibc-union/a", "state/ibc-union/b"]
+        );
+
+        // the running configuration is still the previous one
+        assert_eq!(
+            ctx.reload(
+                vec![],
+                modules(vec![
+                    state_module("a", "ws://127.0.0.1:1"),
+                    state_module("b", "ws://127.0.0.1:1"),
+                ]),
+                EquivalentChainIds::default(),
+                LimitsConfig::default(),
+            )
+            .await
+            .unwrap(),
+            reload_summary(&[], &[], &[], &["state/ibc-union/a", "state/ibc-union/b"])
+        );
+    }
+
+    #[tokio::test]
+    async fn register_duplicate() {
+        let running = Running::default();
+        let mut plan = ReloadPlan::default();
+
+        let remote = RemoteConfig {
+            url: "ws://127.0.0.1:1".to_owned(),
+            auth_token: None,
+            tls: None,
+        };
+
+        let register = |plan: &mut ReloadPlan| {
+            plan.register(
+                &running,
+                "plugin",
+                Value::Null,
+                Transport::Remote(&remote),
+                |_| unreachable!(),
+                Duration::from_secs(1),
+            )
+        };
+
+        register(&mut plan).unwrap();
+        register(&mut plan).unwrap_err();
+
+        assert!(plan.unchanged.is_empty());
+        assert_eq!(plan.start.keys().collect::<Vec<_>>(), ["plugin"]);
+    }
 }
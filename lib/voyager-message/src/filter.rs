@@ -1,6 +1,11 @@
-use std::rc::Rc;
+use std::{
+    collections::HashSet,
+    rc::Rc,
+    sync::{LazyLock, Mutex},
+};
 
 use anyhow::anyhow;
+use arc_swap::ArcSwap;
 use jaq_interpret::{Ctx, Filter, FilterT, ParseCtx, RcIter, Val};
 use tracing::{error, instrument, trace};
 use unionlabs::ErrorReporter;
@@ -11,20 +16,47 @@ use voyager_vm::{
 
 use crate::{module::PluginInfo, VoyagerMessage};
 
-#[derive(Debug, Clone)]
+/// The interest filters of all plugins, which can be atomically replaced when plugins are reloaded.
+#[derive(Debug)]
 pub struct JaqInterestFilter {
-    pub filters: Vec<(Filter, String)>,
+    filters: ArcSwap<Vec<(Filter, &'static str)>>,
 }
 
 impl JaqInterestFilter {
     pub fn new(filters: Vec<PluginInfo>) -> anyhow::Result<Self> {
         Ok(Self {
-            filters: filters
-                .into_iter()
-                .map(make_filter)
-                .collect::<anyhow::Result<_>>()?,
+            filters: ArcSwap::from_pointee(
+                filters
+                    .into_iter()
+                    .map(|info| make_filter(info).map(|(filter, name)| (filter, intern(name))))
+                    .collect::<anyhow::Result<_>>()?,
+            ),
         })
     }
+
+    /// Replaces the filters with the ones of `other`. Ops being checked concurrently see either the
+    /// old or the new filters, never a mix of both.
+    pub fn replace(&self, other: Self) {
+        self.filters.store(other.filters.into_inner());
+    }
+}
+
+/// Plugin names are borrowed by the [`Interest`] returned from the filters, which can outlive the
+/// filters themselves once they are replaced. Names are leaked once, so this is bounded by the
+/// number of distinct plugins ever loaded.
+fn intern(name: String) -> &'static str {
+    static NAMES: LazyLock<Mutex<HashSet<&'static str>>> = LazyLock::new(Default::default);
+
+    let mut names = NAMES.lock().unwrap();
+
+    match names.get(name.as_str()) {
+        Some(name) => name,
+        None => {
+            let name = &*name.leak();
+            names.insert(name);
+            name
+        }
+    }
 }
 
 pub fn make_filter(
@@ -64,14 +96,16 @@ pub fn make_filter(
     // compile the filter in the context of the given definitions
     let filter = ctx.compile(f.conv(&interest_filter));
 
-    assert!(
-        ctx.errs.is_empty(),
-        "{:?}",
-        ctx.errs
-            .into_iter()
-            .map(|x| x.0.to_string())
-            .collect::<Vec<_>>()
-    );
+    // an invalid filter is a config error, which must not bring down voyager on reload
+    if !ctx.errs.is_empty() {
+        return Err(anyhow!(
+            "invalid interest filter for plugin {name}: {:?}",
+            ctx.errs
+                .into_iter()
+                .map(|x| x.0.to_string())
+                .collect::<Vec<_>>()
+        ));
+    }
 
     Ok((filter, name))
 }
@@ -82,7 +116,9 @@ impl InterestFilter<VoyagerMessage> for JaqInterestFilter {
 
         let mut tags = vec![];
 
-        for (filter, plugin_name) in &self.filters {
+        let filters = self.filters.load();
+
+        for &(ref filter, plugin_name) in filters.iter() {
             match run_filter(filter, plugin_name, msg_json.clone()) {
                 Ok(JaqFilterResult::Copy(tag)) => tags.push(tag),
                 Ok(JaqFilterResult::Take(tag)) => {
@@ -5,6 +5,7 @@ use std::{
     env::VarError,
     fmt::{self, Debug},
     future::Future,
+    net::SocketAddr,
     time::Duration,
 };
 
@@ -18,11 +19,12 @@ use jsonrpsee::{
         traits::ToRpcParams,
         RpcResult,
     },
-    server::middleware::rpc::RpcServiceT,
+    server::{middleware::rpc::RpcServiceT, ServerHandle},
     types::{
         error::{INVALID_PARAMS_CODE, METHOD_NOT_FOUND_CODE, PARSE_ERROR_CODE},
         ErrorObject, Response, ResponsePayload,
     },
+    ws_client::{PingConfig, WsClientBuilder},
     Extensions, MethodResponse, RpcModule,
 };
 use macros::model;
@@ -33,6 +35,7 @@ use reth_ipc::{
 use rpc::{SelfClientState, SelfConsensusState};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{json, value::RawValue, Value};
+use tower_http::validate_request::ValidateRequestHeaderLayer;
 use tracing::{
     debug, debug_span, error, info_span, instrument, instrument::Instrumented, trace, Instrument,
 };
@@ -65,6 +68,7 @@ pub mod data;
 
 pub mod context;
 pub mod filter;
+pub mod limits;
 pub mod module;
 pub mod pass;
 
@@ -158,6 +162,10 @@ pub const UNPROCESSABLE_JSONRPC_ERROR_CODE: i32 = -0xDEADC0D; // 🐟
 /// requeued and retried.
 pub const MISSING_STATE_ERROR_CODE: i32 = -0xBADB10B;
 
+/// Method served by remote plugins (see the `serve` command of plugins) returning their
+/// [`PluginInfo`], since voyager can't run the plugin binary to query it.
+pub const PLUGIN_INFO_METHOD: &str = "plugin_info";
+
 /// Convert a [`jsonrpsee::core::client::Error`] to a `voyager-vm` [`QueueError`].
 ///
 /// All errors are treated as retryable, unless `error` is a `Call` variant and the contained
@@ -287,6 +295,17 @@ pub trait Plugin: PluginServer<Self::Call, Self::Callback> + Sized {
 
     async fn cmd(config: Self::Config, cmd: Self::Cmd);
 
+    /// [`PluginServer::into_rpc`], additionally serving the [`PluginInfo`] of this plugin under
+    /// [`PLUGIN_INFO_METHOD`] as is required for remote plugins.
+    fn into_rpc_with_info(self, info: PluginInfo) -> RpcModule<Self> {
+        let mut rpcs = Self::into_rpc(self);
+        rpcs.register_method(PLUGIN_INFO_METHOD, move |_, _, _| {
+            RpcResult::Ok(info.clone())
+        })
+        .expect("plugin info method is not registered by plugins; qed;");
+        rpcs
+    }
+
     async fn run() {
         init_log();
 
@@ -306,15 +325,43 @@ pub trait Plugin: PluginServer<Self::Call, Self::Callback> + Sized {
 
                 run_server(
                     name.clone(),
-                    voyager_socket,
+                    ServerTransport::Ipc {
+                        socket,
+                        voyager_socket,
+                    },
                     config,
-                    socket,
                     Self::new,
                     Self::into_rpc,
                 )
                 .instrument(debug_span!("run_plugin_server", %name))
                 .await
             }
+            PluginApp::Serve {
+                laddr,
+                voyager_url,
+                auth_token,
+                config,
+            } => {
+                let config = must_parse::<Self::Config>(&config);
+
+                let info = Self::info(config.clone());
+
+                let name = info.name.clone();
+
+                run_server(
+                    name.clone(),
+                    ServerTransport::Ws {
+                        laddr,
+                        voyager_url,
+                        auth_token,
+                    },
+                    config,
+                    Self::new,
+                    move |plugin| plugin.into_rpc_with_info(info),
+                )
+                .instrument(debug_span!("serve_plugin", %name))
+                .await
+            }
             PluginApp::Info { config } => {
                 let info = Self::info(must_parse(&config));
 
@@ -349,15 +396,44 @@ pub trait StateModule<V: IbcSpec>: StateModuleServer<V> + Sized {
 
                 run_server(
                     name.clone(),
-                    voyager_socket,
+                    ServerTransport::Ipc {
+                        socket,
+                        voyager_socket,
+                    },
                     (config, info),
-                    socket,
                     |(config, info)| Self::new(config, info),
                     Self::into_rpc,
                 )
                 .instrument(debug_span!("run_state_module_server", %name))
                 .await
             }
+            ModuleApp::Serve {
+                laddr,
+                voyager_url,
+                auth_token,
+                config,
+                info,
+            } => {
+                let config = must_parse::<Self::Config>(&config);
+
+                let info = must_parse::<StateModuleInfo>(&info);
+
+                let name = info.id();
+
+                run_server(
+                    name.clone(),
+                    ServerTransport::Ws {
+                        laddr,
+                        voyager_url,
+                        auth_token,
+                    },
+                    (config, info),
+                    |(config, info)| Self::new(config, info),
+                    Self::into_rpc,
+                )
+                .instrument(debug_span!("serve_state_module", %name))
+                .await
+            }
         }
     }
 }
@@ -386,15 +462,44 @@ pub trait ProofModule<V: IbcSpec>: ProofModuleServer<V> + Sized {
 
                 run_server(
                     name.clone(),
-                    voyager_socket,
+                    ServerTransport::Ipc {
+                        socket,
+                        voyager_socket,
+                    },
                     (config, info),
-                    socket,
                     |(config, info)| Self::new(config, info),
                     Self::into_rpc,
                 )
                 .instrument(debug_span!("run_proof_module_server", %name))
                 .await
             }
+            ModuleApp::Serve {
+                laddr,
+                voyager_url,
+                auth_token,
+                config,
+                info,
+            } => {
+                let config = must_parse::<Self::Config>(&config);
+
+                let info = must_parse::<ProofModuleInfo>(&info);
+
+                let name = info.id();
+
+                run_server(
+                    name.clone(),
+                    ServerTransport::Ws {
+                        laddr,
+                        voyager_url,
+                        auth_token,
+                    },
+                    (config, info),
+                    |(config, info)| Self::new(config, info),
+                    Self::into_rpc,
+                )
+                .instrument(debug_span!("serve_proof_module", %name))
+                .await
+            }
         }
     }
 }
@@ -423,15 +528,44 @@ pub trait ConsensusModule: ConsensusModuleServer + Sized {
 
                 run_server(
                     name.clone(),
-                    voyager_socket,
+                    ServerTransport::Ipc {
+                        socket,
+                        voyager_socket,
+                    },
                     (config, info),
-                    socket,
                     |(config, info)| Self::new(config, info),
                     Self::into_rpc,
                 )
                 .instrument(debug_span!("run_consensus_module_server", %name))
                 .await
             }
+            ModuleApp::Serve {
+                laddr,
+                voyager_url,
+                auth_token,
+                config,
+                info,
+            } => {
+                let config = must_parse::<Self::Config>(&config);
+
+                let info = must_parse::<ConsensusModuleInfo>(&info);
+
+                let name = info.id();
+
+                run_server(
+                    name.clone(),
+                    ServerTransport::Ws {
+                        laddr,
+                        voyager_url,
+                        auth_token,
+                    },
+                    (config, info),
+                    |(config, info)| Self::new(config, info),
+                    Self::into_rpc,
+                )
+                .instrument(debug_span!("serve_consensus_module", %name))
+                .await
+            }
         }
     }
 }
@@ -460,15 +594,44 @@ pub trait ClientModule: ClientModuleServer + Sized {
 
                 run_server(
                     name.clone(),
-                    voyager_socket,
+                    ServerTransport::Ipc {
+                        socket,
+                        voyager_socket,
+                    },
                     (config, info),
-                    socket,
                     |(config, info)| Self::new(config, info),
                     Self::into_rpc,
                 )
                 .instrument(debug_span!("run_client_module_server", %name))
                 .await
             }
+            ModuleApp::Serve {
+                laddr,
+                voyager_url,
+                auth_token,
+                config,
+                info,
+            } => {
+                let config = must_parse::<Self::Config>(&config);
+
+                let info = must_parse::<ClientModuleInfo>(&info);
+
+                let name = info.id();
+
+                run_server(
+                    name.clone(),
+                    ServerTransport::Ws {
+                        laddr,
+                        voyager_url,
+                        auth_token,
+                    },
+                    (config, info),
+                    |(config, info)| Self::new(config, info),
+                    Self::into_rpc,
+                )
+                .instrument(debug_span!("serve_client_module", %name))
+                .await
+            }
         }
     }
 }
@@ -500,15 +663,44 @@ pub trait ClientBootstrapModule: ClientBootstrapModuleServer + Sized {
 
                 run_server(
                     name.clone(),
-                    voyager_socket,
+                    ServerTransport::Ipc {
+                        socket,
+                        voyager_socket,
+                    },
                     (config, info),
-                    socket,
                     |(config, info)| Self::new(config, info),
                     Self::into_rpc,
                 )
                 .instrument(debug_span!("run_client_bootstrap_module_server", %name))
                 .await
             }
+            ModuleApp::Serve {
+                laddr,
+                voyager_url,
+                auth_token,
+                config,
+                info,
+            } => {
+                let config = must_parse::<Self::Config>(&config);
+
+                let info = must_parse::<ClientBootstrapModuleInfo>(&info);
+
+                let name = info.id();
+
+                run_server(
+                    name.clone(),
+                    ServerTransport::Ws {
+                        laddr,
+                        voyager_url,
+                        auth_token,
+                    },
+                    (config, info),
+                    |(config, info)| Self::new(config, info),
+                    Self::into_rpc,
+                )
+                .instrument(debug_span!("serve_client_bootstrap_module", %name))
+                .await
+            }
         }
     }
 }
@@ -535,6 +727,15 @@ fn new_voyager_client(name: String, socket: String) -> reconnecting_jsonrpc_ws_c
     client
 }
 
+fn new_remote_voyager_client(name: String, url: String) -> reconnecting_jsonrpc_ws_client::Client {
+    reconnecting_jsonrpc_ws_client::Client::new(move || {
+        WsClientBuilder::default()
+            .enable_ws_ping(PingConfig::new())
+            .build(url.clone())
+            .instrument(debug_span!("voyager_ws_client", %name))
+    })
+}
+
 #[derive(Debug, Clone)]
 pub struct VoyagerClient(IdThreadClient<reconnecting_jsonrpc_ws_client::Client>);
 
@@ -1095,6 +1296,22 @@ enum PluginApp<Cmd: clap::Subcommand> {
         voyager_socket: String,
         config: String,
     },
+    /// Serve the plugin over a websocket, for voyager instances configured with a remote plugin.
+    ///
+    /// The server only speaks plain `ws://`, TLS must be terminated by a proxy in front of the
+    /// plugin. Don't expose `--laddr` on an untrusted network without one.
+    Serve {
+        #[arg(long)]
+        laddr: SocketAddr,
+        /// The voyager rpc server to call back into, i.e. `ws://voyager:7178`. Only that voyager
+        /// instance can use the served remote.
+        #[arg(long)]
+        voyager_url: String,
+        /// Only accept connections sending this bearer token.
+        #[arg(long)]
+        auth_token: Option<String>,
+        config: String,
+    },
     Info {
         config: String,
     },
@@ -1114,6 +1331,37 @@ enum ModuleApp {
         config: String,
         info: String,
     },
+    /// Serve the module over a websocket, for voyager instances configured with a remote module.
+    ///
+    /// The server only speaks plain `ws://`, TLS must be terminated by a proxy in front of the
+    /// module. Don't expose `--laddr` on an untrusted network without one.
+    Serve {
+        #[arg(long)]
+        laddr: SocketAddr,
+        /// The voyager rpc server to call back into, i.e. `ws://voyager:7178`. Only that voyager
+        /// instance can use the served remote.
+        #[arg(long)]
+        voyager_url: String,
+        /// Only accept connections sending this bearer token.
+        #[arg(long)]
+        auth_token: Option<String>,
+        config: String,
+        info: String,
+    },
+}
+
+enum ServerTransport {
+    /// Spawned by voyager, see [`context::Context`].
+    Ipc {
+        socket: String,
+        voyager_socket: String,
+    },
+    /// Served to remote voyager instances.
+    Ws {
+        laddr: SocketAddr,
+        voyager_url: String,
+        auth_token: Option<String>,
+    },
 }
 
 #[instrument(level = "debug", fields(%config_str))]
@@ -1135,22 +1383,32 @@ async fn run_server<
     IntoRpcF: FnOnce(T) -> RpcModule<T>,
 >(
     id: String,
-    voyager_socket: String,
+    transport: ServerTransport,
     new_t: NewT,
-    socket: String,
     new: NewF,
     into_rpc: IntoRpcF,
 ) {
-    let voyager_client = new_voyager_client(id.clone(), voyager_socket);
+    let voyager_client = match &transport {
+        ServerTransport::Ipc { voyager_socket, .. } => {
+            new_voyager_client(id.clone(), voyager_socket.clone())
+        }
+        ServerTransport::Ws { voyager_url, .. } => {
+            new_remote_voyager_client(id.clone(), voyager_url.clone())
+        }
+    };
     if let Err(err) = voyager_client
-        .wait_until_connected(Duration::from_millis(500))
+        .wait_until_connected(match transport {
+            ServerTransport::Ipc { .. } => Duration::from_millis(500),
+            // voyager may not be up yet when serving remotely
+            ServerTransport::Ws { .. } => Duration::from_secs(10),
+        })
         .await
     {
-        error!("unable to connect to voyager socket: {err}");
+        error!("unable to connect to voyager: {err}");
         std::process::exit(STARTUP_ERROR_EXIT_CODE as i32);
     };
 
-    trace!("connected to voyager socket");
+    trace!("connected to voyager");
 
     let module_server = match new(new_t).await {
         Ok(ctx) => ctx,
@@ -1160,27 +1418,47 @@ async fn run_server<
         }
     };
 
-    let id_ = id.clone();
-    let ipc_server = reth_ipc::server::Builder::default()
-        .set_rpc_middleware(
-            RpcServiceBuilder::new()
-                .layer_fn(move |service| InjectClient {
-                    client: voyager_client.clone(),
-                    service,
-                })
-                .layer_fn(move |service: RpcService| ErrorContext {
-                    service,
-                    id: id_.clone(),
-                }),
-        )
-        .build(socket);
-
     let rpcs = into_rpc(module_server);
 
     trace!(methods = ?*rpcs, "registered methods");
-    let addr = ipc_server.endpoint();
-    let server_handle = ipc_server.start(rpcs).await.unwrap();
-    debug!("listening on {addr}");
+
+    let server_handle = match transport {
+        ServerTransport::Ipc { socket, .. } => {
+            let id_ = id.clone();
+            let ipc_server = reth_ipc::server::Builder::default()
+                .set_rpc_middleware(
+                    RpcServiceBuilder::new()
+                        .layer_fn(move |service| InjectClient {
+                            client: voyager_client.clone(),
+                            service,
+                        })
+                        .layer_fn(move |service: RpcService| ErrorContext {
+                            service,
+                            id: id_.clone(),
+                        }),
+                )
+                .build(socket);
+
+            let addr = ipc_server.endpoint();
+            let server_handle = ipc_server.start(rpcs).await.unwrap();
+            debug!("listening on {addr}");
+
+            server_handle
+        }
+        ServerTransport::Ws {
+            laddr, auth_token, ..
+        } => match start_ws_server(id.clone(), laddr, auth_token, voyager_client, rpcs).await {
+            Ok((addr, server_handle)) => {
+                debug!("listening on {addr}");
+
+                server_handle
+            }
+            Err(err) => {
+                error!("unable to listen on {laddr}: {}", ErrorReporter(err));
+                std::process::exit(STARTUP_ERROR_EXIT_CODE as i32);
+            }
+        },
+    };
 
     tokio::spawn(
         server_handle
@@ -1191,6 +1469,59 @@ async fn run_server<
     .unwrap()
 }
 
+/// Serve `rpcs` over websocket on `laddr` from within the current process, connecting to the
+/// voyager rpc server at `voyager_url`.
+///
+/// This is equivalent to the `serve` command of plugins and modules, without taking over the
+/// process. This allows for running plugins and modules alongside an in-process [`Context`], such
+/// as when testing them.
+pub async fn serve_in_process<T: Send + Sync + 'static>(
+    id: String,
+    laddr: SocketAddr,
+    voyager_url: String,
+    rpcs: RpcModule<T>,
+) -> std::io::Result<(SocketAddr, ServerHandle)> {
+    let voyager_client = new_remote_voyager_client(id.clone(), voyager_url);
+
+    start_ws_server(id, laddr, None, voyager_client, rpcs).await
+}
+
+async fn start_ws_server<T: Send + Sync + 'static>(
+    id: String,
+    laddr: SocketAddr,
+    auth_token: Option<String>,
+    voyager_client: reconnecting_jsonrpc_ws_client::Client,
+    rpcs: RpcModule<T>,
+) -> std::io::Result<(SocketAddr, ServerHandle)> {
+    let ws_server = jsonrpsee::server::Server::builder()
+        .set_http_middleware(
+            tower::ServiceBuilder::new().option_layer(
+                auth_token
+                    .as_deref()
+                    .map(ValidateRequestHeaderLayer::bearer),
+            ),
+        )
+        .set_rpc_middleware(
+            jsonrpsee::server::middleware::rpc::RpcServiceBuilder::new()
+                .layer_fn(move |service| InjectClient {
+                    client: voyager_client.clone(),
+                    service,
+                })
+                .layer_fn(
+                    move |service: jsonrpsee::server::middleware::rpc::RpcService| ErrorContext {
+                        service,
+                        id: id.clone(),
+                    },
+                ),
+        )
+        .build(laddr)
+        .await?;
+
+    let addr = ws_server.local_addr()?;
+
+    Ok((addr, ws_server.start(rpcs)))
+}
+
 struct InjectClient<S> {
     client: reconnecting_jsonrpc_ws_client::Client,
     service: S,
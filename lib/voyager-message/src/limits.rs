@@ -0,0 +1,346 @@
+use std::{
+    collections::HashMap,
+    num::{NonZeroU32, NonZeroUsize},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::{Semaphore, SemaphorePermit},
+    time::Instant,
+};
+use tracing::trace;
+use voyager_primitives::ChainId;
+
+/// Concurrency and rate limits for the requests made to plugins and modules.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// Limits for a chain, keyed by chain id.
+    ///
+    /// The rate limit is shared by all of the modules configured for the chain (state, proof,
+    /// consensus and client bootstrap modules). The concurrency limit is only applied when a call
+    /// for the chain is dispatched from the queue, not to the requests made to the modules while
+    /// handling it. Modules call back into voyager while handling a request (i.e. a proof module
+    /// querying the state module of the same chain), so a concurrency limit shared by them could
+    /// deadlock.
+    #[serde(default)]
+    pub chains: HashMap<ChainId, ChainLimitConfig>,
+    /// Limits for a single plugin or module, keyed by its name.
+    #[serde(default)]
+    pub modules: HashMap<String, LimitConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ChainLimitConfig {
+    /// The maximum amount of calls for this chain that can be processed at once.
+    #[serde(default)]
+    pub max_concurrent_requests: Option<NonZeroUsize>,
+    /// The maximum amount of requests that can be started per second. Bursts of up to this many
+    /// requests are allowed.
+    #[serde(default)]
+    pub requests_per_second: Option<NonZeroU32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LimitConfig {
+    /// The maximum amount of requests that can be in flight at once.
+    #[serde(default)]
+    pub max_concurrent_requests: Option<NonZeroUsize>,
+    /// The maximum amount of requests that can be started per second. Bursts of up to this many
+    /// requests are allowed.
+    #[serde(default)]
+    pub requests_per_second: Option<NonZeroU32>,
+}
+
+/// The limiters built from a [`LimitsConfig`].
+#[derive(Debug, Default)]
+pub struct Limits {
+    chains: HashMap<ChainId, Arc<Limiter>>,
+    chain_calls: HashMap<ChainId, Arc<Limiter>>,
+    modules: HashMap<String, Arc<Limiter>>,
+}
+
+impl Limits {
+    /// Builds the limiters for `config`. The limiters of `previous` whose config is unchanged are
+    /// kept, such that their state is kept across reloads.
+    pub fn new(config: LimitsConfig, previous: &Limits) -> Self {
+        Self {
+            chains: config
+                .chains
+                .iter()
+                .map(|(chain_id, config)| {
+                    let limiter = Limiter::reuse(
+                        previous.chains.get(chain_id),
+                        None,
+                        config.requests_per_second,
+                    );
+                    (chain_id.clone(), limiter)
+                })
+                .collect(),
+            chain_calls: config
+                .chains
+                .into_iter()
+                .filter(|(_, config)| config.max_concurrent_requests.is_some())
+                .map(|(chain_id, config)| {
+                    let limiter = Limiter::reuse(
+                        previous.chain_calls.get(&chain_id),
+                        config.max_concurrent_requests,
+                        None,
+                    );
+                    (chain_id, limiter)
+                })
+                .collect(),
+            modules: config
+                .modules
+                .into_iter()
+                .map(|(name, config)| {
+                    let limiter = Limiter::reuse(
+                        previous.modules.get(&name),
+                        config.max_concurrent_requests,
+                        config.requests_per_second,
+                    );
+                    (name, limiter)
+                })
+                .collect(),
+        }
+    }
+
+    /// The limiters that apply to the plugin or module `name`, optionally configured for
+    /// `chain_id`.
+    ///
+    /// Only the limiter of the module can hold a concurrency permit for the duration of a request,
+    /// the limiter of the chain is only rate limited (see [`LimitsConfig::chains`]).
+    pub(crate) fn limiters(&self, name: &str, chain_id: Option<&ChainId>) -> Vec<Arc<Limiter>> {
+        self.modules
+            .get(name)
+            .into_iter()
+            .chain(chain_id.and_then(|chain_id| self.chains.get(chain_id)))
+            .cloned()
+            .collect()
+    }
+
+    /// The concurrency limiters of the calls dispatched for each chain, keyed by chain id.
+    pub(crate) fn chain_call_limiters(&self) -> HashMap<ChainId, Arc<Limiter>> {
+        self.chain_calls.clone()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Limiter {
+    max_concurrent_requests: Option<NonZeroUsize>,
+    requests_per_second: Option<NonZeroU32>,
+    concurrency: Option<Semaphore>,
+    rate: Option<Mutex<TokenBucket>>,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    /// Tokens refilled per second, this is also the capacity of the bucket.
+    rate: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(requests_per_second: NonZeroU32, now: Instant) -> Self {
+        Self {
+            rate: requests_per_second.get().into(),
+            tokens: requests_per_second.get().into(),
+            last_refill: now,
+        }
+    }
+
+    /// Take a token at `now`, or return how long to wait until the next token is available.
+    fn take(&mut self, now: Instant) -> Result<(), Duration> {
+        self.tokens = (self.tokens
+            + now.duration_since(self.last_refill).as_secs_f64() * self.rate)
+            .min(self.rate);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+}
+
+impl Limiter {
+    fn new(
+        max_concurrent_requests: Option<NonZeroUsize>,
+        requests_per_second: Option<NonZeroU32>,
+    ) -> Self {
+        Self {
+            max_concurrent_requests,
+            requests_per_second,
+            concurrency: max_concurrent_requests.map(|max| Semaphore::new(max.get())),
+            rate: requests_per_second.map(|rps| Mutex::new(TokenBucket::new(rps, Instant::now()))),
+        }
+    }
+
+    /// Returns `previous` if it has the same limits, otherwise a new limiter.
+    fn reuse(
+        previous: Option<&Arc<Limiter>>,
+        max_concurrent_requests: Option<NonZeroUsize>,
+        requests_per_second: Option<NonZeroU32>,
+    ) -> Arc<Self> {
+        match previous {
+            Some(previous)
+                if previous.max_concurrent_requests == max_concurrent_requests
+                    && previous.requests_per_second == requests_per_second =>
+            {
+                previous.clone()
+            }
+            _ => Arc::new(Self::new(max_concurrent_requests, requests_per_second)),
+        }
+    }
+
+    /// Wait until a request can be made. The concurrency slot is held until the returned permit
+    /// is dropped.
+    pub(crate) async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        if let Some(rate) = &self.rate {
+            loop {
+                let Err(wait) = rate.lock().expect("mutex is poisoned").take(Instant::now()) else {
+                    break;
+                };
+
+                trace!(?wait, "rate limited");
+
+                tokio::time::sleep(wait).await;
+            }
+        }
+
+        match &self.concurrency {
+            Some(semaphore) => Some(
+                semaphore
+                    .acquire()
+                    .await
+                    .expect("semaphore is never closed; qed;"),
+            ),
+            None => None,
+        }
+    }
+}
+
+/// Acquire all of `limiters` in order, holding the returned permits until the request is done.
+pub(crate) async fn acquire_all(limiters: &[Arc<Limiter>]) -> Vec<Option<SemaphorePermit<'_>>> {
+    let mut permits = Vec::with_capacity(limiters.len());
+
+    for limiter in limiters {
+        permits.push(limiter.acquire().await);
+    }
+
+    permits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rps(n: u32) -> NonZeroU32 {
+        NonZeroU32::new(n).unwrap()
+    }
+
+    #[test]
+    fn token_bucket_burst() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(rps(2), now);
+
+        assert_eq!(bucket.take(now), Ok(()));
+        assert_eq!(bucket.take(now), Ok(()));
+        assert_eq!(bucket.take(now), Err(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn token_bucket_refill() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(rps(4), now);
+
+        for _ in 0..4 {
+            assert_eq!(bucket.take(now), Ok(()));
+        }
+
+        // a quarter of a token is refilled after 1/16th of a second
+        assert_eq!(
+            bucket.take(now + Duration::from_micros(62_500)),
+            Err(Duration::from_micros(187_500))
+        );
+        assert_eq!(bucket.take(now + Duration::from_millis(250)), Ok(()));
+        assert_eq!(
+            bucket.take(now + Duration::from_millis(250)),
+            Err(Duration::from_millis(250))
+        );
+    }
+
+    #[test]
+    fn token_bucket_capacity() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(rps(2), now);
+
+        assert_eq!(bucket.take(now), Ok(()));
+
+        // refilling never exceeds the rate
+        let later = now + Duration::from_secs(60);
+
+        assert_eq!(bucket.take(later), Ok(()));
+        assert_eq!(bucket.take(later), Ok(()));
+        assert_eq!(bucket.take(later), Err(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn limits_reuse_unchanged() {
+        let chain_id = ChainId::new("chain".to_owned());
+
+        let config = |requests_per_second| LimitsConfig {
+            chains: [(
+                chain_id.clone(),
+                ChainLimitConfig {
+                    max_concurrent_requests: Some(NonZeroUsize::new(1).unwrap()),
+                    requests_per_second: Some(rps(1)),
+                },
+            )]
+            .into(),
+            modules: [(
+                "module".to_owned(),
+                LimitConfig {
+                    max_concurrent_requests: None,
+                    requests_per_second: Some(rps(requests_per_second)),
+                },
+            )]
+            .into(),
+        };
+
+        let limits = Limits::new(config(1), &Limits::default());
+        let reloaded = Limits::new(config(2), &limits);
+
+        assert!(Arc::ptr_eq(
+            &limits.chains[&chain_id],
+            &reloaded.chains[&chain_id]
+        ));
+        assert!(!Arc::ptr_eq(
+            &limits.modules["module"],
+            &reloaded.modules["module"]
+        ));
+
+        let limiters = reloaded.limiters("module", Some(&chain_id));
+
+        assert_eq!(limiters.len(), 2);
+        assert!(limiters[1].concurrency.is_none());
+
+        // the concurrency of the chain is only limited when a call is dispatched
+        let chain_calls = reloaded.chain_call_limiters();
+
+        assert!(Arc::ptr_eq(
+            &limits.chain_calls[&chain_id],
+            &chain_calls[&chain_id]
+        ));
+        assert!(chain_calls[&chain_id].concurrency.is_some());
+        assert!(chain_calls[&chain_id].rate.is_none());
+    }
+}
@@ -1,11 +1,9 @@
 // #![warn(clippy::unwrap_used)]
 
-use std::{
-    fmt::Debug,
-    sync::{Arc, OnceLock},
-};
+use std::{fmt::Debug, sync::Arc};
 
 use anyhow::{anyhow, Context};
+use arc_swap::ArcSwapOption;
 use futures::TryFutureExt;
 use jsonrpsee::{
     core::{async_trait, RpcResult},
@@ -30,7 +28,7 @@ use crate::{
     primitives::{ChainId, ClientInfo, ClientStateMeta, ClientType, IbcInterface, QueryHeight},
     rpc::{
         json_rpc_error_to_error_object,
-        server::cache::{ClientInfoRequest, StateRequest},
+        server::cache::{ClientInfoRequest, ProofRequest, StateRequest},
         IbcProof, IbcProofResponse, IbcState, SelfClientState, SelfConsensusState,
         VoyagerRpcServer,
     },
@@ -51,7 +49,7 @@ pub mod cache {
     use unionlabs::ibc::core::client::height::Height;
     use voyager_primitives::{ChainId, ClientInfo, IbcSpec, IbcSpecId, IbcStorePathKey};
 
-    use crate::RawClientId;
+    use crate::{rpc::ProofType, RawClientId};
 
     #[derive(Debug, Clone)]
     pub struct Cache {
@@ -64,7 +62,11 @@ pub mod cache {
         client_info_cache_size_metric: opentelemetry::metrics::Gauge<u64>,
         client_info_cache_hit_counter_metric: opentelemetry::metrics::Counter<u64>,
         client_info_cache_miss_counter_metric: opentelemetry::metrics::Counter<u64>,
-        // proof_cache: moka::future::Cache,
+
+        proof_cache: moka::future::Cache<ProofRequest, (Value, ProofType)>,
+        proof_cache_size_metric: opentelemetry::metrics::Gauge<u64>,
+        proof_cache_hit_counter_metric: opentelemetry::metrics::Counter<u64>,
+        proof_cache_miss_counter_metric: opentelemetry::metrics::Counter<u64>,
     }
 
     impl Cache {
@@ -109,6 +111,22 @@ pub mod cache {
                 )
                 .u64_counter("miss")
                 .build(),
+                proof_cache: moka::future::CacheBuilder::new(config.proof.capacity)
+                    .time_to_live(Duration::from_secs(config.proof.time_to_live))
+                    .time_to_idle(Duration::from_secs(config.proof.time_to_idle))
+                    .eviction_policy(EvictionPolicy::lru())
+                    .build(),
+                proof_cache_size_metric: opentelemetry::global::meter("voyager.cache.proof")
+                    .u64_gauge("size")
+                    .build(),
+                proof_cache_hit_counter_metric: opentelemetry::global::meter("voyager.cache.proof")
+                    .u64_counter("hit")
+                    .build(),
+                proof_cache_miss_counter_metric: opentelemetry::global::meter(
+                    "voyager.cache.proof",
+                )
+                .u64_counter("miss")
+                .build(),
             }
         }
 
@@ -199,11 +217,50 @@ pub mod cache {
                 None => Ok(None),
             }
         }
+
+        pub async fn proof(
+            &self,
+            proof_request: ProofRequest,
+            fut: impl Future<Output = RpcResult<Option<(Value, ProofType)>>>,
+        ) -> RpcResult<Option<(Value, ProofType)>> {
+            let attributes = &[KeyValue::new(
+                "chain_id",
+                proof_request.chain_id.to_string(),
+            )];
+
+            self.proof_cache_size_metric
+                .record(self.proof_cache.entry_count(), attributes);
+
+            if let Some(proof) = self.proof_cache.get(&proof_request).await {
+                self.proof_cache_hit_counter_metric.add(1, attributes);
+
+                return Ok(Some(proof));
+            };
+
+            self.proof_cache_miss_counter_metric.add(1, attributes);
+
+            match fut.await? {
+                Some(init) => {
+                    let entry = self.proof_cache.entry(proof_request).or_insert(init).await;
+
+                    let (proof, proof_type) = entry.into_value();
+
+                    trace!(%proof, ?proof_type, "cached value");
+
+                    Ok(Some((proof, proof_type)))
+                }
+                None => Ok(None),
+            }
+        }
     }
 
     #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
     pub struct Config {
         pub state: CacheConfig,
+        /// Proofs are immutable for a given height, but are only requested again while the same
+        /// packets are being relayed. Disabled if not set.
+        #[serde(default)]
+        pub proof: CacheConfig,
     }
 
     #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
@@ -250,6 +307,43 @@ pub mod cache {
         }
     }
 
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub struct ProofRequest {
+        chain_id: ChainId,
+        ibc_spec_id: IbcSpecId,
+        height: Height,
+        path: Value,
+    }
+
+    impl ProofRequest {
+        pub fn new<P: IbcStorePathKey>(
+            chain_id: ChainId,
+            height: Height,
+            path: <P::Spec as IbcSpec>::StorePath,
+        ) -> Self {
+            Self {
+                chain_id,
+                ibc_spec_id: P::Spec::ID,
+                height,
+                path: serde_json::to_value(path).expect("serialization is infallible; qed;"),
+            }
+        }
+
+        pub fn new_raw(
+            chain_id: ChainId,
+            ibc_spec_id: IbcSpecId,
+            height: Height,
+            path: Value,
+        ) -> Self {
+            Self {
+                chain_id,
+                ibc_spec_id,
+                height,
+                path,
+            }
+        }
+    }
+
     #[derive(Debug, Clone, PartialEq, Eq, Hash)]
     pub struct ClientInfoRequest {
         chain_id: ChainId,
@@ -284,7 +378,7 @@ pub struct Server {
 
 #[derive(Debug, Clone)]
 pub struct ServerInner {
-    modules: OnceLock<Arc<Modules>>,
+    modules: ArcSwapOption<Modules>,
     cache: cache::Cache,
 }
 
@@ -293,17 +387,17 @@ impl Server {
     pub fn new(cache_config: cache::Config) -> Self {
         Server {
             inner: Arc::new(ServerInner {
-                modules: OnceLock::new(),
+                modules: ArcSwapOption::empty(),
                 cache: cache::Cache::new(cache_config),
             }),
             item_id: None,
         }
     }
 
-    pub fn start(&self, modules: Arc<Modules>) {
-        let was_not_already_started = self.inner.modules.set(modules).is_ok();
-
-        assert!(was_not_already_started, "server has already been started");
+    /// Replaces the loaded modules. Requests that are already being processed keep using the
+    /// modules they started with.
+    pub fn set_modules(&self, modules: Arc<Modules>) {
+        self.inner.modules.store(Some(modules));
     }
 
     pub fn with_id(&self, item_id: Option<ItemId>) -> Server {
@@ -321,7 +415,7 @@ impl Server {
     }
 
     /// Returns the contained modules, if they have been loaded.
-    pub fn modules(&self) -> RpcResult<&Modules> {
+    pub fn modules(&self) -> RpcResult<Arc<Modules>> {
         self.inner.modules()
     }
 
@@ -361,10 +455,9 @@ impl Server {
 
 impl ServerInner {
     /// Returns the contained modules, if they have been loaded.
-    fn modules(&self) -> RpcResult<&Modules> {
+    fn modules(&self) -> RpcResult<Arc<Modules>> {
         self.modules
-            .get()
-            .map(|x| &**x)
+            .load_full()
             .ok_or_else(|| ErrorObject::owned(-2, "server has not started", None::<()>))
     }
 }
@@ -503,8 +596,7 @@ impl Server {
                     return Ok(None);
                 };
 
-                let ibc_spec_handler = self
-                    .modules()?
+                let ibc_spec_handler = modules
                     .ibc_spec_handlers
                     .handlers
                     .get(ibc_spec_id)
@@ -597,8 +689,7 @@ impl Server {
                     return Ok(None);
                 };
 
-                let ibc_spec_handler = self
-                    .modules()?
+                let ibc_spec_handler = modules
                     .ibc_spec_handlers
                     .handlers
                     .get(ibc_spec_id)
@@ -685,9 +776,9 @@ impl Server {
             .in_scope(|| async {
                 trace!("query");
 
-                let state_module = self
-                    .inner
-                    .modules()?
+                let modules = self.inner.modules()?;
+
+                let state_module = modules
                     .state_module(&chain_id, &ibc_spec_id)?
                     .with_id(self.item_id);
 
@@ -718,9 +809,9 @@ impl Server {
 
                 let height = self.query_height(&chain_id, height).await?;
 
-                let state_module = self
-                    .inner
-                    .modules()?
+                let modules = self.inner.modules()?;
+
+                let state_module = modules
                     .state_module(&chain_id, &ibc_spec_id)?
                     .with_id(self.item_id);
 
@@ -769,16 +860,27 @@ impl Server {
 
                 debug!("fetching ibc proof");
 
-                let proof_module = self
-                    .inner
-                    .modules()?
+                let modules = self.inner.modules()?;
+
+                let proof_module = modules
                     .proof_module(&chain_id, &ibc_spec_id)?
                     .with_id(self.item_id);
 
-                let res = proof_module
-                    .query_ibc_proof_raw(height, path)
-                    .await
-                    .map_err(json_rpc_error_to_error_object)?;
+                let res = self
+                    .inner
+                    .cache
+                    .proof(
+                        ProofRequest::new_raw(
+                            chain_id.clone(),
+                            ibc_spec_id.clone(),
+                            height,
+                            path.clone(),
+                        ),
+                        proof_module
+                            .query_ibc_proof_raw(height, path)
+                            .map_err(json_rpc_error_to_error_object),
+                    )
+                    .await?;
 
                 // TODO: Use valuable here
                 debug!(result = %into_value(&res), "fetched ibc proof");
@@ -807,9 +909,9 @@ impl Server {
             .in_scope(|| async {
                 trace!("fetching ibc state");
 
-                let state_module = self
-                    .inner
-                    .modules()?
+                let modules = self.inner.modules()?;
+
+                let state_module = modules
                     .state_module(chain_id, &P::Spec::ID)?
                     .with_id(self.item_id);
 
@@ -854,16 +956,22 @@ impl Server {
             .in_scope(|| async {
                 trace!("fetching ibc state");
 
-                let proof_module = self
-                    .inner
-                    .modules()?
+                let modules = self.inner.modules()?;
+
+                let proof_module = modules
                     .proof_module(chain_id, &P::Spec::ID)?
                     .with_id(self.item_id);
 
-                let res = proof_module
-                    .query_ibc_proof_raw(height, into_value(path.clone()))
-                    .await
-                    .map_err(json_rpc_error_to_error_object)?;
+                let res = self
+                    .inner
+                    .cache
+                    .proof(
+                        ProofRequest::new::<P>(chain_id.clone(), height, path.clone()),
+                        proof_module
+                            .query_ibc_proof_raw(height, into_value(path.clone()))
+                            .map_err(json_rpc_error_to_error_object),
+                    )
+                    .await?;
 
                 // TODO: Use valuable here
                 debug!(result = %into_value(&res), "fetched ibc proof");
@@ -893,9 +1001,9 @@ impl Server {
             .in_scope(|| async {
                 trace!("querying self client state");
 
-                let client_bootstrap_module = self
-                    .inner
-                    .modules()?
+                let modules = self.inner.modules()?;
+
+                let client_bootstrap_module = modules
                     .client_bootstrap_module(&chain_id, &client_type)?
                     .with_id(self.item_id);
 
@@ -924,9 +1032,9 @@ impl Server {
             .in_scope(|| async {
                 trace!("querying self consensus state");
 
-                let client_bootstrap_module = self
-                    .inner
-                    .modules()?
+                let modules = self.inner.modules()?;
+
+                let client_bootstrap_module = modules
                     .client_bootstrap_module(&chain_id, &client_type)?
                     .with_id(self.item_id);
 
@@ -958,9 +1066,9 @@ impl Server {
             .in_scope(|| async {
                 trace!("encoding proof");
 
-                let client_module = self
-                    .inner
-                    .modules()?
+                let modules = self.inner.modules()?;
+
+                let client_module = modules
                     .client_module(client_type, ibc_interface, ibc_spec_id)?
                     .with_id(self.item_id);
 
@@ -989,9 +1097,9 @@ impl Server {
             .in_scope(|| async {
                 trace!("encoding header");
 
-                let client_module = self
-                    .inner
-                    .modules()?
+                let modules = self.inner.modules()?;
+
+                let client_module = modules
                     .client_module(client_type, ibc_interface, ibc_spec_id)?
                     .with_id(self.item_id);
 
@@ -1020,9 +1128,9 @@ impl Server {
             .in_scope(|| async {
                 trace!("decoding client state meta");
 
-                let client_module = self
-                    .inner
-                    .modules()?
+                let modules = self.inner.modules()?;
+
+                let client_module = modules
                     .client_module(client_type, ibc_interface, ibc_spec_id)?
                     .with_id(self.item_id);
 
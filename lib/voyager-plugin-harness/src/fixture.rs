@@ -0,0 +1,138 @@
+use std::path::Path;
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use unionlabs::ibc::core::client::height::Height;
+use voyager_message::{
+    primitives::{ChainId, ClientInfo, ConsensusType, IbcSpecId, Timestamp},
+    rpc::ProofType,
+};
+
+/// Scripted chain state, served by the mock state, proof and consensus modules of the
+/// [`Harness`](crate::Harness).
+///
+/// All paths, client ids and queries are the JSON representation of the respective types of the
+/// IBC specification of the chain, and are matched exactly.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Fixture {
+    pub chains: Vec<ChainFixture>,
+}
+
+impl Fixture {
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("reading fixture {}", path.display()))?;
+
+        Self::from_json(&json).with_context(|| format!("parsing fixture {}", path.display()))
+    }
+
+    pub fn chain(&self, chain_id: &ChainId) -> Option<&ChainFixture> {
+        self.chains.iter().find(|chain| &chain.chain_id == chain_id)
+    }
+
+    pub fn chain_mut(&mut self, chain_id: &ChainId) -> Option<&mut ChainFixture> {
+        self.chains
+            .iter_mut()
+            .find(|chain| &chain.chain_id == chain_id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainFixture {
+    pub chain_id: ChainId,
+    pub ibc_spec_id: IbcSpecId,
+    pub consensus_type: ConsensusType,
+    pub latest_height: Height,
+    /// The latest finalized height of the chain. Defaults to `latest_height` if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finalized_height: Option<Height>,
+    pub latest_timestamp: Timestamp,
+    /// The latest finalized timestamp of the chain. Defaults to `latest_timestamp` if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finalized_timestamp: Option<Timestamp>,
+    #[serde(default)]
+    pub state: Vec<StateFixture>,
+    #[serde(default)]
+    pub proofs: Vec<ProofFixture>,
+    #[serde(default)]
+    pub clients: Vec<ClientFixture>,
+    #[serde(default)]
+    pub queries: Vec<QueryFixture>,
+}
+
+impl ChainFixture {
+    /// The state at `path` as of `height`, i.e. the most recent write to `path` at or before
+    /// `height`. Returns [`Value::Null`] if the state does not exist.
+    pub fn state_at(&self, height: Height, path: &Value) -> Value {
+        self.state
+            .iter()
+            .filter(|state| &state.path == path && state.height <= height)
+            .max_by_key(|state| state.height)
+            .map(|state| state.state.clone())
+            .unwrap_or(Value::Null)
+    }
+
+    /// The proof of `path` at exactly `height`.
+    pub fn proof_at(&self, height: Height, path: &Value) -> Option<(Value, ProofType)> {
+        self.proofs
+            .iter()
+            .find(|proof| &proof.path == path && proof.height == height)
+            .map(|proof| (proof.proof.clone(), proof.proof_type))
+    }
+
+    pub fn client_info(&self, client_id: &Value) -> Option<&ClientInfo> {
+        self.clients
+            .iter()
+            .find(|client| &client.client_id == client_id)
+            .map(|client| &client.info)
+    }
+
+    pub fn query(&self, query: &Value) -> Option<&Value> {
+        self.queries
+            .iter()
+            .find(|q| &q.query == query)
+            .map(|q| &q.response)
+    }
+}
+
+/// A write of `state` to `path` at `height`. The state is visible at all later heights until it
+/// is written again, a `null` state deletes it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StateFixture {
+    pub height: Height,
+    pub path: Value,
+    pub state: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProofFixture {
+    pub height: Height,
+    pub path: Value,
+    pub proof: Value,
+    pub proof_type: ProofType,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClientFixture {
+    pub client_id: Value,
+    pub info: ClientInfo,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QueryFixture {
+    pub query: Value,
+    pub response: Value,
+}
@@ -0,0 +1,392 @@
+//! A deterministic simulation harness for voyager plugins. See the README for an overview.
+
+use std::{
+    collections::VecDeque,
+    net::{Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context as _};
+use futures::{future::LocalBoxFuture, FutureExt};
+use ibc_classic_spec::IbcClassic;
+use ibc_union_spec::IbcUnion;
+use jsonrpsee::server::{middleware::rpc::RpcServiceBuilder, ServerHandle};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tracing::{debug, info};
+use voyager_message::{
+    context::{
+        equivalent_chain_ids::EquivalentChainIds, ibc_spec_handler::IbcSpecHandlers, Context,
+        ExtractItemId, ModuleConfig, ModulesConfig, PluginConfig, RemoteConfig,
+    },
+    data::Data,
+    into_value,
+    limits::LimitsConfig,
+    module::{ConsensusModuleInfo, PluginClient, ProofModuleInfo, StateModuleInfo},
+    pass::PluginOptPass,
+    rpc::{server::cache, VoyagerRpcServer},
+    serve_in_process, Plugin, VoyagerMessage,
+};
+use voyager_vm::{in_memory::InMemoryQueue, pass::PassResult, ItemId, Op, Queue};
+
+use crate::{fixture::Fixture, mock::MockChain};
+
+pub mod fixture;
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// Boots an in-process voyager [`Context`] with the plugins under test and mock state, proof and
+/// consensus modules for every chain in a [`Fixture`], along with an [`InMemoryQueue`] that is
+/// driven one item at a time.
+///
+/// Nothing runs in the background: items are only processed by [`Harness::step`] and
+/// [`Harness::run`], so the resulting [`Op`]s only depend on the plugins and the fixture.
+pub struct Harness {
+    context: Context,
+    queue: InMemoryQueue<VoyagerMessage>,
+    fixture: Arc<RwLock<Fixture>>,
+    servers: Vec<ServerHandle>,
+}
+
+/// A single item processed by the queue of a [`Harness`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub item_id: ItemId,
+    /// The op that was processed.
+    pub op: Op<VoyagerMessage>,
+    /// The ops that were requeued as a result of processing `op`, or the error if processing it
+    /// failed. Items that fail with a retryable error are requeued as is.
+    pub result: Result<Vec<Op<VoyagerMessage>>, String>,
+}
+
+type StartPlugin = Box<
+    dyn FnOnce(String) -> LocalBoxFuture<'static, anyhow::Result<(PluginConfig, ServerHandle)>>,
+>;
+
+pub struct HarnessBuilder {
+    fixture: Fixture,
+    plugins: Vec<StartPlugin>,
+    plugin_configs: Vec<PluginConfig>,
+    modules: ModulesConfig,
+    register_ibc_spec_handlers: fn(&mut IbcSpecHandlers),
+}
+
+impl HarnessBuilder {
+    /// Run the plugin `P` in the current process with the provided config.
+    pub fn plugin<P: Plugin>(mut self, config: P::Config) -> Self
+    where
+        P::Config: 'static,
+    {
+        self.plugins.push(Box::new(move |voyager_url| {
+            async move {
+                let info = P::info(config.clone());
+
+                let plugin = P::new(config)
+                    .await
+                    .map_err(|err| anyhow!(err))
+                    .with_context(|| format!("starting plugin {}", info.name))?;
+
+                let (addr, server) = serve_in_process(
+                    info.name.clone(),
+                    (Ipv4Addr::LOCALHOST, 0).into(),
+                    voyager_url,
+                    plugin.into_rpc_with_info(info),
+                )
+                .await?;
+
+                Ok((
+                    PluginConfig {
+                        path: None,
+                        remote: Some(remote(addr)),
+                        config: json!({}),
+                        enabled: true,
+                    },
+                    server,
+                ))
+            }
+            .boxed_local()
+        }));
+
+        self
+    }
+
+    /// Additional plugins to run, such as plugin binaries spawned by the [`Context`].
+    pub fn plugin_config(mut self, plugin_config: PluginConfig) -> Self {
+        self.plugin_configs.push(plugin_config);
+        self
+    }
+
+    /// Additional modules to run alongside the mock modules of the fixture, such as client
+    /// modules.
+    pub fn modules(mut self, modules: ModulesConfig) -> Self {
+        self.modules = modules;
+        self
+    }
+
+    /// Defaults to registering [`IbcClassic`] and [`IbcUnion`].
+    pub fn register_ibc_spec_handlers(
+        mut self,
+        register_ibc_spec_handlers: fn(&mut IbcSpecHandlers),
+    ) -> Self {
+        self.register_ibc_spec_handlers = register_ibc_spec_handlers;
+        self
+    }
+
+    pub async fn build(self) -> anyhow::Result<Harness> {
+        let rpc_server = jsonrpsee::server::Server::builder()
+            .set_rpc_middleware(RpcServiceBuilder::new().layer_fn(ExtractItemId::new))
+            .build(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+            .await?;
+
+        let voyager_url = format!("ws://{}", rpc_server.local_addr()?);
+
+        debug!(%voyager_url, "starting harness");
+
+        let fixture = Arc::new(RwLock::new(self.fixture));
+
+        let mut servers = vec![];
+
+        let mut modules = self.modules;
+
+        let chains = fixture.read().expect("rwlock is poisoned").chains.clone();
+
+        for chain in chains {
+            let (addr, server) = serve_in_process(
+                format!("mock/{}", chain.chain_id),
+                (Ipv4Addr::LOCALHOST, 0).into(),
+                voyager_url.clone(),
+                MockChain::new(fixture.clone(), chain.chain_id.clone()).into_rpc(),
+            )
+            .await?;
+
+            servers.push(server);
+
+            modules.state.push(mock_module_config(
+                addr,
+                StateModuleInfo {
+                    chain_id: chain.chain_id.clone(),
+                    ibc_spec_id: chain.ibc_spec_id.clone(),
+                },
+            ));
+            modules.proof.push(mock_module_config(
+                addr,
+                ProofModuleInfo {
+                    chain_id: chain.chain_id.clone(),
+                    ibc_spec_id: chain.ibc_spec_id.clone(),
+                },
+            ));
+            modules.consensus.push(mock_module_config(
+                addr,
+                ConsensusModuleInfo {
+                    chain_id: chain.chain_id.clone(),
+                    consensus_type: chain.consensus_type.clone(),
+                },
+            ));
+        }
+
+        let mut plugin_configs = vec![];
+
+        for start_plugin in self.plugins {
+            let (plugin_config, server) = start_plugin(voyager_url.clone()).await?;
+
+            plugin_configs.push(plugin_config);
+            servers.push(server);
+        }
+
+        plugin_configs.extend(self.plugin_configs);
+
+        let context = Context::new(
+            plugin_configs,
+            modules,
+            EquivalentChainIds::default(),
+            self.register_ibc_spec_handlers,
+            Duration::from_secs(60),
+            // caching would make changes to the fixture invisible
+            cache::Config::default(),
+            LimitsConfig::default(),
+        )
+        .await?;
+
+        servers.push(rpc_server.start(context.rpc_server.clone().into_rpc()));
+
+        let queue = InMemoryQueue::new(())
+            .await
+            .expect("the in memory queue is infallible; qed;");
+
+        info!("harness started");
+
+        Ok(Harness {
+            context,
+            queue,
+            fixture,
+            servers,
+        })
+    }
+}
+
+impl Harness {
+    pub fn builder(fixture: Fixture) -> HarnessBuilder {
+        HarnessBuilder {
+            fixture,
+            plugins: vec![],
+            plugin_configs: vec![],
+            modules: ModulesConfig::default(),
+            register_ibc_spec_handlers: |h| {
+                h.register::<IbcClassic>();
+                h.register::<IbcUnion>();
+            },
+        }
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Modify the fixture. Changes are visible to all subsequent requests to the mock modules.
+    pub fn update_fixture(&self, f: impl FnOnce(&mut Fixture)) {
+        f(&mut self.fixture.write().expect("rwlock is poisoned"));
+    }
+
+    /// Call `plugin` directly with `call`, returning the op it produced without queueing it.
+    pub async fn call(
+        &self,
+        plugin: &str,
+        call: impl Serialize + std::fmt::Debug,
+    ) -> anyhow::Result<Op<VoyagerMessage>> {
+        Ok(
+            PluginClient::<Value, Value>::call(&self.context.plugin(plugin)?, into_value(call))
+                .await?,
+        )
+    }
+
+    /// Call `plugin` directly with `callback` and `data`, returning the op it produced without
+    /// queueing it.
+    pub async fn callback(
+        &self,
+        plugin: &str,
+        callback: impl Serialize + std::fmt::Debug,
+        data: VecDeque<Data>,
+    ) -> anyhow::Result<Op<VoyagerMessage>> {
+        Ok(PluginClient::<Value, Value>::callback(
+            &self.context.plugin(plugin)?,
+            into_value(callback),
+            data,
+        )
+        .await?)
+    }
+
+    /// Run an optimization pass of `plugin` directly over `ops`, without queueing the result.
+    pub async fn run_pass(
+        &self,
+        plugin: &str,
+        ops: Vec<Op<VoyagerMessage>>,
+    ) -> anyhow::Result<PassResult<VoyagerMessage>> {
+        Ok(PluginClient::<Value, Value>::run_pass(&self.context.plugin(plugin)?, ops).await?)
+    }
+
+    /// Push `op` to the queue, running the interest filters of the loaded plugins over it.
+    pub async fn enqueue(&self, op: Op<VoyagerMessage>) {
+        self.queue
+            .enqueue(op, self.context.interest_filter())
+            .await
+            .expect("the in memory queue is infallible; qed;");
+    }
+
+    /// Process the item at the front of the queue, returning `None` if there are no items ready
+    /// to be processed.
+    pub async fn step(&self) -> Option<Step> {
+        let step = Mutex::new(None);
+
+        self.queue
+            .process::<_, _, ()>(self.context.interest_filter(), |op, item_id| {
+                op.clone()
+                    .process(voyager_vm::Context::new(item_id, &self.context), 0)
+                    .map(|res| {
+                        let res = res.map(|op| op.into_iter().collect::<Vec<_>>());
+
+                        *step.lock().expect("mutex is poisoned") = Some(Step {
+                            item_id,
+                            op,
+                            result: match &res {
+                                Ok(ops) => Ok(ops.clone()),
+                                Err(err) => Err(err.to_string()),
+                            },
+                        });
+
+                        ((), res)
+                    })
+            })
+            .await
+            .expect("the in memory queue is infallible; qed;");
+
+        step.into_inner().expect("mutex is poisoned")
+    }
+
+    /// Run the optimization passes of all of the loaded plugins over the items they are
+    /// interested in, in the order the plugins are configured in.
+    pub async fn optimize(&self) -> anyhow::Result<()> {
+        for (plugin_name, _, _) in self.context.plugins().await {
+            let client = self.context.plugin(&plugin_name)?;
+
+            self.queue
+                .optimize(
+                    &plugin_name,
+                    self.context.interest_filter(),
+                    &PluginOptPass::new(&client),
+                )
+                .await
+                .map_err(|err| anyhow!("error optimizing {plugin_name}: {err}"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Alternate between running the optimization passes and processing a single item, until
+    /// there are no more items ready to be processed or `max_steps` items have been processed.
+    ///
+    /// Note that items that are waiting on a `defer` are requeued every time they are processed,
+    /// and as such will keep the queue busy until `max_steps` is hit.
+    pub async fn run(&self, max_steps: usize) -> anyhow::Result<Vec<Step>> {
+        let mut steps = vec![];
+
+        while steps.len() < max_steps {
+            self.optimize().await?;
+
+            match self.step().await {
+                Some(step) => steps.push(step),
+                None => break,
+            }
+        }
+
+        Ok(steps)
+    }
+
+    pub async fn shutdown(self) {
+        for server in self.servers {
+            let _ = server.stop();
+        }
+
+        self.context.shutdown().await;
+    }
+}
+
+fn remote(addr: SocketAddr) -> RemoteConfig {
+    RemoteConfig {
+        url: format!("ws://{addr}"),
+        auth_token: None,
+        tls: None,
+    }
+}
+
+fn mock_module_config<Info>(addr: SocketAddr, info: Info) -> ModuleConfig<Info> {
+    ModuleConfig {
+        path: None,
+        remote: Some(remote(addr)),
+        info,
+        config: json!({}),
+        enabled: true,
+    }
+}
@@ -0,0 +1,120 @@
+use std::sync::{Arc, RwLock};
+
+use jsonrpsee::{
+    core::RpcResult,
+    types::{ErrorObject, ErrorObjectOwned},
+    RpcModule,
+};
+use serde_json::Value;
+use unionlabs::ibc::core::client::height::Height;
+use voyager_message::{
+    primitives::{ChainId, Timestamp},
+    rpc::ProofType,
+    FATAL_JSONRPC_ERROR_CODE,
+};
+
+use crate::fixture::{ChainFixture, Fixture};
+
+/// Mock state, proof and consensus module for a single chain of a [`Fixture`].
+///
+/// The fixture is read on every request, so changes made to it while the harness is running are
+/// visible immediately.
+pub(crate) struct MockChain {
+    fixture: Arc<RwLock<Fixture>>,
+    chain_id: ChainId,
+}
+
+impl MockChain {
+    pub(crate) fn new(fixture: Arc<RwLock<Fixture>>, chain_id: ChainId) -> Self {
+        Self { fixture, chain_id }
+    }
+
+    fn with_chain<T>(&self, f: impl FnOnce(&ChainFixture) -> RpcResult<T>) -> RpcResult<T> {
+        let fixture = self.fixture.read().expect("rwlock is poisoned");
+
+        let chain = fixture.chain(&self.chain_id).ok_or_else(|| {
+            fatal(format!(
+                "chain {} was removed from the fixture",
+                self.chain_id
+            ))
+        })?;
+
+        f(chain)
+    }
+
+    /// All of the methods of the state, proof and consensus modules. Since they are namespaced,
+    /// a single server can be used for all three modules.
+    pub(crate) fn into_rpc(self) -> RpcModule<Self> {
+        let mut rpcs = RpcModule::new(self);
+
+        rpcs.register_method("state_query", |params, mock, _| {
+            let (query,) = params.parse::<(Value,)>()?;
+
+            mock.with_chain(|chain| {
+                chain
+                    .query(&query)
+                    .cloned()
+                    .ok_or_else(|| fatal(format!("no response for query {query}")))
+            })
+        })
+        .expect("method is only registered once; qed;");
+
+        rpcs.register_method("state_queryIbcState", |params, mock, _| {
+            let (height, path) = params.parse::<(Height, Value)>()?;
+
+            mock.with_chain(|chain| Ok(chain.state_at(height, &path)))
+        })
+        .expect("method is only registered once; qed;");
+
+        rpcs.register_method("state_clientInfo", |params, mock, _| {
+            let (client_id,) = params.parse::<(Value,)>()?;
+
+            mock.with_chain(|chain| {
+                chain
+                    .client_info(&client_id)
+                    .cloned()
+                    .ok_or_else(|| fatal(format!("client {client_id} not found")))
+            })
+        })
+        .expect("method is only registered once; qed;");
+
+        rpcs.register_method("proof_queryIbcProof", |params, mock, _| {
+            let (height, path) = params.parse::<(Height, Value)>()?;
+
+            mock.with_chain(|chain| -> RpcResult<Option<(Value, ProofType)>> {
+                Ok(chain.proof_at(height, &path))
+            })
+        })
+        .expect("method is only registered once; qed;");
+
+        rpcs.register_method("consensus_queryLatestHeight", |params, mock, _| {
+            let (finalized,) = params.parse::<(bool,)>()?;
+
+            mock.with_chain(|chain| -> RpcResult<Height> {
+                Ok(match chain.finalized_height {
+                    Some(finalized_height) if finalized => finalized_height,
+                    _ => chain.latest_height,
+                })
+            })
+        })
+        .expect("method is only registered once; qed;");
+
+        rpcs.register_method("consensus_queryLatestTimestamp", |params, mock, _| {
+            let (finalized,) = params.parse::<(bool,)>()?;
+
+            mock.with_chain(|chain| -> RpcResult<Timestamp> {
+                Ok(match chain.finalized_timestamp {
+                    Some(finalized_timestamp) if finalized => finalized_timestamp,
+                    _ => chain.latest_timestamp,
+                })
+            })
+        })
+        .expect("method is only registered once; qed;");
+
+        rpcs
+    }
+}
+
+fn fatal(message: String) -> ErrorObjectOwned {
+    ErrorObject::owned(FATAL_JSONRPC_ERROR_CODE, message, None::<()>)
+}
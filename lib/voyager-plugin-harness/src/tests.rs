@@ -0,0 +1,283 @@
+use std::{net::Ipv4Addr, time::Duration};
+
+use ibc_union_spec::{
+    datagram::MsgConnectionOpenTry, event::ConnectionOpenInit, ClientId, ConnectionId, IbcUnion,
+};
+use jsonrpsee::{core::RpcResult, RpcModule};
+use serde_json::{json, Value};
+use unionlabs::{
+    ibc::core::client::height::Height,
+    primitives::{encoding::HexPrefixed, Bytes},
+};
+use voyager_message::{
+    context::{ModuleConfig, ModulesConfig, RemoteConfig},
+    data::IbcDatagram,
+    module::ClientModuleInfo,
+    primitives::{ChainId, ClientType, ConsensusType, IbcInterface, IbcSpec},
+    PluginMessage,
+};
+use voyager_plugin_transaction_batch::{
+    self as transaction_batch,
+    call::{MakeMsg, ModuleCall},
+    data::EventUnion,
+    ClientConfig, ClientConfigsSerde,
+};
+use voyager_vm::{call, data};
+
+use crate::{fixture::Fixture, Harness};
+
+fn fixture() -> Fixture {
+    Fixture::from_json(
+        &json!({
+            "chains": [
+                {
+                    "chain_id": "union-devnet-1",
+                    "ibc_spec_id": "ibc-union",
+                    "consensus_type": "cometbls",
+                    "latest_height": "1-100",
+                    "finalized_height": "1-90",
+                    "latest_timestamp": 1000,
+                    "state": [
+                        {
+                            "height": "1-10",
+                            "path": { "connection": { "connection_id": 1 } },
+                            "state": "a"
+                        },
+                        {
+                            "height": "1-20",
+                            "path": { "connection": { "connection_id": 1 } },
+                            "state": "b"
+                        },
+                        {
+                            "height": "1-30",
+                            "path": { "connection": { "connection_id": 1 } },
+                            "state": null
+                        }
+                    ]
+                }
+            ]
+        })
+        .to_string(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn state_at() {
+    let fixture = fixture();
+    let chain = fixture.chain(&ChainId::new("union-devnet-1")).unwrap();
+
+    let path = json!({ "connection": { "connection_id": 1 } });
+
+    assert_eq!(
+        chain.state_at(Height::new_with_revision(1, 5), &path),
+        Value::Null
+    );
+    assert_eq!(
+        chain.state_at(Height::new_with_revision(1, 10), &path),
+        json!("a")
+    );
+    assert_eq!(
+        chain.state_at(Height::new_with_revision(1, 25), &path),
+        json!("b")
+    );
+    assert_eq!(
+        chain.state_at(Height::new_with_revision(1, 35), &path),
+        Value::Null
+    );
+    assert_eq!(
+        chain.state_at(
+            Height::new_with_revision(1, 25),
+            &json!({ "connection": { "connection_id": 2 } })
+        ),
+        Value::Null
+    );
+}
+
+#[tokio::test]
+async fn mock_consensus_module() {
+    let harness = Harness::builder(fixture()).build().await.unwrap();
+
+    let chain_id = ChainId::new("union-devnet-1");
+
+    let rpc_server = &harness.context().rpc_server;
+
+    assert_eq!(
+        rpc_server
+            .query_latest_height(&chain_id, false)
+            .await
+            .unwrap(),
+        Height::new_with_revision(1, 100)
+    );
+    assert_eq!(
+        rpc_server
+            .query_latest_height(&chain_id, true)
+            .await
+            .unwrap(),
+        Height::new_with_revision(1, 90)
+    );
+
+    harness.update_fixture(|fixture| {
+        fixture.chain_mut(&chain_id).unwrap().latest_height = Height::new_with_revision(1, 101);
+    });
+
+    assert_eq!(
+        rpc_server
+            .query_latest_height(&chain_id, false)
+            .await
+            .unwrap(),
+        Height::new_with_revision(1, 101)
+    );
+
+    harness.shutdown().await;
+}
+
+/// Drives a `MakeMsg` call of the transaction-batch plugin through the queue, with a mock client
+/// module on the target chain to encode the proof.
+#[tokio::test]
+async fn transaction_batch_make_msg() {
+    let origin_chain_id = ChainId::new("union-devnet-1");
+    let target_chain_id = ChainId::new("ethereum-devnet-1");
+
+    let connection_path = json!({ "connection": { "connection_id": 1 } });
+
+    let fixture = Fixture::from_json(
+        &json!({
+            "chains": [
+                {
+                    "chain_id": origin_chain_id,
+                    "ibc_spec_id": "ibc-union",
+                    "consensus_type": "cometbls",
+                    "latest_height": "1-100",
+                    "latest_timestamp": 1000,
+                    "state": [
+                        {
+                            "height": "1-10",
+                            "path": connection_path,
+                            "state": {
+                                "state": "init",
+                                "client_id": 1,
+                                "counterparty_client_id": 2,
+                                "counterparty_connection_id": null
+                            }
+                        }
+                    ],
+                    "proofs": [
+                        {
+                            "height": "1-10",
+                            "path": connection_path,
+                            "proof": "proof",
+                            "proof_type": "membership"
+                        }
+                    ]
+                },
+                {
+                    "chain_id": target_chain_id,
+                    "ibc_spec_id": "ibc-union",
+                    "consensus_type": "ethereum",
+                    "latest_height": "0-50",
+                    "latest_timestamp": 1000,
+                    "clients": [
+                        {
+                            "client_id": 2,
+                            "info": {
+                                "client_type": "cometbls",
+                                "ibc_interface": "ibc-solidity"
+                            }
+                        }
+                    ]
+                }
+            ]
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let mut client_module = RpcModule::new(());
+    client_module
+        .register_method(
+            "client_encodeProof",
+            |params, _, _| -> RpcResult<Bytes<HexPrefixed>> {
+                let (proof,) = params.parse::<(Value,)>()?;
+
+                Ok(serde_json::to_vec(&proof).unwrap().into())
+            },
+        )
+        .unwrap();
+
+    let client_module_server = jsonrpsee::server::Server::builder()
+        .build((Ipv4Addr::LOCALHOST, 0))
+        .await
+        .unwrap();
+    let client_module_addr = client_module_server.local_addr().unwrap();
+    let client_module_server = client_module_server.start(client_module);
+
+    let harness = Harness::builder(fixture)
+        .modules(ModulesConfig {
+            client: vec![ModuleConfig {
+                path: None,
+                remote: Some(RemoteConfig {
+                    url: format!("ws://{client_module_addr}"),
+                    auth_token: None,
+                    tls: None,
+                }),
+                info: ClientModuleInfo {
+                    client_type: ClientType::new_static(ClientType::COMETBLS),
+                    consensus_type: ConsensusType::new_static(ConsensusType::COMETBLS),
+                    ibc_interface: IbcInterface::new_static(IbcInterface::IBC_SOLIDITY),
+                    ibc_spec_id: IbcUnion::ID,
+                },
+                config: json!({}),
+                enabled: true,
+            }],
+            ..Default::default()
+        })
+        .plugin::<transaction_batch::Module>(transaction_batch::Config {
+            chain_id: target_chain_id.clone(),
+            client_configs: ClientConfigsSerde::Any(ClientConfig {
+                min_batch_size: 1,
+                max_batch_size: 1,
+                max_wait_time: Duration::from_secs(10),
+            }),
+        })
+        .build()
+        .await
+        .unwrap();
+
+    let make_msg = call(PluginMessage::new(
+        transaction_batch::plugin_name(&target_chain_id),
+        ModuleCall::from(MakeMsg::<IbcUnion> {
+            origin_chain_id: origin_chain_id.clone(),
+            origin_chain_proof_height: Height::new_with_revision(1, 10),
+            target_chain_id: target_chain_id.clone(),
+            event: EventUnion::ConnectionOpenInit(ConnectionOpenInit {
+                connection_id: ConnectionId::from_raw(1).unwrap(),
+                client_id: ClientId::from_raw(1).unwrap(),
+                counterparty_client_id: ClientId::from_raw(2).unwrap(),
+            }),
+        }),
+    ));
+
+    let msg = data(IbcDatagram::new::<IbcUnion>(MsgConnectionOpenTry {
+        client_id: ClientId::from_raw(2).unwrap(),
+        counterparty_client_id: ClientId::from_raw(1).unwrap(),
+        counterparty_connection_id: ConnectionId::from_raw(1).unwrap(),
+        proof_init: serde_json::to_vec(&json!("proof")).unwrap().into(),
+        proof_height: 10,
+    }));
+
+    harness.enqueue(make_msg.clone()).await;
+
+    let steps = harness.run(10).await.unwrap();
+
+    assert_eq!(
+        steps
+            .into_iter()
+            .map(|step| (step.op, step.result))
+            .collect::<Vec<_>>(),
+        vec![(make_msg, Ok(vec![msg.clone()])), (msg, Ok(vec![]))]
+    );
+
+    harness.shutdown().await;
+    let _ = client_module_server.stop();
+}
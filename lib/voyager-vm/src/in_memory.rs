@@ -1,4 +1,5 @@
 use std::{
+    cmp::Reverse,
     collections::BTreeMap,
     future::Future,
     sync::{
@@ -14,13 +15,15 @@ use unionlabs::ErrorReporter;
 use crate::{
     filter::{FilterResult, Interest, InterestFilter},
     pass::Pass,
-    Captures, EnqueueResult, ItemId, Op, Queue, QueueError, QueueMessage,
+    Captures, EnqueueResult, ItemId, Op, Priority, Queue, QueueError, QueueMessage,
 };
 
 #[derive(Debug, Clone)]
 pub struct InMemoryQueue<T: QueueMessage> {
     idx: Arc<AtomicU32>,
-    ready: Arc<Mutex<BTreeMap<u32, Item<T>>>>,
+    /// Ordered by priority first, and then by insertion order.
+    #[allow(clippy::type_complexity)]
+    ready: Arc<Mutex<BTreeMap<(Reverse<Priority>, u32), Item<T>>>>,
     done: Arc<Mutex<BTreeMap<u32, Item<T>>>>,
     #[allow(clippy::type_complexity)]
     optimizer_queue: Arc<Mutex<BTreeMap<String, BTreeMap<u32, Item<T>>>>>,
@@ -30,6 +33,7 @@ pub struct InMemoryQueue<T: QueueMessage> {
 pub(crate) struct Item<T: QueueMessage> {
     #[allow(dead_code)] // used in debug
     parents: Vec<u32>,
+    priority: Priority,
     op: Op<T>,
 }
 
@@ -57,6 +61,9 @@ impl<T: QueueMessage> Queue<T> for InMemoryQueue<T> {
         let mut ready = self.ready.lock().expect("mutex is poisoned");
 
         for op in op.normalize() {
+            let (priority, op) = op.take_priority();
+            let priority = priority.unwrap_or_default();
+
             match filter.check_interest(&op) {
                 FilterResult::Interest(Interest { tags, remove }) => {
                     for tag in tags {
@@ -64,6 +71,7 @@ impl<T: QueueMessage> Queue<T> for InMemoryQueue<T> {
                             self.idx.fetch_add(1, Ordering::SeqCst),
                             Item {
                                 parents: vec![],
+                                priority,
                                 op: op.clone(),
                             },
                         );
@@ -71,9 +79,10 @@ impl<T: QueueMessage> Queue<T> for InMemoryQueue<T> {
 
                     if !remove {
                         ready.insert(
-                            self.idx.fetch_add(1, Ordering::SeqCst),
+                            (Reverse(priority), self.idx.fetch_add(1, Ordering::SeqCst)),
                             Item {
                                 parents: vec![],
+                                priority,
                                 op,
                             },
                         );
@@ -81,9 +90,10 @@ impl<T: QueueMessage> Queue<T> for InMemoryQueue<T> {
                 }
                 FilterResult::NoInterest => {
                     ready.insert(
-                        self.idx.fetch_add(1, Ordering::SeqCst),
+                        (Reverse(priority), self.idx.fetch_add(1, Ordering::SeqCst)),
                         Item {
                             parents: vec![],
+                            priority,
                             op,
                         },
                     );
@@ -119,7 +129,7 @@ impl<T: QueueMessage> Queue<T> for InMemoryQueue<T> {
         };
 
         match op {
-            Some((item_id, item)) => {
+            Some(((_, item_id), item)) => {
                 let span = info_span!("processing item", %item_id);
 
                 self.done
@@ -140,6 +150,9 @@ impl<T: QueueMessage> Queue<T> for InMemoryQueue<T> {
                 match res {
                     Ok(ops) => {
                         for op in ops.into_iter().flat_map(Op::normalize) {
+                            let (priority, op) = op.take_priority();
+                            let priority = priority.unwrap_or(item.priority);
+
                             match filter.check_interest(&op) {
                                 FilterResult::Interest(Interest { tags, remove }) => {
                                     for tag in tags {
@@ -147,6 +160,7 @@ impl<T: QueueMessage> Queue<T> for InMemoryQueue<T> {
                                             self.idx.fetch_add(1, Ordering::SeqCst),
                                             Item {
                                                 parents: vec![],
+                                                priority,
                                                 op: op.clone(),
                                             },
                                         );
@@ -154,9 +168,13 @@ impl<T: QueueMessage> Queue<T> for InMemoryQueue<T> {
 
                                     if !remove {
                                         ready.insert(
-                                            self.idx.fetch_add(1, Ordering::SeqCst),
+                                            (
+                                                Reverse(priority),
+                                                self.idx.fetch_add(1, Ordering::SeqCst),
+                                            ),
                                             Item {
                                                 parents: vec![],
+                                                priority,
                                                 op,
                                             },
                                         );
@@ -164,9 +182,13 @@ impl<T: QueueMessage> Queue<T> for InMemoryQueue<T> {
                                 }
                                 FilterResult::NoInterest => {
                                     ready.insert(
-                                        self.idx.fetch_add(1, Ordering::SeqCst),
+                                        (
+                                            Reverse(priority),
+                                            self.idx.fetch_add(1, Ordering::SeqCst),
+                                        ),
                                         Item {
                                             parents: vec![],
+                                            priority,
                                             op,
                                         },
                                     );
@@ -187,7 +209,7 @@ impl<T: QueueMessage> Queue<T> for InMemoryQueue<T> {
                         }
                         QueueError::Retry(error) => {
                             info!(error = %ErrorReporter(&*error), "retryable error");
-                            ready.insert(item_id, item);
+                            ready.insert((Reverse(item.priority), item_id), item);
                             Ok(None)
                         }
                     },
@@ -223,10 +245,19 @@ impl<T: QueueMessage> Queue<T> for InMemoryQueue<T> {
                 tagged_optimizer_queue
             };
 
-            let (ids, ops): (Vec<_>, Vec<_>) = tagged_optimizer_queue.clone().into_iter().unzip();
+            let (ids, items): (Vec<_>, Vec<_>) = tagged_optimizer_queue.clone().into_iter().unzip();
+
+            // new messages inherit the highest priority of their parents
+            let parents_priority = |parents_idxs: &[usize]| {
+                parents_idxs
+                    .iter()
+                    .map(|&i| items[i].priority)
+                    .max()
+                    .unwrap_or_default()
+            };
 
             let res = optimizer
-                .run_pass(ops.into_iter().map(|item| item.op).collect())
+                .run_pass(items.iter().map(|item| item.op.clone()).collect())
                 .await
                 .map_err(Either::Right)?;
 
@@ -240,6 +271,9 @@ impl<T: QueueMessage> Queue<T> for InMemoryQueue<T> {
                 let normalized_ops = op.normalize();
 
                 'block: for op in normalized_ops {
+                    let (priority, op) = op.take_priority();
+                    let priority = priority.unwrap_or_else(|| parents_priority(&parents_idxs));
+
                     match filter.check_interest(&op) {
                         FilterResult::Interest(Interest { tags, remove }) => {
                             for tag in tags {
@@ -251,6 +285,7 @@ impl<T: QueueMessage> Queue<T> for InMemoryQueue<T> {
                                             .map(|&i| &ids[i])
                                             .copied()
                                             .collect(),
+                                        priority,
                                         op: op.clone(),
                                     },
                                 );
@@ -264,9 +299,10 @@ impl<T: QueueMessage> Queue<T> for InMemoryQueue<T> {
                     }
 
                     ready.insert(
-                        self.idx.fetch_add(1, Ordering::SeqCst),
+                        (Reverse(priority), self.idx.fetch_add(1, Ordering::SeqCst)),
                         Item {
                             parents: parents_idxs.iter().map(|&i| &ids[i]).copied().collect(),
+                            priority,
                             op,
                         },
                     );
@@ -274,10 +310,13 @@ impl<T: QueueMessage> Queue<T> for InMemoryQueue<T> {
             }
 
             for (parents_idxs, op, tag) in res.optimize_further {
+                let (priority, op) = op.take_priority();
+
                 optimizer_queue.entry(tag.clone()).or_default().insert(
                     self.idx.fetch_add(1, Ordering::SeqCst),
                     Item {
                         parents: parents_idxs.iter().map(|&i| &ids[i]).copied().collect(),
+                        priority: priority.unwrap_or_else(|| parents_priority(&parents_idxs)),
                         op,
                     },
                 );
@@ -143,9 +143,58 @@ pub enum Op<T: QueueMessage> {
     Promise(Promise<T>),
     /// Handle the contained message, voiding any returned `Data` messages that it returns.
     Void(Box<Self>),
+    /// Handle the contained message with the specified [`Priority`].
+    ///
+    /// This only affects scheduling once the message is queued as a top-level message, at which
+    /// point the queue unwraps it and stores the priority alongside the contained message. Messages
+    /// returned from handling a message inherit its priority, unless they specify their own.
+    Priority {
+        priority: Priority,
+        op: Box<Self>,
+    },
     Noop,
 }
 
+/// The priority of a ready message in the queue. Messages with a higher priority are always
+/// processed before messages with a lower priority, regardless of when they were queued.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// Bulk work that can be delayed, such as backfilling old packets.
+    Low,
+    #[default]
+    Normal,
+    /// Time-critical work, such as client updates that are required before the trusting period of
+    /// a client expires, or packets that are close to timing out.
+    High,
+}
+
+impl Priority {
+    /// The representation of this priority in a queue's storage. Higher values are processed
+    /// first.
+    #[must_use]
+    pub fn as_i16(self) -> i16 {
+        match self {
+            Priority::Low => -1,
+            Priority::Normal => 0,
+            Priority::High => 1,
+        }
+    }
+
+    /// The inverse of [`Priority::as_i16`]. Values out of range saturate to the lowest or highest
+    /// priority.
+    #[must_use]
+    pub fn from_i16(priority: i16) -> Self {
+        match priority.cmp(&0) {
+            std::cmp::Ordering::Less => Priority::Low,
+            std::cmp::Ordering::Equal => Priority::Normal,
+            std::cmp::Ordering::Greater => Priority::High,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(bound(serialize = "", deserialize = ""), deny_unknown_fields)]
 pub struct Promise<T: QueueMessage> {
@@ -173,7 +222,7 @@ pub trait Visit<T: QueueMessage> {
                 queue.iter_mut().for_each(|op| self.visit_op(op));
                 data.iter_mut().for_each(|data| self.visit_data(data));
             }
-            Op::Void(op) => self.visit_op(op),
+            Op::Void(op) | Op::Priority { priority: _, op } => self.visit_op(op),
         }
     }
 
@@ -339,6 +388,14 @@ impl<T: QueueMessage> Op<T> {
                         op => void(op),
                     }))
                 }
+                Op::Priority { priority: p, op } => {
+                    // data doesn't need to be scheduled, and must be unwrapped to be usable by an
+                    // enclosing promise
+                    Ok(op.process(store, depth + 1).await?.map(|op| match op {
+                        Op::Data(data) => Op::Data(data),
+                        op => priority(p, op),
+                    }))
+                }
                 Op::Noop => Ok(None),
             }
         };
@@ -420,6 +477,13 @@ impl<T: QueueMessage> Op<T> {
                     receiver,
                 })],
                 Op::Void(op) => vec![Op::Void(op)],
+                Op::Priority { priority: p, op } => go(*op)
+                    .into_iter()
+                    .map(|op| match op {
+                        Op::Data(data) => Op::Data(data),
+                        op => priority(p, op),
+                    })
+                    .collect(),
                 Op::Noop => vec![],
             }
         }
@@ -430,12 +494,30 @@ impl<T: QueueMessage> Op<T> {
                 // flatten conc to multiple messages
                 match op {
                     Op::Conc(ops) => ops.into_iter().collect(),
+                    Op::Priority { priority: p, op } => match *op {
+                        Op::Conc(ops) => ops.into_iter().map(|op| priority(p, op)).collect(),
+                        op => vec![priority(p, op)],
+                    },
                     op => vec![op],
                 }
             })
             .collect()
     }
 
+    /// Unwrap any top-level [`Op::Priority`], returning the priority of this op if one was
+    /// specified.
+    #[must_use]
+    pub fn take_priority(self) -> (Option<Priority>, Op<T>) {
+        match self {
+            Op::Priority { priority, op } => {
+                // the innermost priority is the most specific one
+                let (inner, op) = op.take_priority();
+                (Some(inner.unwrap_or(priority)), op)
+            }
+            op => (None, op),
+        }
+    }
+
     pub fn into_data(self) -> Option<T::Data> {
         if let Self::Data(v) = self {
             Some(v)
@@ -574,3 +656,16 @@ pub fn void<T: QueueMessage>(t: impl Into<Op<T>>) -> Op<T> {
 pub fn noop<T: QueueMessage>() -> Op<T> {
     Op::Noop
 }
+
+/// Convenience constructor for [`Op::Priority`]. If `t` already has a priority, it is kept as is.
+#[inline]
+#[must_use = "constructing an instruction has no effect"]
+pub fn priority<T: QueueMessage>(priority: Priority, t: impl Into<Op<T>>) -> Op<T> {
+    match t.into() {
+        op @ Op::Priority { .. } => op,
+        op => Op::Priority {
+            priority,
+            op: Box::new(op),
+        },
+    }
+}
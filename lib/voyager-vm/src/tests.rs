@@ -1,9 +1,9 @@
 use macros::model;
 
 use crate::{
-    call, conc, data, defer, noop, now, promise, seq,
+    call, conc, data, defer, noop, now, priority, promise, seq,
     tests::utils::{BuildPrintAbc, DataA, DataB, DataC, FetchA, FetchB, PrintAbc, SimpleMessage},
-    CallT, CallbackT, Context, Op, QueueError, QueueMessage, VecDeque,
+    CallT, CallbackT, Context, Op, Priority, QueueError, QueueMessage, VecDeque,
 };
 
 pub mod utils;
@@ -288,3 +288,51 @@ fn seq_conc_conc() {
 
     assert_eq!(op.normalize(), expected_output);
 }
+
+#[test]
+fn priority_distributes_over_conc() {
+    let op = priority::<UnitMessage>(
+        Priority::High,
+        conc([call(()), seq([defer(1), call(())]), data(())]),
+    );
+
+    assert_eq!(
+        op.normalize(),
+        vec![
+            data(()),
+            priority(Priority::High, call(())),
+            priority(Priority::High, seq([defer(1), call(())])),
+        ]
+    );
+}
+
+#[test]
+fn priority_in_seq_is_not_flattened() {
+    let op = seq::<UnitMessage>([
+        priority(Priority::High, conc([call(()), call(())])),
+        call(()),
+    ]);
+
+    assert_eq!(op.clone().normalize(), vec![op]);
+}
+
+#[test]
+fn take_priority_innermost_wins() {
+    let op = Op::<UnitMessage>::Priority {
+        priority: Priority::High,
+        op: Box::new(priority(Priority::Low, call(()))),
+    };
+
+    assert_eq!(op.take_priority(), (Some(Priority::Low), call(())));
+
+    assert_eq!(call::<UnitMessage>(()).take_priority(), (None, call(())));
+}
+
+#[test]
+fn priority_round_trips_through_storage() {
+    for p in [Priority::Low, Priority::Normal, Priority::High] {
+        assert_eq!(Priority::from_i16(p.as_i16()), p);
+    }
+
+    assert!(Priority::High > Priority::Normal && Priority::Normal > Priority::Low);
+}
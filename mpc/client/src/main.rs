@@ -1,3 +1,4 @@
+mod transcript;
 mod types;
 mod ui;
 
@@ -5,26 +6,27 @@ use std::{
     io,
     net::SocketAddr,
     os::unix::fs::MetadataExt,
-    str::FromStr,
+    path::PathBuf,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
-    time::{Duration, Instant, UNIX_EPOCH},
+    time::{Duration, Instant},
 };
 
 use async_sqlite::{rusqlite::OpenFlags, JournalMode, PoolBuilder};
-use base64::{prelude::BASE64_STANDARD, Engine};
+use clap::{Parser, Subcommand};
 use crossterm::{cursor::Show, event, execute};
 use http_body_util::{BodyExt, Full};
-use httpdate::parse_http_date;
 use hyper::{
     body::{Buf, Bytes},
     service::service_fn,
     Method,
 };
 use hyper_util::{rt::TokioIo, server::graceful::GracefulShutdown};
-use mpc_shared::{phase2_contribute, signed_message, supabase::SupabaseMPCApi, CONTRIBUTION_SIZE};
+use mpc_shared::{
+    phase2_contribute, phase2_verify, signed_message, supabase::SupabaseMPCApi, CONTRIBUTION_SIZE,
+};
 use pgp::{
     cleartext::CleartextSignedMessage,
     crypto::{hash::HashAlgorithm, sym::SymmetricKeyAlgorithm},
@@ -32,7 +34,6 @@ use pgp::{
     ArmorOptions, Deserializable, KeyType, SecretKeyParamsBuilder, SignedSecretKey,
 };
 use ratatui::{backend::CrosstermBackend, Terminal, Viewport};
-use reqwest::{header::LOCATION, Body};
 use serde::Deserialize;
 use tokio::{
     net::TcpListener,
@@ -47,6 +48,32 @@ use types::Status;
 const CONTRIBUTE_ENDPOINT: &str = "/contribute";
 const SK_ENDPOINT: &str = "/secret_key";
 
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Without a subcommand, serve the contribution API for the browser.
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Download and verify every successful contribution of the ceremony, from the initial
+    /// payload to the latest one.
+    VerifyTranscript {
+        #[arg(short, long)]
+        url: String,
+        #[arg(short, long)]
+        api_key: String,
+        /// Defaults to the api key.
+        #[arg(short, long)]
+        jwt: Option<String>,
+        /// Where the payloads are downloaded, downloads are resumed from there.
+        #[arg(short, long, default_value = "transcript")]
+        dir: PathBuf,
+    },
+}
+
 #[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct Contribute {
@@ -63,8 +90,8 @@ struct Contribute {
 enum Error {
     #[error("we are not the current contributor.")]
     NotCurrentContributor,
-    #[error("couldn't find expected header: {0}")]
-    HeaderNotFound(String),
+    #[error("resumable upload not found.")]
+    UploadNotFound,
     #[error("current contributor not found.")]
     ContributorNotFound,
     #[error("current payload not found.")]
@@ -174,7 +201,7 @@ async fn contribute(
     } else {
         return Err(Error::PGPKeyNotFound.into());
     };
-    let client = SupabaseMPCApi::new(supabase_project.clone(), api_key, jwt);
+    let client = SupabaseMPCApi::new(supabase_project, api_key, jwt);
     let current_contributor = client
         .current_contributor()
         .await?
@@ -217,6 +244,7 @@ async fn contribute(
     tx_status
         .send(Status::DownloadEnded(current_payload.id.clone()))
         .expect("impossible");
+    let payload = Arc::new(payload);
     let phase2_contribution = if let Ok(true) =
         tokio::fs::metadata(temp_file(&contributor_id, &payload_id))
             .await
@@ -228,9 +256,10 @@ async fn contribute(
             .send(Status::ContributionStarted)
             .expect("impossible");
         let (tx_contrib, rx_contrib) = oneshot::channel();
+        let payload_clone = payload.clone();
         let handle = tokio::task::spawn_blocking(move || {
             tx_contrib
-                .send(phase2_contribute(&payload))
+                .send(phase2_contribute(&payload_clone))
                 .expect("impossible");
         });
         let phase2_contribution = rx_contrib.await??;
@@ -246,6 +275,25 @@ async fn contribute(
         phase2_contribution
     };
 
+    // ----------------------------------------------------
+    // Verify our own contribution before anyone else does.
+    tx_status
+        .send(Status::VerificationStarted)
+        .expect("impossible");
+    let phase2_contribution = Arc::new(phase2_contribution);
+    let phase2_contribution_clone = phase2_contribution.clone();
+    let verification =
+        tokio::task::spawn_blocking(move || phase2_verify(&payload, &phase2_contribution_clone))
+            .await?;
+    if let Err(e) = verification {
+        // Never reuse an invalid contribution, the next attempt must recompute it.
+        tokio::fs::remove_file(temp_file(&contributor_id, &payload_id)).await?;
+        return Err(Error::Phase2VerificationFailed(e).into());
+    }
+    tx_status
+        .send(Status::VerificationEnded)
+        .expect("impossible");
+
     // ------------------------
     // Sign and submits the sig
     // Gnark phase2 contribution appends the sha256 hash at the end
@@ -312,113 +360,47 @@ async fn contribute(
             }
         })
         .await?;
-    let upload_client = client.new_reqwest_builder()?.build()?;
     if let Some(ref location) = upload_location {
-        if upload_client
-            .head(location)
-            .header("Tus-Resumable", "1.0.0")
-            .send()
-            .await?
-            .error_for_status()
-            .is_err()
-        {
+        if client.upload_offset(location).await?.is_none() {
             upload_location = None;
         }
     }
     let upload_location = match upload_location {
         Some(location) => location,
         None => {
-            // =====================================================
-            // https://tus.io/protocols/resumable-upload#creation ==
-            // =====================================================
-            let response = upload_client
-                .post(format!("{supabase_project}/storage/v1/upload/resumable"))
-                .header("Tus-Resumable", "1.0.0")
-                .header("Upload-Length", CONTRIBUTION_SIZE.to_string())
-                .header(
-                    "Upload-Metadata",
-                    format!(
-                        "bucketName {},objectName {}",
-                        BASE64_STANDARD.encode(&bucket),
-                        BASE64_STANDARD.encode(&payload_id)
-                    ),
-                )
-                .send()
-                .await?
-                .error_for_status()?;
-            let location = response
-                .headers()
-                .get(LOCATION)
-                .ok_or(Error::HeaderNotFound(LOCATION.as_str().into()))?
-                .to_str()?
-                .to_string();
-            let expire = response
-                .headers()
-                .get("Upload-Expires")
-                .ok_or(Error::HeaderNotFound("Upload-Expires".into()))?
-                .to_str()?;
-            let expire = parse_http_date(expire)?;
-            let expire_timestamp = expire.duration_since(UNIX_EPOCH)?.as_secs();
-            let location_clone = location.clone();
+            let upload = client.create_upload(&bucket, &payload_id).await?;
+            let location_clone = upload.location.clone();
             pool.conn(move |conn| {
                 let mut stmt =
                     conn.prepare("INSERT INTO resumable_upload (location, expire) VALUES (?, ?)")?;
-                let r = stmt.execute((location_clone, expire_timestamp))?;
+                let r = stmt.execute((location_clone, upload.expire))?;
                 assert!(r == 1);
                 Ok(())
             })
             .await?;
-            location
+            upload.location
         }
     };
-    // =================================================
-    // https://tus.io/protocols/resumable-upload#head ==
-    // =================================================
-    let response = upload_client
-        .head(&upload_location)
-        .header("Tus-Resumable", "1.0.0")
-        .send()
+    let (upload_offset, upload_length) = client
+        .upload_offset(&upload_location)
         .await?
-        .error_for_status()?;
-    let upload_length = usize::from_str(
-        response
-            .headers()
-            .get("Upload-Length")
-            .ok_or(Error::HeaderNotFound("Upload-Length".into()))?
-            .to_str()?,
-    )?;
-    let upload_offset = usize::from_str(
-        response
-            .headers()
-            .get("Upload-Offset")
-            .ok_or(Error::HeaderNotFound("Upload-Offset".into()))?
-            .to_str()?,
-    )?;
+        .ok_or(Error::UploadNotFound)?;
     assert!(upload_length == CONTRIBUTION_SIZE, "invalid upload-length.");
     if upload_offset < upload_length {
         tx_status
             .send(Status::UploadStarted(payload_id.clone()))
             .expect("impossible");
-        // ==================================================
-        // https://tus.io/protocols/resumable-upload#patch ==
-        // ==================================================
-        let chunks = phase2_contribution
-            .into_iter()
-            .skip(upload_offset)
-            .collect::<Vec<_>>()
-            // 4mb
-            .chunks(4 * 1024 * 1024)
-            .map(|x| Ok::<_, std::io::Error>(x.to_vec()))
-            .collect::<Vec<_>>();
-        upload_client
-            .patch(&upload_location)
-            .header("Tus-Resumable", "1.0.0")
-            .header("Content-Type", "application/offset+octet-stream")
-            .header("Upload-Offset", upload_offset.to_string())
-            .body(Body::wrap_stream(futures_util::stream::iter(chunks)))
-            .send()
-            .await?
-            .error_for_status()?;
+        client
+            .upload_payload(&upload_location, &phase2_contribution, |percent| {
+                let tx_status = tx_status.clone();
+                let payload_id = payload_id.clone();
+                async move {
+                    tx_status
+                        .send(Status::Uploading(payload_id, percent as u8))
+                        .expect("impossible");
+                }
+            })
+            .await?;
         tx_status
             .send(Status::UploadEnded(payload_id.clone()))
             .expect("impossible");
@@ -615,6 +597,16 @@ async fn input_and_status_handling(
 
 #[tokio::main]
 async fn main() -> Result<(), DynError> {
+    if let Some(Command::VerifyTranscript {
+        url,
+        api_key,
+        jwt,
+        dir,
+    }) = Args::parse().command
+    {
+        let jwt = jwt.unwrap_or_else(|| api_key.clone());
+        return transcript::verify(SupabaseMPCApi::new(url, api_key, jwt), &dir).await;
+    }
     let status = Arc::new(RwLock::new(Status::Idle));
     let lock = Arc::new(AtomicBool::new(false));
     let (tx_status, rx_status) = broadcast::channel(64);
@@ -0,0 +1,257 @@
+use std::{path::Path, sync::Arc};
+
+use mpc_shared::{
+    phase2_verify, supabase::SupabaseMPCApi, transfer::manifest_path, types::UserContribution,
+    verify_contribution_signature, Phase2Verify, INITIAL_PAYLOAD_ID,
+};
+
+use crate::DynError;
+
+#[derive(thiserror::Error, Debug, Clone)]
+#[error("{invalid} out of {total} contributions failed verification.")]
+pub struct TranscriptError {
+    invalid: usize,
+    total: usize,
+}
+
+async fn download(
+    client: &SupabaseMPCApi,
+    dir: &Path,
+    payload_id: &str,
+) -> Result<Vec<u8>, DynError> {
+    let path = dir.join(payload_id);
+    client
+        .download_payload(payload_id, &path.to_string_lossy(), |_| async {})
+        .await
+}
+
+async fn remove(dir: &Path, payload_id: &str) -> Result<(), DynError> {
+    let path = dir.join(payload_id);
+    tokio::fs::remove_file(manifest_path(&path)).await?;
+    tokio::fs::remove_file(path).await?;
+    Ok(())
+}
+
+/// The chain of contributions, verified up to `previous_id`.
+struct Chain {
+    previous_id: String,
+    previous: Arc<Vec<u8>>,
+    phase2_verify: Phase2Verify,
+    invalid: usize,
+}
+
+impl Chain {
+    fn new(initial: Vec<u8>, phase2_verify: Phase2Verify) -> Self {
+        Self {
+            previous_id: INITIAL_PAYLOAD_ID.to_owned(),
+            previous: Arc::new(initial),
+            phase2_verify,
+            invalid: 0,
+        }
+    }
+
+    /// Checks that the contribution was signed by its contributor and that it is a valid phase2
+    /// contribution on top of the previous payload, then moves to it. Returns the id of the
+    /// previous payload, which isn't needed anymore.
+    async fn next(
+        &mut self,
+        contribution: &UserContribution,
+        payload: Vec<u8>,
+    ) -> Result<String, DynError> {
+        let payload = Arc::new(payload);
+        let signature = verify_contribution_signature(
+            &contribution.public_key,
+            &contribution.signature,
+            &self.previous_id,
+            &contribution.payload_id,
+            &payload,
+        );
+        let (previous, phase2_verify) = (self.previous.clone(), self.phase2_verify);
+        let payload_clone = payload.clone();
+        let phase2 =
+            tokio::task::spawn_blocking(move || phase2_verify(&previous, &payload_clone)).await?;
+        let errors = [
+            signature.err().map(|e| e.to_string()),
+            phase2.err().map(|e| e.to_string()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        if errors.is_empty() {
+            println!(
+                "[{}] {} -> {}: ok",
+                contribution.seq, self.previous_id, contribution.payload_id
+            );
+        } else {
+            self.invalid += 1;
+            println!(
+                "[{}] {} -> {}: invalid, {}",
+                contribution.seq,
+                self.previous_id,
+                contribution.payload_id,
+                errors.join(", ")
+            );
+        }
+        self.previous = payload;
+        Ok(std::mem::replace(
+            &mut self.previous_id,
+            contribution.payload_id.clone(),
+        ))
+    }
+}
+
+/// Walks the chain of successful contributions from the initial payload, checking for every
+/// contribution that it was signed by its contributor and that it is a valid phase2 contribution
+/// on top of the previous payload.
+pub async fn verify(client: SupabaseMPCApi, dir: &Path) -> Result<(), DynError> {
+    tokio::fs::create_dir_all(dir).await?;
+    let contributions = client.contributions().await?;
+    println!("verifying {} contributions...", contributions.len());
+    let initial = download(&client, dir, INITIAL_PAYLOAD_ID).await?;
+    let mut chain = Chain::new(initial, phase2_verify);
+    for contribution in &contributions {
+        let payload = download(&client, dir, &contribution.payload_id).await?;
+        let previous_id = chain.next(contribution, payload).await?;
+        // Only the latest payload is needed to verify the next contribution.
+        remove(dir, &previous_id).await?;
+    }
+    if chain.invalid > 0 {
+        return Err(TranscriptError {
+            invalid: chain.invalid,
+            total: contributions.len(),
+        }
+        .into());
+    }
+    println!(
+        "transcript is valid, latest payload is {}.",
+        chain.previous_id
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use mpc_shared::{signed_message, Phase2VerificationError};
+    use pgp::{
+        cleartext::CleartextSignedMessage, types::SecretKeyTrait, ArmorOptions, SignedSecretKey,
+    };
+
+    use super::*;
+    use crate::generate_pgp_key;
+
+    /// Payloads starting with `0xff` are invalid contributions.
+    fn phase2_verify_stub(_previous: &[u8], payload: &[u8]) -> Result<(), Phase2VerificationError> {
+        if payload[0] == 0xff {
+            Err(Phase2VerificationError::Phase2VerificationFailed)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn payload(byte: u8) -> Vec<u8> {
+        vec![byte; 64]
+    }
+
+    /// A contribution of `payload`, signed as if it was computed on top of `previous_id`.
+    fn contribution(
+        key: &SignedSecretKey,
+        seq: i64,
+        previous_id: &str,
+        payload_id: &str,
+        payload: &[u8],
+    ) -> UserContribution {
+        let signature = CleartextSignedMessage::sign(
+            &signed_message(
+                previous_id,
+                payload_id,
+                &hex::encode(&payload[payload.len() - 32..]),
+            ),
+            key,
+            String::new,
+        )
+        .unwrap();
+        let public_key = key.public_key().sign(key, String::new).unwrap();
+        UserContribution {
+            id: format!("contributor-{seq}"),
+            seq,
+            payload_id: payload_id.to_owned(),
+            public_key: hex::encode(
+                public_key
+                    .to_armored_bytes(ArmorOptions::default())
+                    .unwrap(),
+            ),
+            signature: hex::encode(signature.to_armored_bytes(ArmorOptions::default()).unwrap()),
+        }
+    }
+
+    #[tokio::test]
+    async fn valid_chain() {
+        let key = generate_pgp_key("alice@union.build".into());
+        let mut chain = Chain::new(payload(0), phase2_verify_stub);
+
+        let first = contribution(&key, 1, INITIAL_PAYLOAD_ID, "first", &payload(1));
+        assert_eq!(
+            chain.next(&first, payload(1)).await.unwrap(),
+            INITIAL_PAYLOAD_ID
+        );
+        let second = contribution(&key, 2, "first", "second", &payload(2));
+        assert_eq!(chain.next(&second, payload(2)).await.unwrap(), "first");
+
+        assert_eq!(chain.invalid, 0);
+        assert_eq!(chain.previous_id, "second");
+    }
+
+    #[tokio::test]
+    async fn contribution_on_top_of_another_payload_is_invalid() {
+        let key = generate_pgp_key("alice@union.build".into());
+        let mut chain = Chain::new(payload(0), phase2_verify_stub);
+
+        let first = contribution(&key, 1, INITIAL_PAYLOAD_ID, "first", &payload(1));
+        chain.next(&first, payload(1)).await.unwrap();
+        // signed on top of the initial payload, skipping the first contribution
+        let second = contribution(&key, 2, INITIAL_PAYLOAD_ID, "second", &payload(2));
+        chain.next(&second, payload(2)).await.unwrap();
+        assert_eq!(chain.invalid, 1);
+
+        // the chain continues from the recorded contribution
+        let third = contribution(&key, 3, "second", "third", &payload(3));
+        chain.next(&third, payload(3)).await.unwrap();
+        assert_eq!(chain.invalid, 1);
+        assert_eq!(chain.previous_id, "third");
+    }
+
+    #[tokio::test]
+    async fn contribution_with_bad_signature_is_invalid() {
+        let key = generate_pgp_key("alice@union.build".into());
+        let other_key = generate_pgp_key("mallory@union.build".into());
+        let mut chain = Chain::new(payload(0), phase2_verify_stub);
+
+        // signed by another key than the one of the contributor
+        let mut first = contribution(&key, 1, INITIAL_PAYLOAD_ID, "first", &payload(1));
+        first.public_key =
+            contribution(&other_key, 1, INITIAL_PAYLOAD_ID, "first", &payload(1)).public_key;
+        chain.next(&first, payload(1)).await.unwrap();
+        assert_eq!(chain.invalid, 1);
+
+        // signed for another payload
+        let second = contribution(&key, 2, "first", "second", &payload(2));
+        chain.next(&second, payload(3)).await.unwrap();
+        assert_eq!(chain.invalid, 2);
+
+        // not a signature at all
+        let mut third = contribution(&key, 3, "second", "third", &payload(3));
+        third.signature = hex::encode("not a signature");
+        chain.next(&third, payload(3)).await.unwrap();
+        assert_eq!(chain.invalid, 3);
+    }
+
+    #[tokio::test]
+    async fn invalid_phase2_contribution_is_invalid() {
+        let key = generate_pgp_key("alice@union.build".into());
+        let mut chain = Chain::new(payload(0), phase2_verify_stub);
+
+        let first = contribution(&key, 1, INITIAL_PAYLOAD_ID, "first", &payload(0xff));
+        chain.next(&first, payload(0xff)).await.unwrap();
+        assert_eq!(chain.invalid, 1);
+    }
+}
@@ -9,7 +9,10 @@ pub enum Status {
     DownloadEnded(String),
     ContributionStarted,
     ContributionEnded,
+    VerificationStarted,
+    VerificationEnded,
     UploadStarted(String),
+    Uploading(String, u8),
     UploadEnded(String),
     Failed(String),
     Successful,
@@ -28,7 +31,10 @@ impl Serialize for Status {
             Status::DownloadEnded(_) => "downloadEnded",
             Status::ContributionStarted => "contributionStarted",
             Status::ContributionEnded => "contributionEnded",
+            Status::VerificationStarted => "verificationStarted",
+            Status::VerificationEnded => "verificationEnded",
             Status::UploadStarted(_) => "uploadStarted",
+            Status::Uploading(_, _) => "uploading",
             Status::UploadEnded(_) => "uploadEnded",
             Status::Failed(_) => "failed",
             Status::Successful => "successful",
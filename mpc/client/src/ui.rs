@@ -26,6 +26,8 @@ enum UiState {
     DownloadEnded,
     Contributing(Instant),
     ContributionEnded,
+    Verifying(Instant),
+    VerificationEnded,
     Uploading(String, u8, Instant),
     Successful,
     Failed(String),
@@ -66,11 +68,13 @@ fn ui(f: &mut Frame, state: &UiState, throbber_state: &mut ThrobberState) {
         UiState::DownloadEnded => 1,
         UiState::Contributing(_) => 1,
         UiState::ContributionEnded => 2,
-        UiState::Uploading(_, _, _) => 2,
-        UiState::Successful => 3,
-        UiState::Failed(_) => 3,
+        UiState::Verifying(_) => 2,
+        UiState::VerificationEnded => 3,
+        UiState::Uploading(_, _, _) => 3,
+        UiState::Successful => 4,
+        UiState::Failed(_) => 4,
     };
-    let num_steps = 3;
+    let num_steps = 4;
     #[allow(clippy::cast_precision_loss)]
     let progress = LineGauge::default()
         .filled_style(Style::default().fg(Color::Blue))
@@ -142,7 +146,21 @@ fn ui(f: &mut Frame, state: &UiState, throbber_state: &mut ThrobberState) {
                 .use_type(throbber_widgets_tui::WhichUse::Spin);
             f.render_stateful_widget(full, chunks[0], throbber_state);
         }
-        UiState::Uploading(name, _, started_at) => {
+        UiState::Verifying(started_at) => {
+            let full = throbber_widgets_tui::Throbber::default()
+                .label(format!(
+                    "Your contribution is being verified before upload... ({}s)",
+                    started_at.elapsed().as_secs()
+                ))
+                .style(ratatui::style::Style::default().fg(ratatui::style::Color::White))
+                .throbber_style(
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                )
+                .throbber_set(throbber_widgets_tui::CLOCK)
+                .use_type(throbber_widgets_tui::WhichUse::Spin);
+            f.render_stateful_widget(full, chunks[0], throbber_state);
+        }
+        UiState::Uploading(name, progress, started_at) => {
             let item = ListItem::new(Line::from(vec![
                 Span::raw(symbols::DOT),
                 Span::styled(
@@ -154,6 +172,22 @@ fn ui(f: &mut Frame, state: &UiState, throbber_state: &mut ThrobberState) {
                 Span::raw(format!(" ({}s)", started_at.elapsed().as_secs())),
             ]));
             f.render_widget(List::new(vec![item]), list_area);
+
+            let gauge = Gauge::default()
+                .gauge_style(Style::default().fg(Color::Cyan))
+                .ratio(*progress as f64 / 100.0);
+            if gauge_area.top().saturating_add(0_u16) > area.bottom() {
+                return;
+            }
+            f.render_widget(
+                gauge,
+                Rect {
+                    x: gauge_area.left(),
+                    y: gauge_area.top().saturating_add(0_u16),
+                    width: gauge_area.width,
+                    height: 1,
+                },
+            );
         }
         UiState::Successful => {
             // Set full with state
@@ -192,6 +226,18 @@ fn ui(f: &mut Frame, state: &UiState, throbber_state: &mut ThrobberState) {
             f.render_stateful_widget(full, chunks[0], throbber_state);
         }
         UiState::ContributionEnded => {
+            // Set full with state
+            let full = throbber_widgets_tui::Throbber::default()
+                .label("Initializing verification...")
+                .style(ratatui::style::Style::default().fg(ratatui::style::Color::White))
+                .throbber_style(
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                )
+                .throbber_set(throbber_widgets_tui::CLOCK)
+                .use_type(throbber_widgets_tui::WhichUse::Spin);
+            f.render_stateful_widget(full, chunks[0], throbber_state);
+        }
+        UiState::VerificationEnded => {
             // Set full with state
             let full = throbber_widgets_tui::Throbber::default()
                 .label("Initializing upload...")
@@ -299,6 +345,33 @@ pub async fn run_ui<B: Backend>(
                             })?;
                             UiState::ContributionEnded
                         }
+                        (Status::VerificationStarted, _) => {
+                            terminal.insert_before(1, |buf| {
+                                Paragraph::new(Line::from(vec![
+                                    Span::from("Started "),
+                                    Span::styled(
+                                        "contribution verification...",
+                                        Style::default().add_modifier(Modifier::BOLD),
+                                    ),
+                                ]))
+                                .render(buf.area, buf);
+                            })?;
+                            UiState::Verifying(Instant::now())
+                        }
+                        (Status::VerificationEnded, UiState::Verifying(started_at)) => {
+                            terminal.insert_before(1, |buf| {
+                                Paragraph::new(Line::from(vec![
+                                    Span::from("Finished "),
+                                    Span::styled(
+                                        "contribution verification",
+                                        Style::default().add_modifier(Modifier::BOLD),
+                                    ),
+                                    Span::from(format!(" in {}s", started_at.elapsed().as_secs())),
+                                ]))
+                                .render(buf.area, buf);
+                            })?;
+                            UiState::VerificationEnded
+                        }
                         (Status::UploadStarted(name), _) => {
                             terminal.insert_before(1, |buf| {
                                 Paragraph::new(Line::from(vec![
@@ -312,6 +385,10 @@ pub async fn run_ui<B: Backend>(
                             })?;
                             UiState::Uploading(name, 0, Instant::now())
                         }
+                        (
+                            Status::Uploading(name, progress),
+                            UiState::Uploading(_, _, started_at),
+                        ) => UiState::Uploading(name, progress, started_at),
                         (
                             Status::UploadEnded(_),
                             UiState::Uploading(name, progress, started_at),
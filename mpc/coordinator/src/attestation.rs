@@ -0,0 +1,522 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use mpc_shared::{
+    phase2_verify, supabase::SupabaseMPCApi, verify_contribution_signature, Phase2Verify,
+    INITIAL_PAYLOAD_ID,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info, warn};
+
+use crate::{DynError, Error};
+
+/// Name of the file describing a bundle, the payloads are stored next to it under their id.
+pub const BUNDLE_FILE: &str = "attestations.json";
+
+/// The outcome of the verification of a single contribution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attestation {
+    pub contributor_id: String,
+    /// The payload the contribution was computed on.
+    pub previous_payload_id: String,
+    pub payload_id: String,
+    /// The sha256 appended by gnark at the end of the payload, hex encoded.
+    pub payload_hash: String,
+    /// Hex encoded armored pgp public key of the contributor.
+    pub public_key: String,
+    /// Hex encoded armored pgp signature of the transition from `previous_payload_id` to
+    /// `payload_id`.
+    pub signature: String,
+    /// Whether the coordinator accepted the contribution.
+    pub verified: bool,
+}
+
+/// A self contained export of the ceremony that can be re-verified offline.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bundle {
+    pub initial_payload_id: String,
+    /// In the order the contributions were verified.
+    pub attestations: Vec<Attestation>,
+}
+
+impl Bundle {
+    pub async fn load(dir: &Path) -> Result<Option<Self>, DynError> {
+        match tokio::fs::read(dir.join(BUNDLE_FILE)).await {
+            Ok(content) => Ok(Some(serde_json::from_slice(&content)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn save(&self, dir: &Path) -> Result<(), DynError> {
+        let tmp = dir.join(format!("{BUNDLE_FILE}.tmp"));
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(self)?).await?;
+        tokio::fs::rename(&tmp, dir.join(BUNDLE_FILE)).await?;
+        Ok(())
+    }
+}
+
+pub fn payload_hash(payload: &[u8]) -> String {
+    hex::encode(&payload[payload.len().saturating_sub(32)..])
+}
+
+fn payload_path(dir: &Path, payload_id: &str) -> PathBuf {
+    dir.join(payload_id)
+}
+
+async fn write_payload(dir: &Path, payload_id: &str, payload: &[u8]) -> Result<(), DynError> {
+    let path = payload_path(dir, payload_id);
+    if !matches!(tokio::fs::metadata(&path).await, Ok(meta) if meta.len() == payload.len() as u64) {
+        tokio::fs::write(path, payload).await?;
+    }
+    Ok(())
+}
+
+/// Appends the attestation to the bundle in `dir`, along with the payloads required to verify it.
+/// The bundle starts at `previous_payload` if it doesn't exist yet.
+pub async fn record(
+    dir: &Path,
+    previous_payload: &[u8],
+    attestation: Attestation,
+    payload: &[u8],
+) -> Result<(), DynError> {
+    tokio::fs::create_dir_all(dir).await?;
+    let mut bundle = match Bundle::load(dir).await? {
+        Some(bundle) => bundle,
+        None => Bundle {
+            initial_payload_id: attestation.previous_payload_id.clone(),
+            attestations: Vec::new(),
+        },
+    };
+    write_payload(dir, &attestation.previous_payload_id, previous_payload).await?;
+    write_payload(dir, &attestation.payload_id, payload).await?;
+    // The same contribution is verified again if we restart before the result is inserted.
+    bundle
+        .attestations
+        .retain(|a| a.payload_id != attestation.payload_id);
+    bundle.attestations.push(attestation);
+    bundle.save(dir).await
+}
+
+/// Builds a bundle of every contribution of the ceremony from supabase, downloading the payloads
+/// in `dir`. Downloads are resumed if the export is interrupted.
+pub async fn export(client: &SupabaseMPCApi, dir: &Path) -> Result<(), DynError> {
+    tokio::fs::create_dir_all(dir).await?;
+    let progress = |percent| async move { debug!("downloaded: {:.2}%", percent) };
+    let download = |payload_id: String| async move {
+        info!(%payload_id, "downloading payload...");
+        client
+            .download_payload(
+                &payload_id,
+                &payload_path(dir, &payload_id).to_string_lossy(),
+                progress,
+            )
+            .await
+    };
+    download(INITIAL_PAYLOAD_ID.to_owned()).await?;
+    let mut bundle = Bundle {
+        initial_payload_id: INITIAL_PAYLOAD_ID.to_owned(),
+        attestations: Vec::new(),
+    };
+    let mut previous_payload_id = INITIAL_PAYLOAD_ID.to_owned();
+    for result in client.contribution_results().await? {
+        let payload = client
+            .contributor_payload(&result.id)
+            .await?
+            .ok_or(Error::NextPayloadNotFound)?;
+        let signature = client
+            .contributor_signature(&result.id)
+            .await?
+            .ok_or(Error::ContributorSignatureNotFound)?;
+        let content = download(payload.id.clone()).await?;
+        info!(contributor = %result.id, seq = result.seq, %payload, "exported contribution");
+        bundle.attestations.push(Attestation {
+            contributor_id: result.id,
+            previous_payload_id: previous_payload_id.clone(),
+            payload_id: payload.id.clone(),
+            payload_hash: payload_hash(&content),
+            public_key: signature.public_key,
+            signature: signature.signature,
+            verified: result.success,
+        });
+        if result.success {
+            previous_payload_id = payload.id;
+        }
+    }
+    bundle.save(dir).await?;
+    info!(
+        contributions = bundle.attestations.len(),
+        dir = %dir.display(),
+        "exported bundle"
+    );
+    Ok(())
+}
+
+async fn check(
+    previous_payload_id: &str,
+    previous_payload: Arc<Vec<u8>>,
+    attestation: &Attestation,
+    payload: Arc<Vec<u8>>,
+    phase2_verify: Phase2Verify,
+) -> Result<Result<(), String>, DynError> {
+    if attestation.previous_payload_id != previous_payload_id {
+        return Ok(Err(format!(
+            "contribution is on top of {}, expected {previous_payload_id}",
+            attestation.previous_payload_id
+        )));
+    }
+    if attestation.payload_hash != payload_hash(&payload) {
+        return Ok(Err("payload hash mismatch".into()));
+    }
+    if let Err(e) = verify_contribution_signature(
+        &attestation.public_key,
+        &attestation.signature,
+        &attestation.previous_payload_id,
+        &attestation.payload_id,
+        &payload,
+    ) {
+        return Ok(Err(e.to_string()));
+    }
+    let verification =
+        tokio::task::spawn_blocking(move || phase2_verify(&previous_payload, &payload)).await?;
+    Ok(verification.map_err(|e| e.to_string()))
+}
+
+/// Verifies every contribution of the bundle in `dir` without any network access, and checks
+/// that the results match the ones recorded by the coordinator.
+pub async fn verify(dir: &Path) -> Result<(), DynError> {
+    verify_with(dir, phase2_verify).await
+}
+
+async fn verify_with(dir: &Path, phase2_verify: Phase2Verify) -> Result<(), DynError> {
+    let bundle = Bundle::load(dir).await?.ok_or(Error::BundleNotFound)?;
+    if bundle.initial_payload_id != INITIAL_PAYLOAD_ID {
+        warn!(
+            initial_payload_id = %bundle.initial_payload_id,
+            "the bundle doesn't start from the initial payload of the ceremony"
+        );
+    }
+    let mut previous_payload_id = bundle.initial_payload_id.clone();
+    let mut previous_payload =
+        Arc::new(tokio::fs::read(payload_path(dir, &previous_payload_id)).await?);
+    let mut mismatches = 0;
+    for attestation in &bundle.attestations {
+        let payload = Arc::new(tokio::fs::read(payload_path(dir, &attestation.payload_id)).await?);
+        let result = check(
+            &previous_payload_id,
+            previous_payload.clone(),
+            attestation,
+            payload.clone(),
+            phase2_verify,
+        )
+        .await?;
+        let contributor = &attestation.contributor_id;
+        let payload_id = &attestation.payload_id;
+        match (attestation.verified, result) {
+            (true, Ok(())) => info!(%contributor, %payload_id, "contribution is valid"),
+            (false, Err(reason)) => {
+                info!(%contributor, %payload_id, %reason, "contribution was rightfully rejected")
+            }
+            (true, Err(reason)) => {
+                mismatches += 1;
+                error!(%contributor, %payload_id, %reason, "contribution was accepted but is invalid");
+            }
+            (false, Ok(())) => {
+                mismatches += 1;
+                error!(%contributor, %payload_id, "contribution was rejected but is valid");
+            }
+        }
+        // Follow the chain as recorded, a mismatch is already reported.
+        if attestation.verified {
+            previous_payload_id.clone_from(&attestation.payload_id);
+            previous_payload = payload;
+        }
+    }
+    if mismatches > 0 {
+        return Err(Error::BundleMismatch(mismatches).into());
+    }
+    info!(
+        contributions = bundle.attestations.len(),
+        latest_payload = %previous_payload_id,
+        "bundle verified"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use mpc_shared::{signed_message, Phase2VerificationError};
+    use pgp::{
+        cleartext::CleartextSignedMessage,
+        crypto::{hash::HashAlgorithm, sym::SymmetricKeyAlgorithm},
+        types::SecretKeyTrait,
+        ArmorOptions, KeyType, SecretKeyParamsBuilder, SignedSecretKey,
+    };
+
+    use super::*;
+
+    /// Payloads starting with `0xff` are invalid contributions.
+    fn phase2_verify_stub(_previous: &[u8], payload: &[u8]) -> Result<(), Phase2VerificationError> {
+        if payload[0] == 0xff {
+            Err(Phase2VerificationError::Phase2VerificationFailed)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn payload(byte: u8) -> Vec<u8> {
+        vec![byte; 64]
+    }
+
+    fn generate_key(email: &str) -> SignedSecretKey {
+        SecretKeyParamsBuilder::default()
+            .key_type(KeyType::EdDSA)
+            .can_certify(false)
+            .can_sign(true)
+            .can_encrypt(false)
+            .primary_user_id(email.to_owned())
+            .preferred_symmetric_algorithms([SymmetricKeyAlgorithm::AES256].to_vec().into())
+            .preferred_hash_algorithms([HashAlgorithm::None].to_vec().into())
+            .build()
+            .unwrap()
+            .generate()
+            .unwrap()
+            .sign(String::new)
+            .unwrap()
+    }
+
+    /// An attestation of `payload`, signed by `key` as a contribution on top of
+    /// `previous_payload_id`.
+    fn attestation(
+        key: &SignedSecretKey,
+        previous_payload_id: &str,
+        payload_id: &str,
+        payload: &[u8],
+        verified: bool,
+    ) -> Attestation {
+        let signature = CleartextSignedMessage::sign(
+            &signed_message(previous_payload_id, payload_id, &payload_hash(payload)),
+            key,
+            String::new,
+        )
+        .unwrap();
+        let public_key = key.public_key().sign(key, String::new).unwrap();
+        Attestation {
+            contributor_id: format!("contributor-{payload_id}"),
+            previous_payload_id: previous_payload_id.to_owned(),
+            payload_id: payload_id.to_owned(),
+            payload_hash: payload_hash(payload),
+            public_key: hex::encode(
+                public_key
+                    .to_armored_bytes(ArmorOptions::default())
+                    .unwrap(),
+            ),
+            signature: hex::encode(signature.to_armored_bytes(ArmorOptions::default()).unwrap()),
+            verified,
+        }
+    }
+
+    async fn check_stub(
+        previous_payload_id: &str,
+        attestation: &Attestation,
+        payload: Vec<u8>,
+    ) -> Result<(), String> {
+        check(
+            previous_payload_id,
+            Arc::new(self::payload(0)),
+            attestation,
+            Arc::new(payload),
+            phase2_verify_stub,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn check_accepts_valid_contribution() {
+        let key = generate_key("alice@union.build");
+        let attestation = attestation(&key, INITIAL_PAYLOAD_ID, "first", &payload(1), true);
+
+        check_stub(INITIAL_PAYLOAD_ID, &attestation, payload(1))
+            .await
+            .unwrap();
+
+        let invalid = self::attestation(&key, INITIAL_PAYLOAD_ID, "first", &payload(0xff), true);
+        check_stub(INITIAL_PAYLOAD_ID, &invalid, payload(0xff))
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn check_rejects_contribution_on_top_of_another_payload() {
+        let key = generate_key("alice@union.build");
+
+        // recorded on top of another payload than the previous one of the chain
+        let attestation = attestation(&key, INITIAL_PAYLOAD_ID, "second", &payload(2), true);
+        let reason = check_stub("first", &attestation, payload(2))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            reason,
+            format!("contribution is on top of {INITIAL_PAYLOAD_ID}, expected first")
+        );
+
+        // recorded on top of the previous payload, but signed on top of another one
+        let mut attestation = attestation;
+        attestation.previous_payload_id = "first".to_owned();
+        check_stub("first", &attestation, payload(2))
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn check_rejects_bad_signature() {
+        let key = generate_key("alice@union.build");
+        let other_key = generate_key("mallory@union.build");
+
+        // signed by another key than the one of the contributor
+        let mut attestation = attestation(&key, INITIAL_PAYLOAD_ID, "first", &payload(1), true);
+        attestation.public_key =
+            self::attestation(&other_key, INITIAL_PAYLOAD_ID, "first", &payload(1), true)
+                .public_key;
+        check_stub(INITIAL_PAYLOAD_ID, &attestation, payload(1))
+            .await
+            .unwrap_err();
+
+        // the payload doesn't match the attestation
+        let attestation = self::attestation(&key, INITIAL_PAYLOAD_ID, "first", &payload(1), true);
+        let reason = check_stub(INITIAL_PAYLOAD_ID, &attestation, payload(2))
+            .await
+            .unwrap_err();
+        assert_eq!(reason, "payload hash mismatch");
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("mpc-attestation-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// Verifies the bundle in `dir`, returning the number of mismatches.
+    async fn mismatches(dir: &Path) -> usize {
+        match verify_with(dir, phase2_verify_stub).await {
+            Ok(()) => 0,
+            Err(e) => match e.downcast_ref::<Error>() {
+                Some(Error::BundleMismatch(mismatches)) => *mismatches,
+                _ => panic!("{e}"),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn record_replaces_attestation_verified_again() {
+        let dir = test_dir("record");
+        let key = generate_key("alice@union.build");
+
+        let first = attestation(&key, INITIAL_PAYLOAD_ID, "first", &payload(1), false);
+        record(&dir, &payload(0), first.clone(), &payload(1))
+            .await
+            .unwrap();
+        // restarted before the result was inserted
+        let first = Attestation {
+            verified: true,
+            ..first
+        };
+        record(&dir, &payload(0), first.clone(), &payload(1))
+            .await
+            .unwrap();
+        let second = attestation(&key, "first", "second", &payload(2), true);
+        record(&dir, &payload(1), second.clone(), &payload(2))
+            .await
+            .unwrap();
+
+        let bundle = Bundle::load(&dir).await.unwrap().unwrap();
+        assert_eq!(
+            bundle,
+            Bundle {
+                initial_payload_id: INITIAL_PAYLOAD_ID.to_owned(),
+                attestations: vec![first, second],
+            }
+        );
+        for (payload_id, byte) in [(INITIAL_PAYLOAD_ID, 0), ("first", 1), ("second", 2)] {
+            assert_eq!(
+                tokio::fs::read(payload_path(&dir, payload_id))
+                    .await
+                    .unwrap(),
+                payload(byte)
+            );
+        }
+        assert_eq!(mismatches(&dir).await, 0);
+    }
+
+    #[tokio::test]
+    async fn verify_accepts_matching_results() {
+        let dir = test_dir("matching");
+        let key = generate_key("alice@union.build");
+
+        // accepted and valid
+        let first = attestation(&key, INITIAL_PAYLOAD_ID, "first", &payload(1), true);
+        record(&dir, &payload(0), first, &payload(1)).await.unwrap();
+        // rejected and invalid
+        let rejected = attestation(&key, "first", "rejected", &payload(0xff), false);
+        record(&dir, &payload(1), rejected, &payload(0xff))
+            .await
+            .unwrap();
+        // on top of the last accepted contribution
+        let second = attestation(&key, "first", "second", &payload(2), true);
+        record(&dir, &payload(1), second, &payload(2))
+            .await
+            .unwrap();
+
+        assert_eq!(mismatches(&dir).await, 0);
+    }
+
+    #[tokio::test]
+    async fn verify_counts_mismatching_results() {
+        let dir = test_dir("mismatching");
+        let key = generate_key("alice@union.build");
+
+        // accepted but invalid
+        let first = attestation(&key, INITIAL_PAYLOAD_ID, "first", &payload(0xff), true);
+        record(&dir, &payload(0), first, &payload(0xff))
+            .await
+            .unwrap();
+        // rejected but valid
+        let rejected = attestation(&key, "first", "rejected", &payload(2), false);
+        record(&dir, &payload(0xff), rejected, &payload(2))
+            .await
+            .unwrap();
+        // accepted, but signed with another key than the one of the contributor
+        let other_key = generate_key("mallory@union.build");
+        let mut forged = attestation(&key, "first", "forged", &payload(3), true);
+        forged.public_key =
+            attestation(&other_key, "first", "forged", &payload(3), true).public_key;
+        record(&dir, &payload(0xff), forged, &payload(3))
+            .await
+            .unwrap();
+
+        assert_eq!(mismatches(&dir).await, 3);
+    }
+
+    #[tokio::test]
+    async fn verify_follows_the_recorded_chain() {
+        let dir = test_dir("chain");
+        let key = generate_key("alice@union.build");
+
+        let first = attestation(&key, INITIAL_PAYLOAD_ID, "first", &payload(1), true);
+        record(&dir, &payload(0), first, &payload(1)).await.unwrap();
+        // accepted, but on top of the initial payload instead of the first contribution
+        let fork = attestation(&key, INITIAL_PAYLOAD_ID, "fork", &payload(2), true);
+        record(&dir, &payload(0), fork, &payload(2)).await.unwrap();
+        // the chain continues from the accepted contribution
+        let second = attestation(&key, "fork", "second", &payload(3), true);
+        record(&dir, &payload(2), second, &payload(3))
+            .await
+            .unwrap();
+
+        assert_eq!(mismatches(&dir).await, 1);
+    }
+}
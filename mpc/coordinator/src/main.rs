@@ -1,8 +1,15 @@
-use std::str::FromStr;
+mod attestation;
 
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use attestation::Attestation;
 use clap::{Parser, Subcommand};
-use mpc_shared::{phase2_verify, signed_message, supabase::SupabaseMPCApi};
-use pgp::{cleartext::CleartextSignedMessage, Deserializable, SignedPublicKey};
+use mpc_shared::{
+    phase2_verify, supabase::SupabaseMPCApi, transfer::manifest_path, verify_contribution_signature,
+};
 use tracing::{debug, error, info, warn};
 use tracing_subscriber::EnvFilter;
 
@@ -44,6 +51,23 @@ enum Command {
         url: String,
         #[arg(short, long)]
         jwt: String,
+        /// Record every verified contribution in this attestation bundle.
+        #[arg(short, long)]
+        bundle: Option<PathBuf>,
+    },
+    /// Export an attestation bundle of every contribution of the ceremony.
+    Export {
+        #[arg(short, long)]
+        url: String,
+        #[arg(short, long)]
+        jwt: String,
+        #[arg(short, long)]
+        dir: PathBuf,
+    },
+    /// Verify an attestation bundle offline.
+    VerifyBundle {
+        #[arg(short, long)]
+        dir: PathBuf,
     },
 }
 
@@ -58,10 +82,16 @@ enum Error {
     NextPayloadNotFound,
     #[error("contributor signature not found")]
     ContributorSignatureNotFound,
+    #[error("attestation bundle not found.")]
+    BundleNotFound,
+    #[error("{0} contributions don't match their attestation.")]
+    BundleMismatch(usize),
 }
 
+type DynError = Box<dyn std::error::Error + Send + Sync>;
+
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+async fn main() -> Result<(), DynError> {
     let args = Args::parse();
     match args.log_format {
         LogFormat::Text => {
@@ -77,7 +107,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         }
     };
     match args.command {
-        Command::Start { url, jwt } => {
+        Command::Export { url, jwt, dir } => {
+            attestation::export(&SupabaseMPCApi::new(url, jwt.clone(), jwt), &dir).await?;
+        }
+        Command::VerifyBundle { dir } => {
+            attestation::verify(&dir).await?;
+        }
+        Command::Start { url, jwt, bundle } => {
             let client = SupabaseMPCApi::new(url, jwt.clone(), jwt);
             let progress = |percent| async move { debug!("downloaded: {:.2}%", percent) };
             loop {
@@ -125,63 +161,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
                     .contributor_signature(&current_contributor.id)
                     .await?
                     .ok_or(Error::ContributorSignatureNotFound)?;
-                let signed_public_key = SignedPublicKey::from_armor_single::<&[u8]>(
-                    hex::decode(&contribution_signature.public_key)
-                        .expect("impossible")
-                        .as_ref(),
-                )
-                .expect("impossible")
-                .0;
-
-                // Last bytes are the sh256 of the whole contrib
-                let next_payload_hash = &next_payload_content[&next_payload_content.len() - 32..];
-
-                let public_key_is_valid = signed_public_key.verify().is_ok();
-                if !public_key_is_valid {
-                    error!(
-                        %current_contributor,
-                        %current_payload,
-                        %next_payload,
-                        %contribution_signature.public_key,
-                        %contribution_signature.signature,
-                        "public key is invalid"
-                    );
-                }
-
-                let raw_signature =
-                    hex::decode(&contribution_signature.signature).expect("impossible");
-                let signature = CleartextSignedMessage::from_armor::<&[u8]>(raw_signature.as_ref())
-                    .expect("impossible")
-                    .0;
-
-                let signed_text_matches = signature.signed_text()
-                    == signed_message(
-                        &current_payload.id,
-                        &next_payload.id,
-                        &hex::encode(next_payload_hash),
-                    );
-                if !signed_text_matches {
-                    error!(
-                        %current_contributor,
-                        %current_payload,
-                        %next_payload,
-                        %contribution_signature.public_key,
-                        %contribution_signature.signature,
-                        "signature signed text mismatch"
-                    );
-                }
-
-                let signature_is_valid = signature.verify(&signed_public_key).is_ok();
-                if !signature_is_valid {
-                    error!(
-                        %current_contributor,
-                        %current_payload,
-                        %next_payload,
-                        %contribution_signature.public_key,
-                        %contribution_signature.signature,
-                        "contribution signature is invalid"
-                    );
-                }
+                let signature_is_valid = match verify_contribution_signature(
+                    &contribution_signature.public_key,
+                    &contribution_signature.signature,
+                    &current_payload.id,
+                    &next_payload.id,
+                    &next_payload_content,
+                ) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        error!(
+                            %current_contributor,
+                            %current_payload,
+                            %next_payload,
+                            %contribution_signature.public_key,
+                            %contribution_signature.signature,
+                            "contribution signature is invalid: {e}"
+                        );
+                        false
+                    }
+                };
 
                 info!("verifying payload...");
                 let contribution_is_valid =
@@ -197,11 +196,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
                     );
                 }
 
-                if public_key_is_valid
-                    && signed_text_matches
-                    && signature_is_valid
-                    && contribution_is_valid
-                {
+                let verified = signature_is_valid && contribution_is_valid;
+                if let Some(bundle) = &bundle {
+                    attestation::record(
+                        bundle,
+                        &payload_current,
+                        Attestation {
+                            contributor_id: current_contributor.id.clone(),
+                            previous_payload_id: current_payload.id.clone(),
+                            payload_id: next_payload.id.clone(),
+                            payload_hash: attestation::payload_hash(&next_payload_content),
+                            public_key: contribution_signature.public_key.clone(),
+                            signature: contribution_signature.signature.clone(),
+                            verified,
+                        },
+                        &next_payload_content,
+                    )
+                    .await?;
+                }
+
+                if verified {
                     info!(
                         %current_contributor,
                         %current_payload,
@@ -214,6 +228,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
                         .insert_contribution(current_contributor.id.clone(), true)
                         .await?;
                     tokio::fs::remove_file(&current_payload.id).await?;
+                    tokio::fs::remove_file(manifest_path(Path::new(&current_payload.id))).await?;
                 } else {
                     error!(
                         %current_contributor,
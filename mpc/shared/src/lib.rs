@@ -1,10 +1,16 @@
 pub mod supabase;
+pub mod transfer;
 pub mod types;
 
 use std::ffi::{c_char, c_int};
 
+use pgp::{cleartext::CleartextSignedMessage, Deserializable, SignedPublicKey};
+
 pub const CONTRIBUTION_SIZE: usize = 306032532;
 
+/// The payload the ceremony starts from.
+pub const INITIAL_PAYLOAD_ID: &str = "00000000-0000-0000-0000-000000000000";
+
 #[link(name = "galois")]
 extern "C" {
     fn Phase2Contribute(
@@ -58,6 +64,9 @@ pub fn phase2_contribute(phase2_payload: &[u8]) -> Result<Vec<u8>, Phase2Contrib
     }
 }
 
+/// Verifies a phase2 contribution on top of the previous payload, i.e. [`phase2_verify`].
+pub type Phase2Verify = fn(&[u8], &[u8]) -> Result<(), Phase2VerificationError>;
+
 pub fn phase2_verify(
     phase2_payload: &[u8],
     phase2_contrib: &[u8],
@@ -94,3 +103,53 @@ pub fn signed_message(
         previous_payload_id, next_payload_id, payload_hash
     )
 }
+
+#[derive(thiserror::Error, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SignatureVerificationError {
+    #[error("the public key is not a valid armored pgp key")]
+    InvalidPublicKey,
+    #[error("the public key self signature is invalid")]
+    InvalidPublicKeySignature,
+    #[error("the signature is not a valid armored cleartext message")]
+    InvalidSignature,
+    #[error("the signed text doesn't match the contribution")]
+    SignedTextMismatch,
+    #[error("the contribution signature is invalid")]
+    ContributionSignatureInvalid,
+}
+
+/// Verifies the signature submitted for the contribution that transitioned `previous_payload_id`
+/// into `payload_id`. The public key and signature are the hex encoded armored values stored in
+/// `contribution_signature`.
+pub fn verify_contribution_signature(
+    public_key: &str,
+    signature: &str,
+    previous_payload_id: &str,
+    payload_id: &str,
+    payload: &[u8],
+) -> Result<(), SignatureVerificationError> {
+    let public_key = hex::decode(public_key)
+        .ok()
+        .and_then(|raw| SignedPublicKey::from_armor_single::<&[u8]>(raw.as_ref()).ok())
+        .ok_or(SignatureVerificationError::InvalidPublicKey)?
+        .0;
+    public_key
+        .verify()
+        .map_err(|_| SignatureVerificationError::InvalidPublicKeySignature)?;
+    let signature = hex::decode(signature)
+        .ok()
+        .and_then(|raw| CleartextSignedMessage::from_armor::<&[u8]>(raw.as_ref()).ok())
+        .ok_or(SignatureVerificationError::InvalidSignature)?
+        .0;
+    // Gnark phase2 contribution appends the sha256 hash at the end
+    let payload_hash = &payload[payload.len().saturating_sub(32)..];
+    if signature.signed_text()
+        != signed_message(previous_payload_id, payload_id, &hex::encode(payload_hash))
+    {
+        return Err(SignatureVerificationError::SignedTextMismatch);
+    }
+    signature
+        .verify(&public_key)
+        .map_err(|_| SignatureVerificationError::ContributionSignatureInvalid)?;
+    Ok(())
+}
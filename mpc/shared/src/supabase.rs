@@ -1,14 +1,17 @@
-use std::{future::Future, io::SeekFrom, str::FromStr};
+use std::{future::Future, path::Path};
 
 use postgrest::Postgrest;
 use reqwest::{
-    header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_LENGTH, RANGE},
+    header::{HeaderMap, HeaderValue, AUTHORIZATION},
     ClientBuilder, StatusCode,
 };
-use tokio::io::{AsyncSeekExt, AsyncWriteExt};
 
 use crate::{
-    types::{Contribution, ContributionSignature, ContributorId, PayloadId},
+    transfer::{self, ResumableUpload, DOWNLOAD_CHUNK_SIZE, UPLOAD_CHUNK_SIZE},
+    types::{
+        Contribution, ContributionResult, ContributionSignature, ContributorId, PayloadId,
+        UserContribution,
+    },
     CONTRIBUTION_SIZE,
 };
 
@@ -16,12 +19,6 @@ const API_KEY: &str = "apikey";
 
 pub type DynError = Box<dyn std::error::Error + Send + Sync>;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum StateFileAction {
-    Download(usize),
-    Done(Vec<u8>),
-}
-
 #[derive(thiserror::Error, Debug, Clone)]
 pub enum Error {
     #[error("couldn't find expected header: {0}")]
@@ -183,8 +180,8 @@ impl SupabaseMPCApi {
         &self,
         payload_id: &str,
         payload_output: &str,
-        mut progress: impl FnMut(f64) -> F,
-    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>
+        progress: impl FnMut(f64) -> F,
+    ) -> Result<Vec<u8>, DynError>
     where
         F: Future<Output = ()>,
     {
@@ -192,73 +189,78 @@ impl SupabaseMPCApi {
             "{}/storage/v1/object/contributions/{}",
             &self.project_url, &payload_id
         );
-        let client = ClientBuilder::new()
-            .default_headers(HeaderMap::from_iter([(
-                AUTHORIZATION,
-                HeaderValue::from_str(&format!("Bearer {}", &self.jwt))?,
-            )]))
-            .build()?;
-        let state_path = payload_output;
-        let action = match get_state_file(state_path).await {
-            content if content.len() < CONTRIBUTION_SIZE => {
-                StateFileAction::Download(content.len())
-            }
-            content if content.len() == CONTRIBUTION_SIZE => StateFileAction::Done(content),
-            _ => StateFileAction::Download(0),
-        };
-        match action {
-            StateFileAction::Download(start_position) => {
-                let mut response = client
-                    .get(current_payload_download_url)
-                    .header(RANGE, format!("bytes={}-", start_position))
-                    .send()
-                    .await?
-                    .error_for_status()?;
-                let headers = response.headers();
-                let total_length = start_position
-                    + u64::from_str(
-                        headers
-                            .get(CONTENT_LENGTH)
-                            .ok_or(Error::HeaderNotFound(CONTENT_LENGTH.as_str().into()))?
-                            .to_str()?,
-                    )? as usize;
-                assert!(
-                    total_length == CONTRIBUTION_SIZE,
-                    "contribution length mismatch."
-                );
-                let mut state_file = tokio::fs::OpenOptions::new()
-                    .write(true)
-                    .create(false)
-                    .open(&state_path)
-                    .await?;
-                state_file.set_len(start_position as u64).await?;
-                state_file
-                    .seek(SeekFrom::Start(start_position as u64))
-                    .await?;
-                let mut i = start_position;
-                let mut freq = 0;
-                while let Some(chunk) = response.chunk().await? {
-                    let k = (i as f64 / CONTRIBUTION_SIZE as f64) * 100.;
-                    if freq % 200 == 0 {
-                        progress(k).await;
-                    }
-                    let written = state_file.write(&chunk).await?;
-                    assert!(written == chunk.len(), "couldn't write chunk.");
-                    i += written;
-                    freq += 1;
-                }
-                state_file.sync_data().await?;
-                let final_content = tokio::fs::read(&state_path).await?;
-                Ok(final_content)
-            }
-            StateFileAction::Done(content) => Ok(content),
-        }
+        let client = self.new_reqwest_builder()?.build()?;
+        Ok(transfer::download(
+            &client,
+            &current_payload_download_url,
+            Path::new(payload_output),
+            CONTRIBUTION_SIZE,
+            DOWNLOAD_CHUNK_SIZE,
+            progress,
+        )
+        .await?)
+    }
+
+    pub async fn create_upload(
+        &self,
+        bucket: &str,
+        object_name: &str,
+    ) -> Result<ResumableUpload, DynError> {
+        let client = self.new_reqwest_builder()?.build()?;
+        Ok(transfer::create_upload(
+            &client,
+            &format!("{}/storage/v1/upload/resumable", &self.project_url),
+            &[("bucketName", bucket), ("objectName", object_name)],
+            CONTRIBUTION_SIZE,
+        )
+        .await?)
+    }
+
+    /// Offset and length of a resumable upload, `None` if it doesn't exist or expired.
+    pub async fn upload_offset(&self, location: &str) -> Result<Option<(usize, usize)>, DynError> {
+        let client = self.new_reqwest_builder()?.build()?;
+        Ok(transfer::upload_offset(&client, location).await?)
+    }
+
+    pub async fn upload_payload<F>(
+        &self,
+        location: &str,
+        payload: &[u8],
+        progress: impl FnMut(f64) -> F,
+    ) -> Result<(), DynError>
+    where
+        F: Future<Output = ()>,
+    {
+        let client = self.new_reqwest_builder()?.build()?;
+        Ok(transfer::upload(&client, location, payload, UPLOAD_CHUNK_SIZE, progress).await?)
+    }
+
+    /// The verification result of every contribution, successful or not, in the order they were
+    /// verified.
+    pub async fn contribution_results(&self) -> Result<Vec<ContributionResult>, DynError> {
+        Ok(self
+            .client
+            .from("contribution")
+            .select("id,seq,success")
+            .order("seq.asc")
+            .execute()
+            .await?
+            .error_for_status()?
+            .json::<Vec<ContributionResult>>()
+            .await?)
     }
-}
 
-async fn get_state_file(path: &str) -> Vec<u8> {
-    if !tokio::fs::try_exists(path).await.unwrap() {
-        tokio::fs::write(path, []).await.unwrap();
+    /// All successful contributions, in the order they were verified.
+    pub async fn contributions(&self) -> Result<Vec<UserContribution>, DynError> {
+        Ok(self
+            .client
+            .from("users_contribution")
+            .select("id,seq,payload_id,public_key,signature")
+            .order("seq.asc")
+            .execute()
+            .await?
+            .error_for_status()?
+            .json::<Vec<UserContribution>>()
+            .await?)
     }
-    tokio::fs::read(path).await.unwrap()
 }
@@ -0,0 +1,754 @@
+//! Chunked, resumable transfers of contribution payloads.
+//!
+//! Downloads are fetched in fixed size ranges. The sha256 of every chunk written to disk is
+//! tracked in a manifest next to the payload, so that an interrupted download (network drop,
+//! laptop sleep) can resume from the last intact chunk instead of starting over.
+//!
+//! Uploads follow the [tus](https://tus.io/protocols/resumable-upload) protocol, which is what
+//! the supabase storage API implements. Each chunk is sent in its own `PATCH` and, when the server
+//! supports it, carries an `Upload-Checksum` so that corrupted chunks are rejected.
+
+use std::{
+    future::Future,
+    io::SeekFrom,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use httpdate::parse_http_date;
+use reqwest::{
+    header::{CONTENT_RANGE, CONTENT_TYPE, LOCATION, RANGE},
+    Client, RequestBuilder, Response, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+/// Size of the ranges requested when downloading a payload.
+pub const DOWNLOAD_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Size of the chunks sent when uploading a payload. Supabase requires every chunk but the last
+/// one to be exactly 6MB.
+pub const UPLOAD_CHUNK_SIZE: usize = 6 * 1024 * 1024;
+
+const MAX_RETRIES: u32 = 5;
+
+const TUS_VERSION: &str = "1.0.0";
+
+/// Returned by tus servers when the `Upload-Checksum` of a chunk doesn't match its content.
+const CHECKSUM_MISMATCH: u16 = 460;
+
+#[derive(thiserror::Error, Debug)]
+pub enum TransferError {
+    #[error("http request failed")]
+    Http(#[from] reqwest::Error),
+    #[error("io error")]
+    Io(#[from] std::io::Error),
+    #[error("couldn't find expected header: {0}")]
+    HeaderNotFound(String),
+    #[error("invalid header: {0}")]
+    InvalidHeader(String),
+    #[error("expected a payload of {expected} bytes, got {got}")]
+    SizeMismatch { expected: usize, got: usize },
+    #[error("expected {expected} bytes for the range starting at {start}, got {got}")]
+    ChunkSizeMismatch {
+        start: usize,
+        expected: usize,
+        got: usize,
+    },
+    #[error("chunk at offset {0} was rejected because of a checksum mismatch")]
+    ChecksumMismatch(usize),
+    #[error("upload stalled at offset {0}")]
+    UploadStalled(usize),
+    #[error("upload not found or expired")]
+    UploadNotFound,
+}
+
+impl TransferError {
+    /// Whether retrying the failed request may succeed.
+    fn is_transient(&self) -> bool {
+        match self {
+            TransferError::Http(err) => match err.status() {
+                Some(status) => status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+                // Connection errors, timeouts and truncated bodies.
+                None => true,
+            },
+            TransferError::ChunkSizeMismatch { .. }
+            | TransferError::ChecksumMismatch(_)
+            | TransferError::UploadStalled(_) => true,
+            _ => false,
+        }
+    }
+}
+
+async fn with_retries<T, Fut>(mut f: impl FnMut() -> Fut) -> Result<T, TransferError>
+where
+    Fut: Future<Output = Result<T, TransferError>>,
+{
+    let mut attempt = 0;
+    loop {
+        match f().await {
+            Err(err) if err.is_transient() && attempt < MAX_RETRIES => {
+                attempt += 1;
+                tokio::time::sleep(Duration::from_millis(500 << attempt)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn sha256(content: &[u8]) -> [u8; 32] {
+    Sha256::new().chain_update(content).finalize().into()
+}
+
+/// Hashes of the chunks of a (partially) downloaded payload.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub size: usize,
+    pub chunk_size: usize,
+    /// Hex encoded sha256 of every chunk already written, in order.
+    pub chunks: Vec<String>,
+}
+
+pub fn manifest_path(path: &Path) -> PathBuf {
+    let mut manifest = path.as_os_str().to_owned();
+    manifest.push(".chunks.json");
+    manifest.into()
+}
+
+async fn read_manifest(path: &Path) -> Option<Manifest> {
+    let content = tokio::fs::read(manifest_path(path)).await.ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+async fn write_manifest(path: &Path, manifest: &Manifest) -> Result<(), TransferError> {
+    let manifest_path = manifest_path(path);
+    let mut tmp = manifest_path.clone().into_os_string();
+    tmp.push(".tmp");
+    tokio::fs::write(&tmp, serde_json::to_vec(manifest).expect("impossible")).await?;
+    tokio::fs::rename(&tmp, &manifest_path).await?;
+    Ok(())
+}
+
+/// Downloads `url` to `path` in chunks of `chunk_size` bytes, resuming from the chunks that are
+/// already on disk and still match the manifest. The payload must be exactly `size` bytes.
+pub async fn download<F>(
+    client: &Client,
+    url: &str,
+    path: &Path,
+    size: usize,
+    chunk_size: usize,
+    mut progress: impl FnMut(f64) -> F,
+) -> Result<Vec<u8>, TransferError>
+where
+    F: Future<Output = ()>,
+{
+    let mut content = match tokio::fs::read(path).await {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(err.into()),
+    };
+    let mut manifest = read_manifest(path)
+        .await
+        .filter(|manifest| manifest.size == size && manifest.chunk_size == chunk_size)
+        .unwrap_or(Manifest {
+            size,
+            chunk_size,
+            chunks: Vec::new(),
+        });
+
+    // Only keep the prefix of chunks that are complete and whose hash still matches, anything
+    // after it is downloaded again.
+    let intact = manifest
+        .chunks
+        .iter()
+        .zip(content.chunks(chunk_size))
+        .enumerate()
+        .take_while(|(i, (hash, chunk))| {
+            chunk.len() == chunk_size.min(size - i * chunk_size)
+                && **hash == hex::encode(sha256(chunk))
+        })
+        .count();
+    manifest.chunks.truncate(intact);
+    content.truncate((intact * chunk_size).min(size));
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .await?;
+    file.set_len(content.len() as u64).await?;
+    file.seek(SeekFrom::End(0)).await?;
+
+    while content.len() < size {
+        let start = content.len();
+        let end = (start + chunk_size).min(size);
+        let chunk = with_retries(|| download_range(client, url, start, end, size)).await?;
+        file.write_all(&chunk).await?;
+        file.sync_data().await?;
+        manifest.chunks.push(hex::encode(sha256(&chunk)));
+        write_manifest(path, &manifest).await?;
+        content.extend_from_slice(&chunk);
+        progress((content.len() as f64 / size as f64) * 100.).await;
+    }
+
+    Ok(content)
+}
+
+async fn download_range(
+    client: &Client,
+    url: &str,
+    start: usize,
+    end: usize,
+    size: usize,
+) -> Result<Vec<u8>, TransferError> {
+    let response = client
+        .get(url)
+        .header(RANGE, format!("bytes={}-{}", start, end - 1))
+        .send()
+        .await?
+        .error_for_status()?;
+    // A server ignoring the range would send the whole payload, which only works for the first
+    // chunk of a payload that fits in a single chunk.
+    if response.status() != StatusCode::PARTIAL_CONTENT && (start, end) != (0, size) {
+        return Err(TransferError::InvalidHeader(RANGE.as_str().into()));
+    }
+    if let Some(content_range) = response.headers().get(CONTENT_RANGE) {
+        let total = content_range
+            .to_str()
+            .ok()
+            .and_then(|range| range.rsplit_once('/'))
+            .and_then(|(_, total)| usize::from_str(total).ok())
+            .ok_or(TransferError::InvalidHeader(CONTENT_RANGE.as_str().into()))?;
+        if total != size {
+            return Err(TransferError::SizeMismatch {
+                expected: size,
+                got: total,
+            });
+        }
+    }
+    let chunk = response.bytes().await?;
+    if chunk.len() != end - start {
+        return Err(TransferError::ChunkSizeMismatch {
+            start,
+            expected: end - start,
+            got: chunk.len(),
+        });
+    }
+    Ok(chunk.to_vec())
+}
+
+/// A resumable upload created on the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResumableUpload {
+    pub location: String,
+    /// Seconds since the unix epoch after which the upload can't be resumed anymore.
+    pub expire: u64,
+}
+
+fn tus(request: RequestBuilder) -> RequestBuilder {
+    request.header("Tus-Resumable", TUS_VERSION)
+}
+
+fn header<T: FromStr>(response: &Response, name: &str) -> Result<T, TransferError> {
+    response
+        .headers()
+        .get(name)
+        .ok_or(TransferError::HeaderNotFound(name.into()))?
+        .to_str()
+        .ok()
+        .and_then(|value| T::from_str(value).ok())
+        .ok_or(TransferError::InvalidHeader(name.into()))
+}
+
+/// <https://tus.io/protocols/resumable-upload#creation>
+pub async fn create_upload(
+    client: &Client,
+    endpoint: &str,
+    metadata: &[(&str, &str)],
+    length: usize,
+) -> Result<ResumableUpload, TransferError> {
+    let metadata = metadata
+        .iter()
+        .map(|(key, value)| format!("{} {}", key, BASE64_STANDARD.encode(value)))
+        .collect::<Vec<_>>()
+        .join(",");
+    let response = tus(client.post(endpoint))
+        .header("Upload-Length", length.to_string())
+        .header("Upload-Metadata", metadata)
+        .send()
+        .await?
+        .error_for_status()?;
+    let location = header::<String>(&response, LOCATION.as_str())?;
+    let expire = header::<String>(&response, "Upload-Expires")?;
+    let expire = parse_http_date(&expire)
+        .map_err(|_| TransferError::InvalidHeader("Upload-Expires".into()))?
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| TransferError::InvalidHeader("Upload-Expires".into()))?
+        .as_secs();
+    Ok(ResumableUpload { location, expire })
+}
+
+/// <https://tus.io/protocols/resumable-upload#head>
+///
+/// Returns the offset and length of the upload, or `None` if the server doesn't know about it
+/// (anymore).
+pub async fn upload_offset(
+    client: &Client,
+    location: &str,
+) -> Result<Option<(usize, usize)>, TransferError> {
+    let response = tus(client.head(location)).send().await?;
+    if response.status().is_client_error() {
+        return Ok(None);
+    }
+    let response = response.error_for_status()?;
+    Ok(Some((
+        header(&response, "Upload-Offset")?,
+        header(&response, "Upload-Length")?,
+    )))
+}
+
+/// <https://tus.io/protocols/resumable-upload#options>
+async fn supports_sha256_checksum(client: &Client, location: &str) -> bool {
+    let Ok(response) = tus(client.request(reqwest::Method::OPTIONS, location))
+        .send()
+        .await
+    else {
+        return false;
+    };
+    header::<String>(&response, "Tus-Checksum-Algorithm")
+        .map(|algorithms| algorithms.split(',').any(|a| a.trim() == "sha256"))
+        .unwrap_or(false)
+}
+
+/// <https://tus.io/protocols/resumable-upload#patch>
+///
+/// Returns the new offset of the upload.
+async fn upload_chunk(
+    client: &Client,
+    location: &str,
+    offset: usize,
+    chunk: &[u8],
+    checksum: bool,
+) -> Result<usize, TransferError> {
+    let mut request = tus(client.patch(location))
+        .header(CONTENT_TYPE, "application/offset+octet-stream")
+        .header("Upload-Offset", offset.to_string());
+    if checksum {
+        request = request.header(
+            "Upload-Checksum",
+            format!("sha256 {}", BASE64_STANDARD.encode(sha256(chunk))),
+        );
+    }
+    let response = request.body(chunk.to_vec()).send().await?;
+    if response.status().as_u16() == CHECKSUM_MISMATCH {
+        return Err(TransferError::ChecksumMismatch(offset));
+    }
+    header(&response.error_for_status()?, "Upload-Offset")
+}
+
+/// Uploads `content` to an upload created with [`create_upload`], starting from the offset the
+/// server already has.
+pub async fn upload<F>(
+    client: &Client,
+    location: &str,
+    content: &[u8],
+    chunk_size: usize,
+    mut progress: impl FnMut(f64) -> F,
+) -> Result<(), TransferError>
+where
+    F: Future<Output = ()>,
+{
+    let checksum = supports_sha256_checksum(client, location).await;
+    let current_offset = || async {
+        with_retries(|| upload_offset(client, location))
+            .await?
+            .ok_or(TransferError::UploadNotFound)
+    };
+    let (mut offset, length) = current_offset().await?;
+    if length != content.len() {
+        return Err(TransferError::SizeMismatch {
+            expected: content.len(),
+            got: length,
+        });
+    }
+    let mut attempt = 0;
+    while offset < length {
+        let end = (offset + chunk_size).min(length);
+        let result = upload_chunk(client, location, offset, &content[offset..end], checksum)
+            .await
+            .and_then(|new_offset| {
+                // The server may only store a prefix of the chunk, but never nothing.
+                if new_offset > offset && new_offset <= end {
+                    Ok(new_offset)
+                } else {
+                    Err(TransferError::UploadStalled(offset))
+                }
+            });
+        match result {
+            Ok(new_offset) => {
+                attempt = 0;
+                offset = new_offset;
+                progress((offset as f64 / length as f64) * 100.).await;
+            }
+            Err(err) if err.is_transient() && attempt < MAX_RETRIES => {
+                attempt += 1;
+                tokio::time::sleep(Duration::from_millis(500 << attempt)).await;
+                // The request may have partially succeeded, ask the server where to resume.
+                offset = current_offset().await?.0;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, BufReader},
+        net::{TcpListener, TcpStream},
+    };
+
+    use super::*;
+
+    /// A minimal in memory implementation of the storage API: ranged `GET`s for downloads and
+    /// the tus endpoints for uploads.
+    #[derive(Default)]
+    struct MockStorage {
+        objects: HashMap<String, Vec<u8>>,
+        uploads: HashMap<String, (usize, Vec<u8>)>,
+        checksums: bool,
+        /// Number of requests to drop after sending their headers.
+        fail_next: usize,
+        requests: Vec<String>,
+    }
+
+    struct Request {
+        method: String,
+        path: String,
+        headers: HashMap<String, String>,
+        body: Vec<u8>,
+    }
+
+    async fn read_request(stream: &mut BufReader<TcpStream>) -> Option<Request> {
+        let mut line = String::new();
+        stream.read_line(&mut line).await.ok()?;
+        let mut parts = line.split_whitespace();
+        let (method, path) = (parts.next()?.to_owned(), parts.next()?.to_owned());
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            stream.read_line(&mut line).await.ok()?;
+            let Some((name, value)) = line.trim_end().split_once(": ") else {
+                break;
+            };
+            headers.insert(name.to_lowercase(), value.to_owned());
+        }
+        let mut body = vec![
+            0;
+            headers
+                .get("content-length")
+                .map_or(0, |l| l.parse().unwrap())
+        ];
+        stream.read_exact(&mut body).await.ok()?;
+        Some(Request {
+            method,
+            path,
+            headers,
+            body,
+        })
+    }
+
+    impl MockStorage {
+        fn handle(&mut self, request: Request) -> (u16, Vec<(String, String)>, Vec<u8>) {
+            self.requests
+                .push(format!("{} {}", request.method, request.path));
+            let header = |name: &str| request.headers.get(name).cloned().unwrap_or_default();
+            match request.method.as_str() {
+                "GET" => {
+                    let Some(object) = self.objects.get(&request.path) else {
+                        return (404, vec![], vec![]);
+                    };
+                    let range = header("range");
+                    let (start, end) = range
+                        .strip_prefix("bytes=")
+                        .and_then(|range| range.split_once('-'))
+                        .map(|(start, end)| {
+                            (
+                                start.parse::<usize>().unwrap(),
+                                end.parse::<usize>().unwrap(),
+                            )
+                        })
+                        .unwrap_or((0, object.len() - 1));
+                    let end = end.min(object.len() - 1);
+                    (
+                        206,
+                        vec![(
+                            "Content-Range".into(),
+                            format!("bytes {start}-{end}/{}", object.len()),
+                        )],
+                        object[start..=end].to_vec(),
+                    )
+                }
+                "POST" => {
+                    let location = format!("/upload/{}", self.uploads.len());
+                    self.uploads.insert(
+                        location.clone(),
+                        (header("upload-length").parse().unwrap(), vec![]),
+                    );
+                    (
+                        201,
+                        vec![
+                            ("Location".into(), location),
+                            (
+                                "Upload-Expires".into(),
+                                "Wed, 21 Oct 2099 07:28:00 GMT".into(),
+                            ),
+                        ],
+                        vec![],
+                    )
+                }
+                "OPTIONS" => (
+                    204,
+                    if self.checksums {
+                        vec![("Tus-Checksum-Algorithm".into(), "sha1,sha256".into())]
+                    } else {
+                        vec![]
+                    },
+                    vec![],
+                ),
+                "HEAD" => match self.uploads.get(&request.path) {
+                    Some((length, content)) => (
+                        200,
+                        vec![
+                            ("Upload-Offset".into(), content.len().to_string()),
+                            ("Upload-Length".into(), length.to_string()),
+                        ],
+                        vec![],
+                    ),
+                    None => (404, vec![], vec![]),
+                },
+                "PATCH" => {
+                    let Some((_, content)) = self.uploads.get_mut(&request.path) else {
+                        return (404, vec![], vec![]);
+                    };
+                    if header("upload-offset").parse::<usize>().unwrap() != content.len() {
+                        return (409, vec![], vec![]);
+                    }
+                    if let Some(checksum) = header("upload-checksum").strip_prefix("sha256 ") {
+                        if BASE64_STANDARD.decode(checksum).unwrap() != sha256(&request.body) {
+                            return (460, vec![], vec![]);
+                        }
+                    }
+                    content.extend_from_slice(&request.body);
+                    (
+                        204,
+                        vec![("Upload-Offset".into(), content.len().to_string())],
+                        vec![],
+                    )
+                }
+                _ => (405, vec![], vec![]),
+            }
+        }
+    }
+
+    async fn serve(storage: Arc<Mutex<MockStorage>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let storage = storage.clone();
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    while let Some(request) = read_request(&mut stream).await {
+                        let is_head = request.method == "HEAD";
+                        let (status, headers, body, fail) = {
+                            let mut storage = storage.lock().unwrap();
+                            let fail = storage.fail_next > 0;
+                            storage.fail_next = storage.fail_next.saturating_sub(1);
+                            let (status, headers, body) = storage.handle(request);
+                            (status, headers, body, fail)
+                        };
+                        let mut response = format!("HTTP/1.1 {status} Mock\r\n");
+                        for (name, value) in headers {
+                            response.push_str(&format!("{name}: {value}\r\n"));
+                        }
+                        response.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+                        let stream = stream.get_mut();
+                        stream.write_all(response.as_bytes()).await.unwrap();
+                        if fail {
+                            // Simulate a connection dropped in the middle of the body.
+                            stream.write_all(&body[..body.len() / 2]).await.unwrap();
+                            return;
+                        }
+                        if !is_head {
+                            stream.write_all(&body).await.unwrap();
+                        }
+                    }
+                });
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    fn payload(size: usize) -> Vec<u8> {
+        (0..size).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mpc-transfer-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn download_resumes_from_intact_chunks() {
+        let content = payload(1000);
+        let storage = Arc::new(Mutex::new(MockStorage::default()));
+        storage
+            .lock()
+            .unwrap()
+            .objects
+            .insert("/payload".into(), content.clone());
+        let url = format!("{}/payload", serve(storage.clone()).await);
+        let path = test_dir("download").join("payload");
+
+        // Interrupted download: the first 4 chunks are on disk, the 4th one got corrupted.
+        let mut partial = content[..400].to_vec();
+        let manifest = Manifest {
+            size: 1000,
+            chunk_size: 100,
+            chunks: partial
+                .chunks(100)
+                .map(|c| hex::encode(sha256(c)))
+                .collect(),
+        };
+        partial[350] ^= 0xFF;
+        partial.extend_from_slice(&content[400..450]);
+        tokio::fs::write(&path, &partial).await.unwrap();
+        write_manifest(&path, &manifest).await.unwrap();
+
+        let downloaded = download(&Client::new(), &url, &path, 1000, 100, |_| async {})
+            .await
+            .unwrap();
+        assert_eq!(downloaded, content);
+        assert_eq!(tokio::fs::read(&path).await.unwrap(), content);
+        assert_eq!(read_manifest(&path).await.unwrap().chunks.len(), 10);
+
+        let requests = storage.lock().unwrap().requests.clone();
+        assert_eq!(requests.len(), 7, "chunks 3..10 are downloaded again");
+
+        // Nothing left to fetch.
+        download(&Client::new(), &url, &path, 1000, 100, |_| async {})
+            .await
+            .unwrap();
+        assert_eq!(storage.lock().unwrap().requests.len(), 7);
+    }
+
+    #[tokio::test]
+    async fn download_retries_dropped_connections() {
+        let content = payload(250);
+        let storage = Arc::new(Mutex::new(MockStorage {
+            fail_next: 2,
+            ..Default::default()
+        }));
+        storage
+            .lock()
+            .unwrap()
+            .objects
+            .insert("/payload".into(), content.clone());
+        let url = format!("{}/payload", serve(storage).await);
+        let path = test_dir("retry").join("payload");
+
+        let downloaded = download(&Client::new(), &url, &path, 250, 100, |_| async {})
+            .await
+            .unwrap();
+        assert_eq!(downloaded, content);
+    }
+
+    #[tokio::test]
+    async fn download_rejects_unexpected_size() {
+        let storage = Arc::new(Mutex::new(MockStorage::default()));
+        storage
+            .lock()
+            .unwrap()
+            .objects
+            .insert("/payload".into(), payload(300));
+        let url = format!("{}/payload", serve(storage).await);
+        let path = test_dir("size").join("payload");
+
+        let result = download(&Client::new(), &url, &path, 250, 100, |_| async {}).await;
+        assert!(matches!(
+            result,
+            Err(TransferError::SizeMismatch {
+                expected: 250,
+                got: 300
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn upload_resumes_from_server_offset() {
+        let content = payload(1000);
+        let storage = Arc::new(Mutex::new(MockStorage {
+            checksums: true,
+            ..Default::default()
+        }));
+        let endpoint = serve(storage.clone()).await;
+        let client = Client::new();
+
+        let upload = create_upload(&client, &endpoint, &[("objectName", "payload")], 1000)
+            .await
+            .unwrap();
+        assert_eq!(upload.expire, 4096250880);
+        let location = format!("{endpoint}{}", upload.location);
+
+        // A previous run already uploaded part of the payload.
+        storage
+            .lock()
+            .unwrap()
+            .uploads
+            .get_mut(&upload.location)
+            .unwrap()
+            .1
+            .extend_from_slice(&content[..300]);
+        assert_eq!(
+            upload_offset(&client, &location).await.unwrap(),
+            Some((300, 1000))
+        );
+
+        upload(&client, &location, &content, 256, |_| async {})
+            .await
+            .unwrap();
+        assert_eq!(storage.lock().unwrap().uploads[&upload.location].1, content);
+        assert_eq!(
+            upload_offset(&client, &location).await.unwrap(),
+            Some((1000, 1000))
+        );
+    }
+
+    #[tokio::test]
+    async fn upload_rejects_unknown_location() {
+        let storage = Arc::new(Mutex::new(MockStorage::default()));
+        let endpoint = serve(storage).await;
+        let client = Client::new();
+        let location = format!("{endpoint}/upload/unknown");
+
+        assert_eq!(upload_offset(&client, &location).await.unwrap(), None);
+        assert!(matches!(
+            upload(&client, &location, &payload(10), 4, |_| async {}).await,
+            Err(TransferError::UploadNotFound)
+        ));
+    }
+}
@@ -37,3 +37,19 @@ pub struct ContributionSignature {
     pub public_key: String,
     pub signature: String,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContributionResult {
+    pub id: String,
+    pub seq: i64,
+    pub success: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserContribution {
+    pub id: String,
+    pub seq: i64,
+    pub payload_id: String,
+    pub public_key: String,
+    pub signature: String,
+}
@@ -0,0 +1,459 @@
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{field::display as as_display, info, warn};
+
+/// How backups are taken and pruned.
+#[derive(Clone, Copy, Debug)]
+pub struct BackupPolicy {
+    /// The number of backups to keep.
+    pub keep_last: Option<usize>,
+    /// The maximum total size of all backups, in bytes. The latest backup is always kept.
+    pub max_disk_usage: Option<u64>,
+    /// Whether to store backups as `home.tar.gz` instead of a copy of the home directory.
+    pub compress: bool,
+}
+
+/// Backups of the uniond home directory. Each backup is a directory in `root/backups`:
+///
+/// ```text
+/// backups
+/// └── 1700000000-v0.8.0
+///     ├── backup.json
+///     └── home (or home.tar.gz)
+/// ```
+pub struct Backups {
+    dir: PathBuf,
+}
+
+/// The meta info of a backup, stored in `backup.json`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct BackupMeta {
+    pub id: String,
+    /// The version that was running when the backup was taken.
+    pub version: String,
+    /// The upgrade the backup was taken for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upgrade: Option<String>,
+    /// The upgrade height.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    /// Seconds since the unix epoch.
+    pub created_at: u64,
+    pub compressed: bool,
+    /// The size of the backup on disk, in bytes.
+    pub size: u64,
+}
+
+const META_FILE: &str = "backup.json";
+const HOME_DIR: &str = "home";
+const HOME_ARCHIVE: &str = "home.tar.gz";
+
+impl Backups {
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            dir: root.as_ref().join("backups"),
+        }
+    }
+
+    /// Backs up `home`, which must not be in use.
+    pub fn create(
+        &self,
+        home: impl AsRef<Path>,
+        version: &str,
+        upgrade: Option<(&str, u64)>,
+        compress: bool,
+    ) -> Result<BackupMeta, BackupError> {
+        let home = home.as_ref();
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("time is after the unix epoch")
+            .as_secs();
+
+        let mut id = format!("{created_at}-{version}");
+        let mut n = 1;
+        while self.dir.join(&id).exists() {
+            n += 1;
+            id = format!("{created_at}-{version}-{n}");
+        }
+
+        let path = self.dir.join(&id);
+        fs::create_dir_all(&path).map_err(|source| BackupError::Io(path.clone(), source))?;
+
+        info!(target: "unionvisor", "backing up {} to {}. This might take a while", as_display(home.display()), as_display(path.display()));
+        let result = if compress {
+            archive(home, &path.join(HOME_ARCHIVE))
+        } else {
+            copy_dir(home, &path)
+        };
+        if let Err(err) = result {
+            let _ = fs::remove_dir_all(&path);
+            return Err(err);
+        }
+
+        let meta = BackupMeta {
+            id,
+            version: version.to_owned(),
+            upgrade: upgrade.map(|(name, _)| name.to_owned()),
+            height: upgrade.map(|(_, height)| height),
+            created_at,
+            compressed: compress,
+            size: fs_extra::dir::get_size(&path).map_err(|source| BackupError::Copy {
+                from: path.clone(),
+                source,
+            })?,
+        };
+        let meta_path = path.join(META_FILE);
+        fs::write(
+            &meta_path,
+            serde_json::to_vec_pretty(&meta).expect("meta is serializable"),
+        )
+        .map_err(|source| BackupError::Io(meta_path, source))?;
+
+        info!(target: "unionvisor", id = meta.id.as_str(), size = meta.size, "completed backup");
+        Ok(meta)
+    }
+
+    /// All backups, oldest first.
+    pub fn list(&self) -> Result<Vec<BackupMeta>, BackupError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(source) => return Err(BackupError::Io(self.dir.clone(), source)),
+        };
+
+        let mut backups = vec![];
+        for entry in entries {
+            let path = entry
+                .map_err(|source| BackupError::Io(self.dir.clone(), source))?
+                .path()
+                .join(META_FILE);
+            // backups without meta are incomplete
+            let Ok(contents) = fs::read_to_string(&path) else {
+                continue;
+            };
+            match serde_json::from_str::<BackupMeta>(&contents) {
+                Ok(meta) => backups.push(meta),
+                Err(err) => {
+                    warn!(target: "unionvisor", err = as_display(&err), "ignoring invalid {}", as_display(path.display()));
+                }
+            }
+        }
+
+        backups.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+        Ok(backups)
+    }
+
+    pub fn get(&self, id: &str) -> Result<BackupMeta, BackupError> {
+        self.list()?
+            .into_iter()
+            .find(|backup| backup.id == id)
+            .ok_or_else(|| BackupError::NotFound(id.to_owned()))
+    }
+
+    /// Replaces `home` with the backup, which must not be in use.
+    ///
+    /// The backup is restored next to `home` first, and then swapped in, so a failed restore
+    /// leaves `home` untouched.
+    pub fn restore(&self, id: &str, home: impl AsRef<Path>) -> Result<(), BackupError> {
+        let home = home.as_ref();
+        let meta = self.get(id)?;
+        let path = self.dir.join(&meta.id);
+
+        let parent = home.parent().expect("home is not the root directory");
+        let name = home
+            .file_name()
+            .expect("home has a file name")
+            .to_string_lossy();
+        let staging = parent.join(format!("{name}.restore"));
+        let old = parent.join(format!("{name}.old"));
+        for leftover in [&staging, &old] {
+            remove_dir_if_exists(leftover)?;
+        }
+        fs::create_dir_all(&staging).map_err(|source| BackupError::Io(staging.clone(), source))?;
+
+        info!(target: "unionvisor", "restoring {} from {}. This might take a while", as_display(home.display()), as_display(path.display()));
+        let result = if meta.compressed {
+            unarchive(&path.join(HOME_ARCHIVE), &staging)
+        } else {
+            copy_dir(&path.join(HOME_DIR), &staging)
+        };
+        if let Err(err) = result {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(err);
+        }
+
+        if home.exists() {
+            fs::rename(home, &old).map_err(|source| BackupError::Io(home.to_owned(), source))?;
+        }
+        let restored = staging.join(HOME_DIR);
+        fs::rename(&restored, home).map_err(|source| BackupError::Io(restored, source))?;
+        remove_dir_if_exists(&old)?;
+        remove_dir_if_exists(&staging)?;
+
+        info!(target: "unionvisor", "completed restore");
+        Ok(())
+    }
+
+    /// Moves a backup taken by an older unionvisor (a copy of the home directory in
+    /// `root/home_backup`) into `root/backups`, so it is listed and pruned like the others.
+    pub fn migrate_legacy(&self) -> Result<Option<BackupMeta>, BackupError> {
+        let legacy = self.dir.with_file_name("home_backup");
+        if !legacy.join(HOME_DIR).exists() {
+            return Ok(None);
+        }
+
+        let created_at = fs::metadata(&legacy)
+            .and_then(|metadata| metadata.modified())
+            .map_err(|source| BackupError::Io(legacy.clone(), source))?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let id = format!("{created_at}-legacy");
+        let path = self.dir.join(&id);
+
+        info!(target: "unionvisor", "moving legacy backup {} to {}", as_display(legacy.display()), as_display(path.display()));
+        fs::create_dir_all(&self.dir)
+            .map_err(|source| BackupError::Io(self.dir.clone(), source))?;
+        fs::rename(&legacy, &path).map_err(|source| BackupError::Io(legacy, source))?;
+
+        let meta = BackupMeta {
+            id,
+            // older unionvisors did not record the version
+            version: "unknown".to_owned(),
+            upgrade: None,
+            height: None,
+            created_at,
+            compressed: false,
+            size: fs_extra::dir::get_size(&path).map_err(|source| BackupError::Copy {
+                from: path.clone(),
+                source,
+            })?,
+        };
+        let meta_path = path.join(META_FILE);
+        fs::write(
+            &meta_path,
+            serde_json::to_vec_pretty(&meta).expect("meta is serializable"),
+        )
+        .map_err(|source| BackupError::Io(meta_path, source))?;
+
+        Ok(Some(meta))
+    }
+
+    /// Removes the oldest backups until the policy is met. Returns the removed backups.
+    pub fn prune(&self, policy: &BackupPolicy) -> Result<Vec<BackupMeta>, BackupError> {
+        let mut backups = self.list()?;
+        let mut removed = vec![];
+
+        while backups.len() > 1 {
+            let total: u64 = backups.iter().map(|backup| backup.size).sum();
+            let too_many = policy.keep_last.is_some_and(|keep| backups.len() > keep);
+            let too_large = policy.max_disk_usage.is_some_and(|max| total > max);
+            if !too_many && !too_large {
+                break;
+            }
+
+            let oldest = backups.remove(0);
+            let path = self.dir.join(&oldest.id);
+            info!(target: "unionvisor", id = oldest.id.as_str(), "pruning backup");
+            fs::remove_dir_all(&path).map_err(|source| BackupError::Io(path, source))?;
+            removed.push(oldest);
+        }
+
+        Ok(removed)
+    }
+}
+
+fn remove_dir_if_exists(path: &Path) -> Result<(), BackupError> {
+    match fs::remove_dir_all(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => {
+            Err(BackupError::Io(path.to_owned(), err))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), BackupError> {
+    let options = fs_extra::dir::CopyOptions::new().overwrite(true);
+    fs_extra::dir::copy(from, to, &options).map_err(|source| BackupError::Copy {
+        from: from.to_owned(),
+        source,
+    })?;
+    Ok(())
+}
+
+fn archive(home: &Path, archive: &Path) -> Result<(), BackupError> {
+    let io_err = |source| BackupError::Io(archive.to_owned(), source);
+    let file = File::create(archive).map_err(io_err)?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    builder.append_dir_all(HOME_DIR, home).map_err(io_err)?;
+    builder
+        .into_inner()
+        .and_then(GzEncoder::finish)
+        .and_then(|file| file.sync_all())
+        .map_err(io_err)
+}
+
+fn unarchive(archive: &Path, to: &Path) -> Result<(), BackupError> {
+    let file = File::open(archive).map_err(|source| BackupError::Io(archive.to_owned(), source))?;
+    tar::Archive::new(GzDecoder::new(file))
+        .unpack(to)
+        .map_err(|source| BackupError::Io(archive.to_owned(), source))
+}
+
+#[derive(Debug, Error)]
+pub enum BackupError {
+    #[error("backup {0} not found")]
+    NotFound(String),
+    #[error("io error at {0:?}")]
+    Io(PathBuf, #[source] io::Error),
+    #[error("cannot copy {from:?}")]
+    Copy {
+        from: PathBuf,
+        source: fs_extra::error::Error,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::testdata;
+
+    fn assert_file_contains(file: impl AsRef<Path>, want: &str) {
+        let contents = fs::read_to_string(file.as_ref()).unwrap();
+        assert_eq!(contents, want);
+    }
+
+    #[test]
+    #[traced_test]
+    fn test_backup_and_restore() {
+        for compress in [false, true] {
+            let tmp = testdata::temp_dir_with(&["test_backup"]);
+            let root = tmp.path().join("test_backup");
+            let home = root.join("home");
+            let backups = Backups::new(&root);
+
+            let meta = backups
+                .create(&home, "v0.1.0", Some(("v0.2.0", 123)), compress)
+                .unwrap();
+            assert_eq!(backups.list().unwrap(), vec![meta.clone()]);
+            assert_eq!(meta.compressed, compress);
+            assert_eq!(
+                root.join("backups")
+                    .join(&meta.id)
+                    .join(HOME_ARCHIVE)
+                    .exists(),
+                compress
+            );
+
+            fs::write(home.join("data/foo.db"), "corrupted").unwrap();
+            fs::write(home.join("data/new.db"), "new").unwrap();
+
+            backups.restore(&meta.id, &home).unwrap();
+            assert_file_contains(home.join("data/foo.db"), "foo");
+            assert_file_contains(home.join("data/bar.db"), "bar");
+            assert!(!home.join("data/new.db").exists());
+            assert!(!root.join("home.restore").exists());
+            assert!(!root.join("home.old").exists());
+        }
+    }
+
+    #[test]
+    #[traced_test]
+    fn test_failed_restore_keeps_home() {
+        let tmp = testdata::temp_dir_with(&["test_backup"]);
+        let root = tmp.path().join("test_backup");
+        let home = root.join("home");
+        let backups = Backups::new(&root);
+
+        let meta = backups.create(&home, "v0.1.0", None, true).unwrap();
+        fs::write(
+            root.join("backups").join(&meta.id).join(HOME_ARCHIVE),
+            "corrupted",
+        )
+        .unwrap();
+        fs::write(home.join("data/new.db"), "new").unwrap();
+
+        backups.restore(&meta.id, &home).unwrap_err();
+        assert_file_contains(home.join("data/foo.db"), "foo");
+        assert_file_contains(home.join("data/new.db"), "new");
+        assert!(!root.join("home.restore").exists());
+    }
+
+    #[test]
+    #[traced_test]
+    fn test_migrate_legacy() {
+        let tmp = testdata::temp_dir_with(&["test_backup"]);
+        let root = tmp.path().join("test_backup");
+        let backups = Backups::new(&root);
+        assert_eq!(backups.migrate_legacy().unwrap(), None);
+
+        // older unionvisors copied the home directory into `home_backup`
+        fs::create_dir(root.join("home_backup")).unwrap();
+        copy_dir(&root.join("home"), &root.join("home_backup")).unwrap();
+
+        let meta = backups.migrate_legacy().unwrap().unwrap();
+        assert!(!root.join("home_backup").exists());
+        assert_eq!(backups.list().unwrap(), vec![meta.clone()]);
+
+        fs::write(root.join("home/data/foo.db"), "corrupted").unwrap();
+        backups.restore(&meta.id, root.join("home")).unwrap();
+        assert_file_contains(root.join("home/data/foo.db"), "foo");
+    }
+
+    #[test]
+    #[traced_test]
+    fn test_restore_unknown() {
+        let tmp = testdata::temp_dir_with(&["test_backup"]);
+        let root = tmp.path().join("test_backup");
+        let err = Backups::new(&root)
+            .restore("unknown", root.join("home"))
+            .unwrap_err();
+        assert!(matches!(err, BackupError::NotFound(_)));
+        assert_file_contains(root.join("home/data/foo.db"), "foo");
+    }
+
+    #[test]
+    #[traced_test]
+    fn test_prune() {
+        let tmp = testdata::temp_dir_with(&["test_backup"]);
+        let root = tmp.path().join("test_backup");
+        let backups = Backups::new(&root);
+
+        let ids = (0..3)
+            .map(|_| backups.create(root.join("home"), "v0.1.0", None, false))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+            .into_iter()
+            .map(|meta| meta.id)
+            .collect::<Vec<_>>();
+
+        let policy = BackupPolicy {
+            keep_last: Some(2),
+            max_disk_usage: None,
+            compress: false,
+        };
+        let removed = backups.prune(&policy).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].id, ids[0]);
+
+        // the latest backup is kept, even if it exceeds the limit
+        let policy = BackupPolicy {
+            keep_last: None,
+            max_disk_usage: Some(0),
+            compress: false,
+        };
+        backups.prune(&policy).unwrap();
+        let remaining = backups.list().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, ids[2]);
+    }
+}
@@ -1,13 +1,17 @@
 use std::{
+    collections::BTreeMap,
     ffi::OsString,
     fs, io,
     path::PathBuf,
     process::{Command, Stdio},
+    sync::{Arc, Mutex, PoisonError},
 };
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use tracing::{debug, error, field::display as as_display, info};
+use tracing::{debug, field::display as as_display, info, warn};
+
+use crate::download::{BinarySourceError, DownloadError, UpgradeBinaries};
 
 /// Bundles should have the following structure on the filesystem:
 ///
@@ -35,12 +39,16 @@ use tracing::{debug, error, field::display as as_display, info};
 /// ```
 ///
 /// Bundle meta information is defined in meta.json, which gets deserialized to [`BundleMeta`]
+///
+/// Versions that are not in the bundle can be downloaded into it, see [`Bundle::ensure_binary`].
 #[derive(Clone, Debug)]
 pub struct Bundle {
     /// The path of the bundle
     pub path: PathBuf,
     /// The deserialized meta info from `bundle/meta.json`
     meta: BundleMeta,
+    /// Held while downloading, so a prefetch and an upgrade never download the same binary at once.
+    downloads: Arc<Mutex<()>>,
 }
 
 /// Version paths that have not been validated.
@@ -115,6 +123,9 @@ pub struct BundleMeta {
     fallback_version: String,
     /// The directory containing a directory for each version
     versions_directory: PathBuf,
+    /// Download locations for versions that are not shipped with the bundle, keyed by version.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    upgrades: BTreeMap<String, UpgradeBinaries>,
 }
 
 impl Bundle {
@@ -134,7 +145,11 @@ impl Bundle {
             return Err(NewBundleError::NoGenesisJson);
         }
 
-        let bundle = Bundle { path, meta };
+        let bundle = Bundle {
+            path,
+            meta,
+            downloads: Arc::default(),
+        };
 
         Ok(bundle)
     }
@@ -164,6 +179,60 @@ impl Bundle {
         let fallback_version = &self.meta.fallback_version.clone();
         self.path_to(fallback_version).validate()
     }
+
+    /// Ensures the binary for `version` is in the bundle, downloading it if it is missing.
+    ///
+    /// The download location is read from `info` (the `UpgradeInfo.info` of the upgrade), and
+    /// otherwise from the `upgrades` in `meta.json`. Note that `info` is only known once uniond
+    /// halts at the upgrade height, so binaries that are only listed there are downloaded while
+    /// the node is down. List them in `meta.json` to have them prefetched instead.
+    pub fn ensure_binary(
+        &self,
+        version: &str,
+        info: Option<&str>,
+    ) -> Result<ValidVersionPath, EnsureBinaryError> {
+        let path = self.path_to(version);
+
+        let err = match path.validate() {
+            Ok(valid) => return Ok(valid),
+            Err(err) => err,
+        };
+
+        let Some(binaries) = info
+            .and_then(UpgradeBinaries::from_info)
+            .or_else(|| self.meta.upgrades.get(version).cloned())
+        else {
+            return Err(EnsureBinaryError::NotInBundle(err));
+        };
+
+        let _download = self
+            .downloads
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // the binary may have been prefetched while waiting for the lock
+        if let Ok(valid) = path.validate() {
+            return Ok(valid);
+        }
+
+        info!(target: "unionvisor", version, "binary is not in bundle, downloading it");
+        binaries.for_current_platform()?.download(&path.0)?;
+
+        Ok(path.validate()?)
+    }
+
+    /// Downloads all versions listed in the `upgrades` of `meta.json` that are not in the bundle
+    /// yet, so they are available before their upgrade height. Failures are logged, as the
+    /// download is retried once the upgrade is signaled.
+    ///
+    /// This blocks until all downloads are done, run it on a separate thread to not delay uniond.
+    pub fn prefetch_binaries(&self) {
+        for version in self.meta.upgrades.keys() {
+            if let Err(err) = self.ensure_binary(version, None) {
+                warn!(target: "unionvisor", version, err = as_display(&err), "cannot prefetch binary");
+            }
+        }
+    }
 }
 
 pub fn log_bundle(bundle: &Bundle) {
@@ -190,3 +259,15 @@ pub enum NewBundleError {
     )]
     DeserializeMeta(#[from] serde_json::Error),
 }
+
+#[derive(Debug, Error)]
+pub enum EnsureBinaryError {
+    #[error("binary is not in the bundle and no download location is known")]
+    NotInBundle(#[source] ValidateVersionPathError),
+    #[error("cannot select binary to download")]
+    Source(#[from] BinarySourceError),
+    #[error("cannot download binary")]
+    Download(#[from] DownloadError),
+    #[error("downloaded binary is invalid")]
+    Validate(#[from] ValidateVersionPathError),
+}
@@ -3,6 +3,7 @@ use std::{
     ffi::OsString,
     fs,
     io::{self},
+    net::SocketAddr,
     path::PathBuf,
     process::Stdio,
 };
@@ -13,9 +14,11 @@ use tracing::{field::display as as_display, info};
 use tracing_subscriber::filter::LevelFilter;
 
 use crate::{
+    backups::{BackupError, BackupPolicy, Backups},
     bundle::{log_bundle, Bundle, NewBundleError, ValidateVersionPathError},
     init::{self, SetSeedsError},
     logging::LogFormat,
+    status::{State, Status},
     supervisor::{self, RuntimeError},
     symlinker::{MakeFallbackLinkError, Symlinker, SymlinkerError},
 };
@@ -68,6 +71,13 @@ pub enum Command {
 
     /// Initializes a local directory to join the union network.
     Init(InitCmd),
+
+    /// Manage backups of the uniond home directory.
+    #[command(subcommand)]
+    Backups(BackupsCmd),
+
+    /// Print the status of a running unionvisor.
+    Status(StatusCmd),
 }
 
 #[derive(Clone, Parser)]
@@ -124,6 +134,68 @@ pub struct RunCmd {
     /// Milliseconds in between each poll for an upgrade.
     #[arg(short, long, env = "UNIONVISOR_POLL_INTERVAL")]
     poll_interval: Option<u64>,
+
+    /// Restart uniond when it exits, until it exited this many times within the crash-loop window.
+    /// If unset, unionvisor exits as soon as uniond exits.
+    #[arg(long, env = "UNIONVISOR_CRASH_LOOP_MAX_EXITS")]
+    crash_loop_max_exits: Option<usize>,
+
+    /// The crash-loop window in seconds.
+    #[arg(long, env = "UNIONVISOR_CRASH_LOOP_WINDOW", default_value = "300")]
+    crash_loop_window: u64,
+
+    /// On a crash loop right after an upgrade, restore the pre-upgrade home backup and binary.
+    #[arg(
+        long,
+        env = "UNIONVISOR_ROLLBACK_ON_CRASH_LOOP",
+        default_value = "false"
+    )]
+    rollback_on_crash_loop: bool,
+
+    /// Do not back up the home directory before upgrading.
+    #[arg(long, env = "UNIONVISOR_SKIP_BACKUP", default_value = "false")]
+    skip_backup: bool,
+
+    /// The number of backups to keep.
+    #[arg(long, env = "UNIONVISOR_BACKUP_KEEP_LAST", default_value = "1")]
+    backup_keep_last: usize,
+
+    /// The maximum disk usage of all backups in megabytes. The latest backup is always kept.
+    #[arg(long, env = "UNIONVISOR_BACKUP_MAX_DISK_USAGE_MB")]
+    backup_max_disk_usage_mb: Option<u64>,
+
+    /// Store backups as compressed archives.
+    #[arg(long, env = "UNIONVISOR_BACKUP_COMPRESS", default_value = "false")]
+    backup_compress: bool,
+
+    /// Serve the status and prometheus metrics over HTTP on this address, such as `127.0.0.1:26670`.
+    #[arg(long, env = "UNIONVISOR_STATUS_ADDR")]
+    status_addr: Option<SocketAddr>,
+}
+
+#[derive(Clone, Parser)]
+pub struct StatusCmd {
+    /// Query the status server on this address. If not set, the status file in the root directory
+    /// is read.
+    #[arg(long, env = "UNIONVISOR_STATUS_ADDR")]
+    addr: Option<SocketAddr>,
+}
+
+#[derive(Clone, Parser)]
+pub enum BackupsCmd {
+    /// List the backups, oldest first.
+    List,
+
+    /// Replace the home directory with a backup. uniond must not be running.
+    Restore {
+        /// The id of the backup, as shown by `backups list`.
+        id: String,
+
+        /// Restore even if the status file reports that uniond is running, for when unionvisor
+        /// was killed without updating it. Refused if the recorded uniond pid is still alive.
+        #[arg(long, default_value = "false")]
+        force: bool,
+    },
 }
 
 impl Cli {
@@ -144,11 +216,29 @@ impl Cli {
             Command::Init(cmd) => {
                 cmd.init(self.root)?;
                 Ok(())
+            }
+            Command::Backups(cmd) => {
+                cmd.run(self.root)?;
+                Ok(())
+            }
+            Command::Status(cmd) => {
+                cmd.status(self.root)?;
+                Ok(())
             } // Command::Merge(cmd) => cmd.merge(),
         }
     }
 }
 
+impl RunCliError {
+    /// The exit code of unionvisor for this error.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            RunCliError::Run(RunError::Runtime(err)) => err.exit_code(),
+            _ => 1,
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum RunCliError {
     #[error("set binary error")]
@@ -159,6 +249,10 @@ pub enum RunCliError {
     Run(#[from] RunError),
     #[error("init command error")]
     Init(#[from] InitError),
+    #[error("backups command error")]
+    Backups(#[from] BackupsError),
+    #[error("status command error")]
+    Status(#[from] StatusError),
 }
 
 /// The state that the init command left the fs in.
@@ -234,7 +328,22 @@ impl RunCmd {
             logformat,
             &symlinker,
             &self.args,
-            Duration::from_millis(self.poll_interval.unwrap_or(6000)),
+            supervisor::RunOptions {
+                poll_interval: Duration::from_millis(self.poll_interval.unwrap_or(6000)),
+                crash_loop: self.crash_loop_max_exits.map(|max_exits| {
+                    supervisor::CrashLoopPolicy {
+                        max_exits,
+                        window: Duration::from_secs(self.crash_loop_window),
+                        rollback: self.rollback_on_crash_loop,
+                    }
+                }),
+                backup: (!self.skip_backup).then_some(BackupPolicy {
+                    keep_last: Some(self.backup_keep_last),
+                    max_disk_usage: self.backup_max_disk_usage_mb.map(|mb| mb * 1024 * 1024),
+                    compress: self.backup_compress,
+                }),
+                status_addr: self.status_addr,
+            },
         )?;
         Ok(())
     }
@@ -248,6 +357,108 @@ pub enum RunError {
     Runtime(#[from] RuntimeError),
 }
 
+impl BackupsCmd {
+    fn run(&self, root: impl Into<PathBuf>) -> Result<(), BackupsError> {
+        let root = root.into();
+        let backups = Backups::new(&root);
+        backups.migrate_legacy()?;
+
+        match self {
+            BackupsCmd::List => {
+                for backup in backups.list()? {
+                    println!(
+                        "{}\tversion: {}\tupgrade: {}\theight: {}\tsize: {}MB\tcompressed: {}",
+                        backup.id,
+                        backup.version,
+                        backup.upgrade.as_deref().unwrap_or("-"),
+                        backup
+                            .height
+                            .map_or("-".to_owned(), |height| height.to_string()),
+                        backup.size / (1024 * 1024),
+                        backup.compressed
+                    );
+                }
+            }
+            BackupsCmd::Restore { id, force } => {
+                if let Some(status) = Status::read(&root) {
+                    let running = matches!(
+                        status.state,
+                        State::Running | State::Restarting | State::Upgrading
+                    );
+                    // `--force` only overrides a stale status, never a uniond that is alive
+                    if let Some(pid) = status.pid.filter(|pid| is_alive(*pid)) {
+                        return Err(BackupsError::Alive(pid));
+                    }
+                    if running && !force {
+                        return Err(BackupsError::Running);
+                    }
+                }
+
+                let backup = backups.get(id)?;
+                backups.restore(id, root.join("home"))?;
+                info!(
+                    target: "unionvisor",
+                    "restored backup {id}, which was taken while running {}. use set-uniond-version to run that version",
+                    backup.version
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum BackupsError {
+    #[error("backup error")]
+    Backup(#[from] BackupError),
+    #[error(
+        "the status file reports that uniond is running, stop unionvisor first or pass --force"
+    )]
+    Running,
+    #[error("uniond is still running with pid {0}, stop it first")]
+    Alive(u32),
+}
+
+/// Whether a process with `pid` exists, as reported by `kill -0`.
+fn is_alive(pid: u32) -> bool {
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+impl StatusCmd {
+    fn status(&self, root: impl Into<PathBuf>) -> Result<(), StatusError> {
+        let status = if let Some(addr) = self.addr {
+            reqwest::blocking::get(format!("http://{addr}/status"))
+                .and_then(reqwest::blocking::Response::error_for_status)
+                .and_then(reqwest::blocking::Response::text)?
+        } else {
+            let path = Status::path(root.into());
+            fs::read_to_string(&path).map_err(|source| StatusError::Read(path, source))?
+        };
+
+        let status: serde_json::Value = serde_json::from_str(&status)?;
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&status).expect("json is serializable")
+        );
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum StatusError {
+    #[error("cannot query the status server")]
+    Query(#[from] reqwest::Error),
+    #[error("cannot read status file {0:?}")]
+    Read(PathBuf, #[source] io::Error),
+    #[error("invalid status")]
+    Deserialize(#[from] serde_json::Error),
+}
+
 #[derive(Debug, Error)]
 pub enum SetUniondVersionError {
     #[error("runtime error")]
@@ -268,8 +479,7 @@ impl SetUniondVersionCmd {
         // Ensure version exists in bundle
         symlinker
             .bundle
-            .path_to(&self.version)
-            .validate()
+            .ensure_binary(&self.version, None)
             .map_err(|source| RuntimeError::BinaryUnavailable {
                 name: self.version.clone(),
                 source,
@@ -0,0 +1,331 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, Read, Write},
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tracing::{debug, field::display as as_display, info};
+
+/// Download locations of the binaries of an upgrade, per platform. This follows the cosmovisor
+/// convention for `UpgradeInfo.info`:
+///
+/// ```json
+/// {
+///   "binaries": {
+///     "linux/amd64": "https://example.com/uniond-amd64?checksum=sha256:<hex>",
+///     "linux/arm64": "https://example.com/uniond-arm64?checksum=sha256:<hex>"
+///   }
+/// }
+/// ```
+///
+/// The key `any` matches every platform. Every url must carry a sha256 checksum, unverified
+/// binaries are never installed.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct UpgradeBinaries {
+    pub binaries: BTreeMap<String, String>,
+}
+
+impl UpgradeBinaries {
+    /// Parses the `info` field of an `UpgradeInfo`. Returns `None` if the info does not describe
+    /// binaries, as it is free-form and often just contains release notes.
+    pub fn from_info(info: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(info)
+            .ok()
+            .filter(|binaries| !binaries.binaries.is_empty())
+    }
+
+    /// Selects the source for the platform unionvisor is running on.
+    pub fn for_current_platform(&self) -> Result<BinarySource, BinarySourceError> {
+        self.for_platform(&current_platform())
+    }
+
+    fn for_platform(&self, platform: &str) -> Result<BinarySource, BinarySourceError> {
+        let url = self
+            .binaries
+            .get(platform)
+            .or_else(|| self.binaries.get("any"))
+            .ok_or_else(|| BinarySourceError::NoBinaryForPlatform(platform.to_owned()))?;
+
+        BinarySource::parse(url)
+    }
+}
+
+/// The platform in the format used by [`UpgradeBinaries`], such as `linux/amd64`.
+fn current_platform() -> String {
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        other => other,
+    };
+
+    format!("{}/{arch}", std::env::consts::OS)
+}
+
+/// A verifiable download location of a single binary.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BinarySource {
+    /// The url without the checksum. Either `http(s)://` or `file://`.
+    pub url: String,
+    pub sha256: [u8; 32],
+}
+
+#[derive(Debug, Error)]
+pub enum BinarySourceError {
+    #[error("no binary for platform {0}")]
+    NoBinaryForPlatform(String),
+    #[error("url {0} has no checksum, expected a ?checksum=sha256:<hex> query parameter")]
+    MissingChecksum(String),
+    #[error("url {0} has an invalid sha256 checksum")]
+    InvalidChecksum(String),
+}
+
+impl BinarySource {
+    /// Parses a url with a `checksum=sha256:<hex>` query parameter.
+    pub fn parse(url: &str) -> Result<Self, BinarySourceError> {
+        let (base, query) = url
+            .split_once('?')
+            .ok_or_else(|| BinarySourceError::MissingChecksum(url.to_owned()))?;
+
+        let mut checksum = None;
+        let mut rest = vec![];
+        for param in query.split('&') {
+            match param.strip_prefix("checksum=") {
+                Some(value) => checksum = Some(value),
+                None => rest.push(param),
+            }
+        }
+
+        let checksum = checksum
+            .and_then(|checksum| checksum.strip_prefix("sha256:"))
+            .ok_or_else(|| BinarySourceError::MissingChecksum(url.to_owned()))?;
+
+        let mut sha256 = [0; 32];
+        hex::decode_to_slice(checksum, &mut sha256)
+            .map_err(|_| BinarySourceError::InvalidChecksum(url.to_owned()))?;
+
+        let url = if rest.is_empty() {
+            base.to_owned()
+        } else {
+            format!("{base}?{}", rest.join("&"))
+        };
+
+        Ok(Self { url, sha256 })
+    }
+
+    /// Downloads the binary to `destination`, verifying its checksum. The binary is first written
+    /// next to the destination and only moved into place once verified, so an interrupted or
+    /// corrupted download never leaves a binary behind.
+    pub fn download(&self, destination: impl AsRef<Path>) -> Result<(), DownloadError> {
+        let destination = destination.as_ref();
+        let parent = destination
+            .parent()
+            .expect("destination is a file in a version directory");
+
+        fs::create_dir_all(parent)
+            .map_err(|source| DownloadError::Io(parent.to_owned(), source))?;
+
+        let partial = destination.with_extension("download");
+
+        info!(target: "unionvisor", "downloading {} to {}", self.url, as_display(destination.display()));
+        let result = self.download_to(&partial).and_then(|()| {
+            fs::rename(&partial, destination)
+                .map_err(|source| DownloadError::Io(destination.to_owned(), source))
+        });
+
+        if result.is_err() {
+            let _ = fs::remove_file(&partial);
+        }
+
+        result
+    }
+
+    fn download_to(&self, path: &Path) -> Result<(), DownloadError> {
+        let mut reader = self.open()?;
+        let mut file =
+            File::create(path).map_err(|source| DownloadError::Io(path.to_owned(), source))?;
+
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let read = reader
+                .read(&mut buf)
+                .map_err(|source| DownloadError::Read(self.url.clone(), source))?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buf[..read]);
+            file.write_all(&buf[..read])
+                .map_err(|source| DownloadError::Io(path.to_owned(), source))?;
+        }
+
+        let actual: [u8; 32] = hasher.finalize().into();
+        if actual != self.sha256 {
+            return Err(DownloadError::ChecksumMismatch {
+                url: self.url.clone(),
+                expected: hex::encode(self.sha256),
+                actual: hex::encode(actual),
+            });
+        }
+        debug!(target: "unionvisor", "verified checksum of {}", self.url);
+
+        file.sync_all()
+            .and_then(|()| fs::set_permissions(path, fs::Permissions::from_mode(0o755)))
+            .map_err(|source| DownloadError::Io(path.to_owned(), source))?;
+
+        Ok(())
+    }
+
+    fn open(&self) -> Result<Box<dyn Read>, DownloadError> {
+        if let Some(path) = self.url.strip_prefix("file://") {
+            let file =
+                File::open(path).map_err(|source| DownloadError::Read(self.url.clone(), source))?;
+            return Ok(Box::new(file));
+        }
+
+        let response = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(30 * 60))
+            .build()
+            .and_then(|client| client.get(&self.url).send())
+            .and_then(reqwest::blocking::Response::error_for_status)
+            .map_err(|source| DownloadError::Http(self.url.clone(), source))?;
+
+        Ok(Box::new(response))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DownloadError {
+    #[error("cannot fetch {0}")]
+    Http(String, #[source] reqwest::Error),
+    #[error("cannot read {0}")]
+    Read(String, #[source] io::Error),
+    #[error("cannot write {0:?}")]
+    Io(PathBuf, #[source] io::Error),
+    #[error("checksum mismatch for {url}: expected sha256 {expected}, got {actual}")]
+    ChecksumMismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::BufRead, net::TcpListener, thread};
+
+    use super::*;
+
+    const BINARY: &[u8] = b"#!/usr/bin/env sh\necho $1 $2 $3 $4\n";
+
+    fn sha256_hex(bytes: &[u8]) -> String {
+        hex::encode(Sha256::digest(bytes))
+    }
+
+    /// Serves `body` once over http on a random local port.
+    fn serve_once(body: &'static [u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = io::BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(body).unwrap();
+        });
+        format!("http://{addr}/uniond")
+    }
+
+    #[test]
+    fn test_parse_info() {
+        let info = r#"{"binaries":{"linux/amd64":"https://example.com/uniond?checksum=sha256:0000000000000000000000000000000000000000000000000000000000000001"}}"#;
+        let source = UpgradeBinaries::from_info(info)
+            .unwrap()
+            .for_platform("linux/amd64")
+            .unwrap();
+        assert_eq!(source.url, "https://example.com/uniond");
+        assert_eq!(source.sha256[31], 1);
+    }
+
+    #[test]
+    fn test_parse_info_ignores_free_form_text() {
+        assert_eq!(UpgradeBinaries::from_info("see the release notes"), None);
+        assert_eq!(UpgradeBinaries::from_info(r#"{"binaries":{}}"#), None);
+    }
+
+    #[test]
+    fn test_for_platform_falls_back_to_any() {
+        let binaries = UpgradeBinaries {
+            binaries: [(
+                "any".to_owned(),
+                format!("file:///uniond?checksum=sha256:{}", sha256_hex(BINARY)),
+            )]
+            .into(),
+        };
+        assert_eq!(
+            binaries.for_platform("linux/arm64").unwrap().url,
+            "file:///uniond"
+        );
+    }
+
+    #[test]
+    fn test_checksum_is_required() {
+        assert!(matches!(
+            BinarySource::parse("https://example.com/uniond"),
+            Err(BinarySourceError::MissingChecksum(_))
+        ));
+        assert!(matches!(
+            BinarySource::parse("https://example.com/uniond?checksum=md5:00"),
+            Err(BinarySourceError::MissingChecksum(_))
+        ));
+        assert!(matches!(
+            BinarySource::parse("https://example.com/uniond?checksum=sha256:zz"),
+            Err(BinarySourceError::InvalidChecksum(_))
+        ));
+    }
+
+    #[test]
+    fn test_download_over_http() {
+        let tmp = tempfile::tempdir().unwrap();
+        let destination = tmp.path().join("versions/v0.3.0/uniond");
+        let url = serve_once(BINARY);
+
+        BinarySource::parse(&format!("{url}?checksum=sha256:{}", sha256_hex(BINARY)))
+            .unwrap()
+            .download(&destination)
+            .unwrap();
+
+        assert_eq!(fs::read(&destination).unwrap(), BINARY);
+        let mode = fs::metadata(&destination).unwrap().permissions().mode();
+        assert_eq!(mode & 0o111, 0o111);
+    }
+
+    #[test]
+    fn test_download_rejects_checksum_mismatch() {
+        let tmp = tempfile::tempdir().unwrap();
+        let destination = tmp.path().join("versions/v0.3.0/uniond");
+        let url = serve_once(BINARY);
+
+        let err = BinarySource::parse(&format!("{url}?checksum=sha256:{}", sha256_hex(b"other")))
+            .unwrap()
+            .download(&destination)
+            .unwrap_err();
+
+        assert!(matches!(err, DownloadError::ChecksumMismatch { .. }));
+        assert!(!destination.exists());
+        assert!(!destination.with_extension("download").exists());
+    }
+}
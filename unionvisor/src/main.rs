@@ -1,13 +1,20 @@
 #![warn(clippy::all, clippy::pedantic)]
 #![allow(clippy::module_name_repetitions)]
 
+use std::process::ExitCode;
+
 use clap::Parser;
 use color_eyre::eyre;
+use tracing::error;
 
+mod backups;
 mod bundle;
 mod cli;
+mod download;
 mod init;
 mod logging;
+mod server;
+mod status;
 mod supervisor;
 mod symlinker;
 mod watcher;
@@ -20,13 +27,18 @@ compile_error!(
     "unionvisor heavily interacts with the fs, and hasn't been implemented to work on windows."
 );
 
-fn main() -> eyre::Result<()> {
-    color_eyre::install()?;
+fn main() -> ExitCode {
+    color_eyre::install().unwrap();
 
     let cli = cli::Cli::parse();
 
     logging::init(cli.log_format, cli.log_level);
 
-    cli.run()?;
-    Ok(())
+    if let Err(err) = cli.run() {
+        // Exit with a dedicated code on crash loops, so operators can alert on them.
+        let code = err.exit_code();
+        error!(target: "unionvisor", "{:?}", eyre::Report::from(err));
+        return ExitCode::from(code);
+    }
+    ExitCode::SUCCESS
 }
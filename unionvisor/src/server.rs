@@ -0,0 +1,266 @@
+use std::{
+    fmt::Write as _,
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use serde::Serialize;
+use tracing::{debug, field::display as as_display, info, warn};
+
+use crate::status::{SharedStatus, State, Status};
+
+/// A minimal HTTP server exposing the status of unionvisor:
+///
+/// - `GET /status`: the [`Status`] as json, including the uptime of uniond.
+/// - `GET /health`: `200` while uniond is running, `503` otherwise.
+/// - `GET /metrics`: prometheus metrics.
+///
+/// Requests are handled one at a time on a background thread, which is plenty for monitoring.
+/// Reads and writes time out after [`TIMEOUT`], so a stalled client can't block the server.
+pub fn spawn(addr: SocketAddr, status: Status) -> io::Result<SharedStatus> {
+    let listener = TcpListener::bind(addr)?;
+    info!(target: "unionvisor", "serving status on {}", as_display(listener.local_addr()?));
+
+    let shared = Arc::new(Mutex::new(status));
+
+    let status = shared.clone();
+    thread::Builder::new()
+        .name("status-server".to_owned())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                let result = stream.and_then(|stream| handle(stream, &status));
+                if let Err(err) = result {
+                    debug!(target: "unionvisor", err = as_display(&err), "status request failed");
+                }
+            }
+            warn!(target: "unionvisor", "status server stopped");
+        })?;
+
+    Ok(shared)
+}
+
+/// How long a client may take to send its request or to read the response.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The response of `GET /status`.
+#[derive(Debug, Serialize)]
+struct StatusResponse<'a> {
+    #[serde(flatten)]
+    status: &'a Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uptime: Option<u64>,
+}
+
+fn handle(stream: TcpStream, status: &SharedStatus) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // drain the headers, requests have no body
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next(), parts.next());
+
+    let status = status.lock().expect("not poisoned").clone();
+
+    let (code, content_type, body) = match (method, path) {
+        (Some("GET"), Some("/status")) => (
+            "200 OK",
+            "application/json",
+            serde_json::to_string(&StatusResponse {
+                status: &status,
+                uptime: status.uptime(),
+            })
+            .map_err(io::Error::other)?,
+        ),
+        (Some("GET"), Some("/health")) if status.state == State::Running => {
+            ("200 OK", "text/plain", "ok\n".to_owned())
+        }
+        (Some("GET"), Some("/health")) => (
+            "503 Service Unavailable",
+            "text/plain",
+            format!("{}\n", status.state.as_str()),
+        ),
+        (Some("GET"), Some("/metrics")) => {
+            ("200 OK", "text/plain; version=0.0.4", metrics(&status))
+        }
+        _ => ("404 Not Found", "text/plain", "not found\n".to_owned()),
+    };
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {code}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+/// Renders the status in the prometheus text format.
+#[allow(clippy::cast_precision_loss)]
+fn metrics(status: &Status) -> String {
+    let mut out = String::new();
+
+    let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, f64)]| {
+        let _ = writeln!(out, "# HELP unionvisor_{name} {help}");
+        let _ = writeln!(out, "# TYPE unionvisor_{name} {kind}");
+        for (labels, value) in samples {
+            let _ = writeln!(out, "unionvisor_{name}{labels} {value}");
+        }
+    };
+
+    metric(
+        "info",
+        "gauge",
+        "The version uniond is running.",
+        &[(format!("{{version=\"{}\"}}", escape(&status.version)), 1.0)],
+    );
+    metric(
+        "state",
+        "gauge",
+        "The state of unionvisor, 1 for the current state.",
+        &State::ALL.map(|state| {
+            (
+                format!("{{state=\"{}\"}}", state.as_str()),
+                f64::from(u8::from(state == status.state)),
+            )
+        }),
+    );
+    metric(
+        "uniond_restarts_total",
+        "counter",
+        "The number of times uniond was restarted after exiting.",
+        &[(String::new(), status.restarts as f64)],
+    );
+    metric(
+        "uniond_recent_exits",
+        "gauge",
+        "The number of exits of uniond within the crash-loop window.",
+        &[(String::new(), status.recent_exits as f64)],
+    );
+    metric(
+        "uniond_uptime_seconds",
+        "gauge",
+        "Seconds since uniond was started.",
+        &[(String::new(), status.uptime().unwrap_or_default() as f64)],
+    );
+    if let Some(code) = status.last_exit_code {
+        metric(
+            "uniond_last_exit_code",
+            "gauge",
+            "The exit code of the last exit of uniond.",
+            &[(String::new(), f64::from(code))],
+        );
+    }
+    if let Some(upgrade) = &status.pending_upgrade {
+        metric(
+            "pending_upgrade_height",
+            "gauge",
+            "The height of the upgrade that is not applied yet.",
+            &[(
+                format!("{{name=\"{}\"}}", escape(&upgrade.name)),
+                upgrade.height as f64,
+            )],
+        );
+    }
+
+    out
+}
+
+/// Escapes a label value, as required by the prometheus text format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+    use crate::watcher::UpgradeInfo;
+
+    fn get(addr: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_serves_status() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let mut status = Status::new(State::Running, "v0.1.0");
+        status.spawned(Some(42));
+        let shared = spawn(addr, status).unwrap();
+
+        let response = get(addr, "/status");
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains(r#""version":"v0.1.0""#));
+        assert!(response.contains(r#""pid":42"#));
+        assert!(response.contains(r#""uptime":"#));
+
+        assert!(get(addr, "/health").starts_with("HTTP/1.1 200 OK"));
+        assert!(get(addr, "/unknown").starts_with("HTTP/1.1 404"));
+
+        shared.lock().unwrap().state = State::Upgrading;
+        let response = get(addr, "/health");
+        assert!(response.starts_with("HTTP/1.1 503"));
+        assert!(response.ends_with("upgrading\n"));
+    }
+
+    #[test]
+    fn test_metrics() {
+        let mut status = Status::new(State::CrashLoop, "v0.2.0");
+        status.restarts = 3;
+        status.last_exit_code = Some(1);
+        status.pending_upgrade = Some(UpgradeInfo {
+            name: "v0.3.0".to_owned(),
+            height: 123,
+            info: None,
+        });
+
+        let metrics = metrics(&status);
+        assert!(metrics.contains("unionvisor_info{version=\"v0.2.0\"} 1\n"));
+        assert!(metrics.contains("unionvisor_state{state=\"crash_loop\"} 1\n"));
+        assert!(metrics.contains("unionvisor_state{state=\"running\"} 0\n"));
+        assert!(metrics.contains("unionvisor_uniond_restarts_total 3\n"));
+        assert!(metrics.contains("unionvisor_uniond_last_exit_code 1\n"));
+        assert!(metrics.contains("unionvisor_pending_upgrade_height{name=\"v0.3.0\"} 123\n"));
+    }
+
+    #[test]
+    fn test_metrics_escapes_labels() {
+        let status = Status::new(State::Running, "v0.1.0\"} 1\n\\");
+        let metrics = metrics(&status);
+        assert!(metrics.contains("unionvisor_info{version=\"v0.1.0\\\"} 1\\n\\\\\"} 1\n"));
+    }
+
+    #[test]
+    fn test_stalled_client() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        spawn(addr, Status::new(State::Running, "v0.1.0")).unwrap();
+
+        // never finishes its request
+        let _stalled = TcpStream::connect(addr).unwrap();
+        assert!(get(addr, "/health").starts_with("HTTP/1.1 200 OK"));
+    }
+}
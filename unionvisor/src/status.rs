@@ -0,0 +1,207 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::ExitStatus,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{field::display as as_display, warn};
+
+use crate::watcher::UpgradeInfo;
+
+/// The state unionvisor is in, as written to `root/status.json`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum State {
+    /// uniond is running.
+    Running,
+    /// uniond exited and is being restarted.
+    Restarting,
+    /// An upgrade signal was received and the new version is being installed.
+    Upgrading,
+    /// uniond exited (too often) and unionvisor stopped.
+    Exited,
+    /// uniond crash-looped and unionvisor stopped.
+    CrashLoop,
+    /// uniond crash-looped after an upgrade, and the pre-upgrade home and binary were restored.
+    RolledBack,
+}
+
+impl State {
+    pub const ALL: [State; 6] = [
+        State::Running,
+        State::Restarting,
+        State::Upgrading,
+        State::Exited,
+        State::CrashLoop,
+        State::RolledBack,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            State::Running => "running",
+            State::Restarting => "restarting",
+            State::Upgrading => "upgrading",
+            State::Exited => "exited",
+            State::CrashLoop => "crash_loop",
+            State::RolledBack => "rolled_back",
+        }
+    }
+}
+
+/// The status file operators can alert on. It is rewritten on every state transition.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Status {
+    pub state: State,
+    /// The version `root/uniond` points to.
+    pub version: String,
+    /// The version that ran before the last upgrade, which is restored on rollback.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_version: Option<String>,
+    /// The upgrade that was signaled, but is not applied yet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending_upgrade: Option<UpgradeInfo>,
+    /// The pid of the running uniond.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    /// When the running uniond was started, in seconds since the unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<u64>,
+    /// The number of times uniond was restarted after exiting.
+    #[serde(default)]
+    pub restarts: u64,
+    /// The exit code of the last exit of uniond, or `None` if it was killed by a signal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_exit_code: Option<i32>,
+    /// A description of the last exit of uniond.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_exit: Option<String>,
+    /// The number of exits of uniond within the crash-loop window.
+    pub recent_exits: usize,
+    /// Seconds since the unix epoch.
+    pub updated_at: u64,
+}
+
+impl Status {
+    pub fn new(state: State, version: impl Into<String>) -> Self {
+        Self {
+            state,
+            version: version.into(),
+            previous_version: None,
+            pending_upgrade: None,
+            pid: None,
+            started_at: None,
+            restarts: 0,
+            last_exit_code: None,
+            last_exit: None,
+            recent_exits: 0,
+            updated_at: 0,
+        }
+    }
+
+    /// Records that uniond was started.
+    pub fn spawned(&mut self, pid: Option<u32>) {
+        self.pid = pid;
+        self.started_at = Some(now());
+    }
+
+    /// Records that uniond exited.
+    pub fn exited(&mut self, code: ExitStatus) {
+        self.pid = None;
+        self.started_at = None;
+        self.last_exit_code = code.code();
+        self.last_exit = Some(code.to_string());
+    }
+
+    /// Seconds since the running uniond was started.
+    pub fn uptime(&self) -> Option<u64> {
+        self.started_at
+            .map(|started_at| now().saturating_sub(started_at))
+    }
+
+    pub fn path(root: impl AsRef<Path>) -> PathBuf {
+        root.as_ref().join("status.json")
+    }
+
+    /// Reads the status file, if there is a valid one.
+    pub fn read(root: impl AsRef<Path>) -> Option<Self> {
+        let contents = fs::read_to_string(Self::path(root)).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    /// Atomically writes the status file.
+    pub fn write(&mut self, root: impl AsRef<Path>) -> io::Result<()> {
+        self.updated_at = now();
+
+        let path = Self::path(root);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(
+            &tmp,
+            serde_json::to_vec_pretty(self).map_err(io::Error::other)?,
+        )?;
+        fs::rename(tmp, path)
+    }
+}
+
+/// The status, shared with the status server.
+pub type SharedStatus = Arc<Mutex<Status>>;
+
+/// Publishes status updates to the status file and, if running, the status server.
+pub struct Reporter {
+    root: PathBuf,
+    shared: Option<SharedStatus>,
+}
+
+impl Reporter {
+    pub fn new(root: impl Into<PathBuf>, shared: Option<SharedStatus>) -> Self {
+        Self {
+            root: root.into(),
+            shared,
+        }
+    }
+
+    /// Transitions to `state` and publishes the status, logging failures. A missing status
+    /// update should never take down the node.
+    pub fn update(&self, status: &mut Status, state: State) {
+        status.state = state;
+        if let Err(err) = status.write(&self.root) {
+            warn!(target: "unionvisor", err = as_display(&err), "cannot write {}", as_display(Status::path(&self.root).display()));
+        }
+        if let Some(shared) = &self.shared {
+            status.clone_into(&mut shared.lock().expect("not poisoned"));
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("time is after the unix epoch")
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_read() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut status = Status::new(State::Running, "v0.2.0");
+        status.previous_version = Some("v0.1.0".to_owned());
+        status.write(tmp.path()).unwrap();
+
+        let read = Status::read(tmp.path()).unwrap();
+        assert_eq!(read, status);
+        assert_ne!(read.updated_at, 0);
+        assert!(!tmp.path().join("status.json.tmp").exists());
+    }
+
+    #[test]
+    fn test_read_missing() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert_eq!(Status::read(tmp.path()), None);
+    }
+}
@@ -1,18 +1,22 @@
 use std::{
+    collections::VecDeque,
     ffi::{OsStr, OsString},
-    fs::{self, create_dir_all},
-    io,
-    path::{Path, PathBuf},
+    fs, io,
+    net::SocketAddr,
+    path::PathBuf,
     process::{Child, ExitStatus},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use thiserror::Error;
-use tracing::{error, field::display as as_display, info, warn};
+use tracing::{error, info, warn};
 
 use crate::{
-    bundle::ValidateVersionPathError,
+    backups::{BackupError, BackupMeta, BackupPolicy, Backups},
+    bundle::{EnsureBinaryError, ValidateVersionPathError},
     logging::LogFormat,
+    server,
+    status::{Reporter, State, Status},
     symlinker::{CurrentVersionError, Symlinker, SymlinkerError},
     watcher::{FileReader, FileReaderError},
 };
@@ -86,23 +90,25 @@ impl Supervisor {
         self.root.join("home")
     }
 
-    /// Backup the current uniond home directory to the provided path. The location will be "{dir}/data".
-    pub fn backup(&self, backup_dir: impl AsRef<Path>) -> Result<(), BackupError> {
-        use fs_extra::dir::{copy, CopyOptions};
-        let backup_dir = backup_dir.as_ref();
-        info!(target: "unionvisor", "creating backup dir at {}",  as_display(backup_dir.display()));
-        create_dir_all(backup_dir)
-            .map_err(|source| BackupError::CreateDir(backup_dir.to_owned(), source))?;
-        let home_dir = self.home_dir();
-        let options = CopyOptions::new().overwrite(true);
-        info!(target: "unionvisor", "backing up {} to {}. This might take a while",  as_display(home_dir.display()),  as_display(backup_dir.display()));
-        copy(&home_dir, backup_dir, &options).map_err(|source| BackupError::CopyDir {
-            home: home_dir.clone(),
-            backup: backup_dir.to_owned(),
-            source,
-        })?;
-        info!(target: "unionvisor", "completed backup");
-        Ok(())
+    /// Backup the current uniond home directory, see [`Backups::create`].
+    pub fn backup(
+        &self,
+        backups: &Backups,
+        version: &str,
+        upgrade: Option<(&str, u64)>,
+        compress: bool,
+    ) -> Result<BackupMeta, BackupError> {
+        backups.create(self.home_dir(), version, upgrade, compress)
+    }
+
+    /// Restores the home directory from a backup. uniond must not be running.
+    pub fn restore(&self, backups: &Backups, id: &str) -> Result<(), BackupError> {
+        backups.restore(id, self.home_dir())
+    }
+
+    /// The pid of the running uniond.
+    pub fn pid(&self) -> Option<u32> {
+        self.child.as_ref().map(Child::id)
     }
 
     pub fn try_wait(&mut self) -> Result<Option<ExitStatus>, TryWaitError> {
@@ -138,18 +144,6 @@ pub enum SpawnError {
     SpawnChildError { source: io::Error, command: String },
 }
 
-#[derive(Debug, Error)]
-pub enum BackupError {
-    #[error("Cannot create backup dir {0:?}")]
-    CreateDir(PathBuf, #[source] io::Error),
-    #[error("Cannot copy home dir to backup dir")]
-    CopyDir {
-        home: PathBuf,
-        backup: PathBuf,
-        source: fs_extra::error::Error,
-    },
-}
-
 #[derive(Debug, Error)]
 pub enum RuntimeError {
     #[error("error spawning uniond")]
@@ -160,7 +154,7 @@ pub enum RuntimeError {
     CurrentVersion(#[from] CurrentVersionError),
     #[error("cannot kill supervisor")]
     SupervisorKill(#[from] KillError),
-    #[error("supervisor cannot make backup")]
+    #[error("supervisor cannot make or restore backup")]
     SupervisorBackup(#[from] BackupError),
     #[error("cannot swap symlink")]
     Symlinker(#[from] SymlinkerError),
@@ -169,28 +163,132 @@ pub enum RuntimeError {
     #[error("binary {} unavailable", name)]
     BinaryUnavailable {
         name: String,
-        source: ValidateVersionPathError,
+        source: EnsureBinaryError,
     },
     #[error("uniond exited with code: {code}")]
     UniondExit { code: ExitStatus },
+    #[error("uniond is crash-looping on {version} ({exits} recent exits)")]
+    CrashLoop { version: String, exits: usize },
+    #[error("uniond was crash-looping on {from}, rolled back to {to}")]
+    RolledBack { from: String, to: String },
     #[error("unknown FileReaderError while polling for upgrades")]
     FileReader(#[from] FileReaderError),
+    #[error("cannot serve status")]
+    StatusServer(#[source] io::Error),
     #[error("cannot fixup legacy files")]
     Fixup(#[from] std::io::Error),
 }
 
+/// Crash-loop detection for [`run_and_upgrade`]: uniond is restarted when it exits, until it
+/// exited `max_exits` times within `window`.
+#[derive(Clone, Copy, Debug)]
+pub struct CrashLoopPolicy {
+    pub max_exits: usize,
+    pub window: Duration,
+    /// Whether to restore the pre-upgrade home backup and binary if the crash loop happens after
+    /// an upgrade.
+    pub rollback: bool,
+}
+
+/// Tracks the recent exits of uniond.
+#[derive(Debug)]
+struct CrashLoopDetector {
+    policy: CrashLoopPolicy,
+    exits: VecDeque<Instant>,
+}
+
+impl CrashLoopDetector {
+    fn new(policy: CrashLoopPolicy) -> Self {
+        Self {
+            policy,
+            exits: VecDeque::new(),
+        }
+    }
+
+    /// Records an exit and returns whether uniond is crash-looping.
+    fn record_exit(&mut self, at: Instant) -> bool {
+        self.exits.push_back(at);
+        while self
+            .exits
+            .front()
+            .is_some_and(|exit| at.duration_since(*exit) > self.policy.window)
+        {
+            self.exits.pop_front();
+        }
+        self.exits.len() >= self.policy.max_exits
+    }
+
+    fn reset(&mut self) {
+        self.exits.clear();
+    }
+}
+
+impl RuntimeError {
+    /// The exit code of unionvisor, which operators can alert on.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            RuntimeError::CrashLoop { .. } => EXIT_CODE_CRASH_LOOP,
+            RuntimeError::RolledBack { .. } => EXIT_CODE_ROLLED_BACK,
+            _ => 1,
+        }
+    }
+}
+
+/// Exit code when uniond crash-looped.
+pub const EXIT_CODE_CRASH_LOOP: u8 = 3;
+/// Exit code when uniond crash-looped after an upgrade and the upgrade was rolled back.
+pub const EXIT_CODE_ROLLED_BACK: u8 = 4;
+
+/// Options for [`run_and_upgrade`].
+#[derive(Clone, Copy, Debug)]
+pub struct RunOptions {
+    /// The time in between each poll for an upgrade.
+    pub poll_interval: Duration,
+    pub crash_loop: Option<CrashLoopPolicy>,
+    /// If `None`, no backup is taken before upgrading.
+    pub backup: Option<BackupPolicy>,
+    /// The address to serve the status on, see [`server::spawn`].
+    pub status_addr: Option<SocketAddr>,
+}
+
+#[allow(clippy::too_many_lines)]
 pub fn run_and_upgrade<S: AsRef<OsStr>, I: IntoIterator<Item = S> + Clone>(
     root: impl Into<PathBuf>,
     logformat: LogFormat,
     symlinker: &Symlinker,
     args: &I,
-    pol_interval: Duration,
+    options: RunOptions,
 ) -> Result<(), RuntimeError> {
+    let RunOptions {
+        poll_interval: pol_interval,
+        crash_loop,
+        backup,
+        status_addr,
+    } = options;
     let root = root.into();
     symlinker.fix_legacy_paths()?;
+    let bundle = symlinker.bundle.clone();
+    std::thread::spawn(move || bundle.prefetch_binaries());
     let mut supervisor = Supervisor::new(root.clone(), symlinker.clone());
     let home = supervisor.home_dir();
+    let backups = Backups::new(&root);
+    backups.migrate_legacy()?;
     let mut watcher = FileReader::new(home.join("data/upgrade-info.json"));
+    let mut detector = crash_loop.map(CrashLoopDetector::new);
+
+    let mut status = Status::new(
+        State::Running,
+        symlinker.current_version()?.to_string_lossy(),
+    );
+    // Keep the rollback target if unionvisor itself restarted after an upgrade.
+    status.previous_version = Status::read(&root)
+        .filter(|previous| previous.version == status.version)
+        .and_then(|previous| previous.previous_version);
+    let shared = status_addr
+        .map(|addr| server::spawn(addr, status.clone()))
+        .transpose()
+        .map_err(RuntimeError::StatusServer)?;
+    let reporter = Reporter::new(&root, shared);
 
     info!(target: "unionvisor", "spawning supervisor process for the current uniond binary");
     supervisor
@@ -198,11 +296,86 @@ pub fn run_and_upgrade<S: AsRef<OsStr>, I: IntoIterator<Item = S> + Clone>(
         .inspect_err(|_err| {
             warn!(target: "supervisor", "failed to spawn initial binary call");
         })?;
+    status.spawned(supervisor.pid());
+    reporter.update(&mut status, State::Running);
     info!(target: "unionvisor", "spawned uniond, starting poll for upgrade signals");
     std::thread::sleep(Duration::from_millis(300));
     loop {
-        if let Some(code) = supervisor.try_wait()? {
-            return Err(RuntimeError::UniondExit { code });
+        let exit = supervisor.try_wait()?;
+        if exit.is_some() && halted_for_upgrade(&watcher, symlinker)? {
+            // Not a crash, the upgrade is picked up by polling the watcher below.
+            info!(target: "unionvisor", "uniond halted for an upgrade");
+        } else if let Some(code) = exit {
+            status.exited(code);
+
+            let Some(detector) = &mut detector else {
+                reporter.update(&mut status, State::Exited);
+                return Err(RuntimeError::UniondExit { code });
+            };
+
+            let crash_looping = detector.record_exit(Instant::now());
+            status.recent_exits = detector.exits.len();
+
+            if !crash_looping {
+                warn!(
+                    target: "unionvisor",
+                    "uniond exited with {code}, restarting ({}/{} exits within {} seconds)",
+                    detector.exits.len(),
+                    detector.policy.max_exits,
+                    detector.policy.window.as_secs()
+                );
+                reporter.update(&mut status, State::Restarting);
+                supervisor.spawn(logformat, args.clone())?;
+                status.spawned(supervisor.pid());
+                status.restarts += 1;
+                reporter.update(&mut status, State::Running);
+                std::thread::sleep(pol_interval);
+                continue;
+            }
+
+            error!(
+                target: "unionvisor",
+                "uniond is crash-looping on {}: {} exits within {} seconds, last exit: {code}",
+                status.version,
+                detector.exits.len(),
+                detector.policy.window.as_secs()
+            );
+
+            match status.previous_version.clone() {
+                Some(previous) if detector.policy.rollback => {
+                    // The backup taken for the upgrade to the crash-looping version.
+                    let Some(pre_upgrade) = backups.list()?.into_iter().rev().find(|backup| {
+                        backup.version == previous
+                            && backup.upgrade.as_ref() == Some(&status.version)
+                    }) else {
+                        warn!(target: "unionvisor", "no pre-upgrade backup found, cannot roll back");
+                        reporter.update(&mut status, State::CrashLoop);
+                        return Err(RuntimeError::CrashLoop {
+                            version: status.version.clone(),
+                            exits: detector.exits.len(),
+                        });
+                    };
+
+                    info!(target: "unionvisor", "rolling back from {} to {previous} using backup {}", status.version, pre_upgrade.id);
+                    supervisor.restore(&backups, &pre_upgrade.id)?;
+                    symlinker.swap(&previous)?;
+
+                    let from = std::mem::replace(&mut status.version, previous.clone());
+                    status.previous_version = None;
+                    reporter.update(&mut status, State::RolledBack);
+                    return Err(RuntimeError::RolledBack { from, to: previous });
+                }
+                None if detector.policy.rollback => {
+                    warn!(target: "unionvisor", "no upgrade happened, there is nothing to roll back to");
+                }
+                _ => {}
+            }
+
+            reporter.update(&mut status, State::CrashLoop);
+            return Err(RuntimeError::CrashLoop {
+                version: status.version.clone(),
+                exits: detector.exits.len(),
+            });
         }
 
         match watcher.poll() {
@@ -234,12 +407,14 @@ pub fn run_and_upgrade<S: AsRef<OsStr>, I: IntoIterator<Item = S> + Clone>(
                     height = upgrade.height,
                     "upgrade detected"
                 );
+                status.pending_upgrade = Some(upgrade.clone());
+                reporter.update(&mut status, State::Upgrading);
+
                 info!(target: "unionvisor", "checking binary availability");
 
                 symlinker
                     .bundle
-                    .path_to(&upgrade_name)
-                    .validate()
+                    .ensure_binary(&upgrade.name, upgrade.info.as_deref())
                     .map_err(|source| RuntimeError::BinaryUnavailable {
                         name: upgrade.name.clone(),
                         source,
@@ -247,12 +422,19 @@ pub fn run_and_upgrade<S: AsRef<OsStr>, I: IntoIterator<Item = S> + Clone>(
 
                 info!(target: "unionvisor", "killing supervisor process");
                 supervisor.kill()?;
-                let backup_dir = root.join("home_backup");
 
                 // If we fail to backup, the file system is incorrectly configured (permissions) or we are running
                 // out of disk space. Either way we exit the node as now the server itself has become unreliable.
-                info!(target: "unionvisor", "backing up current home");
-                supervisor.backup(&backup_dir)?;
+                if let Some(policy) = &backup {
+                    info!(target: "unionvisor", "backing up current home");
+                    supervisor.backup(
+                        &backups,
+                        &current_version.to_string_lossy(),
+                        Some((&upgrade.name, upgrade.height)),
+                        policy.compress,
+                    )?;
+                    backups.prune(policy)?;
+                }
 
                 info!(target: "unionvisor", "creating new symlink for {}", &upgrade.name);
                 symlinker.swap(&upgrade_name)?;
@@ -267,6 +449,17 @@ pub fn run_and_upgrade<S: AsRef<OsStr>, I: IntoIterator<Item = S> + Clone>(
                     // This error is most likely caused by incorrect args because of an upgrade. We can reduce the chance of that happening
                     // by introducing a configuration file with name -> args mappings.
                 })?;
+
+                // The new binary gets a fresh crash-loop window.
+                if let Some(detector) = &mut detector {
+                    detector.reset();
+                }
+                status.previous_version = Some(current_version.to_string_lossy().into_owned());
+                status.version.clone_from(&upgrade.name);
+                status.pending_upgrade = None;
+                status.spawned(supervisor.pid());
+                status.recent_exits = 0;
+                reporter.update(&mut status, State::Running);
             }
         }
         info!(target: "unionvisor", "no upgrade detected, sleeping for {} milliseconds.", &pol_interval.as_millis());
@@ -274,13 +467,29 @@ pub fn run_and_upgrade<S: AsRef<OsStr>, I: IntoIterator<Item = S> + Clone>(
     }
 }
 
+/// Whether uniond exited because it reached the height of an upgrade that is not applied yet.
+fn halted_for_upgrade(watcher: &FileReader, symlinker: &Symlinker) -> Result<bool, RuntimeError> {
+    let current_version = symlinker.current_version()?;
+    Ok(watcher
+        .peek()?
+        .is_some_and(|upgrade| OsString::from(upgrade.name) != current_version))
+}
+
 #[cfg(test)]
 mod tests {
+    use std::path::Path;
+
     use tracing_test::traced_test;
 
     use super::*;
     use crate::{bundle::Bundle, testdata};
 
+    const BACKUP_POLICY: BackupPolicy = BackupPolicy {
+        keep_last: Some(1),
+        max_disk_usage: None,
+        compress: false,
+    };
+
     #[test]
     #[traced_test]
     /// Will keep upgrading the `current` version until it hits the signal for upgrade3,
@@ -301,7 +510,12 @@ mod tests {
             LogFormat::Plain,
             &symlinker,
             &vec![root.join("home/data").as_os_str()],
-            Duration::from_secs(1),
+            RunOptions {
+                poll_interval: Duration::from_secs(1),
+                crash_loop: None,
+                backup: Some(BACKUP_POLICY),
+                status_addr: None,
+            },
         )
         .unwrap_err();
 
@@ -330,7 +544,12 @@ mod tests {
             LogFormat::Plain,
             &symlinker,
             &vec![root.join("home/data").as_os_str()],
-            Duration::from_secs(1),
+            RunOptions {
+                poll_interval: Duration::from_secs(1),
+                crash_loop: None,
+                backup: Some(BACKUP_POLICY),
+                status_addr: None,
+            },
         )
         .unwrap_err();
 
@@ -350,10 +569,33 @@ mod tests {
         let bundle = Bundle::new(tmp.join("bundle")).unwrap();
         let symlinker = Symlinker::new(root.clone(), bundle);
         let supervisor: Supervisor = Supervisor::new(root.clone(), symlinker);
-        supervisor.backup(root.join("home_backup")).unwrap();
-        assert_file_contains(root.join("home_backup/home/data/foo.db"), "foo");
+        let backup = supervisor
+            .backup(&Backups::new(&root), "v0.1.0", None, false)
+            .unwrap();
+        let backup_dir = root.join("backups").join(&backup.id);
+        assert_file_contains(backup_dir.join("home/data/foo.db"), "foo");
+        assert_file_contains(root.join("home/data/foo.db"), "foo");
+        assert_file_contains(backup_dir.join("home/data/bar.db"), "bar");
+        assert_file_contains(root.join("home/data/bar.db"), "bar");
+    }
+
+    #[test]
+    #[traced_test]
+    fn test_restore() {
+        let tmp = testdata::temp_dir_with(&["test_backup", "bundle"]);
+        let tmp = tmp.into_path();
+        let root = tmp.join("test_backup");
+        let bundle = Bundle::new(tmp.join("bundle")).unwrap();
+        let symlinker = Symlinker::new(root.clone(), bundle);
+        let supervisor: Supervisor = Supervisor::new(root.clone(), symlinker);
+        let backups = Backups::new(&root);
+        let backup = supervisor
+            .backup(&backups, "v0.1.0", Some(("v0.2.0", 123)), false)
+            .unwrap();
+
+        fs::write(root.join("home/data/foo.db"), "corrupted").unwrap();
+        supervisor.restore(&backups, &backup.id).unwrap();
         assert_file_contains(root.join("home/data/foo.db"), "foo");
-        assert_file_contains(root.join("home_backup/home/data/bar.db"), "bar");
         assert_file_contains(root.join("home/data/bar.db"), "bar");
     }
 
@@ -362,6 +604,152 @@ mod tests {
         assert_eq!(contents, want);
     }
 
+    #[test]
+    fn test_crash_loop_detector() {
+        let mut detector = CrashLoopDetector::new(CrashLoopPolicy {
+            max_exits: 3,
+            window: Duration::from_secs(60),
+            rollback: false,
+        });
+        let start = Instant::now();
+
+        assert!(!detector.record_exit(start));
+        assert!(!detector.record_exit(start + Duration::from_secs(30)));
+        // the first exit dropped out of the window
+        assert!(!detector.record_exit(start + Duration::from_secs(85)));
+        assert!(detector.record_exit(start + Duration::from_secs(88)));
+
+        detector.reset();
+        assert!(!detector.record_exit(start + Duration::from_secs(90)));
+    }
+
+    #[test]
+    #[traced_test]
+    fn test_crash_loop() {
+        let tmp_dir = testdata::temp_dir_with(&["test_early_exit"]);
+        let root = tmp_dir.into_path().join("test_early_exit");
+        let bundle = Bundle::new(root.join("bundle")).expect("should be able to create a bundle");
+        let symlinker = Symlinker::new(root.clone(), bundle);
+
+        symlinker
+            .make_fallback_link()
+            .expect("fallback link should be made");
+
+        let err = run_and_upgrade(
+            root.clone(),
+            LogFormat::Plain,
+            &symlinker,
+            &vec![root.join("data").as_os_str()],
+            RunOptions {
+                poll_interval: Duration::from_millis(100),
+                crash_loop: Some(CrashLoopPolicy {
+                    max_exits: 3,
+                    window: Duration::from_secs(60),
+                    rollback: true,
+                }),
+                backup: Some(BACKUP_POLICY),
+                status_addr: None,
+            },
+        )
+        .unwrap_err();
+
+        assert!(matches!(err, RuntimeError::CrashLoop { exits: 3, .. }));
+        assert_eq!(err.exit_code(), EXIT_CODE_CRASH_LOOP);
+        let status = Status::read(&root).unwrap();
+        assert_eq!(status.state, State::CrashLoop);
+        assert_eq!(status.version, "genesis");
+        assert_eq!(status.restarts, 2);
+        assert_eq!(status.last_exit_code, Some(1));
+        assert_eq!(status.pid, None);
+    }
+
+    #[test]
+    #[traced_test]
+    /// uniond exits at the upgrade height, which must not be counted as a crash.
+    fn test_halt_for_upgrade() {
+        let tmp_dir = testdata::temp_dir_with(&["test_halt"]);
+        let root = tmp_dir.into_path().join("test_halt");
+        let bundle = Bundle::new(root.join("bundle")).expect("should be able to create a bundle");
+        let symlinker = Symlinker::new(root.clone(), bundle);
+
+        symlinker
+            .make_fallback_link()
+            .expect("fallback link should be made");
+
+        let err = run_and_upgrade(
+            root.clone(),
+            LogFormat::Plain,
+            &symlinker,
+            &vec![root.join("home/data").as_os_str()],
+            RunOptions {
+                poll_interval: Duration::from_millis(100),
+                crash_loop: Some(CrashLoopPolicy {
+                    max_exits: 1,
+                    window: Duration::from_secs(60),
+                    rollback: false,
+                }),
+                backup: None,
+                status_addr: None,
+            },
+        )
+        .unwrap_err();
+
+        if let RuntimeError::BinaryUnavailable { name, source: _ } = err {
+            assert_eq!(name, "upgrade1");
+        } else {
+            panic!("didn't receive expected error: {err:?}")
+        }
+    }
+
+    #[test]
+    #[traced_test]
+    /// Upgrades to a binary that corrupts the home and exits, which is rolled back.
+    fn test_crash_loop_rollback() {
+        let tmp_dir = testdata::temp_dir_with(&["test_rollback"]);
+        let root = tmp_dir.into_path().join("test_rollback");
+        let bundle = Bundle::new(root.join("bundle")).expect("should be able to create a bundle");
+        let symlinker = Symlinker::new(root.clone(), bundle);
+
+        symlinker
+            .make_fallback_link()
+            .expect("fallback link should be made");
+
+        let err = run_and_upgrade(
+            root.clone(),
+            LogFormat::Plain,
+            &symlinker,
+            &vec![root.join("home/data").as_os_str()],
+            RunOptions {
+                poll_interval: Duration::from_millis(100),
+                crash_loop: Some(CrashLoopPolicy {
+                    max_exits: 3,
+                    window: Duration::from_secs(60),
+                    rollback: true,
+                }),
+                backup: Some(BACKUP_POLICY),
+                status_addr: None,
+            },
+        )
+        .unwrap_err();
+
+        if let RuntimeError::RolledBack { from, to } = &err {
+            assert_eq!(from, "bad");
+            assert_eq!(to, "genesis");
+        } else {
+            panic!("didn't receive expected error: {err:?}")
+        }
+        assert_eq!(err.exit_code(), EXIT_CODE_ROLLED_BACK);
+        assert_eq!(symlinker.current_version().unwrap(), "genesis");
+        assert_file_contains(root.join("home/data/foo.db"), "foo");
+        let backups = Backups::new(&root).list().unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].version, "genesis");
+        assert_eq!(backups[0].upgrade.as_deref(), Some("bad"));
+        let status = Status::read(&root).unwrap();
+        assert_eq!(status.state, State::RolledBack);
+        assert_eq!(status.version, "genesis");
+    }
+
     #[test]
     #[traced_test]
     fn test_early_exit() {
@@ -380,7 +768,12 @@ mod tests {
             LogFormat::Plain,
             &symlinker,
             &vec![root.join("data").as_os_str()],
-            Duration::from_secs(1),
+            RunOptions {
+                poll_interval: Duration::from_secs(1),
+                crash_loop: None,
+                backup: Some(BACKUP_POLICY),
+                status_addr: None,
+            },
         )
         .unwrap_err();
 
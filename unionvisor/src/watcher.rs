@@ -5,17 +5,18 @@ use std::{
     time::Duration,
 };
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use tracing::error;
 
 /// `UpgradeInfo` is set by the node periodically when a chain upgrade is required.
-#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct UpgradeInfo {
     /// The name of the upgrade; which operators must match to a binary.
     pub name: String,
     /// The height at which to enact the upgrade.
     pub height: u64,
     /// Additional info regarding the upgrade.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub info: Option<String>,
 }
 
@@ -70,6 +71,15 @@ impl FileReader {
         Ok(info)
     }
 
+    /// Reads the upgrade info without marking it as seen, so a later [`FileReader::poll`] still
+    /// returns it.
+    pub fn peek(&self) -> Result<Option<UpgradeInfo>, FileReaderError> {
+        match self.read_upgrade_info() {
+            Err(FileReaderError::FileNotFound) => Ok(None),
+            result => result.map(Some),
+        }
+    }
+
     pub fn poll(&mut self) -> Result<Option<UpgradeInfo>, FileReaderError> {
         match self.read_upgrade_info() {
             Err(FileReaderError::FileNotFound) => Ok(None),
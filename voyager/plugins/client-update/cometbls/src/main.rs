@@ -1,6 +1,7 @@
 use std::{
     collections::{HashMap, VecDeque},
     num::ParseIntError,
+    sync::Arc,
 };
 
 use call::FetchUpdateBoot;
@@ -26,8 +27,8 @@ use jsonrpsee::{
 };
 use num_bigint::BigUint;
 use serde::{Deserialize, Serialize};
-use tracing::{debug, error, info, instrument, trace};
-use unionlabs::{bounded::BoundedI64, ibc::core::client::height::Height};
+use tracing::{debug, error, info, instrument, trace, warn};
+use unionlabs::{bounded::BoundedI64, ibc::core::client::height::Height, ErrorReporter};
 use voyager_message::{
     call::{Call, WaitForHeight},
     data::Data,
@@ -44,11 +45,13 @@ use crate::{
     call::{FetchProveRequest, FetchUpdate, ModuleCall},
     callback::{AggregateHeader, ModuleCallback},
     data::{ModuleData, ProveResponse},
+    prover::{PollError, ProofCache, ProofCacheConfig, ProofCacheKey, ProverHealthConfig, Provers},
 };
 
 pub mod call;
 pub mod callback;
 pub mod data;
+pub mod prover;
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() {
@@ -62,7 +65,8 @@ pub struct Module {
     pub cometbft_client: cometbft_rpc::Client,
     pub chain_revision: u64,
 
-    pub prover_endpoints: Vec<String>,
+    pub provers: Arc<Provers>,
+    pub proof_cache: ProofCache,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,6 +77,12 @@ pub struct Config {
     pub rpc_url: String,
 
     pub prover_endpoints: Vec<String>,
+
+    #[serde(default)]
+    pub prover_health: ProverHealthConfig,
+
+    #[serde(default)]
+    pub proof_cache: ProofCacheConfig,
 }
 
 impl Plugin for Module {
@@ -83,6 +93,10 @@ impl Plugin for Module {
     type Cmd = DefaultCmd;
 
     async fn new(config: Self::Config) -> Result<Self, BoxDynError> {
+        if config.prover_endpoints.is_empty() {
+            return Err("at least one prover endpoint is required".into());
+        }
+
         let cometbft_client = cometbft_rpc::Client::new(config.rpc_url).await?;
 
         let chain_id = cometbft_client
@@ -117,7 +131,8 @@ impl Plugin for Module {
             cometbft_client,
             chain_id: ChainId::new(chain_id),
             chain_revision,
-            prover_endpoints: config.prover_endpoints,
+            provers: Arc::new(Provers::new(config.prover_endpoints, config.prover_health)),
+            proof_cache: ProofCache::new(config.proof_cache).await?,
         })
     }
 
@@ -509,27 +524,40 @@ impl PluginServer<ModuleCall, ModuleCallback> for Module {
                 update_from,
                 request,
             }) => {
-                debug!("submitting prove request");
+                let key = ProofCacheKey::new(update_from, &request);
 
-                let prover_endpoint = &self.prover_endpoints[usize::try_from(
-                    request.untrusted_header.height.inner(),
-                )
-                .expect("never going to happen bro")
-                    % self.prover_endpoints.len()];
+                let done = |response| {
+                    data(PluginMessage::new(
+                        self.plugin_name(),
+                        ModuleData::from(ProveResponse {
+                            prove_response: response,
+                            update_from,
+                            header: request.untrusted_header.clone(),
+                        }),
+                    ))
+                };
 
-                let response = galois_rpc::Client::connect(prover_endpoint)
-                    .await
-                    .unwrap()
-                    .poll(PollRequest {
-                        request: request.clone(),
-                    })
+                if let Some(response) = self.proof_cache.get(&key).await {
+                    info!("proof found in cache");
+
+                    return Ok(done(response));
+                }
+
+                debug!("submitting prove request");
+
+                let (prover_endpoint, response) = self
+                    .provers
+                    .poll(
+                        usize::try_from(key.untrusted_height).expect("never going to happen bro"),
+                        PollRequest {
+                            request: request.clone(),
+                        },
+                    )
                     .await;
 
-                debug!("submitted prove request");
+                debug!(prover = %prover_endpoint, "submitted prove request");
 
                 let retry = || {
-                    debug!("proof pending");
-
                     seq([
                         // REVIEW: How long should we wait between polls?
                         defer(now() + 1),
@@ -543,9 +571,33 @@ impl PluginServer<ModuleCall, ModuleCallback> for Module {
                     ])
                 };
                 match response {
-                    Ok(PollResponse::Pending) => Ok(retry()),
-                    Err(status) if status.message() == "busy_building" => Ok(retry()),
-                    Err(err) => panic!("prove request failed: {:?}", err),
+                    Ok(PollResponse::Pending) => {
+                        debug!("proof pending");
+
+                        Ok(retry())
+                    }
+                    Err(error @ PollError::Transport(_)) => {
+                        warn!(
+                            prover = %prover_endpoint,
+                            error = %ErrorReporter(&error),
+                            "unable to poll prover, retrying"
+                        );
+
+                        Ok(retry())
+                    }
+                    Err(error @ PollError::Rejected(_)) => {
+                        error!(
+                            prover = %prover_endpoint,
+                            error = %ErrorReporter(&error),
+                            "prover rejected the request"
+                        );
+
+                        Err(ErrorObject::owned(
+                            FATAL_JSONRPC_ERROR_CODE,
+                            ErrorReporter(error).to_string(),
+                            None::<()>,
+                        ))
+                    }
                     Ok(PollResponse::Failed(ProveRequestFailed { message })) => {
                         error!(%message, "prove request failed");
 
@@ -558,14 +610,9 @@ impl PluginServer<ModuleCall, ModuleCallback> for Module {
                     Ok(PollResponse::Done(ProveRequestDone { response })) => {
                         info!(prover = %prover_endpoint, "proof generated");
 
-                        Ok(data(PluginMessage::new(
-                            self.plugin_name(),
-                            ModuleData::from(ProveResponse {
-                                prove_response: response,
-                                update_from,
-                                header: request.untrusted_header,
-                            }),
-                        )))
+                        self.proof_cache.insert(key, response.clone()).await;
+
+                        Ok(done(response))
                     }
                 }
             }
@@ -0,0 +1,415 @@
+use std::{
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use galois_rpc::{
+    poll_request::PollRequest, poll_response::PollResponse, prove_request::ProveRequest,
+    prove_response::ProveResponse,
+};
+use serde::{Deserialize, Serialize};
+use tonic::{Code, Status};
+use tracing::{debug, warn};
+use unionlabs::{ibc::core::client::height::Height, primitives::H256};
+use voyager_vm::BoxDynError;
+
+/// Identifies a proof independently of the prover that generated it. The trusted validator set is
+/// fixed by the trusted height, the untrusted one by its hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProofCacheKey {
+    pub trusted_height: Height,
+    pub untrusted_height: i64,
+    pub validators_hash: H256,
+}
+
+impl ProofCacheKey {
+    pub fn new(update_from: Height, request: &ProveRequest) -> Self {
+        Self {
+            trusted_height: update_from,
+            untrusted_height: request.untrusted_header.height.inner(),
+            validators_hash: request.untrusted_header.validators_hash.into_encoding(),
+        }
+    }
+
+    fn file_name(&self) -> String {
+        format!(
+            "{}-{}-{}.json",
+            self.trusted_height, self.untrusted_height, self.validators_hash
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProofCacheConfig {
+    /// Maximum number of proofs kept in memory.
+    #[serde(default = "ProofCacheConfig::default_capacity")]
+    pub capacity: u64,
+    /// Directory where proofs are persisted, shared between voyager instances relaying for the
+    /// same chain so that a proof is only generated once.
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+}
+
+impl ProofCacheConfig {
+    fn default_capacity() -> u64 {
+        64
+    }
+}
+
+impl Default for ProofCacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: Self::default_capacity(),
+            dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProofCache {
+    cache: moka::future::Cache<ProofCacheKey, ProveResponse>,
+    dir: Option<PathBuf>,
+}
+
+impl ProofCache {
+    pub async fn new(config: ProofCacheConfig) -> Result<Self, BoxDynError> {
+        if let Some(dir) = &config.dir {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+
+        Ok(Self {
+            cache: moka::future::Cache::new(config.capacity),
+            dir: config.dir,
+        })
+    }
+
+    pub async fn get(&self, key: &ProofCacheKey) -> Option<ProveResponse> {
+        if let Some(response) = self.cache.get(key).await {
+            return Some(response);
+        }
+
+        let path = self.dir.as_ref()?.join(key.file_name());
+
+        let response = match tokio::fs::read(&path).await {
+            Ok(content) => match serde_json::from_slice::<ProveResponse>(&content) {
+                Ok(response) => response,
+                Err(error) => {
+                    warn!(%error, path = %path.display(), "invalid cached proof");
+                    return None;
+                }
+            },
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(error) => {
+                warn!(%error, path = %path.display(), "unable to read cached proof");
+                return None;
+            }
+        };
+
+        self.cache.insert(*key, response.clone()).await;
+
+        Some(response)
+    }
+
+    pub async fn insert(&self, key: ProofCacheKey, response: ProveResponse) {
+        if let Some(dir) = &self.dir {
+            let path = dir.join(key.file_name());
+            let tmp = path.with_extension("json.tmp");
+
+            let write = async {
+                tokio::fs::write(&tmp, serde_json::to_vec(&response)?).await?;
+                tokio::fs::rename(&tmp, &path).await?;
+                Ok::<_, BoxDynError>(())
+            };
+
+            if let Err(error) = write.await {
+                warn!(%error, path = %path.display(), "unable to persist proof");
+            }
+        }
+
+        self.cache.insert(key, response).await;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProverHealthConfig {
+    /// Number of consecutive failures after which a prover is skipped.
+    #[serde(default = "ProverHealthConfig::default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// How long a failing prover is skipped for before being tried again.
+    #[serde(default = "ProverHealthConfig::default_cooldown_seconds")]
+    pub cooldown_seconds: u64,
+}
+
+impl ProverHealthConfig {
+    fn default_failure_threshold() -> u32 {
+        3
+    }
+
+    fn default_cooldown_seconds() -> u64 {
+        30
+    }
+}
+
+impl Default for ProverHealthConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: Self::default_failure_threshold(),
+            cooldown_seconds: Self::default_cooldown_seconds(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Health {
+    consecutive_failures: u32,
+    skip_until: Option<Instant>,
+}
+
+impl Health {
+    fn is_available(&self, now: Instant) -> bool {
+        self.skip_until.is_none_or(|until| until <= now)
+    }
+}
+
+/// Why a prover could not be polled.
+#[derive(Debug, thiserror::Error)]
+pub enum PollError {
+    /// The prover could not be reached or is overloaded, polling again (possibly on another
+    /// prover) can succeed.
+    #[error("unable to reach prover")]
+    Transport(#[source] BoxDynError),
+    /// The prover rejected the request, polling it again will not succeed.
+    #[error("prover rejected the request")]
+    Rejected(#[source] Status),
+}
+
+impl From<Status> for PollError {
+    fn from(status: Status) -> Self {
+        match status.code() {
+            // tonic reports connection errors as `Unknown`
+            Code::Unavailable
+            | Code::DeadlineExceeded
+            | Code::ResourceExhausted
+            | Code::Aborted
+            | Code::Cancelled
+            | Code::Unknown => PollError::Transport(status.into()),
+            _ => PollError::Rejected(status),
+        }
+    }
+}
+
+/// The configured prover endpoints, along with their health.
+#[derive(Debug)]
+pub struct Provers {
+    endpoints: Vec<String>,
+    health: Mutex<Vec<Health>>,
+    config: ProverHealthConfig,
+}
+
+impl Provers {
+    pub fn new(endpoints: Vec<String>, config: ProverHealthConfig) -> Self {
+        Self {
+            health: Mutex::new(vec![Health::default(); endpoints.len()]),
+            endpoints,
+            config,
+        }
+    }
+
+    /// Picks the prover for `seed`, skipping over the unhealthy ones. Identical requests must use
+    /// the same seed so that they are polled on the same prover, which deduplicates them.
+    ///
+    /// If every prover is unhealthy, the one that will recover first is returned.
+    fn pick(&self, seed: usize) -> usize {
+        let now = Instant::now();
+        let health = self.health.lock().unwrap();
+
+        let start = seed % self.endpoints.len();
+
+        (0..self.endpoints.len())
+            .map(|i| (start + i) % self.endpoints.len())
+            .find(|&i| health[i].is_available(now))
+            .unwrap_or_else(|| {
+                (0..self.endpoints.len())
+                    .min_by_key(|&i| health[i].skip_until)
+                    .expect("there is at least one prover endpoint; qed;")
+            })
+    }
+
+    fn report_success(&self, idx: usize) {
+        self.health.lock().unwrap()[idx] = Health::default();
+    }
+
+    fn report_failure(&self, idx: usize) {
+        let mut health = self.health.lock().unwrap();
+        let health = &mut health[idx];
+
+        health.consecutive_failures += 1;
+
+        if health.consecutive_failures >= self.config.failure_threshold {
+            warn!(
+                prover = %self.endpoints[idx],
+                consecutive_failures = health.consecutive_failures,
+                "prover is unhealthy, skipping it for {}s",
+                self.config.cooldown_seconds
+            );
+
+            health.skip_until =
+                Some(Instant::now() + Duration::from_secs(self.config.cooldown_seconds));
+        }
+    }
+
+    /// Polls the request on a healthy prover, returning the endpoint that was used. A busy prover
+    /// is reported as a pending proof.
+    pub async fn poll(
+        &self,
+        seed: usize,
+        request: PollRequest,
+    ) -> (&str, Result<PollResponse, PollError>) {
+        let idx = self.pick(seed);
+        let endpoint = &self.endpoints[idx];
+
+        debug!(prover = %endpoint, "polling prover");
+
+        let mut client = match galois_rpc::Client::connect(endpoint.clone()).await {
+            Ok(client) => client,
+            Err(error) => {
+                self.report_failure(idx);
+                return (endpoint, Err(PollError::Transport(error.into())));
+            }
+        };
+
+        match client.poll(request).await {
+            Ok(response) => {
+                self.report_success(idx);
+                (endpoint, Ok(response))
+            }
+            Err(status) if status.message() == "busy_building" => {
+                self.report_success(idx);
+                (endpoint, Ok(PollResponse::Pending))
+            }
+            Err(status) => match PollError::from(status) {
+                error @ PollError::Transport(_) => {
+                    self.report_failure(idx);
+                    (endpoint, Err(error))
+                }
+                // the prover is healthy, it answered
+                error @ PollError::Rejected(_) => {
+                    self.report_success(idx);
+                    (endpoint, Err(error))
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use galois_rpc::{
+        mock::{prove_request, MockProver},
+        poll_response::ProveRequestDone,
+    };
+
+    use super::*;
+
+    const DEAD_ENDPOINT: &str = "http://127.0.0.1:1";
+
+    fn config() -> ProverHealthConfig {
+        ProverHealthConfig {
+            failure_threshold: 1,
+            cooldown_seconds: 60,
+        }
+    }
+
+    async fn poll_until_done(
+        provers: &Provers,
+        seed: usize,
+        request: PollRequest,
+    ) -> ProveResponse {
+        loop {
+            match provers.poll(seed, request.clone()).await {
+                (_, Ok(PollResponse::Done(ProveRequestDone { response }))) => return response,
+                (_, Ok(PollResponse::Pending)) => {}
+                (endpoint, res) => panic!("unexpected response from {endpoint}: {res:?}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn dead_prover_is_skipped() {
+        let prover = MockProver::new(1);
+        let endpoint = prover.clone().spawn().await.unwrap();
+
+        let provers = Provers::new(vec![DEAD_ENDPOINT.to_owned(), endpoint.clone()], config());
+
+        let request = PollRequest {
+            request: prove_request(10),
+        };
+
+        let (used, res) = provers.poll(0, request.clone()).await;
+        assert_eq!(used, DEAD_ENDPOINT);
+        assert!(matches!(res, Err(PollError::Transport(_))));
+
+        // the dead prover is now skipped, even though the seed points to it
+        let response = poll_until_done(&provers, 0, request.clone()).await;
+        assert_eq!(response, MockProver::proof(&request.into()));
+        assert_eq!(provers.pick(0), 1);
+        assert_eq!(prover.polls(), 2);
+    }
+
+    #[test]
+    fn rejections_are_not_retried() {
+        assert!(matches!(
+            PollError::from(Status::unavailable("down")),
+            PollError::Transport(_)
+        ));
+        assert!(matches!(
+            PollError::from(Status::unknown("connection reset")),
+            PollError::Transport(_)
+        ));
+        assert!(matches!(
+            PollError::from(Status::invalid_argument("invalid validator set")),
+            PollError::Rejected(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn unhealthy_provers_are_still_used() {
+        let provers = Provers::new(
+            vec![DEAD_ENDPOINT.to_owned(), DEAD_ENDPOINT.to_owned()],
+            config(),
+        );
+
+        provers.report_failure(1);
+        provers.report_failure(0);
+
+        assert_eq!(provers.pick(0), 1);
+    }
+
+    #[tokio::test]
+    async fn proofs_are_shared_through_the_cache_dir() {
+        let dir = std::env::temp_dir().join(format!("cometbls-proof-cache-{}", std::process::id()));
+
+        let config = ProofCacheConfig {
+            capacity: 1,
+            dir: Some(dir.clone()),
+        };
+
+        let request = prove_request(10);
+        let key = ProofCacheKey::new(Height::new(5), &request);
+        let response = MockProver::proof(&PollRequest { request }.into());
+
+        let cache = ProofCache::new(config.clone()).await.unwrap();
+        assert_eq!(cache.get(&key).await, None);
+        cache.insert(key, response.clone()).await;
+        assert_eq!(cache.get(&key).await, Some(response.clone()));
+
+        // another instance with an empty memory cache
+        let other = ProofCache::new(config).await.unwrap();
+        assert_eq!(other.get(&key).await, Some(response));
+
+        tokio::fs::remove_dir_all(dir).await.unwrap();
+    }
+}
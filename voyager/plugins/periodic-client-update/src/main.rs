@@ -16,7 +16,9 @@ use voyager_message::{
     primitives::{ChainId, IbcSpecId, QueryHeight},
     ExtensionsExt, Plugin, PluginMessage, RawClientId, VoyagerClient, VoyagerMessage,
 };
-use voyager_vm::{call, conc, defer, now, pass::PassResult, promise, seq, BoxDynError, Op};
+use voyager_vm::{
+    call, conc, defer, now, pass::PassResult, priority, promise, seq, BoxDynError, Op, Priority,
+};
 
 use crate::call::{CheckForClientAge, ModuleCall};
 
@@ -126,21 +128,26 @@ impl Module {
             info!("client is older than threshold");
 
             Ok(conc([
-                promise(
-                    [call(FetchUpdateHeaders {
-                        client_type: client_info.client_type,
-                        chain_id: client_state_meta.counterparty_chain_id,
-                        counterparty_chain_id: chain_id.clone(),
-                        client_id: client_id.clone(),
-                        update_from: client_state_meta.counterparty_height,
-                        update_to: latest_finalized_height,
-                    })],
-                    [],
-                    AggregateSubmitTxFromOrderedHeaders {
-                        ibc_spec_id: ibc_spec_id.clone(),
-                        chain_id: chain_id.clone(),
-                        client_id: client_id.clone(),
-                    },
+                // updating the client is time-critical, since the client will expire if it is not
+                // updated within its trusting period
+                priority(
+                    Priority::High,
+                    promise(
+                        [call(FetchUpdateHeaders {
+                            client_type: client_info.client_type,
+                            chain_id: client_state_meta.counterparty_chain_id,
+                            counterparty_chain_id: chain_id.clone(),
+                            client_id: client_id.clone(),
+                            update_from: client_state_meta.counterparty_height,
+                            update_to: latest_finalized_height,
+                        })],
+                        [],
+                        AggregateSubmitTxFromOrderedHeaders {
+                            ibc_spec_id: ibc_spec_id.clone(),
+                            chain_id: chain_id.clone(),
+                            client_id: client_id.clone(),
+                        },
+                    ),
                 ),
                 seq([
                     call(WaitForTrustedHeight {
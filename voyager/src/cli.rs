@@ -2,6 +2,7 @@ use std::{ffi::OsString, str::FromStr};
 
 use clap::{self, Parser, Subcommand};
 use ibc_union_spec::IbcUnion;
+use pg_queue::ItemTable;
 use unionlabs::{self, bounded::BoundedI64, ibc::core::client::height::Height, result_unwrap};
 use voyager_message::{
     module::{ClientModuleInfo, ConsensusModuleInfo, ProofModuleInfo, StateModuleInfo},
@@ -90,6 +91,9 @@ pub enum Command {
     },
     #[command(subcommand)]
     Msg(MsgCmd),
+    /// Inspect ops locally, without a running voyager instance.
+    #[command(subcommand)]
+    Op(OpCmd),
 }
 
 #[derive(Debug, Subcommand)]
@@ -100,6 +104,15 @@ pub enum ConfigCmd {
     Default,
     /// Print the JSON Schema for the voyager config, to be used in the top-level `$schema` field.
     Schema,
+    /// Reload the plugins and modules of a running voyager instance from its config file. Only the
+    /// plugins and modules whose config changed are restarted. Sending `SIGHUP` to voyager has the
+    /// same effect.
+    ///
+    /// Calls the admin rpc server of voyager (`voyager.admin_laddr`).
+    Reload {
+        #[arg(long, short = 'a')]
+        admin_url: Option<String>,
+    },
 }
 
 type Pg64 = BoundedI64<1, { i64::MAX }>;
@@ -155,6 +168,10 @@ pub enum QueueCmd {
         #[arg(long)]
         rest_url: Option<String>,
     },
+    /// Prune the `done` table according to the `done_retention` configured for the queue. A
+    /// running voyager instance does this periodically, this is only required to prune
+    /// immediately.
+    PruneDone,
 }
 
 #[derive(Debug, Subcommand)]
@@ -164,6 +181,30 @@ pub enum PluginCmd {
         plugin_name: String,
         message: String,
     },
+    /// Run the interest filters of all enabled plugins over the most recent ops in the queue
+    /// database, reporting which plugins are interested in each op and how long the filters took
+    /// to evaluate.
+    ///
+    /// Ops that are taken by more than one plugin (of which only the first will ever receive the
+    /// op) and ops that a filter failed on are always reported.
+    InterestBatch {
+        /// The tables to sample ops from, one of `queue`, `optimize`, `done` or `failed`.
+        ///
+        /// This can be specified multiple times to sample from multiple tables.
+        #[arg(
+            long = "table",
+            short = 't',
+            value_parser(|s: &str| serde_json::from_value::<ItemTable>(s.into())),
+            default_values = ["queue", "done"]
+        )]
+        tables: Vec<ItemTable>,
+        /// The amount of ops to sample from each table.
+        #[arg(long, default_value_t = 1000)]
+        limit: i64,
+        /// Report every sampled op, not only conflicts and failures.
+        #[arg(long, default_value_t = false)]
+        all: bool,
+    },
     /// Print the plugin info for a plugin.
     Info { plugin_name: String },
     /// Call a plugin directly from the CLI.
@@ -367,6 +408,10 @@ pub enum MsgCmd {
         /// Automatically enqueue the op.
         #[arg(long, short = 'e', default_value_t = false)]
         enqueue: bool,
+        /// Execute the op locally and print the transactions it would submit, instead of printing
+        /// the op. See `voyager op dry-run`.
+        #[arg(long, default_value_t = false, conflicts_with = "enqueue")]
+        dry_run: bool,
         #[arg(long, global = true)]
         rest_url: Option<String>,
     },
@@ -389,11 +434,38 @@ pub enum MsgCmd {
         /// Automatically enqueue the op.
         #[arg(long, short = 'e', default_value_t = false)]
         enqueue: bool,
+        /// Execute the op locally and print the transactions it would submit, instead of printing
+        /// the op. See `voyager op dry-run`.
+        #[arg(long, default_value_t = false, conflicts_with = "enqueue")]
+        dry_run: bool,
         #[arg(long, global = true)]
         rest_url: Option<String>,
     },
 }
 
+#[derive(Debug, Subcommand)]
+pub enum OpCmd {
+    /// Render an op as a tree, along with the plugins that would be interested in each node if it
+    /// were queued as a top-level message.
+    Explain {
+        #[arg(value_parser(|s: &str| serde_json::from_str::<Op<VoyagerMessage>>(s)))]
+        op: Op<VoyagerMessage>,
+    },
+    /// Execute an op against the configured plugins and modules, printing the transactions that
+    /// would be submitted instead of submitting them.
+    ///
+    /// Every `submit_tx` call is intercepted before it is queued, so transaction plugins never
+    /// receive them. All other calls run as usual, including queries made to the chains.
+    DryRun {
+        #[arg(value_parser(|s: &str| serde_json::from_str::<Op<VoyagerMessage>>(s)))]
+        op: Op<VoyagerMessage>,
+        /// The maximum amount of items to process. Items waiting on a `defer` or failing with a
+        /// retryable error are requeued, and will keep the queue busy until this is hit.
+        #[arg(long, default_value_t = crate::op::DEFAULT_DRY_RUN_MAX_STEPS)]
+        max_steps: usize,
+    },
+}
+
 #[allow(
     clippy::unnecessary_wraps,
     reason = "intended as sugar to specify the error type"
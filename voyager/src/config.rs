@@ -1,8 +1,12 @@
 use std::{
+    ffi::OsStr,
+    fs::read_to_string,
     net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::Path,
     time::Duration,
 };
 
+use anyhow::Context as _;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use voyager_message::context::{
@@ -24,6 +28,18 @@ pub struct Config {
     pub voyager: VoyagerConfig,
 }
 
+/// Reads the config file at `path`, which is parsed as jsonc if it has the `.jsonc` extension.
+pub fn read_config(path: &Path) -> anyhow::Result<Config> {
+    let s = read_to_string(path)
+        .with_context(|| format!("unable to read the config file at `{}`", path.display()))?;
+
+    match path.extension().map(OsStr::as_encoded_bytes) {
+        Some(b"jsonc") => serde_jsonc::from_str::<Config>(&s),
+        _ => serde_json::from_str::<Config>(&s),
+    }
+    .with_context(|| format!("unable to parse the config file at `{}`", path.display()))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct VoyagerConfig {
@@ -32,6 +48,10 @@ pub struct VoyagerConfig {
     pub rest_laddr: SocketAddr,
     #[serde(default = "default_rpc_laddr")]
     pub rpc_laddr: SocketAddr,
+    /// The address of the admin rpc server, which serves `voyager_reloadConfig`. It is kept
+    /// separate from the public rpc server, and isn't started if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin_laddr: Option<SocketAddr>,
     #[serde(default = "default_metrics_endpoint")]
     pub metrics_endpoint: String,
     pub queue: QueueConfig,
@@ -41,6 +61,8 @@ pub struct VoyagerConfig {
     #[serde(default = "default_ipc_client_request_timeout")]
     pub ipc_client_request_timeout: Duration,
     pub cache: voyager_message::rpc::server::cache::Config,
+    #[serde(default)]
+    pub limits: voyager_message::limits::LimitsConfig,
 }
 
 #[must_use]
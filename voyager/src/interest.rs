@@ -0,0 +1,277 @@
+//! Batch evaluation of the interest filters of the configured plugins.
+
+use std::collections::BTreeMap;
+
+use jaq_interpret::Filter;
+use pg_queue::{ItemRecord, ItemTable};
+use serde::Serialize;
+use voyager_message::{
+    context::{get_plugin_info, PluginConfig},
+    filter::make_filter,
+    VoyagerMessage,
+};
+
+use crate::op::{check_interest, PluginInterest};
+
+/// The interest filters of all enabled plugins in `plugin_configs`, in the order they are
+/// configured in.
+pub async fn load_filters(
+    plugin_configs: Vec<PluginConfig>,
+) -> anyhow::Result<Vec<(Filter, String)>> {
+    let mut filters = vec![];
+
+    for plugin_config in plugin_configs {
+        if plugin_config.enabled {
+            filters.push(make_filter(get_plugin_info(&plugin_config).await?)?);
+        }
+    }
+
+    Ok(filters)
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct InterestReport {
+    /// The amount of ops the filters were run over.
+    pub sampled: usize,
+    /// The amount of ops that no plugin is interested in. These are processed by voyager directly.
+    pub unclaimed: usize,
+    /// The amount of ops that are taken by more than one plugin. Only the first of these plugins
+    /// (in the order they are configured in) will ever receive the op.
+    pub conflicts: usize,
+    /// The amount of ops that at least one filter failed on.
+    pub failures: usize,
+    pub plugins: BTreeMap<String, PluginReport>,
+    /// The ops that were reported on, see [`InterestReport::new`].
+    pub items: Vec<ItemReport>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PluginReport {
+    pub take: usize,
+    pub copy: usize,
+    pub failed: usize,
+    /// Ops that this plugin takes, but are taken by a plugin configured before it.
+    pub shadowed: usize,
+    /// The total time spent evaluating this plugin's filter, in microseconds.
+    pub total_micros: u128,
+    /// The longest time spent evaluating this plugin's filter on a single op, in microseconds.
+    pub max_micros: u128,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ItemReport {
+    pub table: ItemTable,
+    pub id: i64,
+    /// The plugin that receives this op, if any.
+    pub claimed_by: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub take: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub copy: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<String>,
+}
+
+impl ItemReport {
+    fn is_conflict(&self) -> bool {
+        self.take.len() > 1
+    }
+}
+
+impl InterestReport {
+    /// Run all of `filters` over `records`. If `all` is not set, only ops that are taken by more
+    /// than one plugin or that a filter failed on are included in [`InterestReport::items`].
+    #[must_use]
+    pub fn new(
+        filters: &[(Filter, String)],
+        records: impl IntoIterator<Item = (ItemTable, ItemRecord<VoyagerMessage>)>,
+        all: bool,
+    ) -> Self {
+        let mut report = InterestReport {
+            plugins: filters
+                .iter()
+                .map(|(_, plugin_name)| (plugin_name.clone(), PluginReport::default()))
+                .collect(),
+            ..Default::default()
+        };
+
+        for (table, record) in records {
+            let mut item = ItemReport {
+                table,
+                id: record.id,
+                claimed_by: None,
+                take: vec![],
+                copy: vec![],
+                failed: vec![],
+            };
+
+            for (plugin_name, interest, elapsed) in check_interest(filters, &record.item.0) {
+                let plugin = report
+                    .plugins
+                    .get_mut(plugin_name)
+                    .expect("all plugins are in the report; qed;");
+
+                plugin.total_micros += elapsed.as_micros();
+                plugin.max_micros = plugin.max_micros.max(elapsed.as_micros());
+
+                match interest {
+                    Some(PluginInterest::Take) => {
+                        plugin.take += 1;
+
+                        if item.take.is_empty() {
+                            item.claimed_by = Some(plugin_name.to_owned());
+                        } else {
+                            plugin.shadowed += 1;
+                        }
+
+                        item.take.push(plugin_name.to_owned());
+                    }
+                    Some(PluginInterest::Copy) => {
+                        plugin.copy += 1;
+
+                        if item.claimed_by.is_none() {
+                            item.copy.push(plugin_name.to_owned());
+                        }
+                    }
+                    Some(PluginInterest::Failed) => {
+                        plugin.failed += 1;
+                        item.failed.push(plugin_name.to_owned());
+                    }
+                    None => {}
+                }
+            }
+
+            report.sampled += 1;
+
+            if item.take.is_empty() && item.copy.is_empty() {
+                report.unclaimed += 1;
+            }
+
+            if item.is_conflict() {
+                report.conflicts += 1;
+            }
+
+            if !item.failed.is_empty() {
+                report.failures += 1;
+            }
+
+            if all || item.is_conflict() || !item.failed.is_empty() {
+                report.items.push(item);
+            }
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use sqlx::types::Json;
+    use voyager_message::{module::PluginInfo, PluginMessage};
+    use voyager_vm::{call, data, defer, noop, Op};
+
+    use super::*;
+
+    fn filter(name: &str, interest_filter: &str) -> (Filter, String) {
+        make_filter(PluginInfo {
+            name: name.to_owned(),
+            interest_filter: interest_filter.to_owned(),
+        })
+        .unwrap()
+    }
+
+    fn record(
+        table: ItemTable,
+        id: i64,
+        op: Op<VoyagerMessage>,
+    ) -> (ItemTable, ItemRecord<VoyagerMessage>) {
+        (table, ItemRecord { id, item: Json(op) })
+    }
+
+    fn report(all: bool) -> InterestReport {
+        let filters = [
+            filter("a", r#"if ."@type" == "call" then true else null end"#),
+            filter("c", r#"if ."@type" == "data" then false else null end"#),
+            filter(
+                "b",
+                r#"if ."@type" == "call" or ."@type" == "data" then true else null end"#,
+            ),
+            filter("d", r#"if ."@type" == "defer" then 1 else null end"#),
+        ];
+
+        InterestReport::new(
+            &filters,
+            [
+                record(
+                    ItemTable::Queue,
+                    1,
+                    call(PluginMessage::new("a", json!({ "@type": "fetch_blocks" }))),
+                ),
+                record(
+                    ItemTable::Done,
+                    2,
+                    data(PluginMessage::new("x", json!({ "@type": "event" }))),
+                ),
+                record(ItemTable::Done, 3, defer(10)),
+                record(ItemTable::Queue, 4, noop()),
+            ],
+            all,
+        )
+    }
+
+    #[test]
+    fn counts() {
+        let report = report(false);
+
+        assert_eq!(report.sampled, 4);
+        assert_eq!(report.unclaimed, 2);
+        assert_eq!(report.conflicts, 1);
+        assert_eq!(report.failures, 1);
+
+        let plugin = |name: &str| {
+            let plugin = &report.plugins[name];
+            (plugin.take, plugin.copy, plugin.failed, plugin.shadowed)
+        };
+
+        assert_eq!(plugin("a"), (1, 0, 0, 0));
+        assert_eq!(plugin("b"), (2, 0, 0, 1));
+        assert_eq!(plugin("c"), (0, 1, 0, 0));
+        assert_eq!(plugin("d"), (0, 0, 1, 0));
+    }
+
+    #[test]
+    fn conflicts_and_failures_are_reported() {
+        let report = report(false);
+
+        let [conflict, failure] = &report.items[..] else {
+            panic!("expected 2 items, found {:?}", report.items);
+        };
+
+        assert_eq!(conflict.table, ItemTable::Queue);
+        assert_eq!(conflict.id, 1);
+        assert_eq!(conflict.claimed_by.as_deref(), Some("a"));
+        assert_eq!(conflict.take, ["a", "b"]);
+
+        assert_eq!(failure.table, ItemTable::Done);
+        assert_eq!(failure.id, 3);
+        assert_eq!(failure.claimed_by, None);
+        assert_eq!(failure.failed, ["d"]);
+    }
+
+    #[test]
+    fn all_items_are_reported() {
+        let report = report(true);
+
+        assert_eq!(
+            report.items.iter().map(|item| item.id).collect::<Vec<_>>(),
+            [1, 2, 3, 4]
+        );
+
+        // copied before it was taken
+        let item = &report.items[1];
+        assert_eq!(item.claimed_by.as_deref(), Some("b"));
+        assert_eq!(item.take, ["b"]);
+        assert_eq!(item.copy, ["c"]);
+    }
+}
@@ -0,0 +1,542 @@
+//! Inspection of ops without a running voyager instance.
+
+use std::{
+    fmt::Write,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use futures::FutureExt;
+use jaq_interpret::Filter;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::info;
+use voyager_message::{
+    call::{Call, SubmitTx},
+    callback::Callback,
+    context::Context,
+    data::Data,
+    filter::{run_filter, JaqFilterResult},
+    pass::PluginOptPass,
+    PluginMessage, VoyagerMessage,
+};
+use voyager_vm::{
+    in_memory::InMemoryQueue,
+    noop,
+    pass::{Pass, PassResult},
+    Op, Promise, Queue, QueueError,
+};
+
+/// Render `op` as a tree, annotating every node with the plugins that would be interested in it
+/// if it were queued as a top-level message.
+///
+/// `filters` are checked in order, same as the interest filter of a running voyager instance: a
+/// plugin that takes a message hides it from all of the plugins after it.
+#[must_use]
+pub fn explain(op: &Op<VoyagerMessage>, filters: &[(Filter, String)]) -> String {
+    let mut out = String::new();
+
+    explain_node(&mut out, op, filters, "", "");
+
+    out
+}
+
+fn explain_node(
+    out: &mut String,
+    op: &Op<VoyagerMessage>,
+    filters: &[(Filter, String)],
+    prefix: &str,
+    child_prefix: &str,
+) {
+    let (label, children): (_, Vec<&Op<VoyagerMessage>>) = match op {
+        Op::Data(data) => (format!("data {}", data_label(data)), vec![]),
+        Op::Call(call) => (format!("call {}", call_label(call)), vec![]),
+        Op::Defer { until } => (format!("defer until {until}"), vec![]),
+        Op::Seq(seq) => (format!("seq ({})", seq.len()), seq.iter().collect()),
+        Op::Conc(conc) => (format!("conc ({})", conc.len()), conc.iter().collect()),
+        Op::Promise(Promise {
+            queue,
+            data,
+            receiver,
+        }) => (
+            format!(
+                "promise -> {} ({} queued, {} resolved)",
+                callback_label(receiver),
+                queue.len(),
+                data.len()
+            ),
+            queue.iter().collect(),
+        ),
+        Op::Void(op) => ("void".to_owned(), vec![&**op]),
+        Op::Priority { priority, op } => (
+            format!("priority {}", json_type(priority).unwrap_or_default()),
+            vec![&**op],
+        ),
+        Op::Noop => ("noop".to_owned(), vec![]),
+    };
+
+    let interest = interest(op, filters);
+
+    if interest.is_empty() {
+        writeln!(out, "{prefix}{label}").expect("writing to a string is infallible; qed;");
+    } else {
+        writeln!(out, "{prefix}{label} [{interest}]")
+            .expect("writing to a string is infallible; qed;");
+    }
+
+    let len = children.len();
+    for (i, child) in children.into_iter().enumerate() {
+        let (branch, indent) = if i + 1 == len {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+
+        explain_node(
+            out,
+            child,
+            filters,
+            &format!("{child_prefix}{branch}"),
+            &format!("{child_prefix}{indent}"),
+        );
+    }
+}
+
+/// The interest a single plugin expressed in an op.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginInterest {
+    Take,
+    Copy,
+    /// The filter errored or didn't return a single boolean. This is treated the same as no
+    /// interest by voyager.
+    Failed,
+}
+
+/// Run the filter of every plugin over `op`, along with how long each filter took to evaluate.
+///
+/// Unlike the interest filter used by the queue, this does not stop at the first plugin that takes
+/// the op, such that plugins that would never see it can be reported.
+#[must_use]
+pub fn check_interest<'a>(
+    filters: &'a [(Filter, String)],
+    op: &Op<VoyagerMessage>,
+) -> Vec<(&'a str, Option<PluginInterest>, Duration)> {
+    let msg_json = serde_json::to_value(op).expect("serialization is infallible; qed;");
+
+    filters
+        .iter()
+        .map(|(filter, plugin_name)| {
+            let start = Instant::now();
+
+            let interest = match run_filter(filter, plugin_name, msg_json.clone().into()) {
+                Ok(JaqFilterResult::Take(_)) => Some(PluginInterest::Take),
+                Ok(JaqFilterResult::Copy(_)) => Some(PluginInterest::Copy),
+                Ok(JaqFilterResult::NoInterest) => None,
+                Err(()) => Some(PluginInterest::Failed),
+            };
+
+            (plugin_name.as_str(), interest, start.elapsed())
+        })
+        .collect()
+}
+
+fn interest(op: &Op<VoyagerMessage>, filters: &[(Filter, String)]) -> String {
+    let mut copy = vec![];
+
+    for (plugin_name, interest, _) in check_interest(filters, op) {
+        match interest {
+            Some(PluginInterest::Take) => {
+                return if copy.is_empty() {
+                    format!("take: {plugin_name}")
+                } else {
+                    format!("copy: {}; take: {plugin_name}", copy.join(", "))
+                };
+            }
+            Some(PluginInterest::Copy) => copy.push(plugin_name.to_owned()),
+            Some(PluginInterest::Failed) => copy.push(format!("{plugin_name} (filter failed)")),
+            None => {}
+        }
+    }
+
+    if copy.is_empty() {
+        String::new()
+    } else {
+        format!("copy: {}", copy.join(", "))
+    }
+}
+
+fn call_label(call: &Call) -> String {
+    match call {
+        Call::Plugin(PluginMessage { plugin, message }) => plugin_label(plugin, message),
+        Call::SubmitTx(SubmitTx {
+            chain_id,
+            datagrams,
+        }) => format!("submit_tx on {chain_id} ({} datagrams)", datagrams.len()),
+        call => json_type(call).unwrap_or_default(),
+    }
+}
+
+fn callback_label(callback: &Callback) -> String {
+    match callback {
+        Callback::Plugin(PluginMessage { plugin, message }) => plugin_label(plugin, message),
+        callback => json_type(callback).unwrap_or_default(),
+    }
+}
+
+fn data_label(data: &Data) -> String {
+    match data {
+        Data::Plugin(PluginMessage { plugin, message }) => plugin_label(plugin, message),
+        data => json_type(data).unwrap_or_default(),
+    }
+}
+
+fn plugin_label(plugin: &str, message: &Value) -> String {
+    match message.get("@type").and_then(Value::as_str) {
+        Some(ty) => format!("{plugin}: {ty}"),
+        None => plugin.to_owned(),
+    }
+}
+
+/// The `@type` tag of `t`, or the value itself if it serializes to a string.
+fn json_type(t: &impl Serialize) -> Option<String> {
+    match serde_json::to_value(t).expect("serialization is infallible; qed;") {
+        Value::String(s) => Some(s),
+        value => value
+            .get("@type")
+            .and_then(Value::as_str)
+            .map(ToOwned::to_owned),
+    }
+}
+
+/// The default for the maximum amount of items processed by [`dry_run`].
+pub const DEFAULT_DRY_RUN_MAX_STEPS: usize = 1000;
+
+/// The result of [`dry_run`].
+#[derive(Debug, Serialize)]
+pub struct DryRun {
+    /// The transactions that would have been submitted, in the order they were produced.
+    pub transactions: Vec<SubmitTx>,
+    /// Items that failed to be processed with a non-retryable error, along with the error. Items
+    /// that fail with a retryable error are requeued, as they would be in a running voyager
+    /// instance.
+    pub errors: Vec<DryRunError>,
+    /// The number of items that were processed.
+    pub steps: usize,
+    /// Whether processing stopped because `max_steps` was hit, rather than because there were no
+    /// more items ready to be processed.
+    pub exhausted: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DryRunError {
+    pub op: Op<VoyagerMessage>,
+    pub error: String,
+}
+
+/// Execute `op` in an in-memory queue against the plugins and modules of `ctx`, alternating
+/// between running the optimization passes of all plugins and processing a single item.
+///
+/// Every [`SubmitTx`] is removed from the ops before they are queued, such that they are never
+/// picked up by a transaction plugin, and returned instead. Everything else runs as it would in a
+/// running voyager instance, including requests made to the chains by the modules.
+pub async fn dry_run(
+    ctx: &Context,
+    op: Op<VoyagerMessage>,
+    max_steps: usize,
+) -> anyhow::Result<DryRun> {
+    let interceptor = SubmitTxInterceptor::default();
+
+    let queue = InMemoryQueue::<VoyagerMessage>::new(())
+        .await
+        .expect("the in memory queue is infallible; qed;");
+
+    queue
+        .enqueue(interceptor.intercept(op), ctx.interest_filter())
+        .await
+        .expect("the in memory queue is infallible; qed;");
+
+    let errors = Mutex::new(vec![]);
+
+    let mut steps = 0;
+
+    let exhausted = loop {
+        for (plugin_name, _, _) in ctx.plugins().await {
+            let client = ctx.plugin(&plugin_name)?;
+
+            queue
+                .optimize(
+                    &plugin_name,
+                    ctx.interest_filter(),
+                    &InterceptPass {
+                        pass: PluginOptPass::new(&client),
+                        interceptor: &interceptor,
+                    },
+                )
+                .await
+                .map_err(|err| anyhow!("error optimizing {plugin_name}: {err}"))?;
+        }
+
+        if steps == max_steps {
+            break true;
+        }
+
+        let processed = AtomicBool::new(false);
+
+        queue
+            .process::<_, _, ()>(ctx.interest_filter(), |op, item_id| {
+                processed.store(true, Ordering::SeqCst);
+
+                op.clone()
+                    .process(voyager_vm::Context::new(item_id, ctx), 0)
+                    .map(|res| {
+                        let res = res.map(|res| {
+                            res.into_iter()
+                                .map(|op| interceptor.intercept(op))
+                                .collect::<Vec<_>>()
+                        });
+
+                        if let Err(err @ (QueueError::Fatal(_) | QueueError::Unprocessable(_))) =
+                            &res
+                        {
+                            errors.lock().expect("mutex is poisoned").push(DryRunError {
+                                op,
+                                error: err.to_string(),
+                            });
+                        }
+
+                        ((), res)
+                    })
+            })
+            .await
+            .expect("the in memory queue is infallible; qed;");
+
+        if !processed.into_inner() {
+            break false;
+        }
+
+        steps += 1;
+    };
+
+    Ok(DryRun {
+        transactions: interceptor.into_inner(),
+        errors: errors.into_inner().expect("mutex is poisoned"),
+        steps,
+        exhausted,
+    })
+}
+
+/// Replaces all [`SubmitTx`] calls with [`Op::Noop`], recording them instead.
+#[derive(Default)]
+struct SubmitTxInterceptor {
+    intercepted: Mutex<Vec<SubmitTx>>,
+}
+
+impl SubmitTxInterceptor {
+    fn intercept(&self, mut op: Op<VoyagerMessage>) -> Op<VoyagerMessage> {
+        self.intercept_in_place(&mut op);
+        op
+    }
+
+    fn into_inner(self) -> Vec<SubmitTx> {
+        self.intercepted.into_inner().expect("mutex is poisoned")
+    }
+
+    fn intercept_in_place(&self, op: &mut Op<VoyagerMessage>) {
+        match op {
+            Op::Call(Call::SubmitTx(submit_tx)) => {
+                info!(chain_id = %submit_tx.chain_id, "intercepted transaction submission");
+
+                self.intercepted
+                    .lock()
+                    .expect("mutex is poisoned")
+                    .push(submit_tx.clone());
+
+                *op = noop();
+            }
+            Op::Seq(seq) => seq.iter_mut().for_each(|op| self.intercept_in_place(op)),
+            Op::Conc(conc) => conc.iter_mut().for_each(|op| self.intercept_in_place(op)),
+            Op::Promise(Promise { queue, .. }) => {
+                queue.iter_mut().for_each(|op| self.intercept_in_place(op));
+            }
+            Op::Void(op) | Op::Priority { priority: _, op } => self.intercept_in_place(op),
+            Op::Data(_) | Op::Call(_) | Op::Defer { .. } | Op::Noop => {}
+        }
+    }
+}
+
+/// Wraps an optimization pass, intercepting all [`SubmitTx`] calls it produces.
+struct InterceptPass<'a, P> {
+    pass: P,
+    interceptor: &'a SubmitTxInterceptor,
+}
+
+impl<P: Pass<VoyagerMessage>> Pass<VoyagerMessage> for InterceptPass<'_, P> {
+    type Error = P::Error;
+
+    fn run_pass(
+        &self,
+        ops: Vec<Op<VoyagerMessage>>,
+    ) -> impl Future<Output = Result<PassResult<VoyagerMessage>, Self::Error>> + Send {
+        async move {
+            let PassResult {
+                optimize_further,
+                ready,
+            } = self.pass.run_pass(ops).await?;
+
+            Ok(PassResult {
+                optimize_further: optimize_further
+                    .into_iter()
+                    .map(|(parents, op, tag)| (parents, self.interceptor.intercept(op), tag))
+                    .collect(),
+                ready: ready
+                    .into_iter()
+                    .map(|(parents, op)| (parents, self.interceptor.intercept(op)))
+                    .collect(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use serde_json::json;
+    use voyager_message::{module::PluginInfo, primitives::ChainId};
+    use voyager_vm::{call, conc, data, defer, priority, promise, seq, void, Priority};
+
+    use super::*;
+
+    fn filter(name: &str, interest_filter: &str) -> (Filter, String) {
+        voyager_message::filter::make_filter(PluginInfo {
+            name: name.to_owned(),
+            interest_filter: interest_filter.to_owned(),
+        })
+        .unwrap()
+    }
+
+    fn submit_tx(chain_id: &str) -> Op<VoyagerMessage> {
+        call(SubmitTx {
+            chain_id: ChainId::new(chain_id.to_owned()),
+            datagrams: vec![],
+        })
+    }
+
+    fn callback() -> Callback {
+        Callback::Plugin(PluginMessage::new("b", json!({ "@type": "aggregate" })))
+    }
+
+    #[test]
+    fn explain_tree() {
+        let filters = [
+            filter("a", r#"if ."@type" == "call" then true else null end"#),
+            filter("b", r#"if ."@type" == "data" then false else null end"#),
+            filter("c", r#"if ."@type" == "data" then true else null end"#),
+            filter("d", r#"if ."@type" == "defer" then 1 else null end"#),
+        ];
+
+        let op: Op<VoyagerMessage> = seq([
+            call(PluginMessage::new("a", json!({ "@type": "fetch_blocks" }))),
+            promise(
+                [
+                    data(PluginMessage::new("x", json!({ "@type": "event" }))),
+                    defer(10),
+                ],
+                [],
+                callback(),
+            ),
+            priority(Priority::High, void(noop())),
+        ]);
+
+        assert_eq!(
+            explain(&op, &filters),
+            "\
+seq (3)
+├── call a: fetch_blocks [take: a]
+├── promise -> b: aggregate (2 queued, 0 resolved)
+│   ├── data x: event [copy: b; take: c]
+│   └── defer until 10 [copy: d (filter failed)]
+└── priority high
+    └── void
+        └── noop
+"
+        );
+    }
+
+    #[test]
+    fn intercept_nested() {
+        let interceptor = SubmitTxInterceptor::default();
+
+        let fetch: Op<VoyagerMessage> =
+            call(PluginMessage::new("a", json!({ "@type": "fetch_blocks" })));
+
+        let op = interceptor.intercept(seq([
+            submit_tx("a"),
+            promise([priority(Priority::High, submit_tx("b"))], [], callback()),
+            conc([void(submit_tx("c")), fetch.clone()]),
+        ]));
+
+        assert_eq!(
+            op,
+            seq([
+                noop(),
+                promise([priority(Priority::High, noop())], [], callback()),
+                conc([void(noop()), fetch]),
+            ])
+        );
+
+        assert_eq!(
+            interceptor
+                .into_inner()
+                .into_iter()
+                .map(|submit_tx| submit_tx.chain_id.to_string())
+                .collect::<Vec<_>>(),
+            ["a", "b", "c"]
+        );
+    }
+
+    struct FixedPass(PassResult<VoyagerMessage>);
+
+    impl Pass<VoyagerMessage> for FixedPass {
+        type Error = Infallible;
+
+        fn run_pass(
+            &self,
+            _: Vec<Op<VoyagerMessage>>,
+        ) -> impl Future<Output = Result<PassResult<VoyagerMessage>, Self::Error>> + Send {
+            futures::future::ready(Ok(self.0.clone()))
+        }
+    }
+
+    #[tokio::test]
+    async fn intercept_pass() {
+        let interceptor = SubmitTxInterceptor::default();
+
+        let pass = InterceptPass {
+            pass: FixedPass(PassResult {
+                optimize_further: vec![(vec![0], seq([submit_tx("a")]), "tag".to_owned())],
+                ready: vec![(vec![1], submit_tx("b"))],
+            }),
+            interceptor: &interceptor,
+        };
+
+        let PassResult {
+            optimize_further,
+            ready,
+        } = pass.run_pass(vec![]).await.unwrap();
+
+        assert_eq!(
+            optimize_further,
+            [(vec![0], seq([noop()]), "tag".to_owned())]
+        );
+        assert_eq!(ready, [(vec![1], noop())]);
+
+        assert_eq!(interceptor.into_inner().len(), 2);
+    }
+}
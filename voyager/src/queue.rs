@@ -1,27 +1,45 @@
 #![allow(clippy::type_complexity)]
 
-use std::{fmt::Debug, net::SocketAddr, panic::AssertUnwindSafe};
+use std::{
+    collections::HashMap, fmt::Debug, net::SocketAddr, panic::AssertUnwindSafe, path::PathBuf,
+    time::Duration,
+};
 
 use anyhow::{bail, Context as _};
 use futures::{future::BoxFuture, stream::FuturesUnordered, Future, FutureExt, StreamExt};
 use ibc_classic_spec::IbcClassic;
 use ibc_union_spec::IbcUnion;
+use jsonrpsee::server::middleware::rpc::RpcServiceBuilder;
 use pg_queue::{PgQueue, PgQueueConfig};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use tracing::{debug, error, info, info_span, trace, trace_span};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::mpsc,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, info_span, trace, trace_span, warn};
 use tracing_futures::Instrument;
 use unionlabs::ErrorReporter;
 use voyager_message::{
-    context::Context, filter::JaqInterestFilter, into_value, module::PluginInfo,
-    pass::PluginOptPass, rpc::VoyagerRpcServer, VoyagerMessage,
+    context::{Context, ExtractItemId, ReloadSummary},
+    filter::JaqInterestFilter,
+    into_value,
+    limits::LimitsConfig,
+    pass::PluginOptPass,
+    rpc::VoyagerRpcServer,
+    VoyagerMessage,
 };
 use voyager_vm::{
     engine::Engine, in_memory::InMemoryQueue, pass::Pass, BoxDynError, Captures, EnqueueResult,
     ItemId, Op, Queue, QueueError,
 };
 
-use crate::{api, config::Config};
+use crate::{
+    api,
+    config::{read_config, Config, VoyagerConfig},
+    rpc::{ConfigRpcHandler, ConfigRpcServer, ReloadRequest},
+};
 
 #[derive(Debug)]
 pub struct Voyager {
@@ -30,8 +48,12 @@ pub struct Voyager {
     num_workers: u16,
     rest_laddr: SocketAddr,
     rpc_laddr: SocketAddr,
+    admin_laddr: Option<SocketAddr>,
     queue: QueueImpl,
     optimizer_delay_milliseconds: u64,
+    config_file_path: PathBuf,
+    /// The `voyager` section of the config, which can't be reloaded (except for the limits).
+    voyager_config: VoyagerConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -145,7 +167,7 @@ impl Queue<VoyagerMessage> for QueueImpl {
 }
 
 impl Voyager {
-    pub async fn new(config: Config) -> anyhow::Result<Self> {
+    pub async fn new(config: Config, config_file_path: PathBuf) -> anyhow::Result<Self> {
         let queue = QueueImpl::new(config.voyager.queue.clone())
             .await
             .context("error initializing queue")?;
@@ -160,31 +182,160 @@ impl Voyager {
                     h.register::<IbcUnion>();
                 },
                 config.voyager.ipc_client_request_timeout,
-                config.voyager.cache,
+                config.voyager.cache.clone(),
+                config.voyager.limits.clone(),
             )
             .await
             .context("error initializing plugins")?,
             num_workers: config.voyager.num_workers,
             rest_laddr: config.voyager.rest_laddr,
             rpc_laddr: config.voyager.rpc_laddr,
+            admin_laddr: config.voyager.admin_laddr,
             queue,
             optimizer_delay_milliseconds: config.voyager.optimizer_delay_milliseconds,
+            config_file_path,
+            voyager_config: config.voyager,
         })
     }
 
+    /// Re-reads the config file and reloads the plugins and modules.
+    async fn reload(&self) -> anyhow::Result<ReloadSummary> {
+        info!(path = %self.config_file_path.display(), "reloading config");
+
+        let config = read_config(&self.config_file_path)?;
+
+        // the limits are reloaded along with the plugins and modules
+        let without_limits = |voyager_config: &VoyagerConfig| {
+            serde_json::to_value(VoyagerConfig {
+                limits: LimitsConfig::default(),
+                ..voyager_config.clone()
+            })
+        };
+
+        if without_limits(&config.voyager)? != without_limits(&self.voyager_config)? {
+            warn!("the `voyager` section of the config changed, restart voyager to apply it");
+        }
+
+        self.context
+            .reload(
+                config.plugins,
+                config.modules,
+                config.equivalent_chain_ids,
+                config.voyager.limits,
+            )
+            .await
+    }
+
+    /// Reloads the config on SIGHUP and on the requests of the `voyager_reloadConfig` rpc method.
+    async fn handle_reloads(
+        &self,
+        mut reload_requests: mpsc::Receiver<ReloadRequest>,
+    ) -> Result<(), BoxDynError> {
+        let mut sighup = signal(SignalKind::hangup())?;
+
+        loop {
+            let reply = tokio::select! {
+                Some(()) = sighup.recv() => None,
+                Some(reply) = reload_requests.recv() => Some(reply),
+                else => return Ok(()),
+            };
+
+            let res = self.reload().await;
+
+            if let Err(error) = &res {
+                error!(error = %ErrorReporter(&*error), "unable to reload config");
+            }
+
+            if let Some(reply) = reply {
+                let _ = reply.send(res);
+            }
+        }
+    }
+
+    /// Runs an optimizer for every loaded plugin. The optimizer of a plugin is stopped along with
+    /// the plugin, and started again if the plugin is restarted by a reload.
+    async fn run_optimizers(&self) -> Result<(), BoxDynError> {
+        let mut reloads = self.context.subscribe_reloads();
+
+        let mut optimizers = FuturesUnordered::new();
+        let mut running = HashMap::new();
+
+        loop {
+            for (plugin_name, filter, cancellation_token) in self.context.plugins().await {
+                if running
+                    .get(&plugin_name)
+                    .is_some_and(|running: &CancellationToken| !running.is_cancelled())
+                {
+                    continue;
+                }
+
+                info!(%plugin_name, "spawning optimizer");
+
+                running.insert(plugin_name.clone(), cancellation_token.clone());
+
+                optimizers.push(async move {
+                    cancellation_token
+                        .run_until_cancelled(
+                            self.optimize(&plugin_name)
+                                .instrument(info_span!("optimize", %plugin_name))
+                                .instrument(trace_span!("optimize_verbose", %filter)),
+                        )
+                        .await
+                });
+            }
+
+            running.retain(|_, cancellation_token| !cancellation_token.is_cancelled());
+
+            tokio::select! {
+                Some(_) = optimizers.next() => {}
+                res = reloads.changed() => res?,
+            }
+        }
+    }
+
+    async fn optimize(&self, plugin_name: &str) {
+        let client = match self.context.plugin_client_raw(plugin_name) {
+            Ok(client) => client,
+            Err(error) => {
+                // the plugin was removed by a reload in the meantime
+                warn!(%error, "unable to start optimizer");
+                return;
+            }
+        };
+
+        let pass = PluginOptPass::new(&client);
+
+        loop {
+            trace!("optimizing");
+
+            let res = self
+                .queue
+                .optimize(plugin_name, self.context.interest_filter(), &pass)
+                .await
+                .map_err(|e| {
+                    e.map_either::<_, _, BoxDynError, BoxDynError>(|x| Box::new(x), |x| Box::new(x))
+                        .into_inner()
+                });
+
+            if let Err(error) = res {
+                error!(
+                    error = %ErrorReporter(&*error),
+                    "optimization pass returned with error"
+                );
+            }
+
+            tokio::time::sleep(std::time::Duration::from_millis(
+                self.optimizer_delay_milliseconds,
+            ))
+            .await;
+        }
+    }
+
     #[allow(clippy::too_many_lines)]
     pub async fn run(self) -> anyhow::Result<()> {
-        let interest_filter = JaqInterestFilter::new(
-            self.context
-                .interest_filters()
-                .clone()
-                .into_iter()
-                .map(|(name, interest_filter)| PluginInfo {
-                    name,
-                    interest_filter,
-                })
-                .collect(),
-        )?;
+        let interest_filter = self.context.interest_filter();
+
+        let (reload_requests_tx, reload_requests) = mpsc::channel(1);
 
         let queue_rx = api::run(&self.rest_laddr);
 
@@ -199,6 +350,8 @@ impl Voyager {
                             tower::ServiceBuilder::new()
                                 .layer(tower_http::cors::CorsLayer::permissive()),
                         )
+                        // remote plugins and modules thread the item id through their calls
+                        .set_rpc_middleware(RpcServiceBuilder::new().layer_fn(ExtractItemId::new))
                         .build(&self.rpc_laddr)
                         .await?;
                     let addr = server.local_addr()?;
@@ -213,6 +366,32 @@ impl Voyager {
                 .catch_unwind(),
             ));
 
+            // the admin rpc is served separately so it isn't exposed with the public rpc, reloads
+            // are only triggered by SIGHUP if it isn't configured
+            if let Some(admin_laddr) = self.admin_laddr {
+                tasks.push(Box::pin(
+                    AssertUnwindSafe(async move {
+                        let server = jsonrpsee::server::Server::builder()
+                            .build(&admin_laddr)
+                            .await?;
+                        let addr = server.local_addr()?;
+                        let handle = server.start(
+                            ConfigRpcHandler {
+                                reload_requests: reload_requests_tx,
+                            }
+                            .into_rpc(),
+                        );
+                        info!("admin rpc listening on {addr}");
+                        handle
+                            .stopped()
+                            .instrument(trace_span!("voyager_admin_rpc_server"))
+                            .await;
+                        Err("admin rpc server exited".into())
+                    })
+                    .catch_unwind(),
+                ));
+            }
+
             tasks.push(Box::pin(
                 AssertUnwindSafe(async {
                     debug!("checking for new messages");
@@ -222,7 +401,7 @@ impl Voyager {
                     while let Some(op) = queue_rx.next().await {
                         info!("received new message: {}", into_value(&op));
 
-                        self.queue.enqueue(op, &interest_filter).await?;
+                        self.queue.enqueue(op, interest_filter).await?;
                     }
 
                     Ok(())
@@ -237,7 +416,7 @@ impl Voyager {
 
                 tasks.push(Box::pin(
                     AssertUnwindSafe(
-                        Engine::new(&self.context, &self.queue, &interest_filter)
+                        Engine::new(&self.context, &self.queue, interest_filter)
                             .run()
                             .for_each(async |res| match res {
                                 Ok(data) => {
@@ -260,54 +439,24 @@ impl Voyager {
                 ));
             }
 
-            for (plugin_name, filter) in self.context.interest_filters() {
-                info!(%plugin_name, "spawning optimizer");
+            tasks.push(Box::pin(
+                AssertUnwindSafe(self.run_optimizers()).catch_unwind(),
+            ));
 
-                tasks.push(Box::pin(
-                    AssertUnwindSafe(
-                        async {
-                            let plugin_name = plugin_name.clone();
-
-                            let pass = PluginOptPass::new(
-                                self.context
-                                    .plugin_client_raw(&plugin_name)
-                                    .expect("plugin exists")
-                                    .client(),
-                            );
-
-                            loop {
-                                trace!("optimizing");
-
-                                let res = self
-                                    .queue
-                                    .optimize(&plugin_name, &interest_filter, &pass)
-                                    .await
-                                    .map_err(|e| {
-                                        e.map_either::<_, _, BoxDynError, BoxDynError>(
-                                            |x| Box::new(x),
-                                            |x| Box::new(x),
-                                        )
-                                        .into_inner()
-                                    });
-
-                                if let Err(error) = res {
-                                    error!(
-                                        error = %ErrorReporter(&*error),
-                                        "optimization pass returned with error"
-                                    );
-                                }
+            tasks.push(Box::pin(
+                AssertUnwindSafe(self.handle_reloads(reload_requests)).catch_unwind(),
+            ));
 
-                                tokio::time::sleep(std::time::Duration::from_millis(
-                                    self.optimizer_delay_milliseconds,
-                                ))
-                                .await;
-                            }
-                        }
-                        .instrument(info_span!("optimize", %plugin_name))
-                        .instrument(trace_span!("optimize_verbose", %filter)),
-                    )
-                    .catch_unwind(),
-                ));
+            if let QueueImpl::PgQueue(queue) = &self.queue {
+                if let Some(retention) = queue.done_retention() {
+                    tasks.push(Box::pin(
+                        AssertUnwindSafe(prune_done(
+                            queue,
+                            Duration::from_millis(retention.prune_interval_milliseconds),
+                        ))
+                        .catch_unwind(),
+                    ));
+                }
             }
 
             self.context
@@ -345,3 +494,32 @@ impl Voyager {
         self.context.shutdown().await;
     }
 }
+
+/// Periodically prune the `done` table of the queue. Errors are logged and retried on the next
+/// interval, since a failed prune shouldn't stop voyager from processing messages.
+async fn prune_done(
+    queue: &PgQueue<VoyagerMessage>,
+    interval: Duration,
+) -> Result<(), BoxDynError> {
+    loop {
+        match queue
+            .prune_done()
+            .instrument(info_span!("prune_done"))
+            .await
+        {
+            Ok(summary) => {
+                info!(
+                    dropped_partitions = summary.dropped_partitions.len(),
+                    deleted_rows = summary.deleted_rows,
+                    archived_rows = summary.archived_rows,
+                    "pruned done table"
+                );
+            }
+            Err(error) => {
+                error!(error = %ErrorReporter(&error), "error pruning done table");
+            }
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
@@ -0,0 +1,45 @@
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    types::{error::INTERNAL_ERROR_CODE, ErrorObject},
+};
+use tokio::sync::{mpsc, oneshot};
+use unionlabs::ErrorReporter;
+use voyager_message::context::ReloadSummary;
+
+/// Management of a running voyager instance, served on the admin rpc server (see
+/// `voyager.admin_laddr`) and never on the public voyager rpc.
+#[rpc(client, server, namespace = "voyager")]
+pub trait ConfigRpc {
+    /// Re-reads the config file, restarting only the plugins and modules whose config changed.
+    #[method(name = "reloadConfig")]
+    async fn reload_config(&self) -> RpcResult<ReloadSummary>;
+}
+
+/// A request to reload the config, answered once the reload is done.
+pub type ReloadRequest = oneshot::Sender<anyhow::Result<ReloadSummary>>;
+
+#[derive(Debug, Clone)]
+pub struct ConfigRpcHandler {
+    pub reload_requests: mpsc::Sender<ReloadRequest>,
+}
+
+#[async_trait]
+impl ConfigRpcServer for ConfigRpcHandler {
+    async fn reload_config(&self) -> RpcResult<ReloadSummary> {
+        let (tx, rx) = oneshot::channel();
+
+        self.reload_requests
+            .send(tx)
+            .await
+            .map_err(|_| reload_error("voyager is shutting down"))?;
+
+        rx.await
+            .map_err(|_| reload_error("voyager is shutting down"))?
+            .map_err(|err| reload_error(ErrorReporter(&*err).to_string()))
+    }
+}
+
+fn reload_error(message: impl Into<String>) -> ErrorObject<'static> {
+    ErrorObject::owned(INTERNAL_ERROR_CODE, message.into(), None::<()>)
+}
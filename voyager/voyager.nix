@@ -152,6 +152,30 @@
                         time_to_idle = mkOption { type = types.int; };
                       };
                     };
+                    chainLimitType = types.submodule {
+                      options = {
+                        max_concurrent_requests = mkOption {
+                          type = types.nullOr types.int;
+                          default = null;
+                        };
+                        requests_per_second = mkOption {
+                          type = types.nullOr types.int;
+                          default = null;
+                        };
+                      };
+                    };
+                    limitType = types.submodule {
+                      options = {
+                        max_concurrent_requests = mkOption {
+                          type = types.nullOr types.int;
+                          default = null;
+                        };
+                        requests_per_second = mkOption {
+                          type = types.nullOr types.int;
+                          default = null;
+                        };
+                      };
+                    };
                   in
                   mkOption {
                     type = types.submodule {
@@ -202,6 +226,38 @@
                                 type = types.nullOr types.float;
                                 default = null;
                               };
+                              poll_interval = mkOption {
+                                type = types.nullOr durationType;
+                                default = null;
+                              };
+                              done_retention = mkOption {
+                                type = types.nullOr (
+                                  types.submodule {
+                                    options = {
+                                      retain_days = mkOption {
+                                        type = types.int;
+                                      };
+                                      partition = mkOption {
+                                        type = types.nullOr types.bool;
+                                        default = null;
+                                      };
+                                      archive_dir = mkOption {
+                                        type = types.nullOr types.str;
+                                        default = null;
+                                      };
+                                      prune_interval_milliseconds = mkOption {
+                                        type = types.nullOr types.int;
+                                        default = null;
+                                      };
+                                      prune_batch_size = mkOption {
+                                        type = types.nullOr types.int;
+                                        default = null;
+                                      };
+                                    };
+                                  }
+                                );
+                                default = null;
+                              };
                             };
                           };
                         };
@@ -216,9 +272,30 @@
                           type = types.submodule {
                             options = {
                               state = mkOption { type = cacheType; };
+                              proof = mkOption {
+                                type = types.nullOr cacheType;
+                                default = null;
+                              };
                             };
                           };
                         };
+                        limits = mkOption {
+                          type = types.nullOr (
+                            types.submodule {
+                              options = {
+                                chains = mkOption {
+                                  type = types.attrsOf chainLimitType;
+                                  default = { };
+                                };
+                                modules = mkOption {
+                                  type = types.attrsOf limitType;
+                                  default = { };
+                                };
+                              };
+                            }
+                          );
+                          default = null;
+                        };
                       };
                     };
                   };